@@ -0,0 +1,123 @@
+//! AC adapter state for the plug banner, the event journal and plug notifications.
+//!
+//! Worn connectors and USB-C docks flap online/offline several times a second; a change
+//! only counts once it has held for DEBOUNCE_SECS, so the journal gets one "plugged"
+//! instead of a burst.
+
+use std::fs;
+use std::path::PathBuf;
+
+use crate::sysfs_path;
+
+/// How long a new AC state must hold before it counts
+pub const DEBOUNCE_SECS: u64 = 3;
+/// How long the dashboard shows the plug banner
+#[cfg(feature = "tui")]
+pub const BANNER_SECS: u64 = 8;
+
+fn read_attr(dir: &std::path::Path, name: &str) -> Option<String> {
+    fs::read_to_string(dir.join(name)).ok().map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+/// Power supplies that report `online`: mains adapters and USB-C/PD ports
+pub fn find_adapters() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(sysfs_path("class/power_supply")) else {
+        return Vec::new();
+    };
+    let mut adapters: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| read_attr(path, "type").is_some_and(|kind| kind == "Mains" || kind.starts_with("USB")))
+        .filter(|path| read_attr(path, "online").is_some())
+        .collect();
+    adapters.sort();
+    adapters
+}
+
+/// Whether any adapter is online; None without adapters to ask
+pub fn any_online(adapters: &[PathBuf]) -> Option<bool> {
+    let states: Vec<bool> = adapters.iter().filter_map(|path| read_attr(path, "online")).map(|online| online != "0").collect();
+    (!states.is_empty()).then(|| states.contains(&true))
+}
+
+/// Rated power (W) of the online adapter, from voltage_max × current_max; plain mains
+/// adapters usually don't say
+pub fn online_rating_w(adapters: &[PathBuf]) -> Option<f64> {
+    adapters.iter().filter(|path| read_attr(path, "online").is_some_and(|online| online != "0")).find_map(|path| {
+        let micro = |name: &str| read_attr(path, name)?.parse::<f64>().ok().filter(|v| *v > 0.0);
+        Some(micro("voltage_max")? / 1e6 * micro("current_max")? / 1e6)
+    })
+}
+
+/// Confirms AC changes that held for DEBOUNCE_SECS
+#[derive(Debug, Default)]
+pub struct PlugDebouncer {
+    stable: Option<bool>,
+    pending: Option<(bool, u64)>,
+}
+
+impl PlugDebouncer {
+    /// Feed one observation; returns the new state when a change is confirmed. The first
+    /// observation only sets the baseline.
+    pub fn update(&mut self, timestamp: u64, on_ac: bool) -> Option<bool> {
+        let Some(stable) = self.stable else {
+            self.stable = Some(on_ac);
+            return None;
+        };
+        if on_ac == stable {
+            self.pending = None; // Flapped back
+            return None;
+        }
+        match self.pending {
+            Some((state, since)) if state == on_ac && timestamp.saturating_sub(since) >= DEBOUNCE_SECS => {
+                self.stable = Some(on_ac);
+                self.pending = None;
+                Some(on_ac)
+            }
+            Some((state, _)) if state == on_ac => None,
+            _ => {
+                self.pending = Some((on_ac, timestamp));
+                None
+            }
+        }
+    }
+}
+
+/// A confirmed plug change, shown as a banner for BANNER_SECS
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlugChange {
+    pub plugged: bool,
+    pub timestamp: u64,
+    pub adapter_w: Option<f64>,
+}
+
+impl PlugChange {
+    /// "Plugged in at 41% (65 W adapter)" / "Unplugged at 80%", for the journal and notifications
+    pub fn describe(&self, capacity: u8) -> String {
+        match (self.plugged, self.adapter_w) {
+            (true, Some(watts)) => format!("Plugged in at {}% ({:.0} W adapter)", capacity, watts),
+            (true, None) => format!("Plugged in at {}%", capacity),
+            (false, _) => format!("Unplugged at {}%", capacity),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flapping_connector_yields_one_change() {
+        let mut debouncer = PlugDebouncer::default();
+        assert_eq!(debouncer.update(0, false), None);
+        // A loose plug: online for a second at a time
+        assert_eq!(debouncer.update(1, true), None);
+        assert_eq!(debouncer.update(2, false), None);
+        assert_eq!(debouncer.update(3, true), None);
+        assert_eq!(debouncer.update(5, true), None);
+        assert_eq!(debouncer.update(6, true), Some(true));
+        assert_eq!(debouncer.update(8, true), None);
+        assert_eq!(debouncer.update(9, false), None);
+        assert_eq!(debouncer.update(12, false), Some(false));
+    }
+}
@@ -0,0 +1,233 @@
+use std::fs;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::{format_minutes, sysfs_path, BatteryInfo, BatteryMonitor};
+
+/// How long `batfi advise` watches package energy and interrupts
+const SAMPLE_SECS: u64 = 3;
+/// Rough backlight draw of a laptop panel at full brightness
+const PANEL_FULL_W: f64 = 3.0;
+/// Brightness (fraction of max) the suggestion aims for
+const COMFORTABLE_BRIGHTNESS: f64 = 0.4;
+/// CPU package power of an idle modern laptop; anything well above means real work
+const PACKAGE_IDLE_W: f64 = 2.0;
+const PACKAGE_BUSY_W: f64 = 6.0;
+/// Share of package power a power-saving profile typically saves
+const POWER_SAVER_SHARE: f64 = 0.25;
+/// Without package power, assume the profile saves this share of system power
+const POWER_SAVER_SYSTEM_SHARE: f64 = 0.1;
+const WAKEUPS_BUSY_PER_SEC: f64 = 1500.0;
+const WAKEUPS_QUIET_PER_SEC: f64 = 500.0;
+/// Extra draw per 1000 wakeups/s above quiet, from keeping cores out of deep C-states
+const WAKEUP_W_PER_1000: f64 = 0.3;
+
+/// One ranked change with its estimated payoff
+#[derive(Debug, Clone, Serialize)]
+pub struct Suggestion {
+    pub title: String,
+    pub detail: String,
+    pub savings_w: f64,
+    /// Extra runtime at the current draw, when discharging
+    pub gain_minutes: Option<u32>,
+}
+
+/// What was measured over the sampling window
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Observations {
+    pub system_power_w: Option<f64>,
+    pub package_power_w: Option<f64>,
+    pub wakeups_per_sec: Option<f64>,
+    pub brightness_percent: Option<f64>,
+    pub governor: Option<String>,
+    pub energy_preference: Option<String>,
+    pub platform_profile: Option<String>,
+}
+
+fn read_trimmed(path: &std::path::Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+fn read_package_energy_uj() -> Option<u64> {
+    read_trimmed(&sysfs_path("class/powercap/intel-rapl:0/energy_uj"))?.parse().ok()
+}
+
+/// Interrupts delivered so far on all CPUs, a stand-in for wakeups
+fn read_interrupt_total() -> Option<u64> {
+    let text = fs::read_to_string("/proc/interrupts").ok()?;
+    let mut lines = text.lines();
+    let cpus = lines.next()?.split_whitespace().count();
+    Some(
+        lines
+            .map(|line| {
+                line.split_whitespace()
+                    .skip(1)
+                    .take(cpus)
+                    .map_while(|count| count.parse::<u64>().ok())
+                    .sum::<u64>()
+            })
+            .sum(),
+    )
+}
+
+fn read_brightness_percent() -> Option<f64> {
+    let mut devices: Vec<_> = fs::read_dir(sysfs_path("class/backlight")).ok()?.flatten().map(|e| e.path()).collect();
+    devices.sort();
+    let device = devices.first()?;
+    let current: f64 = read_trimmed(&device.join("brightness"))?.parse().ok()?;
+    let max: f64 = read_trimmed(&device.join("max_brightness"))?.parse().ok()?;
+    (max > 0.0).then(|| current / max * 100.0)
+}
+
+fn observe(monitor: &mut BatteryMonitor) -> (Option<BatteryInfo>, Observations) {
+    let start = Instant::now();
+    let package_start = read_package_energy_uj();
+    let interrupts_start = read_interrupt_total();
+    monitor.get_battery_info();
+    thread::sleep(Duration::from_secs(SAMPLE_SECS));
+    let info = monitor.get_battery_info();
+    let elapsed = start.elapsed().as_secs_f64();
+
+    let cpufreq = sysfs_path("devices/system/cpu/cpu0/cpufreq");
+    let observations = Observations {
+        system_power_w: info.as_ref().filter(|i| i.status == "Discharging").and_then(|i| i.smoothed_power_w.or(i.power_w)),
+        // energy_uj wraps around; a negative delta just means no reading this time
+        package_power_w: match (package_start, read_package_energy_uj()) {
+            (Some(a), Some(b)) if b >= a => Some((b - a) as f64 / 1_000_000.0 / elapsed),
+            _ => None,
+        },
+        wakeups_per_sec: match (interrupts_start, read_interrupt_total()) {
+            (Some(a), Some(b)) if b >= a => Some((b - a) as f64 / elapsed),
+            _ => None,
+        },
+        brightness_percent: read_brightness_percent(),
+        governor: read_trimmed(&cpufreq.join("scaling_governor")),
+        energy_preference: read_trimmed(&cpufreq.join("energy_performance_preference")),
+        platform_profile: read_trimmed(&sysfs_path("firmware/acpi/platform_profile")),
+    };
+    (info, observations)
+}
+
+fn suggest(observed: &Observations) -> Vec<(String, String, f64)> {
+    let mut suggestions = Vec::new();
+
+    let performance_mode = [&observed.governor, &observed.energy_preference, &observed.platform_profile]
+        .iter()
+        .filter_map(|setting| setting.as_deref())
+        .find(|setting| setting.starts_with("performance") || *setting == "balance_performance");
+    if let Some(mode) = performance_mode {
+        let savings = match (observed.package_power_w, observed.system_power_w) {
+            (Some(package), _) => package * POWER_SAVER_SHARE,
+            (None, Some(system)) => system * POWER_SAVER_SYSTEM_SHARE,
+            (None, None) => 0.0,
+        };
+        suggestions.push((
+            "Switch to power-saver".to_string(),
+            format!("CPU is set to '{}'; run `powerprofilesctl set power-saver`", mode),
+            savings,
+        ));
+    }
+
+    if let Some(brightness) = observed.brightness_percent.filter(|b| *b > COMFORTABLE_BRIGHTNESS * 100.0 + 20.0) {
+        suggestions.push((
+            format!("Lower screen brightness to {:.0}%", COMFORTABLE_BRIGHTNESS * 100.0),
+            format!("Backlight is at {:.0}%", brightness),
+            PANEL_FULL_W * (brightness / 100.0 - COMFORTABLE_BRIGHTNESS),
+        ));
+    }
+
+    if let Some(package) = observed.package_power_w.filter(|p| *p > PACKAGE_BUSY_W) {
+        suggestions.push((
+            "Stop what keeps the CPU busy".to_string(),
+            format!("CPU package draws {:.1} W (idle is ~{:.0} W); check `top` for heavy processes", package, PACKAGE_IDLE_W),
+            package - PACKAGE_IDLE_W,
+        ));
+    }
+
+    if let Some(wakeups) = observed.wakeups_per_sec.filter(|w| *w > WAKEUPS_BUSY_PER_SEC) {
+        suggestions.push((
+            "Cut down wakeups".to_string(),
+            format!("{:.0} interrupts/s keep the CPU out of deep sleep; `powertop` shows the culprits", wakeups),
+            (wakeups - WAKEUPS_QUIET_PER_SEC) / 1000.0 * WAKEUP_W_PER_1000,
+        ));
+    }
+
+    suggestions
+}
+
+/// Runtime gained at the current draw if `savings_w` less were used
+fn gain_minutes(info: Option<&BatteryInfo>, system_power_w: Option<f64>, savings_w: f64) -> Option<u32> {
+    let power = system_power_w?;
+    let info = info?;
+    // The smoothed ETA needs more samples than advise takes; fall back to energy over draw
+    let remaining = match info.time_remaining_minutes {
+        Some(minutes) => minutes as f64,
+        None => info.energy_now_wh? / power * 60.0,
+    };
+    if savings_w <= 0.0 || savings_w >= power {
+        return None;
+    }
+    Some((remaining * savings_w / (power - savings_w)).round() as u32)
+}
+
+/// `batfi advise`: ranked, quantified power-saving suggestions
+pub fn run_advise(monitor: &mut BatteryMonitor, json_output: bool) {
+    if !json_output {
+        println!("\x1b[2m⏳ Sampling for {}s...\x1b[0m", SAMPLE_SECS);
+    }
+    let (info, observed) = observe(monitor);
+    let mut suggestions: Vec<Suggestion> = suggest(&observed)
+        .into_iter()
+        .map(|(title, detail, savings_w)| Suggestion {
+            gain_minutes: gain_minutes(info.as_ref(), observed.system_power_w, savings_w),
+            title,
+            detail,
+            savings_w,
+        })
+        .collect();
+    suggestions.sort_by(|a, b| b.savings_w.total_cmp(&a.savings_w));
+
+    if json_output {
+        let report = serde_json::json!({
+            "battery": monitor.battery_name(),
+            "observed": observed,
+            "suggestions": suggestions,
+        });
+        println!("{}", serde_json::to_string_pretty(&report).unwrap_or_else(|_| "{}".to_string()));
+        return;
+    }
+
+    println!("\x1b[1;36m╔══════════════════════════════════════════════════════════════╗\x1b[0m");
+    println!("\x1b[1;36m║\x1b[0m \x1b[1;37m💡 Batfi Advise - {}\x1b[0m", monitor.battery_name());
+    println!("\x1b[1;36m╚══════════════════════════════════════════════════════════════╝\x1b[0m");
+    println!();
+    let dash = || "\x1b[2m—\x1b[0m".to_string();
+    println!(" \x1b[1mNow:\x1b[0m");
+    println!(" ├─ System draw:  {}", observed.system_power_w.map(|p| format!("{:.1} W", p)).unwrap_or_else(|| "\x1b[2mon AC\x1b[0m".to_string()));
+    println!(" ├─ CPU package:  {}", observed.package_power_w.map(|p| format!("{:.1} W", p)).unwrap_or_else(dash));
+    println!(" ├─ Wakeups:      {}", observed.wakeups_per_sec.map(|w| format!("{:.0}/s", w)).unwrap_or_else(dash));
+    println!(" ├─ Brightness:   {}", observed.brightness_percent.map(|b| format!("{:.0}%", b)).unwrap_or_else(dash));
+    let profile = observed.platform_profile.as_ref().or(observed.governor.as_ref()).cloned();
+    println!(" └─ Profile:      {}", profile.unwrap_or_else(dash));
+    println!();
+
+    if suggestions.is_empty() {
+        println!(" ✅ Nothing obvious to save — this machine is already running lean");
+        return;
+    }
+    println!(" \x1b[1mSuggestions:\x1b[0m");
+    for (i, suggestion) in suggestions.iter().enumerate() {
+        let payoff = match suggestion.gain_minutes {
+            Some(minutes) if minutes > 0 => format!("est. +{} (~{:.1} W)", format_minutes(minutes), suggestion.savings_w),
+            _ => format!("est. ~{:.1} W", suggestion.savings_w),
+        };
+        println!(" {}. \x1b[1m{}\x1b[0m: \x1b[32m{}\x1b[0m", i + 1, suggestion.title, payoff);
+        println!("    \x1b[2m{}\x1b[0m", suggestion.detail);
+    }
+    if observed.system_power_w.is_none() {
+        println!();
+        println!(" \x1b[2mOn AC: runtime gains are shown once running on battery\x1b[0m");
+    }
+}
@@ -0,0 +1,513 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::adapter::PlugChange;
+use crate::backlight::Backlight;
+use crate::critical::CriticalAction;
+use crate::{config, data_dir, events, format_minutes, BatteryInfo};
+
+/// How urgent an alert is; channels map this to their own priority scales
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.trim().to_lowercase().as_str() {
+            "info" => Some(Severity::Info),
+            "warning" | "warn" => Some(Severity::Warning),
+            "critical" | "crit" => Some(Severity::Critical),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+/// BatteryInfo values an alert rule can test
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Capacity,
+    Temperature,
+    CpuTemperature,
+    Power,
+    TimeRemaining,
+    Health,
+}
+
+impl Metric {
+    fn parse(text: &str) -> Option<Self> {
+        match text.trim() {
+            "capacity" => Some(Metric::Capacity),
+            "temperature" | "temp" => Some(Metric::Temperature),
+            "cpu_temperature" | "cpu_temp" => Some(Metric::CpuTemperature),
+            "power" => Some(Metric::Power),
+            "time_remaining" => Some(Metric::TimeRemaining),
+            "health" => Some(Metric::Health),
+            _ => None,
+        }
+    }
+
+    fn value(&self, info: &BatteryInfo) -> Option<f64> {
+        match self {
+            Metric::Capacity => Some(info.capacity_percent as f64),
+            Metric::Temperature => info.temperature_c,
+            Metric::CpuTemperature => info.cpu_temperature_c,
+            Metric::Power => info.smoothed_power_w.or(info.power_w),
+            Metric::TimeRemaining => info.time_remaining_minutes.map(|t| t as f64),
+            Metric::Health => Some(info.health_percent),
+        }
+    }
+
+    /// Charge and runtime alerts only make sense while running on battery
+    fn requires_discharging(&self) -> bool {
+        matches!(self, Metric::Capacity | Metric::TimeRemaining)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Below,
+    AtMost,
+    Above,
+    AtLeast,
+}
+
+impl Comparison {
+    fn holds(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Below => value < threshold,
+            Comparison::AtMost => value <= threshold,
+            Comparison::Above => value > threshold,
+            Comparison::AtLeast => value >= threshold,
+        }
+    }
+}
+
+/// Built-in action a rule runs when it fires
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleAction {
+    /// Lower the backlight to this percentage of maximum; restored on AC
+    Dim(u8),
+}
+
+impl RuleAction {
+    fn parse(text: &str) -> Result<Self, String> {
+        let (name, value) = text.trim().split_once('=').unwrap_or((text.trim(), ""));
+        match name {
+            "dim" => value
+                .parse()
+                .ok()
+                .filter(|percent| (1..=100).contains(percent))
+                .map(RuleAction::Dim)
+                .ok_or_else(|| format!("dim needs a percentage 1-100, got '{}'", value)),
+            _ => Err(format!("unknown action '{}' (available: dim=PERCENT)", name)),
+        }
+    }
+}
+
+/// A named threshold on one metric, e.g. `low-battery:capacity<=15:warning`
+#[derive(Debug, Clone)]
+pub struct AlertRule {
+    pub name: String,
+    pub metric: Metric,
+    pub comparison: Comparison,
+    pub threshold: f64,
+    pub severity: Severity,
+    pub action: Option<RuleAction>,
+}
+
+impl AlertRule {
+    /// Parse `NAME:METRIC<OP>VALUE[:SEVERITY][:ACTION]`, e.g. `dim:capacity<=30:info:dim=40`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut parts = spec.splitn(4, ':');
+        let name = parts.next().filter(|n| !n.trim().is_empty()).ok_or("missing rule name")?;
+        let condition = parts.next().ok_or("missing condition (e.g. capacity<=15)")?;
+        let (severity, action) = match (parts.next(), parts.next()) {
+            (None, _) => (Severity::Warning, None),
+            // The severity may be left out before an action
+            (Some(text), None) if text.contains('=') => (Severity::Warning, Some(RuleAction::parse(text)?)),
+            (Some(text), action) => (
+                Severity::parse(text).ok_or_else(|| format!("unknown severity '{}'", text))?,
+                action.map(RuleAction::parse).transpose()?,
+            ),
+        };
+
+        // Check two-character operators first so "<=" isn't read as "<"
+        let (metric, comparison, threshold) = [
+            ("<=", Comparison::AtMost),
+            (">=", Comparison::AtLeast),
+            ("<", Comparison::Below),
+            (">", Comparison::Above),
+        ]
+        .iter()
+        .find_map(|(op, comparison)| {
+            let (metric, value) = condition.split_once(op)?;
+            Some((metric, *comparison, value))
+        })
+        .ok_or_else(|| format!("no comparison operator in '{}'", condition))?;
+
+        Ok(Self {
+            name: name.trim().to_string(),
+            metric: Metric::parse(metric).ok_or_else(|| format!("unknown metric '{}'", metric.trim()))?,
+            comparison,
+            threshold: threshold.trim().parse().map_err(|_| format!("invalid threshold '{}'", threshold.trim()))?,
+            severity,
+            action,
+        })
+    }
+
+    fn is_triggered(&self, info: &BatteryInfo) -> bool {
+        if self.metric.requires_discharging() && info.status != "Discharging" {
+            return false;
+        }
+        self.metric
+            .value(info)
+            .is_some_and(|value| self.comparison.holds(value, self.threshold))
+    }
+
+    fn describe(&self, info: &BatteryInfo) -> String {
+        let value = self.metric.value(info).unwrap_or_default();
+        match self.metric {
+            Metric::Capacity => match info.time_remaining_minutes {
+                Some(minutes) => format!("Battery at {}% ({} remaining)", info.capacity_percent, format_minutes(minutes)),
+                None => format!("Battery at {}%", info.capacity_percent),
+            },
+            Metric::Temperature => format!("Battery temperature {:.1}°C", value),
+            Metric::CpuTemperature => format!("CPU temperature {:.1}°C", value),
+            Metric::Power => format!("Power draw {:.1}W", value),
+            Metric::TimeRemaining => format!("Only {} of battery left", format_minutes(value as u32)),
+            Metric::Health => format!("Battery health {:.0}%", value),
+        }
+    }
+}
+
+/// Rules active when alert channels are configured but no --alert was given
+pub fn default_rules() -> Vec<AlertRule> {
+    let limits = config::thresholds();
+    [
+        format!("low-battery:capacity<={}:warning", limits.capacity_warn),
+        format!("critical-battery:capacity<={}:critical", limits.capacity_crit),
+        format!("overheat:temperature>={}:critical", limits.battery_temp_hot),
+    ]
+    .iter()
+    .filter_map(|spec| AlertRule::parse(spec).ok())
+    .collect()
+}
+
+/// Tells the user when charging reaches the level they want to unplug at
+#[derive(Debug, Clone)]
+pub struct ChargeTarget {
+    pub target: u8,
+    /// Repeat while still plugged in at the target; None notifies once per charge
+    pub reminder_secs: Option<u64>,
+    /// A hardware stop threshold; at or below the target it makes unplugging pointless
+    pub hardware_limit: Option<u8>,
+    last_sent: Option<u64>,
+}
+
+impl ChargeTarget {
+    pub fn new(target: u8, reminder_secs: Option<u64>, hardware_limit: Option<u8>) -> Self {
+        Self { target, reminder_secs, hardware_limit, last_sent: None }
+    }
+
+    fn message(&self, info: &BatteryInfo) -> String {
+        if self.target >= 100 {
+            "Battery fully charged — unplug to spare it".to_string()
+        } else {
+            format!("Battery charged to {}% (target {}%) — unplug to spare it", info.capacity_percent, self.target)
+        }
+    }
+}
+
+fn snooze_path() -> Option<PathBuf> {
+    Some(data_dir()?.join("snooze-until"))
+}
+
+/// End of the current `batfi snooze`, if one is running
+pub fn snoozed_until() -> Option<u64> {
+    let until: u64 = fs::read_to_string(snooze_path()?).ok()?.trim().parse().ok()?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    (until > now).then_some(until)
+}
+
+/// Silence charge-target notifications until `until` (Unix seconds), or end the snooze
+pub fn set_snooze(until: Option<u64>) -> std::io::Result<()> {
+    let path = snooze_path()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no data directory"))?;
+    match until {
+        Some(until) => {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(path, until.to_string())
+        }
+        None if path.exists() => fs::remove_file(path),
+        None => Ok(()),
+    }
+}
+
+/// A fired alert, passed to every channel
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    pub rule: String,
+    pub severity: Severity,
+    pub message: String,
+    pub battery: String,
+    pub timestamp: u64,
+    pub info: BatteryInfo,
+}
+
+/// A condition raised from outside the rule list (e.g. a user script) while it holds
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomAlert {
+    pub name: String,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// A destination for fired alerts (webhook, push service, …)
+pub trait AlertChannel: Send {
+    fn send(&self, alert: &Alert);
+}
+
+/// Evaluates rules on every sample and fires each alert once per crossing
+pub struct AlertEngine {
+    rules: Vec<AlertRule>,
+    channels: Vec<Box<dyn AlertChannel>>,
+    active: HashSet<String>,
+    custom_active: HashSet<String>,
+    charge_target: Option<ChargeTarget>,
+    critical_action: Option<CriticalAction>,
+    backlight: Option<Backlight>,
+    plug_notifications: bool,
+    /// Data directory whose event journal records fired alerts
+    data_dir: Option<PathBuf>,
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Self {
+            rules,
+            channels: Vec::new(),
+            active: HashSet::new(),
+            custom_active: HashSet::new(),
+            charge_target: None,
+            critical_action: None,
+            backlight: None,
+            plug_notifications: false,
+            data_dir: data_dir(),
+        }
+    }
+
+    /// Backlight driven by `dim=` rule actions
+    pub fn set_backlight(&mut self, backlight: Backlight) {
+        self.backlight = Some(backlight);
+    }
+
+    /// Whether any rule does more than notify
+    pub fn has_actions(&self) -> bool {
+        self.rules.iter().any(|rule| rule.action.is_some())
+    }
+
+    pub fn set_charge_target(&mut self, target: ChargeTarget) {
+        self.charge_target = Some(target);
+    }
+
+    pub fn set_critical_action(&mut self, action: CriticalAction) {
+        self.critical_action = Some(action);
+    }
+
+    /// Journal fired alerts in `dir` instead of the user's data directory, or nowhere
+    pub fn set_data_dir(&mut self, dir: Option<PathBuf>) {
+        self.data_dir = dir;
+    }
+
+    /// Send plug and unplug changes to the channels too
+    pub fn set_plug_notifications(&mut self, enabled: bool) {
+        self.plug_notifications = enabled;
+    }
+
+    pub fn add_channel(&mut self, channel: Box<dyn AlertChannel>) {
+        self.channels.push(channel);
+    }
+
+    pub fn has_channels(&self) -> bool {
+        !self.channels.is_empty()
+    }
+
+    /// Rules whose condition currently holds
+    pub fn active_rules(&self) -> impl Iterator<Item = &AlertRule> {
+        self.rules.iter().filter(|rule| self.active.contains(&rule.name))
+    }
+
+    /// Check all rules against a sample and dispatch newly triggered alerts
+    pub fn evaluate(&mut self, battery: &str, info: &BatteryInfo) {
+        if info.status != "Discharging" {
+            if let Some(Err(e)) = self.backlight.as_mut().map(Backlight::restore) {
+                crate::warn(&format!("Could not restore the backlight: {}", e));
+            }
+        }
+        for rule in &self.rules {
+            let triggered = rule.is_triggered(info);
+            if !triggered {
+                // Re-arm once the condition clears
+                self.active.remove(&rule.name);
+                continue;
+            }
+            if !self.active.insert(rule.name.clone()) {
+                continue; // Already fired for this crossing
+            }
+
+            self.dispatch(Alert {
+                rule: rule.name.clone(),
+                severity: rule.severity,
+                message: rule.describe(info),
+                battery: battery.to_string(),
+                timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+                info: info.clone(),
+            });
+            if let (Some(RuleAction::Dim(percent)), Some(backlight)) = (rule.action, self.backlight.as_mut()) {
+                if let Err(e) = backlight.dim_to(percent) {
+                    crate::warn(&format!("Could not dim the backlight: {}", e));
+                }
+            }
+        }
+        self.check_charge_target(battery, info);
+        if let Some(message) = self.critical_action.as_mut().and_then(|action| action.check(self.data_dir.as_deref(), battery, info)) {
+            self.dispatch(Alert {
+                rule: "critical-action".to_string(),
+                severity: Severity::Critical,
+                message,
+                battery: battery.to_string(),
+                timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+                info: info.clone(),
+            });
+        }
+    }
+
+    /// Announce a confirmed AC change, when plug notifications are on
+    pub fn notify_plug(&self, battery: &str, info: &BatteryInfo, change: &PlugChange) {
+        if !self.plug_notifications {
+            return;
+        }
+        self.dispatch(Alert {
+            rule: if change.plugged { "plugged" } else { "unplugged" }.to_string(),
+            severity: Severity::Info,
+            message: change.describe(info.capacity_percent),
+            battery: battery.to_string(),
+            timestamp: change.timestamp,
+            info: info.clone(),
+        });
+    }
+
+    /// Fire custom alerts like rules: once when they first appear, again only after they cleared.
+    /// An alert whose message changes counts as a new one.
+    pub fn evaluate_custom(&mut self, battery: &str, info: &BatteryInfo, alerts: &[CustomAlert]) {
+        let key = |alert: &CustomAlert| format!("{}: {}", alert.name, alert.message);
+        self.custom_active.retain(|active| alerts.iter().any(|alert| &key(alert) == active));
+        for alert in alerts {
+            if !self.custom_active.insert(key(alert)) {
+                continue;
+            }
+            self.dispatch(Alert {
+                rule: alert.name.clone(),
+                severity: alert.severity,
+                message: alert.message.clone(),
+                battery: battery.to_string(),
+                timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+                info: info.clone(),
+            });
+        }
+    }
+
+    /// Announce the charge target once reached on AC, then every reminder interval until unplugged
+    fn check_charge_target(&mut self, battery: &str, info: &BatteryInfo) {
+        let Some(target) = self.charge_target.as_mut() else {
+            return;
+        };
+        let on_ac = matches!(info.status.as_str(), "Charging" | "Full" | "Not charging");
+        if !on_ac || info.capacity_percent < target.target {
+            target.last_sent = None;
+            return;
+        }
+        if target.hardware_limit.is_some_and(|limit| limit <= target.target) {
+            return; // Charging stops there on its own
+        }
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let due = match (target.last_sent, target.reminder_secs) {
+            (None, _) => true,
+            (Some(last), Some(every)) => now.saturating_sub(last) >= every,
+            (Some(_), None) => false,
+        };
+        if !due || snoozed_until().is_some() {
+            return;
+        }
+        target.last_sent = Some(now);
+        let message = target.message(info);
+        self.dispatch(Alert {
+            rule: "charged".to_string(),
+            severity: Severity::Info,
+            message,
+            battery: battery.to_string(),
+            timestamp: now,
+            info: info.clone(),
+        });
+    }
+
+    /// Journal a fired alert and hand it to every channel
+    fn dispatch(&self, alert: Alert) {
+        let _ = events::log_event(self.data_dir.as_deref(), &alert.battery, "alert", &format!("[{}] {}: {}", alert.severity.as_str(), alert.rule, alert.message));
+        for channel in &self.channels {
+            channel.send(&alert);
+        }
+    }
+}
+
+/// Replace `{{placeholder}}` fields in a template with alert values.
+///
+/// Values are JSON-string escaped so templates like `{"text": "{{message}}"}` stay valid.
+#[cfg(feature = "notify")]
+pub fn render_template(template: &str, alert: &Alert, host: &str) -> String {
+    let escape = |value: String| {
+        let quoted = serde_json::to_string(&value).unwrap_or_default();
+        quoted
+            .strip_prefix('"')
+            .and_then(|q| q.strip_suffix('"'))
+            .unwrap_or(&quoted)
+            .to_string()
+    };
+    let info = &alert.info;
+    let fields = [
+        ("rule", alert.rule.clone()),
+        ("severity", alert.severity.as_str().to_string()),
+        ("message", alert.message.clone()),
+        ("battery", alert.battery.clone()),
+        ("nickname", config::display_name(&alert.battery).to_string()),
+        ("host", host.to_string()),
+        ("timestamp", alert.timestamp.to_string()),
+        ("capacity", info.capacity_percent.to_string()),
+        ("status", info.status.clone()),
+        ("time_remaining", info.time_remaining_minutes.map(format_minutes).unwrap_or_default()),
+        ("power", info.smoothed_power_w.or(info.power_w).map(|p| format!("{:.1}", p)).unwrap_or_default()),
+        ("temperature", info.temperature_c.map(|t| format!("{:.1}", t)).unwrap_or_default()),
+    ];
+
+    fields.into_iter().fold(template.to_string(), |text, (key, value)| {
+        text.replace(&format!("{{{{{}}}}}", key), &escape(value))
+    })
+}
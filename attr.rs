@@ -0,0 +1,252 @@
+//! Parsing of numeric power_supply attributes, tolerant of what firmwares actually write.
+//!
+//! The kernel ABI says µWh, µAh, µV, µW and µA, but some ACPI and EC drivers report
+//! milli- or whole units, pad values with NULs, write "unknown", or use hex. Units are
+//! guessed from the full-capacity attributes, which stay put while a pack drains, so a
+//! nearly empty `energy_now` isn't mistaken for a different scale.
+
+use std::ops::RangeInclusive;
+
+/// Full capacities a real pack can have, from earbuds to e-bikes
+const PLAUSIBLE_PACK_WH: RangeInclusive<f64> = 0.5..=2000.0;
+const PLAUSIBLE_PACK_AH: RangeInclusive<f64> = 0.05..=200.0;
+/// Pack voltages from a single cell to a 24S stack
+const PLAUSIBLE_PACK_V: RangeInclusive<f64> = 1.0..=100.0;
+
+/// A raw attribute as a number: surrounding whitespace and NULs are ignored, signs and
+/// `0x` hex are accepted, and placeholders like "unknown" or "N/A" read as missing
+pub fn parse_number(raw: &str) -> Option<f64> {
+    let text = raw.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+    if let Some(value) = parse_integer(text) {
+        return Some(value as f64);
+    }
+    text.parse::<f64>().ok().filter(|value| value.is_finite())
+}
+
+/// A raw attribute as an integer; see `parse_number`
+pub fn parse_integer(raw: &str) -> Option<i64> {
+    let text = raw.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (digits, radix) = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        Some(hex) => (hex, 16),
+        None => (digits, 10),
+    };
+    // from_str_radix would accept a second sign
+    if digits.starts_with(['+', '-']) {
+        return None;
+    }
+    let magnitude = i64::from_str_radix(digits, radix).ok()?;
+    Some(if negative { -magnitude } else { magnitude })
+}
+
+/// How far an attribute's raw value is from its base unit (Wh, Ah, V, W, A)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scale {
+    Micro,
+    Milli,
+    Unit,
+}
+
+impl Scale {
+    pub fn apply(self, raw: f64) -> f64 {
+        match self {
+            Scale::Micro => raw / 1_000_000.0,
+            Scale::Milli => raw / 1000.0,
+            Scale::Unit => raw,
+        }
+    }
+
+    /// The first of µ, m and whole units that puts `raw` within `plausible`; µ (the ABI) when none does
+    fn detect(raw: f64, plausible: RangeInclusive<f64>) -> Scale {
+        [Scale::Micro, Scale::Milli, Scale::Unit]
+            .into_iter()
+            .find(|scale| plausible.contains(&scale.apply(raw.abs())))
+            .unwrap_or(Scale::Micro)
+    }
+}
+
+/// Typed, unit-corrected access to one power supply's attributes, given a way to read them raw
+pub struct Attrs<F: Fn(&str) -> Option<String>> {
+    read: F,
+}
+
+impl<F: Fn(&str) -> Option<String>> Attrs<F> {
+    pub fn new(read: F) -> Self {
+        Self { read }
+    }
+
+    pub fn number(&self, name: &str) -> Option<f64> {
+        parse_number(&(self.read)(name)?)
+    }
+
+    /// An integer attribute that fits `T`; "unknown" and out-of-range values read as missing
+    pub fn integer<T: TryFrom<i64>>(&self, name: &str) -> Option<T> {
+        T::try_from(parse_integer(&(self.read)(name)?)?).ok()
+    }
+
+    /// Scale of energy_* and power_now, judged from the full capacities
+    pub fn energy_scale(&self) -> Scale {
+        self.number("energy_full")
+            .filter(|full| *full > 0.0)
+            .or_else(|| self.number("energy_full_design"))
+            .map(|full| Scale::detect(full, PLAUSIBLE_PACK_WH))
+            .unwrap_or(Scale::Micro)
+    }
+
+    /// Scale of charge_* and current_now, judged from the full capacities
+    pub fn charge_scale(&self) -> Scale {
+        self.number("charge_full")
+            .filter(|full| *full > 0.0)
+            .or_else(|| self.number("charge_full_design"))
+            .map(|full| Scale::detect(full, PLAUSIBLE_PACK_AH))
+            .unwrap_or(Scale::Micro)
+    }
+
+    pub fn voltage_v(&self) -> Option<f64> {
+        let raw = self.number("voltage_now")?;
+        Some(Scale::detect(raw, PLAUSIBLE_PACK_V).apply(raw))
+    }
+
+    /// `charge_<kind>` (now, full, full_design) in mAh
+    pub fn charge_mah(&self, kind: &str) -> Option<f64> {
+        let raw = self.number(&format!("charge_{}", kind))?;
+        Some(self.charge_scale().apply(raw) * 1000.0)
+    }
+
+    /// `energy_<kind>` (now, full, full_design) in Wh, or `charge_<kind>` × voltage_now for charge-based packs
+    pub fn energy_wh(&self, kind: &str) -> Option<f64> {
+        match self.number(&format!("energy_{}", kind)) {
+            Some(raw) => Some(self.energy_scale().apply(raw)),
+            None => Some(self.charge_mah(kind)? / 1000.0 * self.voltage_v()?),
+        }
+    }
+
+    /// power_now in W; some firmwares sign it by direction, which `status` already tells
+    pub fn power_w(&self) -> Option<f64> {
+        let raw = self.number("power_now")?;
+        Some(self.energy_scale().apply(raw).abs())
+    }
+
+    /// current_now in mA, keeping the firmware's sign
+    pub fn current_ma(&self) -> Option<i32> {
+        let raw = self.number("current_now")?;
+        let milliamps = self.charge_scale().apply(raw) * 1000.0;
+        (milliamps.abs() < i32::MAX as f64).then(|| milliamps.trunc() as i32)
+    }
+
+    /// Any attribute whose unit batfi knows, scaled like the typed accessors, with its unit
+    pub fn interpreted(&self, name: &str) -> Option<(f64, &'static str)> {
+        let raw = self.number(name)?;
+        Some(match name {
+            "energy_now" | "energy_full" | "energy_full_design" | "energy_empty" | "energy_empty_design" | "energy_avg" => {
+                (self.energy_scale().apply(raw), "Wh")
+            }
+            "power_now" | "power_avg" => (self.energy_scale().apply(raw), "W"),
+            "charge_now" | "charge_full" | "charge_full_design" | "charge_empty" | "charge_empty_design" | "charge_avg"
+            | "charge_counter" => (self.charge_scale().apply(raw) * 1000.0, "mAh"),
+            "current_now" | "current_avg" | "current_max" | "input_current_limit" => {
+                (self.charge_scale().apply(raw) * 1000.0, "mA")
+            }
+            "voltage_now" | "voltage_avg" | "voltage_ocv" | "voltage_min" | "voltage_max" | "voltage_min_design"
+            | "voltage_max_design" => (Scale::detect(raw, PLAUSIBLE_PACK_V).apply(raw), "V"),
+            "capacity" | "capacity_alert_min" | "capacity_alert_max" | "charge_control_start_threshold"
+            | "charge_control_end_threshold" => (raw, "%"),
+            "temp" | "temp_ambient" | "temp_min" | "temp_max" | "temp_alert_min" | "temp_alert_max" => (raw / 10.0, "°C"),
+            "time_to_empty_now" | "time_to_empty_avg" | "time_to_full_now" | "time_to_full_avg" => (raw / 60.0, "min"),
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    fn attrs(values: &[(&str, &str)]) -> Attrs<impl Fn(&str) -> Option<String>> {
+        let values: HashMap<String, String> = values.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        Attrs::new(move |name: &str| values.get(name).cloned())
+    }
+
+    proptest! {
+        #[test]
+        fn arbitrary_text_never_panics(text in "\\PC*") {
+            let _ = parse_number(&text);
+            let _ = parse_integer(&text);
+        }
+
+        #[test]
+        fn integers_survive_padding(value in any::<i64>(), before in "[ \t\n\0]{0,3}", after in "[ \t\n\0]{0,3}") {
+            let text = format!("{}{}{}", before, value, after);
+            prop_assert_eq!(parse_integer(&text), Some(value));
+            prop_assert_eq!(parse_number(&text), Some(value as f64));
+        }
+
+        #[test]
+        fn hex_matches_decimal(value in 0i64..i64::MAX, upper in any::<bool>()) {
+            let text = if upper { format!("0X{:X}\n", value) } else { format!("0x{:x}\n", value) };
+            prop_assert_eq!(parse_integer(&text), Some(value));
+            prop_assert_eq!(parse_integer(&format!("-{}", text)), Some(-value));
+        }
+
+        #[test]
+        fn words_are_missing(word in "[a-zA-Z/ ]*[a-zA-Z/][a-zA-Z/ ]*") {
+            prop_assert_eq!(parse_number(&word), None);
+        }
+
+        /// Whatever the firmware's unit, a laptop-sized pack reads back as the same Wh
+        #[test]
+        fn energy_scale_is_recovered(full_wh in 1.0..400.0f64, share in 0.0..=1.0f64, scale in 0usize..3) {
+            let factor = [1_000_000.0, 1000.0, 1.0][scale];
+            let full = format!("{}", (full_wh * factor).round());
+            let now = format!("{}", (full_wh * share * factor).round());
+            let pack = attrs(&[("energy_full", &full), ("energy_now", &now)]);
+            let read = pack.energy_wh("now").unwrap();
+            prop_assert!((read - full_wh * share).abs() <= 1.0 / factor + 1e-9);
+        }
+
+        #[test]
+        fn voltage_scale_is_recovered(volts in 1.0..100.0f64, scale in 0usize..3) {
+            let factor = [1_000_000.0, 1000.0, 1.0][scale];
+            let pack = attrs(&[("voltage_now", &format!("{}", volts * factor))]);
+            prop_assert!((pack.voltage_v().unwrap() - volts).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn placeholders_and_empty_files_are_missing() {
+        for text in ["", "\n", "  \0", "unknown", "N/A", "-", "0x", "--5", "+-5", "nan", "inf"] {
+            assert_eq!(parse_number(text), None, "{:?}", text);
+        }
+        assert_eq!(parse_number("12.5\n"), Some(12.5));
+        assert_eq!(parse_number("-1500000"), Some(-1_500_000.0));
+    }
+
+    #[test]
+    fn charge_fallback_uses_scaled_voltage() {
+        // 4400 mAh written in mAh, 7.6 V written in mV: 33.44 Wh
+        let pack = attrs(&[("charge_full", "4400"), ("voltage_now", "7600")]);
+        assert!((pack.energy_wh("full").unwrap() - 33.44).abs() < 1e-9);
+        assert_eq!(pack.charge_scale(), Scale::Milli);
+    }
+
+    #[test]
+    fn interpreted_values_carry_their_unit() {
+        let pack = attrs(&[("energy_full", "50000"), ("energy_now", "25000"), ("temp", "312"), ("status", "Discharging")]);
+        assert_eq!(pack.interpreted("energy_now"), Some((25.0, "Wh")));
+        assert_eq!(pack.interpreted("temp"), Some((31.2, "°C")));
+        assert_eq!(pack.interpreted("status"), None);
+        assert_eq!(pack.interpreted("cycle_count"), None);
+    }
+
+    #[test]
+    fn negative_power_and_current() {
+        let pack = attrs(&[("energy_full", "50000000"), ("power_now", "-9120000"), ("charge_full", "4400000"), ("current_now", "-1100000")]);
+        assert_eq!(pack.power_w(), Some(9.12));
+        assert_eq!(pack.current_ma(), Some(-1100));
+    }
+}
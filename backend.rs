@@ -0,0 +1,109 @@
+use std::fs;
+use std::path::Path;
+
+#[cfg(test)]
+use std::collections::{HashMap, VecDeque};
+
+/// Where a BatteryMonitor gets its power_supply attributes from
+pub trait PowerSupplyBackend: Send {
+    /// Called once at the start of every sample, before any attribute is read
+    fn refresh(&mut self) {}
+
+    /// Whether the battery is present at all
+    fn exists(&self) -> bool;
+
+    /// Trimmed contents of an attribute such as `energy_now`, in sysfs units
+    fn read_attr(&self, attr: &str) -> Option<String>;
+
+    fn has_attr(&self, attr: &str) -> bool {
+        self.read_attr(attr).is_some()
+    }
+}
+
+/// The real thing: files under /sys/class/power_supply/<battery>
+pub struct SysfsBackend {
+    base_path: String,
+}
+
+impl SysfsBackend {
+    pub fn new(base_path: &str) -> Self {
+        Self { base_path: base_path.to_string() }
+    }
+}
+
+impl PowerSupplyBackend for SysfsBackend {
+    fn exists(&self) -> bool {
+        Path::new(&self.base_path).exists()
+    }
+
+    fn read_attr(&self, attr: &str) -> Option<String> {
+        fs::read_to_string(Path::new(&self.base_path).join(attr)).ok().map(|s| s.trim().to_string())
+    }
+
+    fn has_attr(&self, attr: &str) -> bool {
+        Path::new(&self.base_path).join(attr).exists()
+    }
+}
+
+/// Monitor over scripted readings that keeps its state in a scratch directory, deleted when
+/// the returned guard drops
+#[cfg(test)]
+pub fn mock_monitor(backend: MockBackend) -> (crate::BatteryMonitor, tempfile::TempDir) {
+    let dir = tempfile::tempdir().expect("scratch data dir");
+    let mut monitor = crate::BatteryMonitor::with_backend("MOCK0", Box::new(backend));
+    monitor.set_data_dir(Some(dir.path().to_path_buf()));
+    (monitor, dir)
+}
+
+/// Scripted readings for tests: every sample takes the next queued frame,
+/// and the last one repeats once the script runs out
+#[cfg(test)]
+#[derive(Default)]
+pub struct MockBackend {
+    frames: VecDeque<HashMap<String, String>>,
+    current: HashMap<String, String>,
+}
+
+#[cfg(test)]
+impl MockBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue one sample's attributes, e.g. `[("status", "Discharging"), ("power_now", "10000000")]`
+    pub fn push(mut self, attrs: &[(&str, String)]) -> Self {
+        self.frames.push_back(attrs.iter().map(|(k, v)| (k.to_string(), v.clone())).collect());
+        self
+    }
+
+    /// Queue an energy-reporting pack discharging at `power_w`
+    pub fn discharging(self, energy_now_wh: f64, energy_full_wh: f64, power_w: f64) -> Self {
+        let capacity = (energy_now_wh / energy_full_wh * 100.0).round() as u8;
+        self.push(&[
+            ("status", "Discharging".to_string()),
+            ("capacity", capacity.to_string()),
+            ("energy_now", ((energy_now_wh * 1_000_000.0) as u64).to_string()),
+            ("energy_full", ((energy_full_wh * 1_000_000.0) as u64).to_string()),
+            ("energy_full_design", ((energy_full_wh * 1_000_000.0) as u64).to_string()),
+            ("power_now", ((power_w * 1_000_000.0) as u64).to_string()),
+            ("voltage_now", "12000000".to_string()),
+        ])
+    }
+}
+
+#[cfg(test)]
+impl PowerSupplyBackend for MockBackend {
+    fn refresh(&mut self) {
+        if let Some(next) = self.frames.pop_front() {
+            self.current = next;
+        }
+    }
+
+    fn exists(&self) -> bool {
+        !self.current.is_empty() || !self.frames.is_empty()
+    }
+
+    fn read_attr(&self, attr: &str) -> Option<String> {
+        self.current.get(attr).cloned()
+    }
+}
@@ -0,0 +1,107 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use zbus::blocking::Connection;
+
+use crate::sysfs_path;
+
+/// Steps the screen backlight down on battery and puts it back on AC
+pub struct Backlight {
+    device: Option<PathBuf>,
+    /// Run instead of writing sysfs; `{percent}` is replaced with the target level
+    command: Option<String>,
+    /// Brightness before the first step down, restored on AC
+    saved: Option<u32>,
+}
+
+impl Backlight {
+    /// `device` names an entry of /sys/class/backlight; None takes the first one
+    pub fn new(device: Option<&str>, command: Option<&str>) -> Self {
+        let root = sysfs_path("class/backlight");
+        let device = match device {
+            Some(name) => Some(root.join(name)),
+            None => fs::read_dir(&root).ok().and_then(|entries| {
+                let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+                paths.sort();
+                paths.into_iter().next()
+            }),
+        };
+        Self { device, command: command.map(String::from), saved: None }
+    }
+
+    fn read(&self, attr: &str) -> Option<u32> {
+        fs::read_to_string(self.device.as_ref()?.join(attr)).ok()?.trim().parse().ok()
+    }
+
+    fn max(&self) -> Option<u32> {
+        self.read("max_brightness").filter(|max| *max > 0)
+    }
+
+    /// Lower the backlight to `percent` of maximum; never raises it
+    pub fn dim_to(&mut self, percent: u8) -> Result<(), String> {
+        if let Some(command) = &self.command {
+            if self.saved.is_none() {
+                self.saved = self.read("brightness");
+            }
+            return run_command(command, percent);
+        }
+        let max = self.max().ok_or("no backlight device")?;
+        let current = self.read("brightness").ok_or("backlight brightness unreadable")?;
+        let target = (max as u64 * percent as u64 / 100) as u32;
+        if target >= current {
+            return Ok(());
+        }
+        if self.saved.is_none() {
+            self.saved = Some(current);
+        }
+        self.write(target)
+    }
+
+    /// Back to the brightness saved before dimming, if anything was dimmed
+    pub fn restore(&mut self) -> Result<(), String> {
+        let Some(saved) = self.saved.take() else {
+            return Ok(());
+        };
+        match &self.command {
+            Some(command) => {
+                let percent = self.max().map_or(100, |max| (saved as u64 * 100 / max as u64).min(100) as u8);
+                run_command(command, percent)
+            }
+            None => self.write(saved),
+        }
+    }
+
+    /// sysfs needs root or a udev rule; otherwise ask logind, like desktop environments do
+    fn write(&self, value: u32) -> Result<(), String> {
+        let device = self.device.as_ref().ok_or("no backlight device")?;
+        if fs::write(device.join("brightness"), value.to_string()).is_ok() {
+            return Ok(());
+        }
+        let name = device.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        logind_set_brightness(&name, value).map_err(|e| format!("could not set {} brightness: {}", name, e))
+    }
+}
+
+fn logind_set_brightness(device: &str, value: u32) -> zbus::Result<()> {
+    let connection = Connection::system()?;
+    connection.call_method(
+        Some("org.freedesktop.login1"),
+        "/org/freedesktop/login1/session/auto",
+        Some("org.freedesktop.login1.Session"),
+        "SetBrightness",
+        &("backlight", device, value),
+    )?;
+    Ok(())
+}
+
+fn run_command(command: &str, percent: u8) -> Result<(), String> {
+    let command = command.replace("{percent}", &percent.to_string());
+    let mut parts = command.split_whitespace();
+    let program = parts.next().ok_or("empty backlight command")?;
+    match Command::new(program).args(parts).status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("{} exited with {}", program, status)),
+        Err(e) => Err(format!("could not run {}: {}", program, e)),
+    }
+}
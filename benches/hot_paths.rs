@@ -0,0 +1,90 @@
+//! Per-tick costs: reading a sample, rendering it, and keeping the history.
+//!
+//! Run with `cargo bench`; compare against a saved baseline with
+//! `cargo bench -- --save-baseline before` / `--baseline before`.
+
+use std::hint::black_box;
+
+use batfi::simulate::SimulatedBackend;
+use batfi::statusbar::{self, BarFormat};
+use batfi::{estimation, BatteryMonitor};
+use criterion::{criterion_group, criterion_main, Criterion};
+use tempfile::TempDir;
+
+/// Samples a long-running monitor holds on to (MAX_HISTORY_SIZE)
+const FULL_HISTORY: usize = 300;
+
+/// Keep usage logs, events and the learned curve out of the real data dir, in `dir`
+fn scratch(mut monitor: BatteryMonitor, dir: &TempDir) -> BatteryMonitor {
+    monitor.set_data_dir(Some(dir.path().to_path_buf()));
+    monitor
+}
+
+fn simulated_monitor(dir: &TempDir) -> BatteryMonitor {
+    scratch(BatteryMonitor::with_backend("SIM0", Box::new(SimulatedBackend::new("discharge", 90.0, None, 2.0))), dir)
+}
+
+/// A monitor that has been running long enough for every history to be full
+fn warmed_monitor(dir: &TempDir) -> BatteryMonitor {
+    let mut monitor = simulated_monitor(dir);
+    for _ in 0..FULL_HISTORY {
+        monitor.get_battery_info();
+    }
+    monitor
+}
+
+fn sampling(c: &mut Criterion) {
+    batfi::set_sysfs_root(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/intel-laptop"));
+
+    let dir = TempDir::new().expect("scratch data dir");
+    let mut group = c.benchmark_group("tick");
+    // Attribute reads through sysfs, hwmon and thermal zones of a recorded laptop
+    let mut monitor = scratch(BatteryMonitor::new("BAT0"), &dir);
+    group.bench_function("sysfs", |b| b.iter(|| black_box(monitor.get_battery_info())));
+    // Same bookkeeping without file I/O for the attributes
+    let mut monitor = simulated_monitor(&dir);
+    group.bench_function("simulated", |b| b.iter(|| black_box(monitor.get_battery_info())));
+    group.finish();
+}
+
+fn rendering(c: &mut Criterion) {
+    let dir = TempDir::new().expect("scratch data dir");
+    let mut monitor = warmed_monitor(&dir);
+    let info = monitor.get_battery_info().expect("simulated battery");
+
+    let mut group = c.benchmark_group("render");
+    group.bench_function("json", |b| b.iter(|| black_box(monitor.to_json(&info))));
+    for (name, format) in [("polybar", BarFormat::Polybar), ("xmobar", BarFormat::Xmobar), ("dzen", BarFormat::Dzen)] {
+        group.bench_function(name, |b| b.iter(|| black_box(statusbar::render(format, &info))));
+    }
+    #[cfg(feature = "tui")]
+    {
+        group.bench_function("battery_bar", |b| b.iter(|| black_box(monitor.get_battery_bar(info.capacity_percent, 72.0, 40))));
+        group.bench_function("power_graph", |b| b.iter(|| black_box(monitor.get_power_graph(40))));
+        group.bench_function("trend", |b| b.iter(|| black_box(monitor.get_trend_indicator())));
+    }
+    group.finish();
+}
+
+fn history(c: &mut Criterion) {
+    let dir = TempDir::new().expect("scratch data dir");
+    let mut monitor = warmed_monitor(&dir);
+    let readings: Vec<_> = monitor.readings_history().iter().cloned().collect();
+    let powers: Vec<f64> = readings.iter().filter_map(|r| r.power_now_w).collect();
+    let capacities: Vec<u8> = readings.iter().map(|r| r.capacity_percent).collect();
+
+    let mut group = c.benchmark_group("history");
+    // Every deque is full, so each tick pushes and evicts
+    group.bench_function("tick_at_capacity", |b| b.iter(|| black_box(monitor.get_battery_info())));
+    // Run after every dashboard tick to persist resistance estimates
+    group.bench_function("internal_resistance", |b| b.iter(|| black_box(monitor.estimate_internal_resistance())));
+    group.bench_function("power_trend", |b| b.iter(|| black_box(estimation::power_trend(black_box(&powers)))));
+    group.bench_function("capacity_trend", |b| b.iter(|| black_box(estimation::capacity_trend(black_box(&capacities)))));
+    group.bench_function("rolling_average", |b| {
+        b.iter(|| black_box(estimation::rolling_average(black_box(&powers[powers.len() - estimation::ROLLING_WINDOW_SIZE..]))))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, sampling, rendering, history);
+criterion_main!(benches);
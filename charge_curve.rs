@@ -0,0 +1,222 @@
+//! Learned charging speed (%/h) as a function of state of charge, for projecting how a
+//! charge will go: chargers run flat out to about 80% and taper from there, by an amount
+//! that depends on the pack and charger rather than on a fixed formula.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{estimation, BatteryReading};
+
+/// State-of-charge resolution of the learned curve (one bucket per 5%)
+const BUCKET_PERCENT: u8 = 5;
+const BUCKET_COUNT: usize = 100 / BUCKET_PERCENT as usize;
+/// Learning rate for the per-bucket average
+const CURVE_ALPHA: f64 = 0.2;
+/// Percent steps a bucket needs before it is trusted for projections
+const MIN_BUCKET_STEPS: u32 = 2;
+/// Longer sampling gaps (suspend, unplug and replug) restart the step timer
+const MAX_GAP_SECS: u64 = 120;
+/// How far the live rate may rescale the learned curve (e.g. a weaker charger today)
+const LIVE_SCALE_RANGE: (f64, f64) = (0.25, 4.0);
+/// Where chargers typically begin to taper, and where batfi reports a separate ETA
+pub const PROJECTION_MILESTONE_PERCENT: u8 = 80;
+
+/// Learned charging speed by state-of-charge bucket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChargeCurve {
+    pub battery: String,
+    pub percent_per_hour: Vec<Option<f64>>,
+    pub steps: Vec<u32>,
+    /// Timestamp and capacity of the last whole-percent step while charging
+    #[serde(skip)]
+    last_step: Option<(u64, u8)>,
+    #[serde(skip)]
+    last_seen: Option<(u64, u8)>,
+    /// Where `save` writes the curve; None keeps it in memory
+    #[serde(skip)]
+    dir: Option<PathBuf>,
+}
+
+impl ChargeCurve {
+    pub fn new(battery: &str) -> Self {
+        Self {
+            battery: battery.to_string(),
+            percent_per_hour: vec![None; BUCKET_COUNT],
+            steps: vec![0; BUCKET_COUNT],
+            last_step: None,
+            last_seen: None,
+            dir: None,
+        }
+    }
+
+    fn path(dir: Option<&Path>, battery: &str) -> Option<PathBuf> {
+        Some(dir?.join(format!("charge_curve-{}.json", battery)))
+    }
+
+    /// Load the persisted curve for a battery, or start a fresh one
+    pub fn load(dir: Option<&Path>, battery: &str) -> Self {
+        let mut curve = Self::path(dir, battery)
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str::<Self>(&json).ok())
+            .filter(|curve| curve.percent_per_hour.len() == BUCKET_COUNT && curve.steps.len() == BUCKET_COUNT)
+            .unwrap_or_else(|| Self::new(battery));
+        curve.dir = dir.map(Path::to_path_buf);
+        curve
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Self::path(self.dir.as_deref(), &self.battery)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no data directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string(self).map_err(std::io::Error::other)?;
+        fs::write(path, json)
+    }
+
+    /// Forget the learned curve for a battery (e.g. a different pack was installed)
+    pub fn discard(dir: Option<&Path>, battery: &str) {
+        if let Some(path) = Self::path(dir, battery) {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    fn bucket(capacity_percent: u8) -> usize {
+        (capacity_percent.min(99) / BUCKET_PERCENT) as usize
+    }
+
+    fn learned(&self, bucket: usize) -> Option<f64> {
+        self.percent_per_hour[bucket].filter(|_| self.steps[bucket] >= MIN_BUCKET_STEPS)
+    }
+
+    /// Feed a reading; returns true when a bucket was updated and the curve is worth saving
+    pub fn learn(&mut self, reading: &BatteryReading) -> bool {
+        let (now, percent) = (reading.timestamp, reading.capacity_percent);
+        let continuous = reading.status == "Charging"
+            && self.last_seen.is_some_and(|(ts, seen)| now > ts && now - ts <= MAX_GAP_SECS && percent >= seen);
+        let previous = self.last_seen.replace((now, percent)).map(|(_, seen)| seen);
+        if !continuous {
+            self.last_step = None;
+            return false;
+        }
+        if previous == Some(percent) {
+            return false;
+        }
+        // Only time between two observed steps is a whole percent's worth
+        let Some((step_ts, step_percent)) = self.last_step.replace((now, percent)) else {
+            return false;
+        };
+        let hours = (now - step_ts) as f64 / 3600.0;
+        let rate = (percent - step_percent) as f64 / hours;
+        let bucket = Self::bucket(step_percent);
+        self.percent_per_hour[bucket] = Some(match self.percent_per_hour[bucket] {
+            Some(prev) => CURVE_ALPHA * rate + (1.0 - CURVE_ALPHA) * prev,
+            None => rate,
+        });
+        self.steps[bucket] = self.steps[bucket].saturating_add(1);
+        true
+    }
+
+    /// Projected minutes to reach each of `from + 1 ..= 100`, cumulative. The learned curve
+    /// gives the shape, rescaled to `live_rate` (%/h now); buckets not learned yet follow the
+    /// generic taper from the live rate. None when neither is known.
+    pub fn projection(&self, from: u8, live_rate: Option<f64>) -> Option<Vec<f64>> {
+        let live_rate = live_rate.filter(|rate| *rate > 0.0);
+        let taper = |percent: u8| estimation::charging_efficiency(percent as f64 / 100.0).max(0.05);
+        let scale = match (live_rate, self.learned(Self::bucket(from))) {
+            (Some(live), Some(learned)) => (live / learned).clamp(LIVE_SCALE_RANGE.0, LIVE_SCALE_RANGE.1),
+            _ => 1.0,
+        };
+        let mut elapsed = 0.0;
+        (from.min(100)..100)
+            .map(|percent| {
+                let rate = match self.learned(Self::bucket(percent)) {
+                    Some(learned) => learned * scale,
+                    None => live_rate? * taper(percent) / taper(from),
+                };
+                elapsed += 60.0 / rate;
+                Some(elapsed)
+            })
+            .collect()
+    }
+}
+
+/// The projected charge as a sparkline from now to full, green up to the milestone
+#[cfg_attr(not(feature = "tui"), allow(dead_code))] // Only the dashboard shows it
+pub fn curve_line(from: u8, minutes: &[f64], width: usize) -> String {
+    let Some(&total) = minutes.last() else {
+        return String::new();
+    };
+    let bars = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    (1..=width)
+        .map(|column| {
+            let at = total * column as f64 / width as f64;
+            let percent = from as usize + minutes.iter().take_while(|&&m| m <= at + 1e-9).count();
+            let bar = bars[(percent * (bars.len() - 1) + 50) / 100];
+            let color = if percent <= PROJECTION_MILESTONE_PERCENT as usize { "\x1b[32m" } else { "\x1b[33m" };
+            format!("{}{}\x1b[0m", color, bar)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn charging(timestamp: u64, capacity_percent: u8) -> BatteryReading {
+        BatteryReading {
+            timestamp,
+            capacity_percent,
+            energy_now_wh: None,
+            energy_full_wh: None,
+            power_now_w: Some(30.0),
+            voltage_v: Some(12.6),
+            current_ma: Some(2400),
+            status: "Charging".to_string(),
+            temperature_c: None,
+            context: None,
+        }
+    }
+
+    /// A charger doing 60 %/h up to 80%, then half that
+    fn charge_from(curve: &mut ChargeCurve, from: u8) {
+        let mut t = 0;
+        for percent in from..100 {
+            let secs_per_percent = if percent < 80 { 60 } else { 120 };
+            for _ in (0..secs_per_percent).step_by(10) {
+                curve.learn(&charging(t, percent));
+                t += 10;
+            }
+        }
+    }
+
+    #[test]
+    fn learned_taper_shapes_the_projection() {
+        let mut curve = ChargeCurve::new("BAT0");
+        charge_from(&mut curve, 40);
+        assert!((curve.learned(ChargeCurve::bucket(50)).unwrap() - 60.0).abs() < 1e-9);
+        assert!((curve.learned(ChargeCurve::bucket(90)).unwrap() - 30.0).abs() < 1e-9);
+
+        // Same charger: 20 minutes to 80%, 40 more to full
+        let minutes = curve.projection(60, Some(60.0)).unwrap();
+        assert!((minutes[(80 - 60) - 1] - 20.0).abs() < 1e-9);
+        assert!((minutes.last().unwrap() - 60.0).abs() < 1e-9);
+        // Half the speed today: everything takes twice as long
+        let slow = curve.projection(60, Some(30.0)).unwrap();
+        assert!((slow.last().unwrap() - 120.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn unlearned_curve_falls_back_to_the_generic_taper() {
+        let curve = ChargeCurve::new("BAT0");
+        assert_eq!(curve.projection(50, None), None);
+        let minutes = curve.projection(50, Some(60.0)).unwrap();
+        assert_eq!(minutes.len(), 50);
+        // Flat to 80%, slower after
+        assert!((minutes[29] - 30.0).abs() < 1e-9);
+        assert!(minutes[49] - minutes[29] > 20.0);
+        assert!(curve.projection(100, Some(60.0)).unwrap().is_empty());
+    }
+}
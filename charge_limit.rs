@@ -0,0 +1,39 @@
+use std::fs;
+use std::path::Path;
+
+use crate::backend::PowerSupplyBackend;
+
+/// Stop threshold suggested for packs that mostly live on AC
+pub const DEFAULT_CHARGE_LIMIT: u8 = 80;
+
+/// Generic attribute first, then the older ThinkPad/ASUS name
+const THRESHOLD_ATTRS: [&str; 2] = ["charge_control_end_threshold", "charge_stop_threshold"];
+
+fn threshold_path(base_path: &str) -> Option<String> {
+    THRESHOLD_ATTRS
+        .iter()
+        .map(|attr| format!("{}/{}", base_path, attr))
+        .find(|path| Path::new(path).exists())
+}
+
+/// Current charge stop threshold, if the driver supports one
+pub fn read_limit(base_path: &str) -> Option<u8> {
+    let path = threshold_path(base_path)?;
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// `read_limit` through a monitor's backend
+pub fn limit_from(backend: &dyn PowerSupplyBackend) -> Option<u8> {
+    THRESHOLD_ATTRS.iter().find_map(|attr| backend.read_attr(attr))?.parse().ok()
+}
+
+/// Set the charge stop threshold (usually needs root or a udev rule)
+pub fn set_limit(base_path: &str, percent: u8) -> std::io::Result<()> {
+    if !(1..=100).contains(&percent) {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "limit must be between 1 and 100"));
+    }
+    let path = threshold_path(base_path).ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::Unsupported, "this battery has no charge threshold control")
+    })?;
+    fs::write(path, percent.to_string())
+}
@@ -0,0 +1,95 @@
+//! `batfi history --chart`: one metric over time as an SVG, for bug reports and forum posts.
+
+use std::path::Path;
+
+use plotters::prelude::*;
+
+use crate::{timefmt, BatteryReading};
+
+/// Pixel size of the exported chart
+const CHART_SIZE: (u32, u32) = (960, 480);
+/// Spans longer than this get dates on the time axis, not just times of day
+const DATE_LABELS_AFTER_SECS: u64 = 86_400;
+
+/// What `--chart` can plot
+#[derive(Debug, Clone, Copy)]
+pub enum Metric {
+    Power,
+    Capacity,
+    Voltage,
+    Temperature,
+}
+
+impl Metric {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "power" => Some(Metric::Power),
+            "capacity" => Some(Metric::Capacity),
+            "voltage" => Some(Metric::Voltage),
+            "temperature" => Some(Metric::Temperature),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Metric::Power => "Power (W)",
+            Metric::Capacity => "Capacity (%)",
+            Metric::Voltage => "Voltage (V)",
+            Metric::Temperature => "Battery temperature (°C)",
+        }
+    }
+
+    fn value(self, reading: &BatteryReading) -> Option<f64> {
+        match self {
+            Metric::Power => reading.power_now_w,
+            Metric::Capacity => Some(reading.capacity_percent as f64),
+            Metric::Voltage => reading.voltage_v,
+            Metric::Temperature => reading.temperature_c,
+        }
+    }
+}
+
+/// Write `metric` over the readings to `path` as SVG; returns the number of points plotted
+pub fn render_svg(readings: &[BatteryReading], metric: Metric, since: &str, path: &Path) -> Result<usize, String> {
+    let points: Vec<(u64, f64)> = readings.iter().filter_map(|r| Some((r.timestamp, metric.value(r)?))).collect();
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return Err(format!("no {} samples in the last {}", metric.label().to_lowercase(), since));
+    };
+    let (t0, t1) = (first.0, last.0.max(first.0 + 1));
+    let (low, high) = match metric {
+        Metric::Capacity => (0.0, 100.0),
+        _ => {
+            let low = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+            let high = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+            let pad = ((high - low) * 0.1).max(0.5);
+            ((low - pad).max(0.0), high + pad)
+        }
+    };
+    let time_label = |t: &u64| {
+        if t1 - t0 > DATE_LABELS_AFTER_SECS { timefmt::date_time(*t) } else { timefmt::time_of_day(*t) }
+    };
+
+    let root = SVGBackend::new(path, CHART_SIZE).into_drawing_area();
+    let draw = || -> Result<(), Box<dyn std::error::Error>> {
+        root.fill(&WHITE)?;
+        let mut chart = ChartBuilder::on(&root)
+            .caption(format!("batfi · {}, last {}", metric.label(), since), ("sans-serif", 22))
+            .margin(16)
+            .x_label_area_size(36)
+            .y_label_area_size(56)
+            .build_cartesian_2d(t0..t1, low..high)?;
+        chart
+            .configure_mesh()
+            .x_labels(8)
+            .x_label_formatter(&time_label)
+            .y_desc(metric.label())
+            .light_line_style(WHITE.mix(0.0))
+            .draw()?;
+        chart.draw_series(LineSeries::new(points.iter().copied(), RGBColor(41, 128, 185).stroke_width(2)))?;
+        root.present()?;
+        Ok(())
+    };
+    draw().map_err(|e| e.to_string())?;
+    Ok(points.len())
+}
@@ -0,0 +1,943 @@
+use std::fs;
+use std::path::PathBuf;
+
+use clap::{Arg, Command};
+
+use crate::config::{self, config_path, parse_config, FileConfig, Settings, DEFAULT_CONFIG, DEFAULT_NTFY_SERVER};
+use crate::{simulate, timefmt};
+
+/// The full command-line interface; `watch` runs when no subcommand is given
+pub fn build_cli() -> Command {
+    Command::new("batfi")
+        .version("2.0.0")
+        .author("Your Name <your.email@example.com>")
+        .about("Advanced battery monitoring tool with accurate time estimation")
+        .after_help(
+            "Settings are read from the config file (see `batfi config init`), then overridden by\n\
+             BATFI_INTERVAL, BATFI_BATTERY, BATFI_FORMAT, BATFI_FIELDS, BATFI_SEPARATOR,\n\
+             BATFI_UNITS, BATFI_SYSFS_ROOT, BATFI_NO_QUIRKS, BATFI_STATSD and BATFI_NTFY, then by flags.\n\
+             BATFI_CONFIG selects a different config file and BATFI_PROFILE a profile.",
+        )
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .short('c')
+                .value_name("FILE")
+                .help("Read settings from FILE instead of the default config path")
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .short('p')
+                .value_name("NAME")
+                .help("Apply the [profile.NAME] section of the config")
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("interval")
+                .long("interval")
+                .short('i')
+                .value_name("SECS")
+                .help("Seconds between samples [default: 2]")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("run-duration")
+                .long("duration")
+                .value_name("DURATION")
+                .help("Stop watching after DURATION (e.g. 90s, 30m, 8h), or 'forever' [default: forever]")
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("on-exit")
+                .long("on-exit")
+                .value_name("COMMAND")
+                .help("Shell command to run when watching ends, with the session totals in BATFI_* variables")
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("units")
+                .long("units")
+                .value_name("UNITS")
+                .help("Report energy in Wh, or charge in mAh with a current-based ETA")
+                .value_parser(["wh", "mah"])
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("smoothing")
+                .long("smoothing")
+                .value_name("METHOD")
+                .help("Smooth power and temperature with ema[:α], median[:N] or double-ema[:α]")
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("bar")
+                .long("bar")
+                .value_name("MODE")
+                .help("Fill the dashboard bar by capacity percentage or by energy against design capacity")
+                .value_parser(["capacity", "energy"])
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("time-format")
+                .long("time-format")
+                .value_name("STYLE")
+                .help("Remaining time as human (2h 41m), clock (2:41) or minutes (161m)")
+                .value_parser(timefmt::ETA_FORMATS)
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("timestamp-format")
+                .long("timestamp-format")
+                .value_name("STYLE")
+                .help("Timestamps in logs and JSON: unix, iso (local offset) or iso-utc")
+                .value_parser(timefmt::TIMESTAMP_FORMATS)
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("lang")
+                .long("lang")
+                .value_name("LANG")
+                .help("UI language (en, es, de); defaults to LANG")
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .short('j')
+                .help("Output in JSON format")
+                .global(true)
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .help("Print one status bar line per update instead of the dashboard")
+                .value_parser(["polybar", "xmobar", "dzen", "plasma"])
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("fields")
+                .long("fields")
+                .value_name("FIELD,...")
+                .help("Only output these fields, in this order (plain values, JSON keys or `log` CSV columns)")
+                .value_delimiter(',')
+                .global(true)
+                .action(clap::ArgAction::Append),
+        )
+        .arg(
+            Arg::new("separator")
+                .long("separator")
+                .value_name("SEP")
+                .help("Separator between --fields values [default: \" \"]")
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("once")
+                .long("once")
+                .short('o')
+                .help("Take a single sample and exit")
+                .global(true)
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("samples")
+                .long("samples")
+                .value_name("N")
+                .help("One-shot: quietly average N samples before printing (implies --once)")
+                .value_parser(clap::value_parser!(u32).range(1..))
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("warmup")
+                .long("warmup")
+                .value_name("N")
+                .help("Samples taken to prime the estimators before --samples starts counting")
+                .value_parser(clap::value_parser!(u32))
+                .default_value("3")
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("explain")
+                .long("explain")
+                .help("Show which sysfs files, fallbacks and estimator would be used, then exit")
+                .global(true)
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("sysfs-root")
+                .long("sysfs-root")
+                .value_name("DIR")
+                .help("Read power supplies and sensors from DIR instead of /sys (e.g. a recorded tree)")
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("simulate")
+                .long("simulate")
+                .value_name("SCENARIO")
+                .help("Run on a synthetic battery instead of sysfs (same readings every run)")
+                .value_parser(simulate::SCENARIOS)
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("from")
+                .long("from")
+                .value_name("PERCENT")
+                .help("Starting charge of the simulated battery [default: 90, or 20 for charge]")
+                .value_parser(clap::value_parser!(u8).range(0..=100))
+                .requires("simulate")
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("rate")
+                .long("rate")
+                .value_name("POWER")
+                .help("Simulated load, or charger power for charge, e.g. 12W [default: 12W / 45W]")
+                .requires("simulate")
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("speed")
+                .long("speed")
+                .value_name("FACTOR")
+                .help("Simulated seconds per real second [default: 1]")
+                .value_parser(clap::value_parser!(u32).range(1..=3600))
+                .requires("simulate")
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("no-quirks")
+                .long("no-quirks")
+                .help("Don't apply built-in corrections for known firmware quirks")
+                .global(true)
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("script")
+                .long("script")
+                .value_name("FILE")
+                .help("Rhai script whose fields(), alerts() and display() hooks run on every sample")
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("statsd")
+                .long("statsd")
+                .value_name("HOST:PORT")
+                .help("Emit every sample as StatsD/DogStatsD gauges (e.g. localhost:8125)")
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("statsd-prefix")
+                .long("statsd-prefix")
+                .value_name("PREFIX")
+                .help("Metric name prefix for --statsd [default: batfi]")
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("statsd-tag")
+                .long("statsd-tag")
+                .value_name("KEY:VALUE")
+                .help("DogStatsD tag to attach to every metric (repeatable)")
+                .global(true)
+                .action(clap::ArgAction::Append),
+        )
+        .arg(
+            Arg::new("alert")
+                .long("alert")
+                .value_name("NAME:METRIC<OP>VALUE[:SEVERITY][:ACTION]")
+                .help("Alert rule, e.g. low:capacity<=15:warning or dim:capacity<=30:info:dim=50 (repeatable; replaces the defaults)")
+                .global(true)
+                .action(clap::ArgAction::Append),
+        )
+        .arg(
+            Arg::new("webhook")
+                .long("webhook")
+                .value_name("URL")
+                .help("POST a JSON payload to URL when an alert fires (repeatable)")
+                .global(true)
+                .action(clap::ArgAction::Append),
+        )
+        .arg(
+            Arg::new("webhook-template")
+                .long("webhook-template")
+                .value_name("TEMPLATE")
+                .help("Webhook body template with {{message}}, {{severity}}, {{capacity}}, … placeholders")
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("ntfy")
+                .long("ntfy")
+                .value_name("TOPIC")
+                .help("Push alerts to an ntfy topic (priority follows alert severity)")
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("ntfy-server")
+                .long("ntfy-server")
+                .value_name("URL")
+                .help(format!("ntfy server to publish to [default: {}]", DEFAULT_NTFY_SERVER))
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("desktop-notify")
+                .long("desktop-notify")
+                .help("Also show alerts as desktop notifications")
+                .global(true)
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("notify-charged")
+                .long("notify-charged")
+                .value_name("PERCENT")
+                .help("Notify when charging reaches PERCENT (default 100) so you can unplug")
+                .num_args(0..=1)
+                .default_missing_value("100")
+                .value_parser(clap::value_parser!(u8).range(1..=100))
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("remind-every")
+                .long("remind-every")
+                .value_name("DURATION")
+                .help("Repeat the --notify-charged notification while still plugged in (e.g. 15m)")
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("bell")
+                .long("bell")
+                .help("Ring the terminal bell when an alert fires")
+                .global(true)
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("sound")
+                .long("sound")
+                .value_name("FILE")
+                .help("Play a sound file when an alert fires (via paplay, pw-play or aplay)")
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("critical-action")
+                .long("critical-action")
+                .value_name("ACTION")
+                .help("Suspend, hibernate or power off when the battery is nearly empty (after a warning and grace period)")
+                .value_parser(["suspend", "hibernate", "hybrid-sleep", "poweroff", "none"])
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("critical-grace")
+                .long("critical-grace")
+                .value_name("DURATION")
+                .help("Time to plug in before --critical-action runs [default: 60s]")
+                .global(true)
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            Arg::new("battery")
+                .long("battery")
+                .short('b')
+                .value_name("NAME")
+                .help("Battery name, glob (e.g. 'BAT*') or 'all'; repeatable")
+                .global(true)
+                .action(clap::ArgAction::Append),
+        )
+        .subcommand(
+            Command::new("status")
+                .about("Print a one-shot status, from a running server when one is available")
+                .arg(
+                    Arg::new("remote")
+                        .long("remote")
+                        .value_name("ADDR")
+                        .help("Fetch from a running server (local socket, or ADDR for --http servers)")
+                        .num_args(0..=1)
+                        .default_missing_value("")
+                        .conflicts_with("local")
+                        .action(clap::ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("local")
+                        .long("local")
+                        .help("Always read sysfs directly, even if a server is running")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("list")
+                .about("List batteries, AC adapters, peripherals and temperature sensors with a sample reading"),
+        )
+        .subcommand(
+            Command::new("top")
+                .about("Live table of every power supply and temperature sensor, sortable from the keyboard"),
+        )
+        .subcommand(
+            Command::new("watch")
+                .about("Live dashboard with time estimates (the default when no command is given)"),
+        )
+        .subcommand(
+            Command::new("daemon")
+                .about("Sample in the background and answer `batfi status` over the local socket"),
+        )
+        .subcommand(
+            Command::new("query")
+                .about("Print the running daemon's latest reading, history, sensors or events as JSON")
+                .arg(
+                    Arg::new("what")
+                        .value_name("WHAT")
+                        .help("Which endpoint to fetch")
+                        .value_parser(["battery", "history", "sensors", "events"])
+                        .default_value("battery"),
+                )
+                .arg(
+                    Arg::new("since")
+                        .long("since")
+                        .value_name("DURATION")
+                        .help("For history and events: only entries from the last DURATION (e.g. 10m, 2h)")
+                        .action(clap::ArgAction::Set),
+                ),
+        )
+        .subcommand(
+            Command::new("log")
+                .about("Append one CSV row (or JSON line with --json) per sample")
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('O')
+                        .value_name("FILE")
+                        .help("Append to FILE instead of stdout")
+                        .action(clap::ArgAction::Set),
+                ),
+        )
+        .subcommand(
+            Command::new("history")
+                .about("Show recorded capacity, power, voltage and temperature samples (pick a pack with --battery)")
+                .arg(
+                    Arg::new("since")
+                        .long("since")
+                        .value_name("DURATION")
+                        .help("Only samples from the last DURATION (e.g. 10m, 2h, 7d), or since @<Unix seconds> or an RFC 3339 time")
+                        .default_value("1h")
+                        .action(clap::ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("histogram")
+                        .long("histogram")
+                        .help("Bucket discharging samples by power draw instead of listing them")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("chart")
+                        .long("chart")
+                        .value_name("METRIC")
+                        .help("Plot METRIC over time to the --output file as SVG")
+                        .value_parser(["power", "capacity", "voltage", "temperature"])
+                        .requires("output")
+                        .conflicts_with("histogram")
+                        .action(clap::ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Where --chart writes the SVG (e.g. chart.svg)")
+                        .requires("chart")
+                        .action(clap::ArgAction::Set),
+                ),
+        )
+        .subcommand(
+            Command::new("events")
+                .about("Show the event journal: plug changes, charge limits, alerts, throttling, suspend gaps")
+                .arg(
+                    Arg::new("since")
+                        .long("since")
+                        .value_name("DURATION")
+                        .help("Only events from the last DURATION (e.g. 30m, 2h, 7d)")
+                        .default_value("24h")
+                        .action(clap::ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("kind")
+                        .long("kind")
+                        .value_name("KIND")
+                        .help("Only events of KIND (e.g. unplugged, charge-limited, alert); repeatable")
+                        .action(clap::ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("follow")
+                        .long("follow")
+                        .short('f')
+                        .help("Keep running and print each new event as one line (JSON with --json)")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("remote")
+                        .long("remote")
+                        .value_name("ADDR")
+                        .help("Follow a running server's journal (local socket, or ADDR for --http servers)")
+                        .num_args(0..=1)
+                        .default_missing_value("")
+                        .requires("follow")
+                        .action(clap::ArgAction::Set),
+                ),
+        )
+        .subcommand(
+            Command::new("summary")
+                .about("Summarize usage (time on battery, energy, cycles, temperature) from recorded samples")
+                .arg(
+                    Arg::new("period")
+                        .long("period")
+                        .value_name("PERIOD")
+                        .help("Summary window")
+                        .value_parser(["day", "week"])
+                        .default_value("day"),
+                ),
+        )
+        .subcommand(
+            Command::new("snooze")
+                .about("Silence charge-target notifications for a while")
+                .arg(
+                    Arg::new("duration")
+                        .value_name("DURATION")
+                        .help("How long (e.g. 30m, 2h), or 'off' to end a snooze")
+                        .default_value("1h"),
+                ),
+        )
+        .subcommand(
+            Command::new("report")
+                .about("Write a shareable health report")
+                .arg(
+                    Arg::new("kind")
+                        .value_name("FORMAT")
+                        .help("Report format")
+                        .value_parser(["md", "html"])
+                        .default_value("html"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('O')
+                        .value_name("FILE")
+                        .help("Write the report to FILE instead of stdout")
+                        .action(clap::ArgAction::Set),
+                ),
+        )
+        .subcommand(
+            Command::new("health")
+                .about("Show battery health: capacity wear, cycles, age and an overall grade")
+                .arg(
+                    Arg::new("export")
+                        .long("export")
+                        .value_name("FORMAT")
+                        .help("Export a shareable health report")
+                        .value_parser(["md", "html"])
+                        .action(clap::ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('O')
+                        .value_name("FILE")
+                        .help("Write the exported report to FILE instead of stdout")
+                        .requires("export")
+                        .action(clap::ArgAction::Set),
+                ),
+        )
+        .subcommand(
+            Command::new("doctor")
+                .about("Check which battery attributes, sensors and integrations are available"),
+        )
+        .subcommand(
+            Command::new("advise")
+                .about("Measure for a few seconds and suggest ranked, quantified ways to save power"),
+        )
+        .subcommand(
+            Command::new("bench-self")
+                .about("Time back-to-back samples on this machine and report the per-tick CPU cost")
+                .hide(true)
+                .arg(
+                    Arg::new("ticks")
+                        .long("ticks")
+                        .value_name("N")
+                        .help("Samples to time")
+                        .default_value("50")
+                        .value_parser(clap::value_parser!(u32).range(1..))
+                        .action(clap::ArgAction::Set),
+                ),
+        )
+        .subcommand(
+            Command::new("eval")
+                .about("Replay recorded discharge sessions and score each ETA estimator's accuracy")
+                .arg(
+                    Arg::new("sessions")
+                        .value_name("FILE")
+                        .help("Recordings from `batfi --json log` or the usage log (one JSON reading per line)")
+                        .num_args(1..)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("raw")
+                .about("Dump every readable sysfs attribute of a power supply, raw and with units applied")
+                .arg(
+                    Arg::new("device")
+                        .value_name("DEVICE")
+                        .help("Power supply to dump, e.g. BAT0 or AC (default: the selected battery)"),
+                ),
+        )
+        .subcommand(
+            Command::new("limit")
+                .about("Show or set the charge stop threshold")
+                .arg(
+                    Arg::new("percent")
+                        .value_name("PERCENT|off")
+                        .help("New threshold (1-100), or 'off' to charge fully"),
+                ),
+        )
+        .subcommand(
+            Command::new("serve")
+                .about("Sample continuously and serve readings over an HTTP REST API")
+                .arg(
+                    Arg::new("http")
+                        .long("http")
+                        .value_name("ADDR")
+                        .help("Also serve over TCP on ADDR (e.g. 127.0.0.1:8080)")
+                        .action(clap::ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("prometheus")
+                        .long("prometheus")
+                        .value_name("ADDR")
+                        .help("Also serve Prometheus metrics at http://ADDR/metrics (e.g. 0.0.0.0:9101)")
+                        .action(clap::ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("dbus")
+                        .long("dbus")
+                        .help("Also publish ETA, power and health on the session bus (org.batfi.Battery)")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("rpc")
+                .about("Speak JSON-RPC 2.0 on stdin/stdout, for editors and apps that run batfi as a child process"),
+        )
+        .subcommand(
+            Command::new("fleet")
+                .about("Aggregate battery status from several machines")
+                .arg(
+                    Arg::new("listen")
+                        .long("listen")
+                        .value_name("ADDR")
+                        .help("Run the aggregator and accept reports on ADDR (default 127.0.0.1:9123; pass e.g. 0.0.0.0:9123 to accept other machines)")
+                        .num_args(0..=1)
+                        .default_missing_value(crate::fleet::DEFAULT_LISTEN)
+                        .conflicts_with("push")
+                        .action(clap::ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("push")
+                        .long("push")
+                        .value_name("ADDR")
+                        .help("Report this machine's battery to the aggregator at ADDR")
+                        .action(clap::ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("name")
+                        .long("name")
+                        .value_name("HOST")
+                        .help("Name to report as (defaults to the hostname)")
+                        .requires("push")
+                        .action(clap::ArgAction::Set),
+                ),
+        )
+        .subcommand(
+            Command::new("menu")
+                .about("Print stats and actions for rofi/wofi; run the action given as SELECTION")
+                .after_help("Script mode: rofi -show batfi -modi batfi:'batfi menu'\nDmenu mode: batfi menu | rofi -dmenu | xargs -r -d '\\n' batfi menu")
+                .arg(Arg::new("selection").value_name("SELECTION").help("The chosen menu line")),
+        )
+        .subcommand(
+            Command::new("install-service")
+                .about("Install and enable a systemd unit running `batfi daemon`")
+                .arg(
+                    Arg::new("user")
+                        .long("user")
+                        .help("Install a user unit instead of a system-wide one")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("log-every")
+                        .long("log-every")
+                        .value_name("DURATION")
+                        .help("Also install a timer that appends a JSON sample every DURATION (e.g. 5m)")
+                        .action(clap::ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("sleep-hook")
+                        .long("sleep-hook")
+                        .help("Also install a systemd-sleep hook that records standby drain at every suspend")
+                        .conflicts_with("user")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("print")
+                        .long("print")
+                        .help("Print the units instead of installing them")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("sleep-hook")
+                .about("Record the battery around a suspend; run by systemd-sleep")
+                .hide(true)
+                .arg(
+                    Arg::new("phase")
+                        .value_name("PHASE")
+                        .value_parser(["pre", "post"])
+                        .required(true),
+                )
+                .arg(Arg::new("kind").value_name("KIND").help("suspend, hibernate, hybrid-sleep or suspend-then-hibernate")),
+        )
+        .subcommand(
+            Command::new("uninstall-service")
+                .about("Stop, disable and remove the units and hook written by install-service")
+                .arg(
+                    Arg::new("user")
+                        .long("user")
+                        .help("Remove the user units instead of the system-wide ones")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("tray")
+                .about("Show a battery icon with percentage in the system tray"),
+        )
+        .subcommand(
+            Command::new("config")
+                .about("Create or check the config file")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("init")
+                        .about("Write a commented default config to the config path")
+                        .arg(
+                            Arg::new("force")
+                                .long("force")
+                                .help("Overwrite an existing config")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("validate")
+                        .about("Check a config file and print the effective merged settings")
+                        .arg(Arg::new("file").value_name("FILE").help("Config to check (defaults to the active one)")),
+                ),
+        )
+}
+
+/// Command-line flags, the last layer over the config file and environment
+fn apply_flags(settings: &mut Settings, matches: &clap::ArgMatches) {
+    let many = |id: &str| matches.get_many::<String>(id).map(|values| values.cloned().collect::<Vec<_>>());
+    let one = |id: &str| matches.get_one::<String>(id).cloned();
+
+    settings.lang = one("lang").or(settings.lang.take());
+    if let Some(interval) = matches.get_one::<u64>("interval") {
+        settings.interval = *interval;
+    }
+    if let Some(duration) = one("run-duration") {
+        settings.duration = duration;
+    }
+    settings.on_exit = one("on-exit").or(settings.on_exit.take());
+    if let Some(units) = one("units") {
+        settings.units = units;
+    }
+    if let Some(smoothing) = one("smoothing") {
+        settings.smoothing = smoothing;
+    }
+    if let Some(bar) = one("bar") {
+        settings.bar = bar;
+    }
+    if let Some(format) = one("time-format") {
+        settings.time_format = format;
+    }
+    if let Some(format) = one("timestamp-format") {
+        settings.timestamp_format = format;
+    }
+    if let Some(battery) = many("battery") {
+        settings.battery = battery;
+    }
+    if let Some(root) = one("sysfs-root") {
+        settings.sysfs_root = root;
+    }
+    settings.format = one("format").or(settings.format.take());
+    settings.fields = many("fields").or(settings.fields.take());
+    if let Some(separator) = one("separator") {
+        settings.separator = separator;
+    }
+    if matches.get_flag("no-quirks") {
+        settings.no_quirks = true;
+    }
+    settings.script = one("script").or(settings.script.take());
+
+    settings.statsd.address = one("statsd").or(settings.statsd.address.take());
+    if let Some(prefix) = one("statsd-prefix") {
+        settings.statsd.prefix = prefix;
+    }
+    if let Some(tags) = many("statsd-tag") {
+        settings.statsd.tags = tags;
+    }
+
+    if let Some(rules) = many("alert") {
+        settings.alerts.rules = rules;
+    }
+    if let Some(webhooks) = many("webhook") {
+        settings.alerts.webhooks = webhooks;
+    }
+    settings.alerts.webhook_template = one("webhook-template").or(settings.alerts.webhook_template.take());
+    settings.alerts.ntfy_topic = one("ntfy").or(settings.alerts.ntfy_topic.take());
+    if let Some(server) = one("ntfy-server") {
+        settings.alerts.ntfy_server = server;
+    }
+    if matches.get_flag("desktop-notify") {
+        settings.alerts.desktop = true;
+    }
+    if let Some(target) = matches.get_one::<u8>("notify-charged") {
+        settings.alerts.charge_target = Some(*target);
+    }
+    settings.alerts.charge_reminder = one("remind-every").or(settings.alerts.charge_reminder.take());
+    if matches.get_flag("bell") {
+        settings.alerts.bell = true;
+    }
+    settings.alerts.sound = one("sound").or(settings.alerts.sound.take());
+    if let Some(action) = one("critical-action") {
+        settings.alerts.critical_action = (action != "none").then_some(action);
+    }
+    settings.alerts.critical_grace = one("critical-grace").or(settings.alerts.critical_grace.take());
+}
+
+/// The file given with --config, else the default path if it exists
+pub fn selected_config(matches: &clap::ArgMatches) -> Option<PathBuf> {
+    match matches.get_one::<String>("config") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => config_path().filter(|path| path.exists()),
+    }
+}
+
+/// Every configuration layer, flags last
+pub fn resolve(file: Option<FileConfig>, matches: &clap::ArgMatches) -> Settings {
+    let mut settings = config::resolve(file, matches.get_one::<String>("profile").cloned());
+    apply_flags(&mut settings, matches);
+    settings
+}
+
+/// Load and merge all configuration layers, exiting on a broken config file
+pub fn load_settings(matches: &clap::ArgMatches) -> Settings {
+    let file = selected_config(matches).map(|path| {
+        parse_config(&path).unwrap_or_else(|e| {
+            eprintln!("❌ {}", e);
+            eprintln!("   Run `batfi config validate` for details");
+            std::process::exit(1);
+        })
+    });
+    let settings = resolve(file, matches);
+    if let Some(problem) = settings.problems().first() {
+        eprintln!("❌ Invalid setting {}", problem);
+        std::process::exit(1);
+    }
+    config::activate(&settings);
+    settings
+}
+
+/// `batfi config init`: write the commented default config
+fn run_init(force: bool) {
+    let Some(path) = config_path() else {
+        eprintln!("❌ Could not determine the config directory (HOME unset)");
+        std::process::exit(1);
+    };
+    if path.exists() && !force {
+        eprintln!("❌ {} already exists (use --force to overwrite)", path.display());
+        std::process::exit(1);
+    }
+    let written = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&path, DEFAULT_CONFIG));
+    if let Err(e) = written {
+        eprintln!("❌ Could not write {}: {}", path.display(), e);
+        std::process::exit(1);
+    }
+    println!("✅ Wrote default config to {}", path.display());
+}
+
+/// `batfi config validate [FILE]`: report errors and print the effective settings
+fn run_validate(file: Option<&String>, matches: &clap::ArgMatches) {
+    let path = file.map(PathBuf::from).or_else(|| selected_config(matches));
+    let parsed = match &path {
+        Some(path) => match parse_config(path) {
+            Ok(config) => Some(config),
+            Err(e) => {
+                eprintln!("❌ {}", e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let settings = resolve(parsed, matches);
+    let problems = settings.problems();
+    for problem in &problems {
+        eprintln!("❌ {}", problem);
+    }
+    match &path {
+        Some(path) if problems.is_empty() => println!("✅ {} is valid", path.display()),
+        Some(_) => {}
+        None => println!("ℹ️  No config file found; showing built-in defaults"),
+    }
+
+    println!();
+    println!("# Effective settings (defaults + file + env + flags)");
+    print!("{}", toml::to_string_pretty(&settings).unwrap_or_default());
+    if !problems.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+/// `batfi config ...`
+pub fn run_config(config_matches: &clap::ArgMatches, matches: &clap::ArgMatches) {
+    match config_matches.subcommand() {
+        Some(("init", init_matches)) => run_init(init_matches.get_flag("force")),
+        Some(("validate", validate_matches)) => run_validate(validate_matches.get_one::<String>("file"), matches),
+        _ => unreachable!("subcommand_required"),
+    }
+}
@@ -0,0 +1,82 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+
+use crate::server::socket_path;
+
+const CLIENT_TIMEOUT_SECS: u64 = 5;
+
+/// Where a running batfi server can be reached
+#[derive(Debug, Clone)]
+pub enum Remote {
+    /// The per-user Unix socket of `batfi serve`
+    Socket,
+    /// A TCP address of `batfi serve --http`
+    Http(String),
+}
+
+/// Send a request and return the response body, failing on non-200 responses
+fn send<S: Read + Write>(mut stream: S, method: &str, path: &str, body: &str) -> std::io::Result<String> {
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        method,
+        path,
+        body.len(),
+        body
+    )?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "malformed response"))?;
+    let status = head.lines().next().unwrap_or_default();
+    if !status.contains(" 200 ") {
+        return Err(std::io::Error::other(format!("{} ({})", status.trim(), body.trim())));
+    }
+    Ok(body.to_string())
+}
+
+fn request<S: Read + Write>(stream: S, path: &str) -> std::io::Result<String> {
+    send(stream, "GET", path, "")
+}
+
+/// Fetch a path from the local server over its Unix socket
+pub fn fetch_local(path: &str) -> std::io::Result<String> {
+    let stream = UnixStream::connect(socket_path()?)?;
+    stream.set_read_timeout(Some(Duration::from_secs(CLIENT_TIMEOUT_SECS)))?;
+    request(stream, path)
+}
+
+pub fn fetch(remote: &Remote, path: &str) -> std::io::Result<String> {
+    match remote {
+        Remote::Socket => fetch_local(path),
+        Remote::Http(addr) => {
+            let stream = TcpStream::connect(addr)?;
+            stream.set_read_timeout(Some(Duration::from_secs(CLIENT_TIMEOUT_SECS)))?;
+            request(stream, path)
+        }
+    }
+}
+
+/// Fetch and decode a JSON endpoint
+pub fn fetch_json<T: DeserializeOwned>(remote: &Remote, path: &str) -> std::io::Result<T> {
+    let body = fetch(remote, path)?;
+    serde_json::from_str(&body).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// POST a JSON body to a TCP address
+pub fn post_json(addr: &str, path: &str, body: &str) -> std::io::Result<String> {
+    let stream = TcpStream::connect(addr)?;
+    stream.set_read_timeout(Some(Duration::from_secs(CLIENT_TIMEOUT_SECS)))?;
+    send(stream, "POST", path, body)
+}
+
+/// Whether a local server is listening on the per-user socket
+pub fn daemon_available() -> bool {
+    socket_path().is_ok_and(|path| path.exists() && UnixStream::connect(path).is_ok())
+}
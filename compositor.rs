@@ -0,0 +1,151 @@
+use std::env;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+const IPC_TIMEOUT_MS: u64 = 500;
+/// i3/sway IPC message type for GET_OUTPUTS
+const SWAY_GET_OUTPUTS: u32 = 3;
+const SWAY_MAGIC: &[u8] = b"i3-ipc";
+
+/// What the machine was doing when a sample was taken; drain differs hugely between these
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageContext {
+    /// At least one external monitor is lit
+    Docked,
+    /// Only the built-in panel is lit
+    Mobile,
+    /// Every display is off (DPMS), i.e. the session is idle
+    Idle,
+}
+
+impl UsageContext {
+    pub const ALL: [UsageContext; 3] = [UsageContext::Docked, UsageContext::Mobile, UsageContext::Idle];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            UsageContext::Docked => "docked",
+            UsageContext::Mobile => "on the couch",
+            UsageContext::Idle => "idle",
+        }
+    }
+}
+
+/// A compositor whose IPC socket we can query
+#[derive(Debug, Clone)]
+pub enum Compositor {
+    Sway(PathBuf),
+    Hyprland(PathBuf),
+}
+
+struct OutputState {
+    name: String,
+    lit: bool,
+}
+
+impl Compositor {
+    /// Find the running compositor from the session environment
+    pub fn detect() -> Option<Self> {
+        if let Some(path) = env::var_os("SWAYSOCK") {
+            return Some(Compositor::Sway(PathBuf::from(path)));
+        }
+        let signature = env::var("HYPRLAND_INSTANCE_SIGNATURE").ok()?;
+        // Hyprland moved its sockets from /tmp/hypr to the runtime dir in 0.40
+        let runtime = env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".to_string());
+        [format!("{}/hypr/{}/.socket.sock", runtime, signature), format!("/tmp/hypr/{}/.socket.sock", signature)]
+            .into_iter()
+            .map(PathBuf::from)
+            .find(|path| path.exists())
+            .map(Compositor::Hyprland)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compositor::Sway(_) => "sway",
+            Compositor::Hyprland(_) => "Hyprland",
+        }
+    }
+
+    /// Classify the current display state; None if the IPC query fails
+    pub fn query_context(&self) -> Option<UsageContext> {
+        let outputs = match self {
+            Compositor::Sway(path) => sway_outputs(path)?,
+            Compositor::Hyprland(path) => hyprland_monitors(path)?,
+        };
+        let lit: Vec<&OutputState> = outputs.iter().filter(|o| o.lit).collect();
+        if lit.is_empty() {
+            Some(UsageContext::Idle)
+        } else if lit.iter().any(|o| !is_internal_panel(&o.name)) {
+            Some(UsageContext::Docked)
+        } else {
+            Some(UsageContext::Mobile)
+        }
+    }
+}
+
+fn is_internal_panel(name: &str) -> bool {
+    ["eDP", "LVDS", "DSI"].iter().any(|prefix| name.starts_with(prefix))
+}
+
+fn connect(path: &PathBuf) -> Option<UnixStream> {
+    let stream = UnixStream::connect(path).ok()?;
+    let timeout = Some(Duration::from_millis(IPC_TIMEOUT_MS));
+    stream.set_read_timeout(timeout).ok()?;
+    stream.set_write_timeout(timeout).ok()?;
+    Some(stream)
+}
+
+fn sway_outputs(path: &PathBuf) -> Option<Vec<OutputState>> {
+    let mut stream = connect(path)?;
+    let mut message = SWAY_MAGIC.to_vec();
+    message.extend_from_slice(&0u32.to_ne_bytes());
+    message.extend_from_slice(&SWAY_GET_OUTPUTS.to_ne_bytes());
+    stream.write_all(&message).ok()?;
+
+    let mut header = [0u8; 14];
+    stream.read_exact(&mut header).ok()?;
+    if &header[..6] != SWAY_MAGIC {
+        return None;
+    }
+    let length = u32::from_ne_bytes(header[6..10].try_into().ok()?) as usize;
+    let mut payload = vec![0u8; length];
+    stream.read_exact(&mut payload).ok()?;
+
+    let outputs: Vec<serde_json::Value> = serde_json::from_slice(&payload).ok()?;
+    Some(
+        outputs
+            .iter()
+            .map(|output| {
+                let active = output["active"].as_bool().unwrap_or(false);
+                // sway 1.8 renamed "dpms" to "power"
+                let powered = output["power"].as_bool().or(output["dpms"].as_bool()).unwrap_or(true);
+                OutputState {
+                    name: output["name"].as_str().unwrap_or_default().to_string(),
+                    lit: active && powered,
+                }
+            })
+            .collect(),
+    )
+}
+
+fn hyprland_monitors(path: &PathBuf) -> Option<Vec<OutputState>> {
+    let mut stream = connect(path)?;
+    stream.write_all(b"j/monitors").ok()?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).ok()?;
+
+    let monitors: Vec<serde_json::Value> = serde_json::from_str(&response).ok()?;
+    Some(
+        monitors
+            .iter()
+            .map(|monitor| OutputState {
+                name: monitor["name"].as_str().unwrap_or_default().to_string(),
+                lit: monitor["dpmsStatus"].as_bool().unwrap_or(true) && !monitor["disabled"].as_bool().unwrap_or(false),
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,813 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{alerts, fields, timefmt};
+
+/// Written by `batfi config init`; everything is commented out so the defaults apply
+pub const DEFAULT_CONFIG: &str = r#"# batfi configuration
+#
+# Precedence, lowest first: built-in defaults, this file, BATFI_* environment
+# variables, command-line flags. Check the result with `batfi config validate`.
+
+# Seconds between samples
+# interval = 2
+
+# Stop watching after this long ("90s", "30m", "8h"), or "forever"
+# duration = "forever"
+
+# Shell command run when `watch` ends, with BATFI_DURATION_SECS, BATFI_SAMPLES,
+# BATFI_ENERGY_USED_WH, BATFI_AVG_POWER_W and BATFI_CAPACITY set
+# on_exit = "notify-send batfi \"Used $BATFI_ENERGY_USED_WH Wh\""
+
+# How far back readings are kept for the rate fits, and the span of the rolling power
+# average behind the ETA; both become sample counts for the interval in use
+# history = "10m"
+# rolling_window = "20s"
+
+# How power, current and temperature are smoothed: "ema:0.25" (α, higher follows
+# changes faster), "median:15" (last N readings, ignores bursts) or "double-ema"
+# (steadiest, most lag)
+# smoothing = "ema:0.25"
+
+# Where sysfs is mounted (point at a copied tree to replay another machine)
+# sysfs_root = "/sys"
+
+# Show capacity in Wh ("wh") or, for charge_*-reporting packs, mAh with a
+# current-based time estimate ("mah")
+# units = "wh"
+
+# Dashboard bar: "capacity" fills by percentage, "energy" by energy left against
+# the design capacity, so a worn pack never shows a full bar
+# bar = "capacity"
+
+# Remaining time as "human" (2h 41m), "clock" (2:41) or "minutes" (161m)
+# time_format = "human"
+
+# Timestamps in logs and JSON: "unix", "iso" (local offset) or "iso-utc"
+# timestamp_format = "unix"
+
+# UI language: en, es or de (default: from LANG)
+# lang = "de"
+
+# Batteries to monitor: names, globs like "BAT*", or "all" (default: first found)
+# battery = ["BAT0"]
+
+# Single-line output for status bars: polybar, xmobar, dzen or plasma
+# format = "polybar"
+
+# Only output these fields (plain values, JSON keys or `batfi log` CSV columns)
+# fields = ["capacity", "time_remaining", "power_w"]
+# separator = " "
+
+# Read raw sysfs values without corrections for known firmware quirks
+# no_quirks = false
+
+# Pac-Cat and countdown in the dashboard
+# animations = true
+
+# Histogram of discharging power in the dashboard, to tell idle from load at a glance
+# histogram = false
+
+# Devices that fired most interrupts and wakeups while on battery, in the dashboard;
+# wakeup sources need a readable /sys/kernel/debug (usually root)
+# wakeups = false
+
+# Rhai script whose fields(info), alerts(info) and display(info) run on every sample
+# script = "/home/me/.config/batfi/hooks.rhai"
+
+[statsd]
+# address = "localhost:8125"
+# prefix = "batfi"
+# tags = ["env:laptop"]
+
+[alerts]
+# NAME:METRIC<OP>VALUE[:SEVERITY]; replaces the built-in low/critical/overheat rules
+# rules = ["low:capacity<=15:warning", "hot:temperature>=50:critical"]
+# webhooks = ["https://example.com/hook"]
+# webhook_template = '{"text": "{{host}}: {{message}}"}'
+# ntfy_topic = "my-laptop"
+# ntfy_server = "https://ntfy.sh"
+# Desktop notifications through the session bus
+# desktop = true
+# Also notify when the charger is plugged in or out (debounced against flaky connectors)
+# plug = true
+# Notify once charging reaches this percentage, so you can unplug; skipped when a
+# hardware charge limit already stops there. Repeat every charge_reminder while plugged in.
+# charge_target = 80
+# charge_reminder = "15m"
+# Audible alerts for a bare TTY or a kiosk without a notification daemon: ring the
+# terminal bell and/or play a sound file (player defaults to paplay, pw-play or aplay)
+# bell = true
+# sound = "/usr/share/sounds/freedesktop/stereo/dialog-warning.oga"
+# sound_player = "paplay"
+# Only beep for alerts at least this severe (info, warning, critical)
+# audible_severity = "warning"
+# When discharging reaches critical_action_percent: take a logind delay lock, warn,
+# wait critical_grace for the charger, then suspend, hibernate, hybrid-sleep or poweroff
+# critical_action = "hibernate"
+# critical_action_percent = 5
+# critical_grace = "60s"
+# Rules ending in :dim=PERCENT lower the backlight (restored on AC), e.g.
+# rules = ["dim:capacity<=30:info:dim=50", "dimmer:capacity<=15:warning:dim=25"]
+# Device under /sys/class/backlight (default: the first one), or a command to run
+# instead of writing sysfs, with {percent} replaced by the level
+# backlight = "intel_backlight"
+# backlight_command = "brightnessctl set {percent}%"
+
+[thresholds]
+# Capacity bands for the bar, tray and status bar colors; the built-in low and
+# critical alerts fire at capacity_warn and capacity_crit
+# capacity_crit = 15
+# capacity_warn = 30
+# capacity_high = 80
+# Temperatures in °C; battery_temp_hot also drives the built-in overheat alert
+# battery_temp_warn = 45
+# battery_temp_hot = 55
+# cpu_temp_warn = 75
+# cpu_temp_hot = 85
+# Sensors reading outside this range (°C) are treated as broken and skipped
+# temp_valid_min = 10
+# temp_valid_max = 110
+
+[nicknames]
+# Friendly labels for kernel device names, shown in the dashboard, `batfi list`,
+# JSON output and logs ("nickname") and alerts
+# BAT0 = "Internal"
+# hidpp_battery_3 = "MX Master"
+
+# Named profiles override any of the settings above; pick one with `batfi -p NAME`
+# [profile.debug]
+# interval = 1
+# animations = false
+#
+# [profile.minimal]
+# interval = 10
+# format = "polybar"
+#
+# [profile.presentation]
+# animations = false
+# [profile.presentation.alerts]
+# webhooks = []
+# ntfy_topic = ""
+"#;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileStatsd {
+    pub address: Option<String>,
+    pub prefix: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileAlerts {
+    pub rules: Option<Vec<String>>,
+    pub webhooks: Option<Vec<String>>,
+    pub webhook_template: Option<String>,
+    pub ntfy_topic: Option<String>,
+    pub ntfy_server: Option<String>,
+    pub desktop: Option<bool>,
+    pub plug: Option<bool>,
+    pub charge_target: Option<u8>,
+    pub charge_reminder: Option<String>,
+    pub bell: Option<bool>,
+    pub sound: Option<String>,
+    pub sound_player: Option<String>,
+    pub audible_severity: Option<String>,
+    pub critical_action: Option<String>,
+    pub critical_action_percent: Option<u8>,
+    pub critical_grace: Option<String>,
+    pub backlight: Option<String>,
+    pub backlight_command: Option<String>,
+}
+
+/// The config file as written; every key is optional
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileConfig {
+    pub interval: Option<u64>,
+    pub duration: Option<String>,
+    pub on_exit: Option<String>,
+    pub history: Option<String>,
+    pub rolling_window: Option<String>,
+    pub smoothing: Option<String>,
+    pub sysfs_root: Option<String>,
+    pub lang: Option<String>,
+    pub units: Option<String>,
+    pub bar: Option<String>,
+    pub time_format: Option<String>,
+    pub timestamp_format: Option<String>,
+    pub battery: Option<Vec<String>>,
+    pub format: Option<String>,
+    pub fields: Option<Vec<String>>,
+    pub separator: Option<String>,
+    pub no_quirks: Option<bool>,
+    pub animations: Option<bool>,
+    pub histogram: Option<bool>,
+    pub wakeups: Option<bool>,
+    pub script: Option<String>,
+    pub statsd: FileStatsd,
+    pub alerts: FileAlerts,
+    pub thresholds: FileThresholds,
+    pub nicknames: BTreeMap<String, String>,
+    pub profile: BTreeMap<String, FileConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileThresholds {
+    pub capacity_crit: Option<u8>,
+    pub capacity_warn: Option<u8>,
+    pub capacity_high: Option<u8>,
+    pub battery_temp_warn: Option<f64>,
+    pub battery_temp_hot: Option<f64>,
+    pub cpu_temp_warn: Option<f64>,
+    pub cpu_temp_hot: Option<f64>,
+    pub temp_valid_min: Option<f64>,
+    pub temp_valid_max: Option<f64>,
+}
+
+/// Color and default-alert cutoffs; a value at a cutoff belongs to the lower band
+#[derive(Debug, Clone, Serialize)]
+pub struct Thresholds {
+    pub capacity_crit: u8,
+    pub capacity_warn: u8,
+    pub capacity_high: u8,
+    pub battery_temp_warn: f64,
+    pub battery_temp_hot: f64,
+    pub cpu_temp_warn: f64,
+    pub cpu_temp_hot: f64,
+    /// Plausible sensor readings (°C); anything outside means a broken or virtual sensor
+    pub temp_valid_min: f64,
+    pub temp_valid_max: f64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            capacity_crit: 15,
+            capacity_warn: 30,
+            capacity_high: 80,
+            battery_temp_warn: 45.0,
+            battery_temp_hot: 55.0,
+            cpu_temp_warn: 75.0,
+            cpu_temp_hot: 85.0,
+            temp_valid_min: 10.0,
+            temp_valid_max: 110.0,
+        }
+    }
+}
+
+static THRESHOLDS: OnceLock<Thresholds> = OnceLock::new();
+static NICKNAMES: OnceLock<BTreeMap<String, String>> = OnceLock::new();
+
+/// Thresholds from the loaded settings, or the defaults before they are loaded
+pub fn thresholds() -> &'static Thresholds {
+    THRESHOLDS.get_or_init(Thresholds::default)
+}
+
+/// The configured label for a device (e.g. "BAT0"), if any
+pub fn nickname(device: &str) -> Option<&'static str> {
+    NICKNAMES.get()?.get(device).map(String::as_str)
+}
+
+/// The label for a device where people read it: its nickname, else the kernel name
+pub fn display_name(device: &str) -> &str {
+    nickname(device).unwrap_or(device)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatsdSettings {
+    pub address: Option<String>,
+    pub prefix: String,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertSettings {
+    /// Empty means the built-in rules
+    pub rules: Vec<String>,
+    pub webhooks: Vec<String>,
+    pub webhook_template: Option<String>,
+    pub ntfy_topic: Option<String>,
+    pub ntfy_server: String,
+    pub desktop: bool,
+    /// Plug and unplug changes go to the channels as info alerts
+    pub plug: bool,
+    /// Percentage to announce while charging, None to stay quiet
+    pub charge_target: Option<u8>,
+    /// Repeat the charge-target notification at this interval (a duration like "15m")
+    pub charge_reminder: Option<String>,
+    pub bell: bool,
+    /// Sound file played on alerts
+    pub sound: Option<String>,
+    /// Command the sound file is passed to, None to pick an installed one
+    pub sound_player: Option<String>,
+    /// Least severe alert the bell and sound react to
+    pub audible_severity: String,
+    /// One of critical::ACTIONS, None to never act
+    pub critical_action: Option<String>,
+    pub critical_action_percent: u8,
+    /// Time to plug in before the action runs (a duration like "60s")
+    pub critical_grace: Option<String>,
+    /// /sys/class/backlight device for `dim=` actions, None for the first one
+    pub backlight: Option<String>,
+    pub backlight_command: Option<String>,
+}
+
+/// Effective settings after merging defaults, config file, environment and flags
+#[derive(Debug, Clone, Serialize)]
+pub struct Settings {
+    /// The [profile.NAME] applied on top of the file
+    pub profile: Option<String>,
+    /// Seconds between samples
+    pub interval: u64,
+    /// How long `watch` runs, as a duration ("30m") or "forever"
+    pub duration: String,
+    /// Shell command run when `watch` ends
+    pub on_exit: Option<String>,
+    /// How far back the reading history reaches, as a duration ("10m")
+    pub history: String,
+    /// Span of the rolling power average ("20s")
+    pub rolling_window: String,
+    /// Smoother for power, current and temperature ("ema:0.25", "median:15", "double-ema")
+    pub smoothing: String,
+    pub sysfs_root: String,
+    /// UI language; None follows LC_ALL / LC_MESSAGES / LANG
+    pub lang: Option<String>,
+    /// "wh" (energy) or "mah" (charge and current)
+    pub units: String,
+    /// Dashboard bar fill: "capacity" (percentage) or "energy" (against design capacity)
+    pub bar: String,
+    /// ETA style: human, clock or minutes
+    pub time_format: String,
+    /// Log and JSON timestamps: unix, iso or iso-utc
+    pub timestamp_format: String,
+    /// Empty means the first battery found
+    pub battery: Vec<String>,
+    pub format: Option<String>,
+    pub fields: Option<Vec<String>>,
+    pub separator: String,
+    pub no_quirks: bool,
+    pub animations: bool,
+    pub histogram: bool,
+    pub wakeups: bool,
+    /// Rhai hook script run on every sample
+    pub script: Option<String>,
+    pub statsd: StatsdSettings,
+    pub alerts: AlertSettings,
+    pub thresholds: Thresholds,
+    /// Device name → friendly label
+    pub nicknames: BTreeMap<String, String>,
+    /// Problems found while merging: unparsable BATFI_* variables, unknown profiles
+    #[serde(skip)]
+    merge_errors: Vec<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            profile: None,
+            interval: crate::UPDATE_INTERVAL_SECS,
+            duration: "forever".to_string(),
+            on_exit: None,
+            history: "10m".to_string(),
+            rolling_window: "20s".to_string(),
+            smoothing: crate::estimation::Smoothing::default().to_string(),
+            sysfs_root: "/sys".to_string(),
+            lang: None,
+            units: "wh".to_string(),
+            bar: "capacity".to_string(),
+            time_format: "human".to_string(),
+            timestamp_format: "unix".to_string(),
+            battery: Vec::new(),
+            format: None,
+            fields: None,
+            separator: " ".to_string(),
+            no_quirks: false,
+            animations: true,
+            histogram: false,
+            wakeups: false,
+            script: None,
+            statsd: StatsdSettings {
+                address: None,
+                prefix: "batfi".to_string(),
+                tags: Vec::new(),
+            },
+            alerts: AlertSettings {
+                rules: Vec::new(),
+                webhooks: Vec::new(),
+                webhook_template: None,
+                ntfy_topic: None,
+                ntfy_server: DEFAULT_NTFY_SERVER.to_string(),
+                desktop: false,
+                plug: false,
+                charge_target: None,
+                charge_reminder: None,
+                bell: false,
+                sound: None,
+                sound_player: None,
+                audible_severity: "info".to_string(),
+                critical_action: None,
+                critical_action_percent: crate::critical::DEFAULT_PERCENT,
+                critical_grace: None,
+                backlight: None,
+                backlight_command: None,
+            },
+            thresholds: Thresholds::default(),
+            nicknames: BTreeMap::new(),
+            merge_errors: Vec::new(),
+        }
+    }
+}
+
+/// Where ntfy pushes go unless `ntfy_server` says otherwise
+pub const DEFAULT_NTFY_SERVER: &str = "https://ntfy.sh";
+
+/// Output formats accepted by --format and `format =`
+pub const FORMATS: [&str; 4] = ["polybar", "xmobar", "dzen", "plasma"];
+
+fn split_list(value: &str) -> Vec<String> {
+    value.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect()
+}
+
+impl Settings {
+    /// `duration` in seconds, None for "forever"; validation rejects what doesn't parse
+    pub fn duration_secs(&self) -> Option<u64> {
+        match self.duration.as_str() {
+            "forever" => None,
+            duration => crate::parse_duration_secs(duration),
+        }
+    }
+
+    /// `history` in seconds; validation rejects what doesn't parse
+    pub fn history_secs(&self) -> u64 {
+        crate::parse_duration_secs(&self.history).unwrap_or(crate::estimation::HISTORY_SECS)
+    }
+
+    /// `rolling_window` in seconds
+    pub fn rolling_window_secs(&self) -> u64 {
+        crate::parse_duration_secs(&self.rolling_window).unwrap_or(crate::estimation::ROLLING_WINDOW_SECS)
+    }
+
+    /// `smoothing` parsed; validation rejects what doesn't parse
+    pub fn smoothing(&self) -> crate::estimation::Smoothing {
+        crate::estimation::Smoothing::parse(&self.smoothing).unwrap_or_default()
+    }
+
+    fn apply_file(&mut self, file: FileConfig) {
+        if let Some(interval) = file.interval {
+            self.interval = interval;
+        }
+        if let Some(duration) = file.duration {
+            self.duration = duration;
+        }
+        if let Some(command) = file.on_exit {
+            self.on_exit = Some(command);
+        }
+        if let Some(history) = file.history {
+            self.history = history;
+        }
+        if let Some(window) = file.rolling_window {
+            self.rolling_window = window;
+        }
+        if let Some(smoothing) = file.smoothing {
+            self.smoothing = smoothing;
+        }
+        if let Some(root) = file.sysfs_root {
+            self.sysfs_root = root;
+        }
+        self.lang = file.lang.or(self.lang.take());
+        if let Some(units) = file.units {
+            self.units = units;
+        }
+        if let Some(bar) = file.bar {
+            self.bar = bar;
+        }
+        if let Some(format) = file.time_format {
+            self.time_format = format;
+        }
+        if let Some(format) = file.timestamp_format {
+            self.timestamp_format = format;
+        }
+        if let Some(battery) = file.battery {
+            self.battery = battery;
+        }
+        self.format = file.format.or(self.format.take());
+        self.fields = file.fields.or(self.fields.take());
+        if let Some(separator) = file.separator {
+            self.separator = separator;
+        }
+        if let Some(no_quirks) = file.no_quirks {
+            self.no_quirks = no_quirks;
+        }
+        if let Some(animations) = file.animations {
+            self.animations = animations;
+        }
+        if let Some(histogram) = file.histogram {
+            self.histogram = histogram;
+        }
+        if let Some(wakeups) = file.wakeups {
+            self.wakeups = wakeups;
+        }
+        if let Some(script) = file.script {
+            // An empty path lets a profile switch the script off again
+            self.script = (!script.is_empty()).then_some(script);
+        }
+
+        self.statsd.address = file.statsd.address.or(self.statsd.address.take());
+        if let Some(prefix) = file.statsd.prefix {
+            self.statsd.prefix = prefix;
+        }
+        if let Some(tags) = file.statsd.tags {
+            self.statsd.tags = tags;
+        }
+
+        if let Some(rules) = file.alerts.rules {
+            self.alerts.rules = rules;
+        }
+        if let Some(webhooks) = file.alerts.webhooks {
+            self.alerts.webhooks = webhooks;
+        }
+        self.alerts.webhook_template = file.alerts.webhook_template.or(self.alerts.webhook_template.take());
+        if let Some(topic) = file.alerts.ntfy_topic {
+            // An empty topic lets a profile switch ntfy off again
+            self.alerts.ntfy_topic = (!topic.is_empty()).then_some(topic);
+        }
+        if let Some(server) = file.alerts.ntfy_server {
+            self.alerts.ntfy_server = server;
+        }
+        if let Some(desktop) = file.alerts.desktop {
+            self.alerts.desktop = desktop;
+        }
+        if let Some(plug) = file.alerts.plug {
+            self.alerts.plug = plug;
+        }
+        self.alerts.charge_target = file.alerts.charge_target.or(self.alerts.charge_target);
+        self.alerts.charge_reminder = file.alerts.charge_reminder.or(self.alerts.charge_reminder.take());
+        if let Some(bell) = file.alerts.bell {
+            self.alerts.bell = bell;
+        }
+        if let Some(sound) = file.alerts.sound {
+            self.alerts.sound = (!sound.is_empty()).then_some(sound);
+        }
+        self.alerts.sound_player = file.alerts.sound_player.or(self.alerts.sound_player.take());
+        if let Some(severity) = file.alerts.audible_severity {
+            self.alerts.audible_severity = severity;
+        }
+        if let Some(action) = file.alerts.critical_action {
+            // "none" lets a profile switch it off again
+            self.alerts.critical_action = (action != "none").then_some(action);
+        }
+        if let Some(percent) = file.alerts.critical_action_percent {
+            self.alerts.critical_action_percent = percent;
+        }
+        self.alerts.critical_grace = file.alerts.critical_grace.or(self.alerts.critical_grace.take());
+        self.alerts.backlight = file.alerts.backlight.or(self.alerts.backlight.take());
+        self.alerts.backlight_command = file.alerts.backlight_command.or(self.alerts.backlight_command.take());
+
+        let limits = file.thresholds;
+        let t = &mut self.thresholds;
+        t.capacity_crit = limits.capacity_crit.unwrap_or(t.capacity_crit);
+        t.capacity_warn = limits.capacity_warn.unwrap_or(t.capacity_warn);
+        t.capacity_high = limits.capacity_high.unwrap_or(t.capacity_high);
+        t.battery_temp_warn = limits.battery_temp_warn.unwrap_or(t.battery_temp_warn);
+        t.battery_temp_hot = limits.battery_temp_hot.unwrap_or(t.battery_temp_hot);
+        t.cpu_temp_warn = limits.cpu_temp_warn.unwrap_or(t.cpu_temp_warn);
+        t.cpu_temp_hot = limits.cpu_temp_hot.unwrap_or(t.cpu_temp_hot);
+        t.temp_valid_min = limits.temp_valid_min.unwrap_or(t.temp_valid_min);
+        t.temp_valid_max = limits.temp_valid_max.unwrap_or(t.temp_valid_max);
+
+        // Profiles add or rename labels; an empty one drops the nickname
+        for (device, label) in file.nicknames {
+            match label.is_empty() {
+                true => self.nicknames.remove(&device),
+                false => self.nicknames.insert(device, label),
+            };
+        }
+    }
+
+    fn apply_env(&mut self) {
+        if let Ok(interval) = env::var("BATFI_INTERVAL") {
+            match interval.trim().parse() {
+                Ok(secs) => self.interval = secs,
+                Err(_) => self.merge_errors.push(format!("BATFI_INTERVAL: '{}' is not a number of seconds", interval)),
+            }
+        }
+        if let Ok(units) = env::var("BATFI_UNITS") {
+            self.units = units;
+        }
+        if let Ok(root) = env::var("BATFI_SYSFS_ROOT") {
+            self.sysfs_root = root;
+        }
+        if let Ok(battery) = env::var("BATFI_BATTERY") {
+            self.battery = split_list(&battery);
+        }
+        if let Ok(format) = env::var("BATFI_FORMAT") {
+            self.format = Some(format);
+        }
+        if let Ok(fields) = env::var("BATFI_FIELDS") {
+            self.fields = Some(split_list(&fields));
+        }
+        if let Ok(separator) = env::var("BATFI_SEPARATOR") {
+            self.separator = separator;
+        }
+        if let Ok(no_quirks) = env::var("BATFI_NO_QUIRKS") {
+            match no_quirks.trim() {
+                "1" | "true" | "yes" => self.no_quirks = true,
+                "0" | "false" | "no" | "" => self.no_quirks = false,
+                other => self.merge_errors.push(format!("BATFI_NO_QUIRKS: expected true or false, got '{}'", other)),
+            }
+        }
+        if let Ok(address) = env::var("BATFI_STATSD") {
+            self.statsd.address = Some(address);
+        }
+        if let Ok(topic) = env::var("BATFI_NTFY") {
+            self.alerts.ntfy_topic = Some(topic);
+        }
+    }
+
+    /// Value checks the TOML parser can't do; each entry is one problem
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = self.merge_errors.clone();
+        if self.interval == 0 {
+            problems.push("interval: must be at least 1 second".to_string());
+        }
+        if self.duration != "forever" && crate::parse_duration_secs(&self.duration).is_none_or(|secs| secs == 0) {
+            problems.push(format!("duration: expected a duration (e.g. 90s, 30m) or forever, got '{}'", self.duration));
+        }
+        let t = &self.thresholds;
+        if !(t.capacity_crit < t.capacity_warn && t.capacity_warn < t.capacity_high && t.capacity_high <= 100) {
+            problems.push("thresholds: need capacity_crit < capacity_warn < capacity_high <= 100".to_string());
+        }
+        if t.battery_temp_warn >= t.battery_temp_hot || t.cpu_temp_warn >= t.cpu_temp_hot {
+            problems.push("thresholds: *_temp_warn must be below *_temp_hot".to_string());
+        }
+        if t.temp_valid_min >= t.temp_valid_max {
+            problems.push("thresholds: temp_valid_min must be below temp_valid_max".to_string());
+        }
+        match (crate::parse_duration_secs(&self.history), crate::parse_duration_secs(&self.rolling_window)) {
+            (None, _) => problems.push(format!("history: invalid duration '{}' (e.g. 10m, 1h)", self.history)),
+            (_, None) => problems.push(format!("rolling_window: invalid duration '{}' (e.g. 20s, 1m)", self.rolling_window)),
+            (Some(history), Some(window)) if window == 0 || history < window => {
+                problems.push("rolling_window: must be above zero and no longer than history".to_string())
+            }
+            _ => {}
+        }
+        if crate::estimation::Smoothing::parse(&self.smoothing).is_none() {
+            problems.push(format!(
+                "smoothing: expected one of {} with an optional :parameter, got '{}'",
+                crate::estimation::SMOOTHING_METHODS.join(", "), self.smoothing
+            ));
+        }
+        if !["wh", "mah"].contains(&self.units.as_str()) {
+            problems.push(format!("units: expected wh or mah, got '{}'", self.units));
+        }
+        if !["capacity", "energy"].contains(&self.bar.as_str()) {
+            problems.push(format!("bar: expected capacity or energy, got '{}'", self.bar));
+        }
+        if timefmt::EtaFormat::parse(&self.time_format).is_none() {
+            problems.push(format!("time_format: expected one of {}, got '{}'", timefmt::ETA_FORMATS.join(", "), self.time_format));
+        }
+        if timefmt::TimestampFormat::parse(&self.timestamp_format).is_none() {
+            problems.push(format!(
+                "timestamp_format: expected one of {}, got '{}'",
+                timefmt::TIMESTAMP_FORMATS.join(", "),
+                self.timestamp_format
+            ));
+        }
+        if let Some(lang) = &self.lang {
+            if !crate::i18n::available().contains(&lang.as_str()) {
+                problems.push(format!("lang: no '{}' translation (available: {})", lang, crate::i18n::available().join(", ")));
+            }
+        }
+        if let Some(format) = &self.format {
+            if !FORMATS.contains(&format.as_str()) {
+                problems.push(format!("format: unknown format '{}' (expected one of {})", format, FORMATS.join(", ")));
+            }
+        }
+        if let Some(selected) = &self.fields {
+            if let Err(unknown) = fields::validate(selected) {
+                problems.push(format!("fields: unknown field '{}' (available: {})", unknown, fields::known_fields().join(", ")));
+            }
+        }
+        for rule in &self.alerts.rules {
+            if let Err(e) = alerts::AlertRule::parse(rule) {
+                problems.push(format!("alerts.rules: '{}': {}", rule, e));
+            }
+        }
+        if let Some(target) = self.alerts.charge_target.filter(|t| !(1..=100).contains(t)) {
+            problems.push(format!("alerts.charge_target: expected 1-100, got {}", target));
+        }
+        if let Some(reminder) = &self.alerts.charge_reminder {
+            if crate::parse_duration_secs(reminder).is_none_or(|secs| secs == 0) {
+                problems.push(format!("alerts.charge_reminder: invalid duration '{}' (e.g. 15m, 1h)", reminder));
+            }
+        }
+        if alerts::Severity::parse(&self.alerts.audible_severity).is_none() {
+            problems.push(format!("alerts.audible_severity: expected info, warning or critical, got '{}'", self.alerts.audible_severity));
+        }
+        if let Some(action) = self.alerts.critical_action.as_ref().filter(|a| !crate::critical::ACTIONS.contains(&a.as_str())) {
+            problems.push(format!("alerts.critical_action: expected one of {} or none, got '{}'", crate::critical::ACTIONS.join(", "), action));
+        }
+        if !(1..=50).contains(&self.alerts.critical_action_percent) {
+            problems.push(format!("alerts.critical_action_percent: expected 1-50, got {}", self.alerts.critical_action_percent));
+        }
+        if let Some(grace) = &self.alerts.critical_grace {
+            if crate::parse_duration_secs(grace).is_none() {
+                problems.push(format!("alerts.critical_grace: invalid duration '{}' (e.g. 30s, 2m)", grace));
+            }
+        }
+        if let Some(script) = self.script.as_ref().filter(|path| !Path::new(path).is_file()) {
+            problems.push(format!("script: no such file '{}'", script));
+        }
+        if let Some(sound) = self.alerts.sound.as_ref().filter(|path| !Path::new(path).is_file()) {
+            problems.push(format!("alerts.sound: no such file '{}'", sound));
+        }
+        problems
+    }
+}
+
+/// A config file that could not be parsed
+#[derive(Debug)]
+pub struct ConfigError {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.path.display(), line, self.message),
+            None => write!(f, "{}: {}", self.path.display(), self.message),
+        }
+    }
+}
+
+/// $BATFI_CONFIG, else $XDG_CONFIG_HOME/batfi/config.toml (falling back to ~/.config)
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("BATFI_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("batfi").join("config.toml"))
+}
+
+pub fn parse_config(path: &Path) -> Result<FileConfig, ConfigError> {
+    let text = fs::read_to_string(path).map_err(|e| ConfigError {
+        path: path.to_path_buf(),
+        line: None,
+        message: e.to_string(),
+    })?;
+    toml::from_str(&text).map_err(|e| ConfigError {
+        path: path.to_path_buf(),
+        line: e.span().map(|span| text[..span.start].matches('\n').count() + 1),
+        message: e.message().to_string(),
+    })
+}
+
+/// Defaults, then the config file with `profile` (or $BATFI_PROFILE) applied on top, then the
+/// BATFI_* environment; command-line flags go on top of this
+pub fn resolve(file: Option<FileConfig>, profile: Option<String>) -> Settings {
+    let mut settings = Settings {
+        profile: profile.or_else(|| env::var("BATFI_PROFILE").ok()),
+        ..Default::default()
+    };
+
+    let mut file = file.unwrap_or_default();
+    let mut profiles = std::mem::take(&mut file.profile);
+    settings.apply_file(file);
+    if let Some(name) = &settings.profile {
+        match profiles.remove(name) {
+            Some(profile) if !profile.profile.is_empty() => {
+                settings.merge_errors.push(format!("profile.{}: profiles can't contain other profiles", name));
+            }
+            Some(profile) => settings.apply_file(profile),
+            None if profiles.is_empty() => settings.merge_errors.push(format!("profile '{}' is not defined", name)),
+            None => settings.merge_errors.push(format!(
+                "profile '{}' is not defined (available: {})",
+                name,
+                profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+            )),
+        }
+    }
+    settings.apply_env();
+    settings
+}
+
+/// Make `settings`' thresholds and nicknames the ones readings are checked and labelled with.
+/// Only the first call counts.
+pub fn activate(settings: &Settings) {
+    let _ = THRESHOLDS.set(settings.thresholds.clone());
+    let _ = NICKNAMES.set(settings.nicknames.clone());
+}
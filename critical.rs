@@ -0,0 +1,125 @@
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use zbus::blocking::Connection;
+use zbus::zvariant::OwnedFd;
+
+use crate::{events, BatteryInfo};
+
+/// What `critical_action` may ask logind to do
+pub const ACTIONS: [&str; 4] = ["suspend", "hibernate", "hybrid-sleep", "poweroff"];
+pub const DEFAULT_PERCENT: u8 = 5;
+pub const DEFAULT_GRACE_SECS: u64 = 60;
+
+const LOGIND: &str = "org.freedesktop.login1";
+const LOGIND_PATH: &str = "/org/freedesktop/login1";
+const LOGIND_MANAGER: &str = "org.freedesktop.login1.Manager";
+
+/// Suspends, hibernates or powers off when the battery runs out, like a desktop
+/// environment would: hold a logind delay lock, warn, give the user a grace period
+/// to plug in, then act.
+pub struct CriticalAction {
+    action: String,
+    percent: u8,
+    grace_secs: u64,
+    status_path: String,
+    /// Grace period running in the background
+    pending: Arc<AtomicBool>,
+    /// Already started for this discharge; re-armed once charging or above the threshold
+    fired: bool,
+}
+
+impl CriticalAction {
+    pub fn new(action: &str, percent: u8, grace_secs: u64, base_path: &str) -> Self {
+        Self {
+            action: action.to_string(),
+            percent,
+            grace_secs,
+            status_path: format!("{}/status", base_path),
+            pending: Arc::new(AtomicBool::new(false)),
+            fired: false,
+        }
+    }
+
+    /// Start the grace period when the threshold is crossed; returns the warning to send
+    pub fn check(&mut self, dir: Option<&Path>, battery: &str, info: &BatteryInfo) -> Option<String> {
+        if info.status != "Discharging" || info.capacity_percent > self.percent {
+            self.fired = false;
+            return None;
+        }
+        if self.fired || self.pending.load(Ordering::SeqCst) {
+            return None;
+        }
+        self.fired = true;
+        self.pending.store(true, Ordering::SeqCst);
+
+        // Taken before warning so nothing else suspends the machine under the user mid-grace
+        let lock = match inhibit(&format!("Battery critical, {} in {}s", self.action, self.grace_secs)) {
+            Ok(lock) => Some(lock),
+            Err(e) => {
+                crate::warn(&format!("Could not take a logind inhibitor lock: {}", e));
+                None
+            }
+        };
+
+        let action = self.action.clone();
+        let grace_secs = self.grace_secs;
+        let status_path = self.status_path.clone();
+        let pending = Arc::clone(&self.pending);
+        let battery_name = battery.to_string();
+        let dir = dir.map(Path::to_path_buf);
+        thread::spawn(move || {
+            for _ in 0..grace_secs {
+                thread::sleep(Duration::from_secs(1));
+                let status = fs::read_to_string(&status_path).map(|s| s.trim().to_string()).unwrap_or_default();
+                if status != "Discharging" {
+                    let _ = events::log_event(dir.as_deref(), &battery_name, "critical-action", &format!("{} cancelled: on AC", action));
+                    pending.store(false, Ordering::SeqCst);
+                    return;
+                }
+            }
+            // Our own delay lock would otherwise hold up the very sleep we ask for
+            drop(lock);
+            let _ = events::log_event(dir.as_deref(), &battery_name, "critical-action", &format!("Battery critical: {}", action));
+            if let Err(e) = run_action(&action) {
+                crate::warn(&format!("Critical battery {} failed: {}", action, e));
+            }
+            pending.store(false, Ordering::SeqCst);
+        });
+
+        Some(format!(
+            "Battery at {}% — {} in {}s unless plugged in",
+            info.capacity_percent, self.action, self.grace_secs
+        ))
+    }
+}
+
+/// A logind delay lock on sleep and shutdown, released when the descriptor is dropped
+fn inhibit(why: &str) -> zbus::Result<OwnedFd> {
+    let connection = Connection::system()?;
+    let reply = connection.call_method(
+        Some(LOGIND),
+        LOGIND_PATH,
+        Some(LOGIND_MANAGER),
+        "Inhibit",
+        &("sleep:shutdown", "batfi", why, "delay"),
+    )?;
+    reply.body().deserialize()
+}
+
+fn run_action(action: &str) -> zbus::Result<()> {
+    let method = match action {
+        "hibernate" => "Hibernate",
+        "hybrid-sleep" => "HybridSleep",
+        "poweroff" => "PowerOff",
+        _ => "Suspend",
+    };
+    let connection = Connection::system()?;
+    // false: don't prompt for authorization interactively
+    connection.call_method(Some(LOGIND), LOGIND_PATH, Some(LOGIND_MANAGER), method, &(false,))?;
+    Ok(())
+}
@@ -0,0 +1,828 @@
+//! The live dashboard: battery, power, power graph, temperature and history panels drawn with
+//! ratatui on the alternate screen, with keys to switch batteries, pause and change units.
+//!
+//! The panel text keeps the ANSI colours the rest of batfi prints and is converted with
+//! ansi-to-tui; the charge bar and the power graph are native widgets so they follow the
+//! terminal size.
+
+use std::io::{self, Stdout};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use ansi_to_tui::IntoText;
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::crossterm::{cursor, execute};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Padding, Paragraph, Sparkline, Tabs};
+use ratatui::{Frame, Terminal};
+
+use crate::{
+    adapter, celsius_to_fahrenheit, charge_curve, config, distribution, estimation, events, footprint, format_minutes,
+    generate_countdown_dots, generate_pacman_cat_animation, multi, timefmt, wakeups, BatteryInfo, BatteryMonitor, ChargeLevel,
+    CHARGE_CURVE_WIDTH, HISTOGRAM_BUCKETS, RECENT_EVENTS_SHOWN, THERMAL_CHART_ROWS,
+};
+use crate::i18n::{self, t};
+
+/// What the dashboard wants after waiting out an interval
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wake {
+    Sample,
+    Quit,
+}
+
+/// Longest wait for a key before checking for a signal again
+const KEY_POLL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, PartialEq, Eq)]
+enum Key {
+    Quit,
+    Pause,
+    Units,
+    NextBattery,
+    PreviousBattery,
+    Battery(usize),
+    /// The terminal was resized
+    Redraw,
+}
+
+/// Leave raw mode and the alternate screen; also what a forced exit calls
+pub fn restore_terminal() {
+    let _ = terminal::disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, cursor::Show);
+}
+
+/// Raw mode on the alternate screen for as long as it lives
+struct Screen {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+}
+
+impl Screen {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        if let Err(e) = execute!(io::stdout(), EnterAlternateScreen, cursor::Hide) {
+            restore_terminal();
+            return Err(e);
+        }
+        // A panic message printed on the alternate screen would vanish along with it
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |panic| {
+            restore_terminal();
+            previous(panic);
+        }));
+        Ok(Self { terminal: Terminal::new(CrosstermBackend::new(io::stdout()))? })
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+/// Wait up to `timeout` for a key the dashboard knows; None when the time runs out first or
+/// the key means nothing here
+fn read_key(timeout: Duration) -> Option<Key> {
+    if !event::poll(timeout).ok()? {
+        return None;
+    }
+    match event::read().ok()? {
+        Event::Key(key) if key.kind == KeyEventKind::Press => match key.code {
+            // Raw mode delivers Ctrl+C as a key rather than a signal
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Some(Key::Quit),
+            KeyCode::Char('q' | 'Q') | KeyCode::Esc => Some(Key::Quit),
+            KeyCode::Char('p' | 'P' | ' ') => Some(Key::Pause),
+            KeyCode::Char('u' | 'U') => Some(Key::Units),
+            KeyCode::Right | KeyCode::Tab | KeyCode::Char('l') => Some(Key::NextBattery),
+            KeyCode::Left | KeyCode::BackTab | KeyCode::Char('h') => Some(Key::PreviousBattery),
+            KeyCode::Char(digit @ '1'..='9') => Some(Key::Battery(digit as usize - '1' as usize)),
+            _ => None,
+        },
+        Event::Resize(..) => Some(Key::Redraw),
+        _ => None,
+    }
+}
+
+/// The dashboard for `batfi watch` on a terminal. The watch loop owns the battery it reports
+/// on; the other batteries on the machine live here so they can be switched to.
+pub struct Dashboard {
+    screen: Screen,
+    started: Instant,
+    updates: u32,
+    /// The watched battery's latest sample
+    latest: Option<BatteryInfo>,
+    others: Vec<(BatteryMonitor, Option<BatteryInfo>)>,
+    /// Every readable pack seen as one battery, once there are at least two
+    combined: Option<multi::CombinedInfo>,
+    /// 0 for the watched battery, else 1 + its index in `others`
+    selected: usize,
+    paused: bool,
+}
+
+impl Dashboard {
+    pub fn enter(others: Vec<BatteryMonitor>) -> io::Result<Self> {
+        // Sensor discovery narrates to stderr, which would scribble over the panels
+        crate::set_discovery_log(false);
+        Ok(Self {
+            screen: Screen::enter()?,
+            started: Instant::now(),
+            updates: 0,
+            latest: None,
+            others: others.into_iter().map(|monitor| (monitor, None)).collect(),
+            combined: None,
+            selected: 0,
+            paused: false,
+        })
+    }
+
+    /// Show a new sample of the watched battery, sampling the others alongside it
+    pub fn update(&mut self, monitor: &mut BatteryMonitor, info: &BatteryInfo) {
+        self.updates += 1;
+        self.latest = Some(info.clone());
+        for (other, latest) in &mut self.others {
+            *latest = other.get_battery_info();
+        }
+        let packs: Vec<(String, BatteryInfo)> = std::iter::once((monitor.battery_name().to_string(), info.clone()))
+            .chain(self.others.iter().filter_map(|(other, latest)| Some((other.battery_name().to_string(), latest.clone()?))))
+            .collect();
+        self.combined = (packs.len() > 1).then(|| multi::combine(packs));
+        self.draw(monitor);
+    }
+
+    /// Handle keys until the next sample is due. While paused nothing is sampled, so a
+    /// --duration run ends on the first sample after resuming. SIGINT and SIGTERM from
+    /// outside quit like the q key.
+    pub fn wait(&mut self, monitor: &mut BatteryMonitor) -> Wake {
+        let due = Instant::now() + monitor.update_interval();
+        loop {
+            if crate::interrupted() {
+                return Wake::Quit;
+            }
+            let left = due.saturating_duration_since(Instant::now());
+            if left.is_zero() && !self.paused {
+                return Wake::Sample;
+            }
+            let timeout = if self.paused { KEY_POLL } else { left.min(KEY_POLL) };
+            let Some(key) = read_key(timeout) else { continue };
+            let batteries = self.others.len() + 1;
+            match key {
+                Key::Quit => return Wake::Quit,
+                Key::Pause => self.paused = !self.paused,
+                Key::Units => {
+                    let charge_units = !monitor.charge_units();
+                    monitor.set_charge_units(charge_units);
+                    for (other, _) in &mut self.others {
+                        other.set_charge_units(charge_units);
+                    }
+                    // The new units only show up in a fresh sample
+                    if !self.paused {
+                        return Wake::Sample;
+                    }
+                }
+                Key::NextBattery => self.selected = (self.selected + 1) % batteries,
+                Key::PreviousBattery => self.selected = (self.selected + batteries - 1) % batteries,
+                Key::Battery(index) if index < batteries => self.selected = index,
+                Key::Battery(_) => continue,
+                Key::Redraw => {
+                    let _ = self.screen.terminal.clear();
+                }
+            }
+            self.draw(monitor);
+        }
+    }
+
+    fn draw(&mut self, watched: &BatteryMonitor) {
+        let names: Vec<String> = std::iter::once(watched)
+            .chain(self.others.iter().map(|(other, _)| other))
+            .map(|monitor| config::display_name(monitor.battery_name()).to_string())
+            .collect();
+        let (monitor, info) = match self.selected {
+            0 => (watched, self.latest.as_ref()),
+            i => (&self.others[i - 1].0, self.others[i - 1].1.as_ref()),
+        };
+        let header = Header {
+            names: &names,
+            selected: self.selected,
+            updates: self.updates,
+            elapsed: self.started.elapsed().as_secs(),
+            paused: self.paused,
+            combined: self.combined.as_ref(),
+        };
+        let _ = self.screen.terminal.draw(|frame| render(frame, &header, monitor, info));
+    }
+}
+
+/// The top lines: title, battery tabs and the update counter, then the combined estimate
+/// when there are several packs
+struct Header<'a> {
+    names: &'a [String],
+    selected: usize,
+    updates: u32,
+    elapsed: u64,
+    paused: bool,
+    combined: Option<&'a multi::CombinedInfo>,
+}
+
+fn render(frame: &mut Frame, header: &Header, monitor: &BatteryMonitor, info: Option<&BatteryInfo>) {
+    let banner = info.and_then(|info| plug_banner(monitor, info));
+    let footer = info.map(|_| footer_lines(monitor)).unwrap_or_default();
+    let [top, combined_area, banner_area, body, footer_area] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(header.combined.is_some() as u16),
+        Constraint::Length(banner.is_some() as u16),
+        Constraint::Min(0),
+        Constraint::Length(footer.len() as u16),
+    ])
+    .areas(frame.area());
+
+    render_header(frame, top, header);
+    if let Some(combined) = header.combined {
+        frame.render_widget(Paragraph::new(ansi_text(&[combined_line(combined)])), combined_area);
+    }
+    if let Some(banner) = banner {
+        frame.render_widget(Paragraph::new(banner), banner_area);
+    }
+    frame.render_widget(Paragraph::new(ansi_text(&footer)), footer_area);
+
+    let Some(info) = info else {
+        let message = format!("❌ Could not read battery information from {}", monitor.base_path());
+        frame.render_widget(Paragraph::new(message).block(panel(monitor.battery_name().to_string())), body);
+        return;
+    };
+
+    let power = power_lines(monitor, info);
+    let temperature = temperature_lines(monitor);
+    let history = history_lines(monitor, info);
+    let [panels, history_area] = Layout::vertical([
+        Constraint::Min(0),
+        Constraint::Length(if history.is_empty() { 0 } else { history.len() as u16 + 2 }),
+    ])
+    .areas(body);
+    let [left, right] = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(panels);
+    let [power_area, graph_area, temperature_area] = Layout::vertical([
+        Constraint::Length(power.len() as u16 + 2),
+        Constraint::Min(5),
+        Constraint::Length(temperature.len() as u16 + 2),
+    ])
+    .areas(right);
+
+    render_battery(frame, left, monitor, info, header.elapsed);
+    frame.render_widget(Paragraph::new(ansi_text(&power)).block(panel(t!("panel-power"))), power_area);
+    render_power_graph(frame, graph_area, monitor);
+    let title = t!("panel-temperature", secs = monitor.update_interval().as_secs());
+    frame.render_widget(Paragraph::new(ansi_text(&temperature)).block(panel(title)), temperature_area);
+    if !history.is_empty() {
+        frame.render_widget(Paragraph::new(ansi_text(&history)).block(panel(t!("panel-history"))), history_area);
+    }
+}
+
+fn panel<'a>(title: String) -> Block<'a> {
+    Block::bordered()
+        .title(Span::styled(format!(" {} ", title), Style::default().add_modifier(Modifier::BOLD)))
+        .padding(Padding::horizontal(1))
+}
+
+/// Lines carrying ANSI colours as ratatui text
+fn ansi_text(lines: &[String]) -> Text<'static> {
+    let joined = lines.join("\n");
+    joined.into_text().unwrap_or_else(|_| Text::raw(joined))
+}
+
+fn render_header(frame: &mut Frame, area: Rect, header: &Header) {
+    let title = format!(" 🔋 {} ", t!("app-title"));
+    let mut status = format!("Update #{} ({}s elapsed) ", header.updates, header.elapsed);
+    if header.paused {
+        status = format!("{} • {}", t!("header-paused"), status);
+    }
+    let [title_area, tabs_area, status_area] = Layout::horizontal([
+        Constraint::Length(title.chars().count() as u16 + 1),
+        Constraint::Min(0),
+        Constraint::Length(status.chars().count() as u16),
+    ])
+    .areas(area);
+    let bold_cyan = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+    frame.render_widget(Paragraph::new(Span::styled(title, bold_cyan)), title_area);
+    // Only worth a tab bar when there is something to switch to
+    if header.names.len() > 1 {
+        let tabs = Tabs::new(header.names.iter().map(String::as_str))
+            .select(header.selected)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD));
+        frame.render_widget(tabs, tabs_area);
+    }
+    let status_style = if header.paused { Style::default().fg(Color::Yellow) } else { Style::default().add_modifier(Modifier::DIM) };
+    frame.render_widget(Paragraph::new(Span::styled(status, status_style)), status_area);
+}
+
+/// All packs as one battery, then the order they empty in: firmware drains one at a time
+fn combined_line(combined: &multi::CombinedInfo) -> String {
+    let time = match combined.time_remaining_minutes {
+        Some(minutes) if combined.status == "Charging" => format!(" • {} {}", format_minutes(minutes), t!("time-to-full")),
+        Some(minutes) => format!(" • {} {}", format_minutes(minutes), t!("time-remaining")),
+        None => String::new(),
+    };
+    let order: Vec<String> = combined
+        .batteries
+        .iter()
+        .map(|pack| match pack.empties_in_minutes {
+            Some(minutes) => format!("{} \x1b[2m({})\x1b[0m", config::display_name(&pack.battery), format_minutes(minutes)),
+            None => config::display_name(&pack.battery).to_string(),
+        })
+        .collect();
+    format!(
+        " \x1b[1mΣ {}:\x1b[0m \x1b[1m{}%\x1b[0m{} • {}: {}",
+        t!("label-combined"),
+        combined.capacity_percent,
+        time,
+        t!("label-drain-order"),
+        order.join(" → ")
+    )
+}
+
+/// "Plugged in" / "Unplugged" for a few seconds after the change
+fn plug_banner(monitor: &BatteryMonitor, info: &BatteryInfo) -> Option<Line<'static>> {
+    let change = monitor.last_plug().filter(|c| monitor.last_update().saturating_sub(c.timestamp) < adapter::BANNER_SECS)?;
+    let banner = match (change.plugged, change.adapter_w) {
+        (true, Some(watts)) => t!("banner-plugged-adapter", watts = format!("{:.0}", watts)),
+        (true, None) => t!("banner-plugged"),
+        (false, _) => t!("banner-unplugged", capacity = info.capacity_percent),
+    };
+    let background = if change.plugged { Color::Green } else { Color::Yellow };
+    let style = Style::default().bg(background).fg(Color::Black).add_modifier(Modifier::BOLD);
+    Some(Line::from(Span::styled(format!(" {} ", banner), style)))
+}
+
+fn render_battery(frame: &mut Frame, area: Rect, monitor: &BatteryMonitor, info: &BatteryInfo, elapsed: u64) {
+    let block = panel(t!("panel-battery"));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let [gauge_area, note_area, text_area] =
+        Layout::vertical([Constraint::Length(1), Constraint::Length(1), Constraint::Min(0)]).areas(inner);
+
+    // Filled by energy against the design capacity with `bar = "energy"`, so wear shows up
+    let design_percent = if monitor.energy_bar() { monitor.design_energy_percent(info) } else { None };
+    let fill = design_percent.unwrap_or(info.capacity_percent as f64);
+    let color = match ChargeLevel::from_capacity(info.capacity_percent) {
+        ChargeLevel::Critical => Color::Red,
+        ChargeLevel::Low => Color::Yellow,
+        ChargeLevel::Normal => Color::Green,
+        ChargeLevel::High => Color::Cyan,
+    };
+    let filled = ((fill.clamp(0.0, 100.0) / 100.0 * gauge_area.width as f64) as usize).min(gauge_area.width as usize);
+    let bar = Line::from(vec![
+        Span::styled("█".repeat(filled), Style::default().fg(color)),
+        Span::styled("░".repeat(gauge_area.width as usize - filled), Style::default().fg(color)),
+    ]);
+    frame.render_widget(Paragraph::new(bar), gauge_area);
+
+    let mut note = format!("\x1b[1m{}%\x1b[0m {}", info.capacity_percent, monitor.get_trend_indicator());
+    if let Some(percent) = design_percent {
+        note.push_str(&format!(" \x1b[2m{}\x1b[0m", t!("bar-of-design", percent = format!("{:.0}", percent))));
+    }
+    frame.render_widget(Paragraph::new(ansi_text(&[note])), note_area);
+    frame.render_widget(Paragraph::new(ansi_text(&battery_lines(monitor, info, elapsed))), text_area);
+}
+
+/// Power over the samples that fit, scaled between the lowest and highest reading, with
+/// journal events marked underneath
+fn render_power_graph(frame: &mut Frame, area: Rect, monitor: &BatteryMonitor) {
+    let samples = monitor.power_history().len();
+    let block = panel(t!("panel-power-history", count = samples));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    if samples < 2 {
+        return;
+    }
+
+    let width = inner.width as usize;
+    let (annotations, kinds) = monitor.get_power_graph_annotations(width);
+    let marker_rows = if kinds.is_empty() { 0 } else { 2 };
+    let [graph_area, markers_area, range_area] =
+        Layout::vertical([Constraint::Min(1), Constraint::Length(marker_rows), Constraint::Length(1)]).areas(inner);
+
+    let shown: Vec<f64> = monitor.power_history().iter().skip(samples.saturating_sub(width)).map(|s| s.power_w).collect();
+    let min = shown.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = shown.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = if max - min < 0.1 { 0.1 } else { max - min };
+    // Sparklines start at zero; lift every bar by one step so the lowest reading still shows
+    let data: Vec<u64> = shown.iter().map(|w| ((w - min) / range * 100.0).round() as u64 + 10).collect();
+    let sparkline = Sparkline::default().data(&data).max(110).style(Style::default().fg(Color::Yellow));
+    frame.render_widget(sparkline, graph_area);
+
+    if !kinds.is_empty() {
+        let legend: Vec<String> = kinds
+            .iter()
+            .map(|kind| format!("{}{}\x1b[0m \x1b[2m{}\x1b[0m", events::kind_color(kind), events::graph_marker(kind), kind))
+            .collect();
+        frame.render_widget(Paragraph::new(ansi_text(&[annotations, legend.join("  ")])), markers_area);
+    }
+    let range = format!("\x1b[2m{:.1}–{:.1} W\x1b[0m", min, max);
+    frame.render_widget(Paragraph::new(ansi_text(&[range])), range_area);
+}
+
+fn battery_lines(monitor: &BatteryMonitor, info: &BatteryInfo, elapsed: u64) -> Vec<String> {
+    let mut lines = Vec::new();
+    // Dual-pack machines drain one pack after the other; one percentage hides which
+    if let Some(packs) = crate::packs::read_packs() {
+        lines.push(format!("[{}] {}", crate::packs::render_bar(&packs, 30), t!("label-packs")));
+        lines.push(format!(" \x1b[2m{}\x1b[0m", crate::packs::legend(&packs)));
+    }
+    if let Some(nickname) = &info.nickname {
+        lines.push(format!("{:<8}\x1b[1m{}\x1b[0m \x1b[2m({})\x1b[0m", format!("{}:", t!("label-device")), nickname, monitor.battery_name()));
+    }
+    let status = i18n::status_label(&info.status);
+    lines.push(format!("{:<8}\x1b[1m{}\x1b[0m", format!("{}:", t!("label-status")), match info.status.as_str() {
+        "Charging" => format!("\x1b[32m{} ⚡\x1b[0m", status),
+        "Discharging" => format!("\x1b[33m{} 🔋\x1b[0m", status),
+        "Full" => format!("\x1b[36m{} ✓\x1b[0m", status),
+        _ => format!("\x1b[37m{}\x1b[0m", status),
+    }));
+    if let Some(online) = info.ac_online {
+        let source = if online { format!("\x1b[32m{}\x1b[0m", t!("source-ac")) } else { format!("\x1b[33m{}\x1b[0m", t!("source-battery")) };
+        lines.push(format!("{:<8}{}", format!("{}:", t!("label-source")), source));
+    }
+    lines.push(time_line(monitor, info));
+
+    if monitor.animations() {
+        lines.push(String::new());
+        lines.push(format!("🐱 {}", generate_pacman_cat_animation(elapsed, monitor.run_duration())));
+        if let Some(duration) = monitor.run_duration() {
+            lines.push(format!("⏰ {}", generate_countdown_dots(elapsed, duration)));
+        }
+    }
+
+    let energy = energy_rows(monitor, info);
+    if !energy.is_empty() {
+        lines.push(String::new());
+        lines.push(format!("\x1b[1m{}:\x1b[0m", t!("panel-energy")));
+        lines.extend(tree(energy.into_iter().map(|(label, value)| format!("{:<11}{}", format!("{}:", label), value)).collect()));
+    }
+
+    // Projected charge from the learned charge-rate curve
+    let projection = monitor.charge_curve().projection(info.capacity_percent, info.percent_per_hour).filter(|_| info.status == "Charging");
+    if let Some(&to_full) = projection.as_ref().and_then(|minutes| minutes.last()) {
+        let minutes = projection.as_deref().unwrap_or_default();
+        let mut rows = Vec::new();
+        if let Some(rate) = info.percent_per_hour {
+            rows.push(format!("{:<11}\x1b[32m{}\x1b[0m", format!("{}:", t!("charge-rate")), t!("power-percent-per-hour", rate = format!("{:+.1}", rate))));
+        }
+        if let Some(to_milestone) = info.time_to_80_minutes {
+            let label = t!("charge-to-percent", percent = charge_curve::PROJECTION_MILESTONE_PERCENT);
+            rows.push(format!("{:<11}\x1b[1m{}\x1b[0m", format!("{}:", label), format_minutes(to_milestone)));
+        }
+        rows.push(format!("{:<11}\x1b[1m{}\x1b[0m", format!("{}:", t!("charge-to-percent", percent = 100)), format_minutes(to_full.round().max(1.0) as u32)));
+        rows.push(format!("{} \x1b[2m{}\x1b[0m", charge_curve::curve_line(info.capacity_percent, minutes, CHARGE_CURVE_WIDTH), t!("charge-curve-span")));
+        lines.push(String::new());
+        lines.push(format!("\x1b[1m{}:\x1b[0m", t!("panel-charge-projection")));
+        lines.extend(tree(rows));
+    }
+
+    // Runtime at hypothetical sustained loads, with the current draw slotted in
+    if let Some(energy) = info.energy_now_wh.filter(|_| info.status != "Charging") {
+        let now = info.smoothed_power_w.filter(|_| info.status == "Discharging");
+        let mut loads: Vec<(f64, bool)> = estimation::WHAT_IF_LOADS_W.iter().map(|&w| (w, false)).collect();
+        loads.extend(now.map(|w| (w, true)));
+        loads.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let rows: Vec<String> = loads
+            .into_iter()
+            .filter_map(|(watts, current)| {
+                let minutes = estimation::runtime_at(energy, watts)?;
+                Some(match current {
+                    true => format!("\x1b[33m{:>5.1} W\x1b[0m  \x1b[1m{:<8}\x1b[0m \x1b[33m◀ {}\x1b[0m", watts, format_minutes(minutes), t!("what-if-now")),
+                    false => format!("{:>5.0} W  {}", watts, format_minutes(minutes)),
+                })
+            })
+            .collect();
+        if !rows.is_empty() {
+            lines.push(String::new());
+            lines.push(format!("\x1b[1m{}:\x1b[0m", t!("panel-what-if")));
+            lines.extend(tree(rows));
+        }
+    }
+    lines
+}
+
+/// Prefix rows with ├─, and └─ on the last
+fn tree(rows: Vec<String>) -> Vec<String> {
+    let last = rows.len().saturating_sub(1);
+    rows.into_iter().enumerate().map(|(i, row)| format!("{} {}", if i == last { "└─" } else { "├─" }, row)).collect()
+}
+
+/// Time to empty or full, with how far the estimate can be trusted
+fn time_line(monitor: &BatteryMonitor, info: &BatteryInfo) -> String {
+    let label = format!("{}:", t!("label-time"));
+    let Some(time) = info.time_remaining_minutes else {
+        let calculating_dots = match SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() % 4 {
+            0 => "   ",
+            1 => "●  ",
+            2 => "●● ",
+            _ => "●●●",
+        };
+        return format!("{:<8}\x1b[2m{}{}\x1b[0m", label, t!("time-calculating"), calculating_dots);
+    };
+    let (icon, status_text) = match info.status.as_str() {
+        "Charging" => {
+            let charge_phase = if info.capacity_percent > 95 {
+                t!("phase-trickle")
+            } else if info.capacity_percent > 80 {
+                t!("phase-slowing")
+            } else {
+                t!("phase-fast")
+            };
+            ("⚡", format!("{} {}", t!("time-to-full"), charge_phase))
+        }
+        _ => ("🔋", t!("time-remaining")),
+    };
+    let samples = monitor.power_history().len();
+    let accuracy = if monitor.rolling_samples() >= monitor.window_sizes().rolling {
+        "\x1b[32m●●●\x1b[0m"
+    } else if samples >= monitor.window_sizes().min_samples * 3 {
+        "\x1b[32m●●\x1b[0m"
+    } else if samples >= monitor.window_sizes().min_samples {
+        "\x1b[33m●\x1b[0m"
+    } else {
+        "\x1b[31m○\x1b[0m"
+    };
+    // A dim tilde when the ETA has been jumping around
+    let rough = match info.eta_stability {
+        Some(stability) if stability < estimation::ROUGH_ETA_STABILITY => "\x1b[2m~\x1b[0m",
+        _ => "",
+    };
+    format!("{:<8}{}\x1b[1m{} {} {}\x1b[0m {}", label, rough, monitor.format_time(time), icon, status_text, accuracy)
+}
+
+fn energy_rows(monitor: &BatteryMonitor, info: &BatteryInfo) -> Vec<(String, String)> {
+    let mut rows: Vec<(String, String)> = Vec::new();
+    if let (Some(now), Some(full)) = (info.charge_now_mah, info.charge_full_mah) {
+        rows.push((t!("energy-now"), format!("\x1b[1m{:.0} mAh\x1b[0m", now)));
+        rows.push((t!("energy-full"), match monitor.attrs().charge_mah("full_design").filter(|d| *d > 0.0) {
+            Some(design) => t!("energy-full-of-design",
+                full = format!("\x1b[1m{:.0} mAh\x1b[0m", full),
+                design = format!("{:.0} mAh", design),
+                health = format!("{:.0}", full / design * 100.0)),
+            None => format!("\x1b[1m{:.0} mAh\x1b[0m", full),
+        }));
+    } else if let (Some(now), Some(full)) = (info.energy_now_wh, info.energy_full_wh) {
+        rows.push((t!("energy-now"), format!("\x1b[1m{:.1} Wh\x1b[0m", now)));
+        rows.push((t!("energy-full"), match info.energy_full_design_wh {
+            Some(design) => t!("energy-full-of-design",
+                full = format!("\x1b[1m{:.1} Wh\x1b[0m", full),
+                design = format!("{:.1} Wh", design),
+                health = format!("{:.0}", info.health_percent)),
+            None => format!("\x1b[1m{:.1} Wh\x1b[0m", full),
+        }));
+    }
+    if let (Some(used), Some(secs)) = (info.energy_since_unplug_wh, info.unplugged_secs) {
+        rows.push((t!("energy-unplugged"), t!("energy-used-in",
+            energy = format!("\x1b[1m{:.1} Wh\x1b[0m", used),
+            duration = format_minutes((secs / 60) as u32))));
+    }
+    if let (Some(added), Some(start), Some(secs)) =
+        (info.charge_session_added_wh, info.charge_session_start_percent, info.charge_session_secs)
+    {
+        rows.push((t!("energy-charging"), t!("energy-added",
+            energy = format!("\x1b[1m{:.1} Wh\x1b[0m", added),
+            from = start,
+            to = info.capacity_percent,
+            duration = format_minutes((secs / 60) as u32))));
+    }
+    if let Some((wh, from, to)) = monitor.energy_per_percent().near(info.capacity_percent) {
+        rows.push((t!("energy-per-percent"), t!("energy-per-percent-learned",
+            energy = format!("\x1b[1m{:.2} Wh\x1b[0m", wh),
+            from = from,
+            to = to)));
+    }
+    match info.last_full_secs_ago {
+        Some(_) if info.status == "Full" => rows.push((t!("energy-last-full"), t!("last-full-now"))),
+        Some(secs) => rows.push((t!("energy-last-full"), t!("last-full-ago", duration = format_minutes((secs / 60) as u32)))),
+        None => {}
+    }
+    rows
+}
+
+fn power_lines(monitor: &BatteryMonitor, info: &BatteryInfo) -> Vec<String> {
+    let mut rows = Vec::new();
+    if let Some(power) = info.power_w {
+        let power_color = if info.status == "Charging" { "\x1b[32m" } else { "\x1b[33m" };
+        let rate = info
+            .percent_per_hour
+            .map(|rate| format!(" \x1b[2m({})\x1b[0m", t!("power-percent-per-hour", rate = format!("{:+.1}", rate).replace('-', "−"))))
+            .unwrap_or_default();
+        rows.push(format!("{:<11}{}{:.2}W\x1b[0m{}", format!("{}:", t!("power-current")), power_color, power, rate));
+    }
+    if let Some(smoothed) = info.smoothed_power_w {
+        let arrow = match info.power_trend.as_str() {
+            "increasing" => "\x1b[31m↑\x1b[0m",
+            "decreasing" => "\x1b[32m↓\x1b[0m",
+            _ => "\x1b[37m→\x1b[0m",
+        };
+        rows.push(format!("{:<11}\x1b[1m{:.2}W\x1b[0m ({})", format!("{}:", t!("power-smoothed")), smoothed, t!("power-trend", arrow = arrow)));
+        if monitor.rolling_samples() >= 3 {
+            let rolling_avg = monitor.get_rolling_average_power().unwrap_or(smoothed);
+            let secs = monitor.rolling_samples() * monitor.update_interval().as_secs() as usize;
+            rows.push(format!("{:<11}\x1b[1m{:.2}W\x1b[0m ({})", format!("{}:", t!("power-rolling")), rolling_avg, t!("power-rolling-window", secs = secs)));
+        }
+    }
+    if let Some(p) = monitor.session_power().percentiles() {
+        rows.push(format!("{:<11}p50 \x1b[1m{:.1}W\x1b[0m · p90 \x1b[1m{:.1}W\x1b[0m · p99 \x1b[1m{:.1}W\x1b[0m ({})",
+            format!("{}:", t!("power-spread")), p.p50, p.p90, p.p99,
+            t!("power-spread-detail", samples = monitor.session_power().len())));
+    }
+    for disagreement in &info.data_quality.disagreements {
+        let values: Vec<String> = disagreement.values.iter().map(|v| format!("{} {:.1}", v.source, v.value)).collect();
+        rows.push(format!("\x1b[33m{}\x1b[0m", t!("power-sources-disagree",
+            attribute = disagreement.attribute.as_str(), values = values.join(" · "), chosen = disagreement.chosen.as_str())));
+    }
+    if let Some(voltage) = info.voltage_v {
+        rows.push(format!("{:<11}\x1b[1m{:.2}V\x1b[0m", format!("{}:", t!("power-voltage")), voltage));
+        if let Some(sag) = info.voltage_sag_v {
+            rows.push(format!("\x1b[33m{}\x1b[0m", t!("power-sag", sag = format!("{:.2}", sag), capacity = info.capacity_percent)));
+        }
+    }
+    if let (Some(current), true) = (info.current_ma, monitor.charge_units()) {
+        rows.push(format!("{:<11}\x1b[1m{} mA\x1b[0m", format!("{}:", t!("current-draw-abs")), current.unsigned_abs()));
+    } else if let Some(current) = info.current_ma {
+        let current_str = if current >= 0 { format!("\x1b[32m+{} mA\x1b[0m", current) } else { format!("\x1b[31m{} mA\x1b[0m", current) };
+        rows.push(format!("{:<11}{}", format!("{}:", t!("current-draw")), current_str));
+    }
+    let mut lines = tree(rows);
+
+    let drains = monitor.drain_by_context();
+    if !drains.is_empty() {
+        let parts: Vec<String> = drains.iter().map(|(context, watts)| format!("{} \x1b[1m{:.1}W\x1b[0m", context.label(), watts)).collect();
+        lines.push(format!("{}: {}", t!("drain-by-context"), parts.join(" · ")));
+    }
+    lines
+}
+
+fn temperature_lines(monitor: &BatteryMonitor) -> Vec<String> {
+    let limits = config::thresholds();
+    let mut rows = Vec::new();
+    // Battery and CPU temperatures, smoothed like power
+    match monitor.temperature_monitor().last_battery_temp.as_ref() {
+        Some(reading) => {
+            let temp_c = reading.smoothed_value;
+            let temp_color = if temp_c <= 35.0 {
+                "\x1b[36m" // Cyan (cool)
+            } else if temp_c <= limits.battery_temp_warn {
+                "\x1b[32m" // Green (normal)
+            } else if temp_c <= limits.battery_temp_hot {
+                "\x1b[33m" // Yellow (warm)
+            } else {
+                "\x1b[31m" // Red (hot)
+            };
+            rows.push(format!("{:<11}{}{:.1}°C ({:.1}°F)\x1b[0m [{}]",
+                format!("{}:", t!("temp-battery")), temp_color, temp_c, celsius_to_fahrenheit(temp_c), reading.sensor_info.sensor_type));
+        }
+        None => rows.push(format!("{:<11}\x1b[2m—\x1b[0m {}", format!("{}:", t!("temp-battery")), t!("temp-no-sensor"))),
+    }
+    match monitor.temperature_monitor().last_cpu_temp.as_ref() {
+        Some(reading) => {
+            let temp_c = reading.smoothed_value;
+            let temp_color = if temp_c <= 45.0 {
+                "\x1b[36m" // Cyan (cool)
+            } else if temp_c <= 60.0 {
+                "\x1b[32m" // Green (normal)
+            } else if temp_c <= limits.cpu_temp_warn {
+                "\x1b[33m" // Yellow (warm)
+            } else if temp_c <= limits.cpu_temp_hot {
+                "\x1b[31m" // Red (hot)
+            } else {
+                "\x1b[41m\x1b[37m" // Red background (critical)
+            };
+            rows.push(format!("{:<11}{}{:.1}°C ({:.1}°F)\x1b[0m [{}]",
+                format!("{}:", t!("temp-cpu")), temp_color, temp_c, celsius_to_fahrenheit(temp_c), reading.sensor_info.sensor_type));
+        }
+        None => rows.push(format!("{:<11}\x1b[2m—\x1b[0m {}", format!("{}:", t!("temp-cpu")), t!("temp-no-sensor"))),
+    }
+    let monitored = &monitor.temperature_monitor();
+    if monitored.last_battery_temp.is_none() && monitored.last_cpu_temp.is_none() {
+        rows.push(t!("temp-none-valid", min = limits.temp_valid_min, max = limits.temp_valid_max));
+    }
+    let mut lines = tree(rows);
+
+    if let Some(thermal) = monitor.thermal_correlation() {
+        lines.push(String::new());
+        lines.push(format!("\x1b[1m{}:\x1b[0m", t!("panel-thermal", count = thermal.samples)));
+        lines.extend(monitor.get_thermal_chart(40, THERMAL_CHART_ROWS));
+        let strength = match thermal.r.abs() {
+            r if r >= 0.7 => t!("thermal-strong"),
+            r if r >= 0.4 => t!("thermal-moderate"),
+            _ => t!("thermal-weak"),
+        };
+        lines.push(format!("\x1b[2m{}\x1b[0m", t!("thermal-legend")));
+        lines.push(format!("r = \x1b[1m{:+.2}\x1b[0m ({}) · \x1b[1m{:+.1}°C\x1b[0m {}",
+            thermal.r, strength, thermal.celsius_per_watt, t!("thermal-per-watt")));
+        let hottest = monitor.power_history().iter().filter_map(|s| s.cpu_temperature_c).fold(f64::NEG_INFINITY, f64::max);
+        if hottest > limits.cpu_temp_warn {
+            if thermal.r <= -0.4 {
+                lines.push(format!("\x1b[33m{}\x1b[0m", t!("thermal-hint-throttled")));
+            } else if thermal.r.abs() < 0.4 {
+                lines.push(format!("\x1b[33m{}\x1b[0m", t!("thermal-hint-cooling")));
+            }
+        }
+    }
+    lines
+}
+
+/// Recent events, the power distribution, wakeups and script lines; empty when there is none
+fn history_lines(monitor: &BatteryMonitor, info: &BatteryInfo) -> Vec<String> {
+    let mut sections: Vec<Vec<String>> = Vec::new();
+
+    let recent = events::recent(monitor.data_dir(), monitor.battery_name(), RECENT_EVENTS_SHOWN);
+    if !recent.is_empty() {
+        let rows = recent
+            .iter()
+            .map(|event| format!("\x1b[2m{}\x1b[0m {}{:<16}\x1b[0m {}",
+                timefmt::time_of_day(event.timestamp), events::kind_color(&event.kind), event.kind, event.message))
+            .collect();
+        sections.push([vec![format!("\x1b[1m{}:\x1b[0m", t!("panel-events"))], tree(rows)].concat());
+    }
+
+    if monitor.histogram() && monitor.session_power().len() >= distribution::MIN_SAMPLES_FOR_PERCENTILES {
+        let mut section = vec![format!("\x1b[1m{}:\x1b[0m", t!("panel-power-distribution", count = monitor.session_power().len()))];
+        section.extend(distribution::histogram_lines(&monitor.session_power().histogram(HISTOGRAM_BUCKETS), 40));
+        sections.push(section);
+    }
+
+    let offenders = monitor.wakeups().map(|t| t.top(wakeups::TOP_OFFENDERS)).unwrap_or_default();
+    if let (false, Some(tracker)) = (offenders.is_empty(), monitor.wakeups()) {
+        let rows = offenders
+            .iter()
+            .map(|offender| {
+                let kind = match offender.kind {
+                    wakeups::Kind::Interrupt => t!("wakeups-interrupts"),
+                    wakeups::Kind::WakeupSource => t!("wakeups-events"),
+                };
+                format!("{:<32} \x1b[1m{:>8.1}\x1b[0m/min \x1b[2m{}\x1b[0m", offender.name, offender.per_minute, kind)
+            })
+            .collect();
+        sections.push([vec![format!("\x1b[1m{}:\x1b[0m", t!("panel-wakeups", minutes = tracker.observed_secs() / 60))], tree(rows)].concat());
+    }
+
+    if !info.script_lines.is_empty() {
+        sections.push([vec![format!("\x1b[1m{}:\x1b[0m", t!("panel-script"))], info.script_lines.clone()].concat());
+    }
+    sections.join(&String::new())
+}
+
+/// How settled the estimate is, when the last sample came in, the keys, and batfi's own cost
+fn footer_lines(monitor: &BatteryMonitor) -> Vec<String> {
+    let samples = monitor.power_history().len();
+    let rolling_samples = monitor.rolling_samples();
+    let accuracy_text = if rolling_samples >= monitor.window_sizes().rolling {
+        let secs = rolling_samples * monitor.update_interval().as_secs() as usize;
+        format!("\x1b[32m{}\x1b[0m {}", t!("accuracy-ultra"), t!("accuracy-ultra-detail", samples = samples, secs = secs))
+    } else if samples >= monitor.window_sizes().min_samples * 3 {
+        format!("\x1b[32m{}\x1b[0m {}", t!("accuracy-high"), t!("accuracy-samples", samples = samples))
+    } else if samples >= monitor.window_sizes().min_samples {
+        format!("\x1b[33m{}\x1b[0m {}", t!("accuracy-medium"), t!("accuracy-samples", samples = samples))
+    } else {
+        format!("\x1b[31m{}\x1b[0m {}", t!("accuracy-building"),
+            t!("accuracy-building-detail", samples = samples, needed = monitor.window_sizes().min_samples))
+    };
+    let elapsed = if monitor.last_update() > 0 {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        t!("footer-ago", secs = now.saturating_sub(monitor.last_update()))
+    } else {
+        t!("footer-starting")
+    };
+
+    let mut lines = vec![format!(" {} • \x1b[2m{} • {}\x1b[0m",
+        accuracy_text,
+        t!("footer-last-update", when = elapsed),
+        t!("footer-updates", secs = monitor.update_interval().as_secs()))];
+    if let Some(own) = footprint::Footprint::current() {
+        lines.push(format!(" \x1b[2m{}\x1b[0m", t!("footer-self",
+            cpu = format!("{:.2}s", own.cpu_secs), percent = format!("{:.2}", own.cpu_percent), mwh = format!("{:.1}", own.mwh_per_hour))));
+    }
+    lines.push(format!(" \x1b[2m{}\x1b[0m", t!("footer-keys")));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tree_marks_the_last_row() {
+        assert_eq!(tree(vec!["a".to_string(), "b".to_string()]), ["├─ a", "└─ b"]);
+        assert!(tree(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn combined_line_lists_packs_in_drain_order() {
+        let pack = |status: &str, energy_now_wh: f64, power_w: f64| BatteryInfo {
+            status: status.to_string(),
+            energy_now_wh: Some(energy_now_wh),
+            energy_full_wh: Some(50.0),
+            power_w: Some(power_w),
+            ..Default::default()
+        };
+        let combined = multi::combine(vec![("BAT1".to_string(), pack("Unknown", 50.0, 0.0)), ("BAT0".to_string(), pack("Discharging", 10.0, 10.0))]);
+        let line = combined_line(&combined);
+        assert!(line.contains("60%"), "{}", line);
+        assert!(line.find("BAT0").unwrap() < line.find("BAT1").unwrap(), "{}", line);
+    }
+}
@@ -0,0 +1,110 @@
+use zbus::blocking::{connection, Connection};
+use zbus::interface;
+
+use crate::{format_minutes, BatteryInfo};
+
+/// Well-known session bus name a GNOME extension or generic indicator can watch
+pub const BUS_NAME: &str = "org.batfi.Battery";
+pub const OBJECT_PATH: &str = "/org/batfi/Battery";
+
+/// Latest sample exposed as D-Bus properties.
+///
+/// Unknown values use UPower's conventions (0 for times, 0.0 for power) so
+/// existing indicators that already understand UPower can read them as-is.
+#[derive(Default)]
+struct BatteryObject {
+    info: Option<BatteryInfo>,
+}
+
+#[interface(name = "org.batfi.Battery1")]
+impl BatteryObject {
+    #[zbus(property)]
+    fn percentage(&self) -> f64 {
+        self.info.as_ref().map_or(0.0, |i| i.capacity_percent as f64)
+    }
+
+    #[zbus(property)]
+    fn state(&self) -> String {
+        self.info.as_ref().map_or_else(|| "Unknown".to_string(), |i| i.status.clone())
+    }
+
+    /// Smoothed ETA in seconds (to empty or to full, following State)
+    #[zbus(property)]
+    fn time_remaining(&self) -> i64 {
+        self.info
+            .as_ref()
+            .and_then(|i| i.time_remaining_minutes)
+            .map_or(0, |m| m as i64 * 60)
+    }
+
+    #[zbus(property)]
+    fn smoothed_power(&self) -> f64 {
+        self.info.as_ref().and_then(|i| i.smoothed_power_w.or(i.power_w)).unwrap_or(0.0)
+    }
+
+    #[zbus(property)]
+    fn health(&self) -> f64 {
+        self.info.as_ref().map_or(0.0, |i| i.health_percent)
+    }
+
+    /// Ready-made top bar label, e.g. "84% · 3h 12m", for indicators that only show text
+    #[zbus(property)]
+    fn label(&self) -> String {
+        match &self.info {
+            Some(info) => match info.time_remaining_minutes {
+                Some(minutes) => format!("{}% · {}", info.capacity_percent, format_minutes(minutes)),
+                None => format!("{}%", info.capacity_percent),
+            },
+            None => String::new(),
+        }
+    }
+}
+
+/// Owns the session bus connection and republishes each new sample
+pub struct DbusPublisher {
+    connection: Connection,
+}
+
+impl DbusPublisher {
+    pub fn start() -> zbus::Result<Self> {
+        let connection = connection::Builder::session()?
+            .name(BUS_NAME)?
+            .serve_at(OBJECT_PATH, BatteryObject::default())?
+            .build()?;
+        Ok(Self { connection })
+    }
+
+    /// Store a sample and emit PropertiesChanged only for values that moved
+    pub fn publish(&self, info: &BatteryInfo) -> zbus::Result<()> {
+        let iface_ref = self
+            .connection
+            .object_server()
+            .interface::<_, BatteryObject>(OBJECT_PATH)?;
+        let mut iface = iface_ref.get_mut();
+        let previous = iface.info.replace(info.clone());
+        let emitter = iface_ref.signal_emitter();
+
+        let changed = |f: fn(&BatteryInfo) -> String| previous.as_ref().is_none_or(|p| f(p) != f(info));
+        zbus::block_on(async {
+            if changed(|i| i.capacity_percent.to_string()) {
+                iface.percentage_changed(emitter).await?;
+            }
+            if changed(|i| i.status.clone()) {
+                iface.state_changed(emitter).await?;
+            }
+            if changed(|i| format!("{:?}", i.time_remaining_minutes)) {
+                iface.time_remaining_changed(emitter).await?;
+                iface.label_changed(emitter).await?;
+            } else if changed(|i| i.capacity_percent.to_string()) {
+                iface.label_changed(emitter).await?;
+            }
+            if changed(|i| format!("{:.1}", i.smoothed_power_w.or(i.power_w).unwrap_or_default())) {
+                iface.smoothed_power_changed(emitter).await?;
+            }
+            if changed(|i| format!("{:.1}", i.health_percent)) {
+                iface.health_changed(emitter).await?;
+            }
+            Ok(())
+        })
+    }
+}
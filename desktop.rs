@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use std::thread;
+
+use zbus::blocking::Connection;
+use zbus::zvariant::Value;
+
+use crate::alerts::{Alert, AlertChannel, Severity};
+use crate::config;
+
+/// Shows alerts as desktop notifications through org.freedesktop.Notifications
+pub struct DesktopChannel;
+
+/// Urgency hint from the notification spec: 0 low, 1 normal, 2 critical (stays until dismissed)
+fn urgency(severity: Severity) -> u8 {
+    match severity {
+        Severity::Info => 0,
+        Severity::Warning => 1,
+        Severity::Critical => 2,
+    }
+}
+
+fn icon(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Info => "battery-full-charged",
+        Severity::Warning => "battery-low",
+        Severity::Critical => "battery-caution",
+    }
+}
+
+fn notify(alert: &Alert) -> zbus::Result<()> {
+    let connection = Connection::session()?;
+    let summary = format!("batfi: {}", alert.rule);
+    let body = format!("{} ({})", alert.message, config::display_name(&alert.battery));
+    let mut hints: HashMap<&str, Value> = HashMap::new();
+    hints.insert("urgency", Value::U8(urgency(alert.severity)));
+    connection.call_method(
+        Some("org.freedesktop.Notifications"),
+        "/org/freedesktop/Notifications",
+        Some("org.freedesktop.Notifications"),
+        "Notify",
+        &("batfi", 0u32, icon(alert.severity), summary.as_str(), body.as_str(), Vec::<&str>::new(), hints, -1i32),
+    )?;
+    Ok(())
+}
+
+impl AlertChannel for DesktopChannel {
+    fn send(&self, alert: &Alert) {
+        let alert = alert.clone();
+        thread::spawn(move || {
+            if let Err(e) = notify(&alert) {
+                crate::warn(&format!("Desktop notification failed: {}", e));
+            }
+        });
+    }
+}
@@ -0,0 +1,100 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// State-of-charge resolution of the learned curve (one bucket per 5%)
+const BUCKET_PERCENT: u8 = 5;
+const BUCKET_COUNT: usize = 100 / BUCKET_PERCENT as usize + 1;
+/// Learning rate for the per-bucket voltage average
+const CURVE_ALPHA: f64 = 0.05;
+/// Samples a bucket needs before it is trusted for sag detection
+const MIN_BUCKET_SAMPLES: u32 = 20;
+/// Sag below the expected voltage that counts as abnormal (absolute and relative)
+const SAG_THRESHOLD_V: f64 = 0.25;
+const SAG_THRESHOLD_FRACTION: f64 = 0.03;
+
+/// Learned pack voltage as a function of state of charge while discharging
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DischargeCurve {
+    pub battery: String,
+    pub voltage_v: Vec<Option<f64>>,
+    pub samples: Vec<u32>,
+    /// Where `save` writes the curve; None keeps it in memory
+    #[serde(skip)]
+    dir: Option<PathBuf>,
+}
+
+impl DischargeCurve {
+    pub fn new(battery: &str) -> Self {
+        Self {
+            battery: battery.to_string(),
+            voltage_v: vec![None; BUCKET_COUNT],
+            samples: vec![0; BUCKET_COUNT],
+            dir: None,
+        }
+    }
+
+    fn path(dir: Option<&Path>, battery: &str) -> Option<PathBuf> {
+        Some(dir?.join(format!("discharge_curve-{}.json", battery)))
+    }
+
+    /// Load the persisted curve for a battery, or start a fresh one
+    pub fn load(dir: Option<&Path>, battery: &str) -> Self {
+        let mut curve = Self::path(dir, battery)
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str::<Self>(&json).ok())
+            .filter(|curve| curve.voltage_v.len() == BUCKET_COUNT && curve.samples.len() == BUCKET_COUNT)
+            .unwrap_or_else(|| Self::new(battery));
+        curve.dir = dir.map(Path::to_path_buf);
+        curve
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Self::path(self.dir.as_deref(), &self.battery)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no data directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string(self).map_err(std::io::Error::other)?;
+        fs::write(path, json)
+    }
+
+    /// Forget the learned curve for a battery (e.g. a different pack was installed)
+    pub fn discard(dir: Option<&Path>, battery: &str) {
+        if let Some(path) = Self::path(dir, battery) {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    fn bucket(capacity_percent: u8) -> usize {
+        ((capacity_percent.min(100) + BUCKET_PERCENT / 2) / BUCKET_PERCENT) as usize
+    }
+
+    /// Expected voltage at this state of charge, once enough samples were learned
+    pub fn expected_voltage(&self, capacity_percent: u8) -> Option<f64> {
+        let bucket = Self::bucket(capacity_percent);
+        if self.samples[bucket] < MIN_BUCKET_SAMPLES {
+            return None;
+        }
+        self.voltage_v[bucket]
+    }
+
+    /// How far (V) the reading sags below the learned curve, if abnormally so
+    pub fn sag_below_expected(&self, capacity_percent: u8, voltage_v: f64) -> Option<f64> {
+        let expected = self.expected_voltage(capacity_percent)?;
+        let sag = expected - voltage_v;
+        let threshold = SAG_THRESHOLD_V.max(expected * SAG_THRESHOLD_FRACTION);
+        (sag > threshold).then_some(sag)
+    }
+
+    /// Feed a discharging sample into the curve
+    pub fn learn(&mut self, capacity_percent: u8, voltage_v: f64) {
+        let bucket = Self::bucket(capacity_percent);
+        self.voltage_v[bucket] = Some(match self.voltage_v[bucket] {
+            Some(prev) => CURVE_ALPHA * voltage_v + (1.0 - CURVE_ALPHA) * prev,
+            None => voltage_v,
+        });
+        self.samples[bucket] = self.samples[bucket].saturating_add(1);
+    }
+}
@@ -0,0 +1,197 @@
+//! Distribution of power draw, kept as a fixed-resolution histogram so that a session
+//! of any length costs a few kilobytes and percentiles stay exact to the bin width.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+/// Resolution samples are recorded at, in watts
+pub const BIN_W: f64 = 0.1;
+/// Samples needed before percentiles say more than "the few readings so far"
+pub const MIN_SAMPLES_FOR_PERCENTILES: u64 = 10;
+/// Histogram bucket widths, so edges land on round wattages
+const NICE_WIDTHS_W: [f64; 9] = [0.1, 0.2, 0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Percentiles {
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+}
+
+/// Samples from `from_w` up to (not including) `to_w`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Bucket {
+    pub from_w: f64,
+    pub to_w: f64,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PowerDistribution {
+    /// Sample count per BIN_W-wide bin, keyed by bin index
+    bins: BTreeMap<u32, u64>,
+    count: u64,
+}
+
+impl PowerDistribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add one reading; negative and non-finite values are ignored
+    pub fn record(&mut self, watts: f64) {
+        if !watts.is_finite() || watts < 0.0 {
+            return;
+        }
+        *self.bins.entry((watts / BIN_W).round() as u32).or_insert(0) += 1;
+        self.count += 1;
+    }
+
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Nearest-rank percentile, `q` in 0..=100
+    pub fn percentile(&self, q: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((q.clamp(0.0, 100.0) / 100.0 * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (&bin, &count) in &self.bins {
+            seen += count;
+            if seen >= rank {
+                return Some(bin as f64 * BIN_W);
+            }
+        }
+        None
+    }
+
+    /// p50/p90/p99 once there are MIN_SAMPLES_FOR_PERCENTILES samples
+    pub fn percentiles(&self) -> Option<Percentiles> {
+        if self.count < MIN_SAMPLES_FOR_PERCENTILES {
+            return None;
+        }
+        Some(Percentiles { p50: self.percentile(50.0)?, p90: self.percentile(90.0)?, p99: self.percentile(99.0)? })
+    }
+
+    /// At most `max_buckets` equal-width buckets from the lowest to the highest reading
+    pub fn histogram(&self, max_buckets: usize) -> Vec<Bucket> {
+        let (Some((&low, _)), Some((&high, _))) = (self.bins.first_key_value(), self.bins.last_key_value()) else {
+            return Vec::new();
+        };
+        let max_buckets = max_buckets.max(1) as u32;
+        // Work in whole bins so edges don't drift with float rounding
+        let needed = |width: u32| high / width - low / width + 1;
+        let widest = (NICE_WIDTHS_W[NICE_WIDTHS_W.len() - 1] / BIN_W).round() as u32;
+        let width = NICE_WIDTHS_W
+            .iter()
+            .map(|w| (w / BIN_W).round() as u32)
+            .chain((2..).map(|n| n * widest))
+            .find(|&width| needed(width) <= max_buckets)
+            .expect("one bucket as wide as the range always fits");
+        let start = low / width * width;
+        let mut buckets: Vec<Bucket> = (0..=(high - start) / width)
+            .map(|i| {
+                let from = start + i * width;
+                Bucket { from_w: from as f64 * BIN_W, to_w: (from + width) as f64 * BIN_W, count: 0 }
+            })
+            .collect();
+        for (&bin, &count) in &self.bins {
+            buckets[((bin - start) / width) as usize].count += count;
+        }
+        buckets
+    }
+}
+
+/// One text row per bucket, bars scaled so the fullest bucket is `bar_width` wide
+pub fn histogram_lines(buckets: &[Bucket], bar_width: usize) -> Vec<String> {
+    let total: u64 = buckets.iter().map(|b| b.count).sum();
+    let fullest = buckets.iter().map(|b| b.count).max().unwrap_or(0).max(1);
+    buckets
+        .iter()
+        .map(|bucket| {
+            let len = (bucket.count as f64 / fullest as f64 * bar_width as f64).round() as usize;
+            format!(
+                "{:>5.1}–{:<5.1}W \x1b[36m{}\x1b[0m{} {:>3.0}%",
+                bucket.from_w,
+                bucket.to_w,
+                "█".repeat(len),
+                " ".repeat(bar_width - len),
+                bucket.count as f64 / total.max(1) as f64 * 100.0
+            )
+        })
+        .collect()
+}
+
+impl FromIterator<f64> for PowerDistribution {
+    fn from_iter<I: IntoIterator<Item = f64>>(values: I) -> Self {
+        let mut distribution = Self::new();
+        values.into_iter().for_each(|watts| distribution.record(watts));
+        distribution
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn bursts_show_in_the_tail_not_the_median() {
+        // Idle at 6 W with one sample in ten at 25 W: the mean (7.9 W) hides both
+        let distribution: PowerDistribution = (0..100).map(|i| if i % 10 == 0 { 25.0 } else { 6.0 }).collect();
+        let p = distribution.percentiles().unwrap();
+        assert!((p.p50 - 6.0).abs() < 1e-9);
+        assert!((p.p90 - 6.0).abs() < 1e-9);
+        assert!((p.p99 - 25.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn too_few_samples_and_junk() {
+        let mut distribution: PowerDistribution = [f64::NAN, -3.0, f64::INFINITY].into_iter().collect();
+        assert!(distribution.is_empty());
+        assert_eq!(distribution.percentile(50.0), None);
+        distribution.record(4.04);
+        assert_eq!(distribution.percentile(0.0), Some(4.0));
+        assert_eq!(distribution.percentiles(), None);
+    }
+
+    #[test]
+    fn histogram_splits_idle_from_load() {
+        let distribution: PowerDistribution = (0..100).map(|i| if i % 4 == 0 { 23.4 } else { 5.2 + (i % 3) as f64 * 0.3 }).collect();
+        let buckets = distribution.histogram(10);
+        // 5.2–23.4 W fits ten 2 W buckets starting at 4 W
+        assert_eq!(buckets.len(), 10);
+        assert_eq!((buckets[0].from_w, buckets[0].to_w), (4.0, 6.0));
+        assert_eq!(buckets[0].count, 75);
+        assert_eq!(buckets[9].count, 25);
+        assert!(buckets[1..9].iter().all(|b| b.count == 0));
+        assert!(PowerDistribution::new().histogram(10).is_empty());
+    }
+
+    proptest! {
+        #[test]
+        fn histogram_keeps_every_sample(values in prop::collection::vec(0.0..500.0f64, 1..200), max_buckets in 1usize..20) {
+            let distribution: PowerDistribution = values.iter().copied().collect();
+            let buckets = distribution.histogram(max_buckets);
+            prop_assert!(buckets.len() <= max_buckets);
+            prop_assert_eq!(buckets.iter().map(|b| b.count).sum::<u64>(), values.len() as u64);
+        }
+
+        #[test]
+        fn percentiles_are_ordered_and_within_range(values in prop::collection::vec(0.0..200.0f64, 10..300)) {
+            let distribution: PowerDistribution = values.iter().copied().collect();
+            let p = distribution.percentiles().unwrap();
+            let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+            let max = values.iter().cloned().fold(0.0, f64::max);
+            prop_assert!(p.p50 <= p.p90 && p.p90 <= p.p99);
+            prop_assert!(p.p50 >= min - BIN_W && p.p99 <= max + BIN_W);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::fs;
+use std::path::Path;
+
+use crate::footprint::{self, Footprint};
+use crate::{charge_limit, client, data_dir, health, standby, BatteryMonitor};
+
+/// Samples timed for the footprint section
+const FOOTPRINT_TICKS: u32 = 20;
+/// Hourly CPU share above which batfi's own overhead is worth a warning
+const FOOTPRINT_WARN_PERCENT: f64 = 0.5;
+
+fn check(label: &str, ok: bool, detail: &str) {
+    let mark = if ok { "\x1b[32m✅" } else { "\x1b[33m⚠️ " };
+    println!(" ├─ {} {:<22}\x1b[0m {}", mark, label, detail);
+}
+
+/// First attribute of `names` present under the battery directory
+fn first_attr<'a>(base_path: &str, names: &[&'a str]) -> Option<&'a str> {
+    names.iter().copied().find(|name| Path::new(&format!("{}/{}", base_path, name)).exists())
+}
+
+fn check_attr(base_path: &str, label: &str, names: &[&str], missing: &str) {
+    match first_attr(base_path, names) {
+        Some(name) => check(label, true, name),
+        None => check(label, false, missing),
+    }
+}
+
+/// `batfi doctor`: report what this machine exposes and which features will work
+pub fn run_doctor(monitor: &mut BatteryMonitor) {
+    let base_path = monitor.base_path();
+    println!("\x1b[1;36m╔══════════════════════════════════════════════════════════════╗\x1b[0m");
+    println!("\x1b[1;36m║\x1b[0m \x1b[1;37m🩺 Batfi Doctor - {}\x1b[0m", monitor.battery_name());
+    println!("\x1b[1;36m╚══════════════════════════════════════════════════════════════╝\x1b[0m");
+    println!();
+
+    println!(" \x1b[1mBattery attributes ({}):\x1b[0m", base_path);
+    check_attr(base_path, "Status", &["status"], "missing - charging state unknown");
+    check_attr(base_path, "Capacity", &["capacity"], "missing - percentage unavailable");
+    check_attr(base_path, "Energy", &["energy_now", "charge_now"], "missing - no time estimates");
+    check_attr(base_path, "Power", &["power_now", "current_now"], "missing - no power readings");
+    check_attr(base_path, "Voltage", &["voltage_now"], "missing - no voltage sag or resistance");
+    check_attr(base_path, "Design capacity", &["energy_full_design", "charge_full_design"], "missing - health unknown");
+    check_attr(base_path, "Cycle count", &["cycle_count"], "missing");
+    match charge_limit::read_limit(base_path) {
+        Some(limit) => check("Charge threshold", true, &format!("{}% (batfi limit)", limit)),
+        None => check("Charge threshold", false, "not supported by this driver"),
+    }
+    println!();
+
+    println!(" \x1b[1mTemperature sensors:\x1b[0m");
+    let temps = monitor.temperature_monitor();
+    check("CPU", !temps.cpu_sensors.is_empty(), &format!("{} found", temps.cpu_sensors.len()));
+    check("Battery", !temps.battery_sensors.is_empty(), &format!("{} found", temps.battery_sensors.len()));
+    println!();
+
+    if monitor.sensor_providers().next().is_some() {
+        println!(" \x1b[1mSensor providers:\x1b[0m");
+        for (name, sensors) in monitor.sensor_providers() {
+            let inputs: Vec<String> = sensors.iter().map(|sensor| format!("{} ({:?})", sensor.label, sensor.quantity)).collect();
+            check(name, true, &inputs.join(", "));
+        }
+        println!();
+    }
+
+    println!(" \x1b[1mQuirks:\x1b[0m");
+    if monitor.quirks().is_empty() {
+        println!(" ├─ none needed for this pack");
+    }
+    for quirk in monitor.quirks() {
+        println!(" ├─ {}", quirk.description());
+    }
+    println!();
+
+    println!(" \x1b[1mStandby:\x1b[0m");
+    match standby::mem_sleep_mode() {
+        Some(mode) if mode == "deep" => check("Suspend mode", true, "deep (S3)"),
+        Some(mode) => check("Suspend mode", true, &format!("{} (needs S0ix to drain little)", mode)),
+        None => check("Suspend mode", false, "/sys/power/mem_sleep not readable"),
+    }
+    match standby::read_residency_us() {
+        Some((_, counter)) => check("S0ix residency", true, counter),
+        None => check("S0ix residency", false, "no counter readable (try as root for debugfs)"),
+    }
+    let history = health::load_health_history(monitor.data_dir(), monitor.battery_name());
+    match standby::summarize(history.iter().filter_map(|s| s.suspend.as_ref())) {
+        Some(summary) => {
+            let ok = summary.measured == 0 || summary.reached_deep_idle * 2 >= summary.measured;
+            check("Recent suspends", ok, &standby::summary_line(&summary));
+        }
+        None => check("Recent suspends", true, "none recorded yet"),
+    }
+    println!();
+
+    println!(" \x1b[1mIntegrations:\x1b[0m");
+    let writable = data_dir().is_some_and(|dir| {
+        let probe = dir.join(".doctor");
+        let ok = fs::create_dir_all(&dir).and_then(|_| fs::write(&probe, b"")).is_ok();
+        let _ = fs::remove_file(&probe);
+        ok
+    });
+    let data_path = data_dir().map(|d| d.display().to_string()).unwrap_or_else(|| "no HOME".to_string());
+    check("Data directory", writable, &data_path);
+    check("Daemon", client::daemon_available(), "batfi daemon / batfi serve");
+    check("Session bus", std::env::var_os("DBUS_SESSION_BUS_ADDRESS").is_some(), "serve --dbus, tray");
+    match monitor.compositor() {
+        Some(compositor) => check("Compositor IPC", true, compositor.name()),
+        None => check("Compositor IPC", false, "no sway/Hyprland session"),
+    }
+    println!();
+
+    println!(" \x1b[1mFootprint:\x1b[0m");
+    let cost = footprint::measure_ticks(monitor, FOOTPRINT_TICKS);
+    match (cost.cpu_per_tick_us, Footprint::hourly(&cost)) {
+        (Some(per_tick), Some(hourly)) => {
+            let draw = monitor
+                .get_battery_info()
+                .and_then(|info| Some((info.power_w?, hourly.share_of_draw(info.power_w?)?)))
+                .map(|(watts, share)| format!(", {:.3}% of the current {:.1} W draw", share, watts))
+                .unwrap_or_default();
+            check("Per sample", true, &format!("{:.0} µs CPU, {:.0} µs wall", per_tick, cost.wall_median_us));
+            check(
+                "Per hour",
+                hourly.cpu_percent < FOOTPRINT_WARN_PERCENT,
+                &format!("{:.2}s CPU at {}s updates, ~{:.2} mWh{}", hourly.cpu_secs, cost.interval_secs, hourly.mwh_per_hour, draw),
+            );
+        }
+        _ => check("Per sample", false, "CPU time unavailable (no /proc)"),
+    }
+}
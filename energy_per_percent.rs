@@ -0,0 +1,237 @@
+//! How much energy one percent of charge is worth at each state of charge, learned from
+//! the energy the pack reports (or, lacking that, integrated power) between capacity steps.
+//!
+//! Fuel gauges rarely spread a pack's Wh evenly over 0–100%, and capacity-only packs don't
+//! report Wh at all; the learned table turns their percentages back into energy.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{BatteryReading};
+
+/// State-of-charge resolution of the table (one bucket per 10%)
+const BUCKET_PERCENT: u8 = 10;
+const BUCKET_COUNT: usize = 100 / BUCKET_PERCENT as usize;
+/// Whole-percent steps per measurement, so one late capacity update doesn't skew it
+const STEP_PERCENT: u8 = 2;
+/// Learning rate for the per-bucket average
+const LEARN_ALPHA: f64 = 0.2;
+/// Measurements a bucket needs before it is shown or used
+const MIN_BUCKET_STEPS: u32 = 3;
+/// Longer sampling gaps (suspend, stalls) break the integral
+const MAX_GAP_SECS: u64 = 120;
+/// More than this per percent would be a 2 kWh pack; treat as a glitch
+const MAX_WH_PER_PERCENT: f64 = 20.0;
+
+/// Where the current measurement started: the reading right after a capacity step
+#[derive(Debug, Clone)]
+struct Anchor {
+    percent: u8,
+    energy_wh: Option<f64>,
+    /// ∫P dt since the anchor; None once a reading lacked power
+    integrated_wh: Option<f64>,
+    /// False until the first step is seen, as the first reading may sit anywhere within its percent
+    aligned: bool,
+    last: (u64, Option<f64>), // (timestamp, power_w)
+}
+
+/// Learned Wh per percent of charge, by state-of-charge bucket, while discharging
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnergyPerPercent {
+    pub battery: String,
+    pub wh: Vec<Option<f64>>,
+    pub steps: Vec<u32>,
+    #[serde(skip)]
+    anchor: Option<Anchor>,
+    /// Where `save` writes the table; None keeps it in memory
+    #[serde(skip)]
+    dir: Option<PathBuf>,
+}
+
+impl EnergyPerPercent {
+    pub fn new(battery: &str) -> Self {
+        Self {
+            battery: battery.to_string(),
+            wh: vec![None; BUCKET_COUNT],
+            steps: vec![0; BUCKET_COUNT],
+            anchor: None,
+            dir: None,
+        }
+    }
+
+    fn path(dir: Option<&Path>, battery: &str) -> Option<PathBuf> {
+        Some(dir?.join(format!("energy_per_percent-{}.json", battery)))
+    }
+
+    /// Load the persisted table for a battery, or start a fresh one
+    pub fn load(dir: Option<&Path>, battery: &str) -> Self {
+        let mut table = Self::path(dir, battery)
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str::<Self>(&json).ok())
+            .filter(|table| table.wh.len() == BUCKET_COUNT && table.steps.len() == BUCKET_COUNT)
+            .unwrap_or_else(|| Self::new(battery));
+        table.dir = dir.map(Path::to_path_buf);
+        table
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Self::path(self.dir.as_deref(), &self.battery)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no data directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string(self).map_err(std::io::Error::other)?;
+        fs::write(path, json)
+    }
+
+    /// Forget the learned table for a battery (e.g. a different pack was installed)
+    pub fn discard(dir: Option<&Path>, battery: &str) {
+        if let Some(path) = Self::path(dir, battery) {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    fn bucket(capacity_percent: u8) -> usize {
+        (capacity_percent.min(99) / BUCKET_PERCENT) as usize
+    }
+
+    fn learned(&self, bucket: usize) -> Option<f64> {
+        self.wh[bucket].filter(|_| self.steps[bucket] >= MIN_BUCKET_STEPS)
+    }
+
+    /// Learned Wh per percent around this state of charge, with the bucket's range
+    #[cfg_attr(not(feature = "tui"), allow(dead_code))] // Only the dashboard shows it
+    pub fn near(&self, capacity_percent: u8) -> Option<(f64, u8, u8)> {
+        let bucket = Self::bucket(capacity_percent);
+        let from = bucket as u8 * BUCKET_PERCENT;
+        Some((self.learned(bucket)?, from, from + BUCKET_PERCENT))
+    }
+
+    /// Energy between empty and `capacity_percent`; buckets not learned yet borrow their
+    /// nearest learned neighbour. None until any bucket is learned.
+    pub fn energy_below(&self, capacity_percent: u8) -> Option<f64> {
+        let learned: Vec<(usize, f64)> = (0..BUCKET_COUNT).filter_map(|b| Some((b, self.learned(b)?))).collect();
+        if learned.is_empty() {
+            return None;
+        }
+        let per_percent = |bucket: usize| {
+            learned.iter().min_by_key(|(b, _)| b.abs_diff(bucket)).map_or(0.0, |(_, wh)| *wh)
+        };
+        Some((0..capacity_percent.min(100)).map(|percent| per_percent(Self::bucket(percent))).sum())
+    }
+
+    /// Feed a reading; returns true when a bucket was updated and the table is worth saving
+    pub fn learn(&mut self, reading: &BatteryReading) -> bool {
+        let fresh = Anchor {
+            percent: reading.capacity_percent,
+            energy_wh: reading.energy_now_wh,
+            integrated_wh: Some(0.0),
+            aligned: false,
+            last: (reading.timestamp, reading.power_now_w),
+        };
+        let anchor = match self.anchor.as_mut() {
+            Some(anchor)
+                if reading.status == "Discharging"
+                    && reading.timestamp > anchor.last.0
+                    && reading.timestamp - anchor.last.0 <= MAX_GAP_SECS
+                    && reading.capacity_percent <= anchor.percent =>
+            {
+                anchor
+            }
+            _ => {
+                self.anchor = (reading.status == "Discharging").then_some(fresh);
+                return false;
+            }
+        };
+
+        let (last_ts, last_power) = anchor.last;
+        anchor.integrated_wh = match (anchor.integrated_wh, last_power, reading.power_now_w) {
+            (Some(sum), Some(before), Some(now)) => {
+                Some(sum + (before + now) / 2.0 * (reading.timestamp - last_ts) as f64 / 3600.0)
+            }
+            _ => None,
+        };
+        anchor.last = (reading.timestamp, reading.power_now_w);
+
+        let dropped = anchor.percent - reading.capacity_percent;
+        if dropped == 0 || (anchor.aligned && dropped < STEP_PERCENT) {
+            return false;
+        }
+        let measured = anchor.aligned.then(|| {
+            let energy = match (anchor.energy_wh, reading.energy_now_wh) {
+                (Some(before), Some(now)) => Some(before - now),
+                _ => anchor.integrated_wh,
+            };
+            (energy, (anchor.percent + reading.capacity_percent) / 2)
+        });
+        self.anchor = Some(Anchor { aligned: true, ..fresh });
+
+        let Some((Some(energy), midpoint)) = measured else {
+            return false;
+        };
+        let per_percent = energy / dropped as f64;
+        if !(per_percent > 0.0 && per_percent <= MAX_WH_PER_PERCENT) {
+            return false;
+        }
+        let bucket = Self::bucket(midpoint);
+        self.wh[bucket] = Some(match self.wh[bucket] {
+            Some(prev) => LEARN_ALPHA * per_percent + (1.0 - LEARN_ALPHA) * prev,
+            None => per_percent,
+        });
+        self.steps[bucket] = self.steps[bucket].saturating_add(1);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(timestamp: u64, energy_now_wh: Option<f64>, power_w: f64) -> BatteryReading {
+        // 50 Wh pack whose gauge rounds like the kernel's capacity attribute
+        let percent = energy_now_wh.map_or(0, |wh| (wh / 50.0 * 100.0).floor() as u8);
+        BatteryReading {
+            timestamp,
+            capacity_percent: percent,
+            energy_now_wh,
+            energy_full_wh: Some(50.0),
+            power_now_w: Some(power_w),
+            voltage_v: Some(11.4),
+            current_ma: None,
+            status: "Discharging".to_string(),
+            temperature_c: None,
+            context: None,
+        }
+    }
+
+    #[test]
+    fn learns_half_a_watt_hour_per_percent() {
+        // 10 W for an hour from 90%, sampled every 10 s: 0.5 Wh per percent
+        let mut table = EnergyPerPercent::new("BAT0");
+        for t in (0..3600).step_by(10) {
+            table.learn(&reading(t, Some(45.0 - 10.0 * t as f64 / 3600.0), 10.0));
+        }
+        let (wh, from, to) = table.near(75).unwrap();
+        assert!((wh - 0.5).abs() < 0.01, "{}", wh);
+        assert_eq!((from, to), (70, 80));
+        assert!((table.energy_below(40).unwrap() - 20.0).abs() < 0.5);
+        assert_eq!(EnergyPerPercent::new("BAT0").energy_below(40), None);
+    }
+
+    #[test]
+    fn capacity_only_packs_learn_from_integrated_power() {
+        let mut table = EnergyPerPercent::new("BAT0");
+        for t in (0..3600).step_by(10) {
+            let energy = 45.0 - 10.0 * t as f64 / 3600.0;
+            let reading = BatteryReading { energy_now_wh: None, capacity_percent: (energy / 0.5).floor() as u8, ..reading(t, None, 10.0) };
+            table.learn(&reading);
+        }
+        assert!((table.near(80).unwrap().0 - 0.5).abs() < 0.02);
+        // A suspend gap restarts the measurement instead of counting as a huge step
+        let before = table.clone();
+        table.learn(&BatteryReading { capacity_percent: 60, ..reading(9000, None, 10.0) });
+        assert_eq!(table.steps, before.steps);
+    }
+}
@@ -0,0 +1,702 @@
+//! Time-to-empty/full, trend and fitting math on plain samples: no I/O, no clock, no display.
+
+use std::collections::VecDeque;
+
+use crate::BatteryReading;
+
+/// Exponential moving average factor for power
+pub const POWER_SMOOTHING_ALPHA: f64 = 0.25;
+/// Readings in a median smoother when `median` is given without a count
+const DEFAULT_MEDIAN_WINDOW: usize = 5;
+/// Smoothing methods `--smoothing` accepts
+pub const SMOOTHING_METHODS: [&str; 3] = ["ema", "median", "double-ema"];
+/// Minimum power in watts for calculations; below this is noise or an idle system
+pub const MIN_POWER_THRESHOLD: f64 = 0.05;
+/// Minimum samples before showing an estimate (at the default 2 s interval)
+pub const MIN_SAMPLES_FOR_ESTIMATE: usize = 3;
+/// Rolling average window for ultra-smooth estimates (at the default 2 s interval)
+pub const ROLLING_WINDOW_SIZE: usize = 10;
+/// How far back the reading and power histories reach by default
+pub const HISTORY_SECS: u64 = 600;
+/// Span of the rolling power window by default
+pub const ROLLING_WINDOW_SECS: u64 = 20;
+/// Interval the sample-count defaults above are tuned for
+const DEFAULT_INTERVAL_SECS: u64 = 2;
+/// Average change per sample (W) that counts as a power trend
+const TREND_THRESHOLD_W: f64 = 0.5;
+/// Samples the power trend looks back over
+const TREND_SAMPLES: usize = 5;
+/// How far back the capacity fit for %/h looks
+pub const DRAIN_RATE_WINDOW_SECS: u64 = 600;
+/// Fitted capacity change (%/h) that still counts as a stable charge level
+const TREND_DEAD_BAND_PERCENT_PER_HOUR: f64 = 0.5;
+/// How far back the ETA stability looks
+pub const ETA_STABILITY_WINDOW_SECS: u64 = 300;
+/// ETA stability below which the dashboard marks the estimate as rough
+pub const ROUGH_ETA_STABILITY: f64 = 0.85;
+/// Sustained loads the what-if table projects: light browsing to a full compile
+pub const WHAT_IF_LOADS_W: [f64; 4] = [5.0, 8.0, 12.0, 20.0];
+/// Paired samples before a temperature/power correlation is reported
+pub const MIN_CORRELATION_SAMPLES: usize = 10;
+
+/// Direction a series is moving in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trend {
+    Increasing,
+    Decreasing,
+    Stable,
+}
+
+impl Trend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Trend::Increasing => "increasing",
+            Trend::Decreasing => "decreasing",
+            Trend::Stable => "stable",
+        }
+    }
+}
+
+/// Sample counts for the history buffers, derived from spans of time so a 10 s interval
+/// keeps the same ten minutes of history as a 2 s one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowSizes {
+    /// Readings and power samples kept
+    pub history: usize,
+    /// Power readings in the rolling average
+    pub rolling: usize,
+    /// Samples before an estimate is shown
+    pub min_samples: usize,
+}
+
+impl WindowSizes {
+    pub fn for_interval(history_secs: u64, rolling_secs: u64, interval_secs: u64) -> Self {
+        let samples = |secs: u64| (secs / interval_secs.max(1)) as usize;
+        // The rolling average needs three readings; fewer would never mature
+        let rolling = samples(rolling_secs).max(3);
+        Self {
+            history: samples(history_secs).max(rolling),
+            rolling,
+            min_samples: (rolling * MIN_SAMPLES_FOR_ESTIMATE / ROLLING_WINDOW_SIZE).max(MIN_SAMPLES_FOR_ESTIMATE),
+        }
+    }
+}
+
+impl Default for WindowSizes {
+    fn default() -> Self {
+        Self::for_interval(HISTORY_SECS, ROLLING_WINDOW_SECS, DEFAULT_INTERVAL_SECS)
+    }
+}
+
+/// How power, current and temperature readings are smoothed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Smoothing {
+    /// Exponential moving average with this α; higher follows changes faster
+    Ema(f64),
+    /// Median of the last N readings: ignores short bursts entirely
+    Median(usize),
+    /// EMA of the EMA at this α: steadier than `Ema`, at the cost of more lag
+    DoubleEma(f64),
+}
+
+impl Default for Smoothing {
+    fn default() -> Self {
+        Smoothing::Ema(POWER_SMOOTHING_ALPHA)
+    }
+}
+
+impl Smoothing {
+    /// "ema", "ema:0.25", "median:15" or "double-ema[:α]"
+    pub fn parse(text: &str) -> Option<Self> {
+        let (method, parameter) = match text.trim().split_once(':') {
+            Some((method, parameter)) => (method, Some(parameter.trim())),
+            None => (text.trim(), None),
+        };
+        let alpha = || match parameter {
+            Some(alpha) => alpha.parse::<f64>().ok().filter(|a| *a > 0.0 && *a <= 1.0),
+            None => Some(POWER_SMOOTHING_ALPHA),
+        };
+        match method {
+            "ema" => alpha().map(Smoothing::Ema),
+            "double-ema" => alpha().map(Smoothing::DoubleEma),
+            "median" => match parameter {
+                Some(count) => count.parse::<usize>().ok().filter(|n| *n >= 1).map(Smoothing::Median),
+                None => Some(Smoothing::Median(DEFAULT_MEDIAN_WINDOW)),
+            },
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Smoothing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Smoothing::Ema(alpha) => write!(f, "ema:{}", alpha),
+            Smoothing::Median(count) => write!(f, "median:{}", count),
+            Smoothing::DoubleEma(alpha) => write!(f, "double-ema:{}", alpha),
+        }
+    }
+}
+
+/// Running state of one smoothed series
+#[derive(Debug, Clone)]
+pub struct Smoother {
+    method: Smoothing,
+    value: Option<f64>,
+    /// The first EMA stage of a double EMA
+    inner: Option<f64>,
+    /// Latest readings for the median
+    recent: VecDeque<f64>,
+}
+
+impl Smoother {
+    pub fn new(method: Smoothing) -> Self {
+        Self { method, value: None, inner: None, recent: VecDeque::new() }
+    }
+
+    /// Take one reading; returns the smoothed value
+    pub fn update(&mut self, reading: f64) -> f64 {
+        let smoothed = match self.method {
+            Smoothing::Ema(alpha) => ema(self.value, reading, alpha),
+            Smoothing::DoubleEma(alpha) => {
+                let inner = ema(self.inner, reading, alpha);
+                self.inner = Some(inner);
+                ema(self.value, inner, alpha)
+            }
+            Smoothing::Median(count) => {
+                self.recent.push_back(reading);
+                if self.recent.len() > count {
+                    self.recent.pop_front();
+                }
+                let mut sorted: Vec<f64> = self.recent.iter().copied().collect();
+                sorted.sort_by(f64::total_cmp);
+                let mid = sorted.len() / 2;
+                if sorted.len().is_multiple_of(2) { (sorted[mid - 1] + sorted[mid]) / 2.0 } else { sorted[mid] }
+            }
+        };
+        self.value = Some(smoothed);
+        smoothed
+    }
+
+    pub fn method(&self) -> Smoothing {
+        self.method
+    }
+
+    /// The smoothed value so far, None before the first reading
+    pub fn value(&self) -> Option<f64> {
+        self.value
+    }
+
+    /// Forget every reading, keeping the method
+    pub fn reset(&mut self) {
+        *self = Self::new(self.method);
+    }
+}
+
+/// One step of an exponential moving average, starting at the first value
+pub fn ema(previous: Option<f64>, value: f64, alpha: f64) -> f64 {
+    match previous {
+        Some(prev) => alpha * value + (1.0 - alpha) * prev,
+        None => value,
+    }
+}
+
+/// Mean of the rolling window, or None while it holds fewer than three samples
+pub fn rolling_average(window: &[f64]) -> Option<f64> {
+    if window.len() < 3 {
+        return None;
+    }
+    Some(window.iter().sum::<f64>() / window.len() as f64)
+}
+
+/// Blend of instantaneous, smoothed and rolling power: quick to adapt early, stable once mature
+pub fn weighted_power(instantaneous: f64, smoothed: f64, rolling: f64, samples: usize, sizes: WindowSizes) -> f64 {
+    if samples < 5 {
+        // Very early: mostly instantaneous for quick adaptation
+        0.8 * instantaneous + 0.2 * smoothed
+    } else if samples < sizes.rolling {
+        // Early: balance instantaneous and smoothed
+        0.5 * instantaneous + 0.5 * smoothed
+    } else {
+        // Mature: use all three methods for ultra-stable estimates
+        0.2 * instantaneous + 0.3 * smoothed + 0.5 * rolling
+    }
+}
+
+/// Share of charger power that ends up in the pack at a given fill (0.0-1.0);
+/// charging slows down significantly above 80%
+pub fn charging_efficiency(progress: f64) -> f64 {
+    if progress > 0.8 {
+        0.6 + (0.9 - progress) * 2.0 // Efficiency drops as we approach 100%
+    } else {
+        0.9
+    }
+}
+
+fn minutes(hours: f64) -> u32 {
+    (hours * 60.0).max(1.0) as u32 // At least 1 minute
+}
+
+/// Minutes `energy_wh` lasts at a constant `watts`
+pub fn runtime_at(energy_wh: f64, watts: f64) -> Option<u32> {
+    (energy_wh > 0.0 && watts >= MIN_POWER_THRESHOLD).then(|| minutes(energy_wh / watts))
+}
+
+/// Minutes to empty (discharging) or full (charging) for the latest reading.
+///
+/// `power_window` is the rolling window of recent power readings and `samples`
+/// the number of power samples seen so far.
+pub fn time_remaining(
+    reading: &BatteryReading,
+    smoothed_power: Option<f64>,
+    power_window: &[f64],
+    samples: usize,
+    sizes: WindowSizes,
+) -> Option<u32> {
+    let instantaneous_power = reading.power_now_w?;
+    let smoothed_power = smoothed_power?;
+    let rolling_power = rolling_average(power_window).unwrap_or(smoothed_power);
+
+    // Skip calculation if power is too low (likely noise or system idle)
+    if instantaneous_power.abs() < MIN_POWER_THRESHOLD || samples < sizes.min_samples {
+        return None;
+    }
+    let weighted = weighted_power(instantaneous_power, smoothed_power, rolling_power, samples, sizes);
+
+    match reading.status.as_str() {
+        "Discharging" => match reading.energy_now_wh {
+            // Time to drain = Current Energy / Power Consumption
+            Some(energy_now) => (weighted > 0.0).then(|| minutes(energy_now / weighted)),
+            None => discharge_fallback(reading),
+        },
+        "Charging" => match (reading.energy_now_wh, reading.energy_full_wh) {
+            (Some(energy_now), Some(energy_full)) => {
+                if weighted <= 0.0 {
+                    return None;
+                }
+                let effective_power = weighted * charging_efficiency(energy_now / energy_full);
+                Some(minutes((energy_full - energy_now) / effective_power))
+            }
+            _ => charge_fallback(reading),
+        },
+        // Battery is full or not charging
+        _ => None,
+    }
+}
+
+/// Without energy readings: a rough 3 Ah pack at the present voltage
+fn discharge_fallback(reading: &BatteryReading) -> Option<u32> {
+    let (voltage, current) = (reading.voltage_v?, reading.current_ma?);
+    if current >= 0 || voltage <= 0.0 {
+        return None;
+    }
+    let estimated_energy = voltage * 3.0 * (reading.capacity_percent as f64 / 100.0);
+    let power = voltage * ((-current) as f64 / 1000.0);
+    (power > MIN_POWER_THRESHOLD).then(|| minutes(estimated_energy / power))
+}
+
+/// Without energy readings: guess the pack size from its voltage
+fn charge_fallback(reading: &BatteryReading) -> Option<u32> {
+    let (voltage, current) = (reading.voltage_v?, reading.current_ma?);
+    if current <= 0 || voltage <= 0.0 {
+        return None;
+    }
+    let remaining_capacity = (100 - reading.capacity_percent.min(100)) as f64 / 100.0;
+    let estimated_full_capacity = match voltage {
+        v if v > 12.0 => 4.0, // Larger battery
+        v if v > 7.0 => 3.0,  // Standard laptop battery
+        _ => 2.0,             // Smaller battery
+    };
+    let energy_needed = voltage * estimated_full_capacity * remaining_capacity;
+    let efficiency = if reading.capacity_percent > 80 { 0.7 } else { 0.9 };
+    let effective_power = voltage * (current as f64 / 1000.0) * efficiency;
+    (effective_power > MIN_POWER_THRESHOLD).then(|| minutes(energy_needed / effective_power))
+}
+
+/// ETA in the charge domain: mAh left (or to fill) over the smoothed current draw
+pub fn charge_time_remaining(
+    status: &str,
+    charge_now_mah: f64,
+    charge_full_mah: Option<f64>,
+    smoothed_current_ma: f64,
+    samples: usize,
+    sizes: WindowSizes,
+) -> Option<u32> {
+    if smoothed_current_ma < 1.0 || samples < sizes.min_samples {
+        return None;
+    }
+    let hours = match status {
+        "Discharging" => charge_now_mah / smoothed_current_ma,
+        "Charging" => {
+            let full = charge_full_mah?;
+            // Same charging-curve slowdown as the energy estimate
+            let efficiency = charging_efficiency(charge_now_mah / full).max(0.3);
+            (full - charge_now_mah).max(0.0) / (smoothed_current_ma * efficiency)
+        }
+        _ => return None,
+    };
+    Some(minutes(hours))
+}
+
+/// Trend of the last few power readings, given oldest first
+pub fn power_trend(powers: &[f64]) -> Trend {
+    if powers.len() < TREND_SAMPLES {
+        return Trend::Stable;
+    }
+    let recent = &powers[powers.len() - TREND_SAMPLES..];
+    let avg_change = recent.windows(2).map(|w| w[1] - w[0]).sum::<f64>() / (recent.len() - 1) as f64;
+    if avg_change > TREND_THRESHOLD_W {
+        Trend::Increasing
+    } else if avg_change < -TREND_THRESHOLD_W {
+        Trend::Decreasing
+    } else {
+        Trend::Stable
+    }
+}
+
+/// Net direction of the last few capacity readings, given oldest first; the trend
+/// arrow falls back to it until there are enough timed readings for `percent_per_hour`
+#[cfg_attr(not(feature = "tui"), allow(dead_code))] // Only the dashboard shows it
+pub fn capacity_trend(capacities: &[u8]) -> Trend {
+    let recent = &capacities[capacities.len().saturating_sub(TREND_SAMPLES)..];
+    let change: i32 = recent.windows(2).map(|w| w[1] as i32 - w[0] as i32).sum();
+    match change {
+        c if c > 0 => Trend::Increasing,
+        c if c < 0 => Trend::Decreasing,
+        _ => Trend::Stable,
+    }
+}
+
+/// Capacity change in percent per hour, from a least-squares fit over the readings (oldest
+/// first) of the last `window_secs` that share the newest one's status; negative while
+/// draining. Uses the energy ratio where the pack reports one, as whole percents step too
+/// coarsely to fit over a few minutes.
+pub fn percent_per_hour<'a>(readings: impl DoubleEndedIterator<Item = &'a BatteryReading>, window_secs: u64) -> Option<f64> {
+    let mut readings = readings.rev().peekable();
+    let newest = *readings.peek()?;
+    let since = newest.timestamp.saturating_sub(window_secs);
+    let points: Vec<(f64, f64)> = readings
+        .take_while(|r| r.timestamp >= since && r.status == newest.status)
+        .map(|r| {
+            let percent = match (r.energy_now_wh, r.energy_full_wh) {
+                (Some(now), Some(full)) if full > 0.0 => now / full * 100.0,
+                _ => r.capacity_percent as f64,
+            };
+            (newest.timestamp.saturating_sub(r.timestamp) as f64, percent)
+        })
+        .collect();
+    if points.len() < MIN_SAMPLES_FOR_ESTIMATE {
+        return None;
+    }
+    // x counts seconds back from the newest reading, so the slope comes out negated
+    linear_fit(&points).map(|(slope, _)| -slope * 3600.0)
+}
+
+/// How steady the ETA has been over `(timestamp, minutes)` samples, oldest first: 1 minus
+/// the coefficient of variation of the predicted finish time, clamped to 0..=1. The finish
+/// time rather than the ETA itself, so an ETA counting down on schedule scores 1.
+pub fn eta_stability(etas: &[(u64, u32)]) -> Option<f64> {
+    if etas.len() < MIN_SAMPLES_FOR_ESTIMATE {
+        return None;
+    }
+    let n = etas.len() as f64;
+    let start = etas[0].0;
+    let finishes: Vec<f64> = etas.iter().map(|(t, eta)| (t - start) as f64 / 60.0 + *eta as f64).collect();
+    let mean_finish = finishes.iter().sum::<f64>() / n;
+    let mean_eta = etas.iter().map(|(_, eta)| *eta as f64).sum::<f64>() / n;
+    if mean_eta <= 0.0 {
+        return None;
+    }
+    let deviation = (finishes.iter().map(|f| (f - mean_finish).powi(2)).sum::<f64>() / n).sqrt();
+    Some((1.0 - deviation / mean_eta).clamp(0.0, 1.0))
+}
+
+/// Direction of a fitted capacity change, ignoring drift within the dead band
+pub fn rate_trend(percent_per_hour: f64) -> Trend {
+    if percent_per_hour > TREND_DEAD_BAND_PERCENT_PER_HOUR {
+        Trend::Increasing
+    } else if percent_per_hour < -TREND_DEAD_BAND_PERCENT_PER_HOUR {
+        Trend::Decreasing
+    } else {
+        Trend::Stable
+    }
+}
+
+/// Least-squares line through the points, as (slope, intercept); None while x doesn't vary
+pub fn linear_fit(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    let n = points.len() as f64;
+    if points.len() < 2 {
+        return None;
+    }
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    let sxy: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    if sxx <= f64::EPSILON * n {
+        return None;
+    }
+    let slope = sxy / sxx;
+    Some((slope, mean_y - slope * mean_x))
+}
+
+/// Pearson correlation of the points, in -1..=1; None while either coordinate is constant
+pub fn correlation(points: &[(f64, f64)]) -> Option<f64> {
+    let n = points.len() as f64;
+    if points.len() < 2 {
+        return None;
+    }
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    let syy: f64 = points.iter().map(|p| (p.1 - mean_y).powi(2)).sum();
+    let sxy: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    if sxx <= f64::EPSILON * n || syy <= f64::EPSILON * n {
+        return None;
+    }
+    Some((sxy / (sxx * syy).sqrt()).clamp(-1.0, 1.0))
+}
+
+/// How CPU temperature moves with power draw
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThermalCorrelation {
+    /// Pearson r of (power, temperature)
+    pub r: f64,
+    /// Fitted temperature rise per extra watt
+    pub celsius_per_watt: f64,
+    pub samples: usize,
+}
+
+/// Correlate (watts, °C) pairs once there are MIN_CORRELATION_SAMPLES of them
+pub fn thermal_correlation(points: &[(f64, f64)]) -> Option<ThermalCorrelation> {
+    if points.len() < MIN_CORRELATION_SAMPLES {
+        return None;
+    }
+    Some(ThermalCorrelation {
+        r: correlation(points)?,
+        celsius_per_watt: linear_fit(points)?.0,
+        samples: points.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn reading(status: &str, energy_now_wh: f64, energy_full_wh: f64, power_w: f64) -> BatteryReading {
+        BatteryReading {
+            timestamp: 0,
+            capacity_percent: (energy_now_wh / energy_full_wh * 100.0).round() as u8,
+            energy_now_wh: Some(energy_now_wh),
+            energy_full_wh: Some(energy_full_wh),
+            power_now_w: Some(power_w),
+            voltage_v: Some(12.0),
+            current_ma: None,
+            status: status.to_string(),
+            temperature_c: None,
+            context: None,
+        }
+    }
+
+    /// Feed readings through the same smoothing the monitor does; ETA after each
+    fn run(readings: &[BatteryReading]) -> Vec<Option<u32>> {
+        let mut smoothed = None;
+        let mut window: Vec<f64> = Vec::new();
+        readings
+            .iter()
+            .enumerate()
+            .map(|(i, r)| {
+                let power = r.power_now_w.unwrap();
+                smoothed = Some(ema(smoothed, power, POWER_SMOOTHING_ALPHA));
+                window.push(power);
+                if window.len() > ROLLING_WINDOW_SIZE {
+                    window.remove(0);
+                }
+                time_remaining(r, smoothed, &window, i + 1, WindowSizes::default())
+            })
+            .collect()
+    }
+
+    proptest! {
+        #[test]
+        fn ema_stays_between_previous_and_value(prev in 0.0..100.0f64, value in 0.0..100.0f64, alpha in 0.0..=1.0f64) {
+            let next = ema(Some(prev), value, alpha);
+            prop_assert!(next >= prev.min(value) - 1e-9 && next <= prev.max(value) + 1e-9);
+        }
+
+        #[test]
+        fn rolling_average_stays_within_window(window in prop::collection::vec(0.0..100.0f64, 3..20)) {
+            let avg = rolling_average(&window).unwrap();
+            let (min, max) = window.iter().fold((f64::MAX, f64::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+            prop_assert!(avg >= min - 1e-9 && avg <= max + 1e-9);
+        }
+
+        #[test]
+        fn weighted_power_stays_within_inputs(a in 0.1..100.0f64, b in 0.1..100.0f64, c in 0.1..100.0f64, samples in 0usize..50) {
+            let w = weighted_power(a, b, c, samples, WindowSizes::default());
+            prop_assert!(w >= a.min(b).min(c) - 1e-9 && w <= a.max(b).max(c) + 1e-9);
+        }
+
+        /// Monotonic energy at a positive draw always ends in a finite ETA
+        #[test]
+        fn falling_energy_gives_finite_eta(
+            start in 1.0..100.0f64,
+            drops in prop::collection::vec(0.0..0.5f64, MIN_SAMPLES_FOR_ESTIMATE..30),
+            powers in prop::collection::vec(0.1..60.0f64, 30),
+        ) {
+            let mut energy = start;
+            let readings: Vec<BatteryReading> = drops
+                .iter()
+                .zip(&powers)
+                .map(|(drop, power)| {
+                    energy = (energy - drop).max(0.01);
+                    reading("Discharging", energy, 100.0, *power)
+                })
+                .collect();
+            let etas = run(&readings);
+            for eta in &etas[..MIN_SAMPLES_FOR_ESTIMATE - 1] {
+                prop_assert!(eta.is_none());
+            }
+            let last = etas.last().unwrap().expect("an ETA once enough samples are in");
+            // Never more than energy over the smallest draw seen
+            prop_assert!(last >= 1 && (last as f64) <= energy / 0.1 * 60.0 + 1.0);
+        }
+
+        #[test]
+        fn more_power_never_means_more_time(energy in 1.0..100.0f64, low in 0.1..50.0f64, extra in 0.0..50.0f64) {
+            let window = [low; ROLLING_WINDOW_SIZE];
+            let slow = time_remaining(&reading("Discharging", energy, 100.0, low), Some(low), &window, 20, WindowSizes::default());
+            let window = [low + extra; ROLLING_WINDOW_SIZE];
+            let fast = time_remaining(&reading("Discharging", energy, 100.0, low + extra), Some(low + extra), &window, 20, WindowSizes::default());
+            prop_assert!(fast.unwrap() <= slow.unwrap());
+        }
+
+        #[test]
+        fn charging_eta_is_finite_below_full(energy in 0.0..49.9f64, power in 1.0..100.0f64) {
+            let window = [power; ROLLING_WINDOW_SIZE];
+            let eta = time_remaining(&reading("Charging", energy, 50.0, power), Some(power), &window, 20, WindowSizes::default());
+            prop_assert!(eta.is_some_and(|m| m >= 1));
+        }
+
+        #[test]
+        fn steady_power_has_no_trend(power in 0.0..100.0f64, len in 0usize..20) {
+            prop_assert_eq!(power_trend(&vec![power; len]), Trend::Stable);
+        }
+
+        #[test]
+        fn capacity_trend_follows_direction(start in 10u8..90, steps in 1u8..5) {
+            let rising: Vec<u8> = (0..=steps).map(|i| start + i).collect();
+            let falling: Vec<u8> = rising.iter().rev().copied().collect();
+            prop_assert_eq!(capacity_trend(&rising), Trend::Increasing);
+            prop_assert_eq!(capacity_trend(&falling), Trend::Decreasing);
+        }
+    }
+
+    #[test]
+    fn full_and_idle_have_no_eta() {
+        let window = [10.0; ROLLING_WINDOW_SIZE];
+        assert_eq!(time_remaining(&reading("Full", 50.0, 50.0, 10.0), Some(10.0), &window, 20, WindowSizes::default()), None);
+        assert_eq!(time_remaining(&reading("Discharging", 30.0, 50.0, 0.01), Some(0.01), &window, 20, WindowSizes::default()), None);
+    }
+
+    #[test]
+    fn smoothers_follow_their_method() {
+        assert_eq!(Smoothing::parse("ema:0.5"), Some(Smoothing::Ema(0.5)));
+        assert_eq!(Smoothing::parse("median"), Some(Smoothing::Median(DEFAULT_MEDIAN_WINDOW)));
+        assert_eq!(Smoothing::parse("double-ema"), Some(Smoothing::DoubleEma(POWER_SMOOTHING_ALPHA)));
+        assert_eq!(Smoothing::parse("ema:2"), None);
+        assert_eq!(Smoothing::parse("median:0"), None);
+        assert_eq!(Smoothing::parse(&Smoothing::Median(15).to_string()), Some(Smoothing::Median(15)));
+
+        // One burst among steady readings: the median ignores it, the EMAs move part way,
+        // the double EMA less so
+        let last = |method| {
+            let mut smoother = Smoother::new(method);
+            [10.0, 10.0, 40.0, 10.0].iter().map(|reading| smoother.update(*reading)).last().unwrap()
+        };
+        assert_eq!(last(Smoothing::Median(3)), 10.0);
+        let (single, double) = (last(Smoothing::Ema(0.25)), last(Smoothing::DoubleEma(0.25)));
+        assert!(10.0 < double && double < single, "{} vs {}", double, single);
+    }
+
+    #[test]
+    fn eta_stability_scores_the_finish_time() {
+        // Counting down on schedule is perfectly stable
+        let steady: Vec<(u64, u32)> = (0..5).map(|i| (i * 60, 100 - i as u32)).collect();
+        assert_eq!(eta_stability(&steady), Some(1.0));
+        let jumpy = [(0, 100), (60, 60), (120, 140), (180, 70)];
+        assert!(eta_stability(&jumpy).unwrap() < ROUGH_ETA_STABILITY);
+        assert_eq!(eta_stability(&steady[..2]), None);
+    }
+
+    #[test]
+    fn window_sizes_keep_their_span_across_intervals() {
+        assert_eq!(WindowSizes::default(), WindowSizes { history: 300, rolling: ROLLING_WINDOW_SIZE, min_samples: MIN_SAMPLES_FOR_ESTIMATE });
+        let slow = WindowSizes::for_interval(HISTORY_SECS, ROLLING_WINDOW_SECS, 10);
+        assert_eq!((slow.history, slow.rolling, slow.min_samples), (60, 3, MIN_SAMPLES_FOR_ESTIMATE));
+        let fast = WindowSizes::for_interval(HISTORY_SECS, ROLLING_WINDOW_SECS, 1);
+        assert_eq!((fast.history, fast.rolling, fast.min_samples), (600, 20, 6));
+    }
+
+    #[test]
+    fn charging_slows_above_eighty_percent() {
+        assert_eq!(charging_efficiency(0.5), 0.9);
+        assert!(charging_efficiency(0.9) < charging_efficiency(0.81));
+    }
+
+    #[test]
+    fn what_if_runtimes() {
+        assert_eq!(runtime_at(36.0, 12.0), Some(180));
+        assert_eq!(runtime_at(36.0, 0.0), None);
+        assert_eq!(runtime_at(0.0, 12.0), None);
+        let runtimes: Vec<u32> = WHAT_IF_LOADS_W.iter().filter_map(|&w| runtime_at(50.0, w)).collect();
+        assert!(runtimes.windows(2).all(|pair| pair[0] > pair[1]));
+    }
+
+    #[test]
+    fn drain_rate_fits_the_current_run() {
+        // 50 Wh pack losing 5.65 W: 11.3 %/h, with readings every 2 s for ten minutes
+        let draining = |t: u64| BatteryReading {
+            timestamp: 1000 + t,
+            ..reading("Discharging", 40.0 - 5.65 * t as f64 / 3600.0, 50.0, 5.65)
+        };
+        let mut readings: Vec<BatteryReading> = (0..300).map(|i| draining(i * 2)).collect();
+        let rate = percent_per_hour(readings.iter(), DRAIN_RATE_WINDOW_SECS).unwrap();
+        assert!((rate + 11.3).abs() < 1e-6, "{}", rate);
+        assert_eq!(rate_trend(rate), Trend::Decreasing);
+        // Plugging in starts a new fit rather than averaging across the switch
+        readings.push(BatteryReading { timestamp: 1600, ..reading("Charging", 39.0, 50.0, 20.0) });
+        assert_eq!(percent_per_hour(readings.iter(), DRAIN_RATE_WINDOW_SECS), None);
+        assert_eq!(rate_trend(0.2), Trend::Stable);
+    }
+
+    #[test]
+    fn heat_that_follows_load_correlates() {
+        // 40 °C at idle plus 1.5 °C per watt, with a little sensor jitter
+        let points: Vec<(f64, f64)> = (0..20).map(|i| {
+            let watts = 5.0 + i as f64;
+            (watts, 40.0 + 1.5 * watts + if i % 2 == 0 { 0.3 } else { -0.3 })
+        }).collect();
+        let thermal = thermal_correlation(&points).unwrap();
+        assert!(thermal.r > 0.99);
+        assert!((thermal.celsius_per_watt - 1.5).abs() < 0.05);
+        assert_eq!(thermal_correlation(&points[..MIN_CORRELATION_SAMPLES - 1]), None);
+        // A flat series has nothing to correlate with
+        assert_eq!(correlation(&[(5.0, 40.0), (6.0, 40.0), (7.0, 40.0)]), None);
+        assert_eq!(linear_fit(&[(5.0, 40.0), (5.0, 41.0)]), None);
+    }
+
+    proptest! {
+        #[test]
+        fn linear_fit_recovers_a_line(slope in -10.0..10.0f64, intercept in -100.0..100.0f64, xs in prop::collection::vec(-50.0..50.0f64, 2..50)) {
+            prop_assume!(xs.iter().any(|x| (x - xs[0]).abs() > 0.1));
+            let points: Vec<(f64, f64)> = xs.iter().map(|&x| (x, slope * x + intercept)).collect();
+            let (fit_slope, fit_intercept) = linear_fit(&points).unwrap();
+            prop_assert!((fit_slope - slope).abs() < 1e-6);
+            prop_assert!((fit_intercept - intercept).abs() < 1e-6);
+            if slope.abs() > 0.01 {
+                prop_assert!((correlation(&points).unwrap() - slope.signum()).abs() < 1e-9);
+            }
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use chrono::DateTime;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::estimation::{self, WindowSizes, MIN_SAMPLES_FOR_ESTIMATE, POWER_SMOOTHING_ALPHA, ROLLING_WINDOW_SIZE};
+use crate::BatteryReading;
+
+/// Estimators replayed by `batfi eval`; "batfi" is the blend the monitor shows
+const ESTIMATORS: [&str; 4] = ["batfi", "instant", "ema", "rolling"];
+/// Capacities at which predictions are scored
+const CHECKPOINTS: [u8; 3] = [80, 50, 20];
+/// Samples further apart than this split a recording into separate sessions (suspend, batfi stopped)
+const MAX_SESSION_GAP_SECS: u64 = 600;
+
+/// Mean absolute error of one estimator at one checkpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckpointError {
+    pub percent: u8,
+    pub points: usize,
+    pub mae_minutes: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EstimatorScore {
+    pub estimator: String,
+    pub checkpoints: Vec<CheckpointError>,
+    /// Over all checkpoints together
+    pub mae_minutes: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalReport {
+    pub files: usize,
+    pub sessions: usize,
+    pub samples: usize,
+    pub estimators: Vec<EstimatorScore>,
+}
+
+/// One reading from a recording: `batfi --json log` lines and usage.jsonl records both work
+fn parse_line(line: &str) -> Option<(String, BatteryReading)> {
+    let value: Value = serde_json::from_str(line).ok()?;
+    let timestamp = match &value["timestamp"] {
+        Value::String(text) => DateTime::parse_from_rfc3339(text).ok()?.timestamp() as u64,
+        other => other.as_u64()?,
+    };
+    let number = |key: &str| value[key].as_f64();
+    let reading = BatteryReading {
+        timestamp,
+        capacity_percent: value["capacity_percent"].as_u64()?.min(100) as u8,
+        energy_now_wh: number("energy_now_wh"),
+        energy_full_wh: number("energy_full_wh"),
+        power_now_w: number("power_now_w").or_else(|| number("power_w")).map(f64::abs),
+        voltage_v: number("voltage_v"),
+        current_ma: value["current_ma"].as_i64().map(|ma| ma as i32),
+        status: value["status"].as_str()?.to_string(),
+        temperature_c: number("temperature_c"),
+        context: None,
+    };
+    let battery = value["battery"].as_str().unwrap_or_default().to_string();
+    Some((battery, reading))
+}
+
+/// Uninterrupted runs of discharging readings, per recording and battery
+fn split_sessions(recordings: &[String]) -> Vec<Vec<BatteryReading>> {
+    let mut by_battery: BTreeMap<(usize, String), Vec<BatteryReading>> = BTreeMap::new();
+    for (file, text) in recordings.iter().enumerate() {
+        for (battery, reading) in text.lines().filter_map(parse_line) {
+            by_battery.entry((file, battery)).or_default().push(reading);
+        }
+    }
+
+    let mut sessions = Vec::new();
+    for mut readings in by_battery.into_values() {
+        readings.sort_by_key(|r| r.timestamp);
+        let mut current: Vec<BatteryReading> = Vec::new();
+        for reading in readings {
+            let continues = current
+                .last()
+                .is_some_and(|last| reading.timestamp.saturating_sub(last.timestamp) <= MAX_SESSION_GAP_SECS);
+            if !continues || reading.status != "Discharging" {
+                sessions.push(std::mem::take(&mut current));
+            }
+            if reading.status == "Discharging" {
+                current.push(reading);
+            }
+        }
+        sessions.push(current);
+    }
+    sessions.retain(|session| session.len() >= MIN_SAMPLES_FOR_ESTIMATE);
+    sessions
+}
+
+/// Minutes to empty from each estimator, given the smoothing state after `reading`
+fn predictions(reading: &BatteryReading, smoothed: f64, window: &[f64], samples: usize) -> [Option<f64>; 4] {
+    let to_empty = |power: Option<f64>| {
+        let (energy, power) = (reading.energy_now_wh?, power?);
+        (power >= estimation::MIN_POWER_THRESHOLD).then(|| energy / power * 60.0)
+    };
+    [
+        estimation::time_remaining(reading, Some(smoothed), window, samples, WindowSizes::default()).map(f64::from),
+        to_empty(reading.power_now_w),
+        to_empty(Some(smoothed)),
+        to_empty(estimation::rolling_average(window)),
+    ]
+}
+
+/// Absolute errors in minutes, indexed [estimator][checkpoint]
+///
+/// Sessions rarely run all the way to empty, so each time-to-empty prediction is
+/// scaled down to the share of the remaining charge that the session actually used,
+/// and compared with the time the session really took to get there.
+fn score_session(session: &[BatteryReading], errors: &mut [[Vec<f64>; 3]; 4]) {
+    let (first, last) = (&session[0], &session[session.len() - 1]);
+    let mut smoothed = None;
+    let mut window: Vec<f64> = Vec::new();
+    let mut pending: Vec<usize> = (0..CHECKPOINTS.len())
+        .filter(|&i| first.capacity_percent > CHECKPOINTS[i] && last.capacity_percent < CHECKPOINTS[i])
+        .collect();
+
+    for (i, reading) in session.iter().enumerate() {
+        if let Some(power) = reading.power_now_w {
+            smoothed = Some(estimation::ema(smoothed, power, POWER_SMOOTHING_ALPHA));
+            window.push(power);
+            if window.len() > ROLLING_WINDOW_SIZE {
+                window.remove(0);
+            }
+        }
+        let reached: Vec<usize> = pending.iter().copied().filter(|&c| reading.capacity_percent <= CHECKPOINTS[c]).collect();
+        if reached.is_empty() {
+            continue;
+        }
+        pending.retain(|c| !reached.contains(c));
+
+        let used_share = match (reading.energy_now_wh, last.energy_now_wh) {
+            (Some(now), Some(end)) if now > 0.0 => (now - end) / now,
+            _ => reading.capacity_percent.saturating_sub(last.capacity_percent) as f64 / reading.capacity_percent as f64,
+        };
+        let actual_minutes = (last.timestamp - reading.timestamp) as f64 / 60.0;
+        let Some(smoothed) = smoothed else { continue };
+        for (estimator, predicted) in predictions(reading, smoothed, &window, i + 1).into_iter().enumerate() {
+            let Some(predicted) = predicted else { continue };
+            for &checkpoint in &reached {
+                errors[estimator][checkpoint].push((predicted * used_share - actual_minutes).abs());
+            }
+        }
+    }
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Replay recorded sessions through every estimator
+pub fn evaluate(recordings: &[String]) -> EvalReport {
+    let sessions = split_sessions(recordings);
+    let mut errors: [[Vec<f64>; 3]; 4] = Default::default();
+    for session in &sessions {
+        score_session(session, &mut errors);
+    }
+
+    let estimators = ESTIMATORS
+        .iter()
+        .zip(&errors)
+        .map(|(name, per_checkpoint)| EstimatorScore {
+            estimator: name.to_string(),
+            checkpoints: CHECKPOINTS
+                .iter()
+                .zip(per_checkpoint)
+                .map(|(&percent, errors)| CheckpointError { percent, points: errors.len(), mae_minutes: mean(errors) })
+                .collect(),
+            mae_minutes: mean(&per_checkpoint.concat()),
+        })
+        .collect();
+
+    EvalReport {
+        files: recordings.len(),
+        sessions: sessions.len(),
+        samples: sessions.iter().map(Vec::len).sum(),
+        estimators,
+    }
+}
+
+/// `batfi eval FILE...`: mean absolute ETA error of each estimator over recorded discharges
+pub fn run_eval(eval_matches: &clap::ArgMatches, json_output: bool) {
+    let recordings: Vec<String> = eval_matches
+        .get_many::<String>("sessions")
+        .unwrap_or_default()
+        .map(|path| {
+            fs::read_to_string(path).unwrap_or_else(|e| {
+                eprintln!("❌ Could not read {}: {}", path, e);
+                std::process::exit(1);
+            })
+        })
+        .collect();
+    let report = evaluate(&recordings);
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap_or_else(|_| "{}".to_string()));
+        return;
+    }
+
+    println!("\x1b[1;36m╔══════════════════════════════════════════════════════════════╗\x1b[0m");
+    println!("\x1b[1;36m║\x1b[0m \x1b[1;37m🎯 Batfi Eval - ETA accuracy\x1b[0m");
+    println!("\x1b[1;36m╚══════════════════════════════════════════════════════════════╝\x1b[0m");
+    println!();
+    if report.sessions == 0 {
+        println!(" \x1b[2mNo discharge sessions found — record one with `batfi --json log -O session.ndjson`\x1b[0m");
+        return;
+    }
+    println!(
+        " {} discharge session{} ({} samples) from {} file{}",
+        report.sessions,
+        if report.sessions == 1 { "" } else { "s" },
+        report.samples,
+        report.files,
+        if report.files == 1 { "" } else { "s" },
+    );
+    println!();
+
+    let cell = |error: Option<f64>| error.map(|m| format!("{:>7.1}m", m)).unwrap_or_else(|| format!("{:>8}", "—"));
+    let best = report
+        .estimators
+        .iter()
+        .filter_map(|score| Some((score.estimator.as_str(), score.mae_minutes?)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(name, _)| name);
+    println!(" \x1b[1m{:<10}{:>8}{:>8}{:>8}{:>8}\x1b[0m", "Estimator", "@80%", "@50%", "@20%", "All");
+    for score in &report.estimators {
+        let cells: String = score.checkpoints.iter().map(|c| cell(c.mae_minutes)).collect();
+        let marker = if Some(score.estimator.as_str()) == best { " \x1b[32m◀ best\x1b[0m" } else { "" };
+        println!(" {:<10}{}{}{}", score.estimator, cells, cell(score.mae_minutes), marker);
+    }
+    println!();
+    let points: Vec<String> = CHECKPOINTS
+        .iter()
+        .enumerate()
+        .map(|(i, percent)| {
+            let scored = report.estimators.iter().map(|score| score.checkpoints[i].points).max().unwrap_or(0);
+            format!("{}% ×{}", percent, scored)
+        })
+        .collect();
+    println!(" \x1b[2mMean absolute error of predicted remaining time; scored points: {}\x1b[0m", points.join(", "));
+}
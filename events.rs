@@ -0,0 +1,95 @@
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+
+/// How far back the dashboard looks for its recent events panel
+#[cfg(feature = "tui")]
+const RECENT_WINDOW_SECS: u64 = 86_400;
+
+/// A timestamped, persisted battery event (anomalies, warnings, state changes)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub timestamp: u64,
+    pub battery: String,
+    pub kind: String,
+    pub message: String,
+}
+
+/// Location of the event log in a data directory (XDG_DATA_HOME/batfi/events.jsonl for the user's)
+pub fn events_log_path(dir: Option<&Path>) -> Option<PathBuf> {
+    Some(dir?.join("events.jsonl"))
+}
+
+/// Append an event to the persistent event log in `dir`
+pub fn log_event(dir: Option<&Path>, battery: &str, kind: &str, message: &str) -> std::io::Result<()> {
+    let path = events_log_path(dir)
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no data directory"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let event = Event {
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        battery: battery.to_string(),
+        kind: kind.to_string(),
+        message: message.to_string(),
+    };
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let line = serde_json::to_string(&event).map_err(std::io::Error::other)?;
+    writeln!(file, "{}", line)
+}
+
+/// Load events at or after `since` (Unix seconds), oldest first
+pub fn load_events(dir: Option<&Path>, since: u64) -> Vec<Event> {
+    let Some(file) = events_log_path(dir).and_then(|path| fs::File::open(path).ok()) else {
+        return Vec::new();
+    };
+
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<Event>(&line).ok())
+        .filter(|event| event.timestamp >= since)
+        .collect()
+}
+
+/// The newest `count` events for one battery from the last day, oldest first
+#[cfg(feature = "tui")]
+pub fn recent(dir: Option<&Path>, battery: &str, count: usize) -> Vec<Event> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let mut events: Vec<Event> = load_events(dir, now.saturating_sub(RECENT_WINDOW_SECS))
+        .into_iter()
+        .filter(|event| event.battery == battery)
+        .collect();
+    let skip = events.len().saturating_sub(count);
+    events.drain(..skip);
+    events
+}
+
+/// Color for an event kind in terminal output
+pub fn kind_color(kind: &str) -> &'static str {
+    match kind {
+        "plugged" | "full" | "charge-limited" => "\x1b[32m",
+        "unplugged" | "suspend" => "\x1b[36m",
+        "alert" | "voltage-sag" | "thermal-throttle" => "\x1b[33m",
+        "critical-action" => "\x1b[31m",
+        _ => "\x1b[37m",
+    }
+}
+
+/// Symbol marking an event kind under the dashboard's power graph
+#[cfg(feature = "tui")]
+pub fn graph_marker(kind: &str) -> char {
+    match kind {
+        "plugged" => '+',
+        "unplugged" => '-',
+        "suspend" => 'z',
+        "thermal-throttle" => 't',
+        "alert" | "voltage-sag" | "critical-action" => '!',
+        _ => '*',
+    }
+}
@@ -0,0 +1,109 @@
+use std::fs;
+use std::path::Path;
+
+use crate::estimation::MIN_POWER_THRESHOLD;
+use crate::quirks::Quirk;
+use crate::BatteryMonitor;
+
+fn has(base_path: &str, attr: &str) -> bool {
+    Path::new(base_path).join(attr).exists()
+}
+
+fn line(label: &str, detail: &str) {
+    println!(" ├─ {:<14} {}", label, detail);
+}
+
+/// First alternative whose files all exist, or `missing`
+fn source(base_path: &str, alternatives: &[(&[&str], &str)], missing: &str) -> String {
+    alternatives
+        .iter()
+        .find(|(attrs, _)| attrs.iter().all(|attr| has(base_path, attr)))
+        .map(|(_, how)| how.to_string())
+        .unwrap_or_else(|| format!("\x1b[33m{}\x1b[0m", missing))
+}
+
+/// `--explain`: show which files would be read and which code paths would run, without monitoring
+pub fn run_explain(monitor: &BatteryMonitor) {
+    let base = monitor.base_path();
+    let quirks = monitor.quirks();
+    println!("\x1b[1;36m╔══════════════════════════════════════════════════════════════╗\x1b[0m");
+    println!("\x1b[1;36m║\x1b[0m \x1b[1;37m🔎 Batfi Explain - {}\x1b[0m", monitor.battery_name());
+    println!("\x1b[1;36m╚══════════════════════════════════════════════════════════════╝\x1b[0m");
+    println!();
+
+    println!(" \x1b[1mBattery files ({}):\x1b[0m", base);
+    line("Status", &source(base, &[(&["status"], "status")], "missing - reported as Unknown"));
+    let capacity = source(base, &[(&["capacity"], "capacity")], "missing - reported as 0%");
+    if quirks.contains(&Quirk::CapacityStuckAt99) {
+        line("Capacity", &format!("{} (99% shown as 100% when Full)", capacity));
+    } else {
+        line("Capacity", &capacity);
+    }
+    line("Energy now", &source(base, &[
+        (&["energy_now"], "energy_now (µWh)"),
+        (&["charge_now", "voltage_now"], "charge_now × voltage_now (µAh × µV fallback)"),
+    ], "unavailable - no energy-based estimates"));
+    line("Energy full", &source(base, &[
+        (&["energy_full"], "energy_full (µWh)"),
+        (&["charge_full", "voltage_now"], "charge_full × voltage_now (µAh × µV fallback)"),
+    ], "unavailable"));
+    line("Power", &source(base, &[
+        (&["power_now"], "power_now (µW)"),
+        (&["voltage_now", "current_now"], "voltage_now × |current_now| (V×I fallback)"),
+    ], "unavailable - no power readings or estimates"));
+    let current = source(base, &[(&["current_now"], "current_now (µA)")], "unavailable");
+    if quirks.contains(&Quirk::CurrentSignInverted) {
+        line("Current", &format!("{} (sign inverted)", current));
+    } else {
+        line("Current", &current);
+    }
+    line("Health", &source(base, &[
+        (&["energy_full", "energy_full_design"], "energy_full ÷ energy_full_design"),
+        (&["charge_full", "charge_full_design"], "charge_full ÷ charge_full_design"),
+    ], "unavailable - shown as 0%"));
+    let identity: Vec<&str> = ["manufacturer", "model_name", "technology", "cycle_count"]
+        .into_iter()
+        .filter(|attr| has(base, attr))
+        .collect();
+    line("Identity", &if identity.is_empty() { "none".to_string() } else { identity.join(", ") });
+    println!();
+
+    println!(" \x1b[1mTemperature sensors (first valid one wins):\x1b[0m");
+    let temps = monitor.temperature_monitor();
+    for (role, sensors) in [("Battery", &temps.battery_sensors), ("CPU", &temps.cpu_sensors)] {
+        if sensors.is_empty() {
+            line(role, "\x1b[33mnone found\x1b[0m");
+        }
+        for sensor in sensors {
+            line(role, &format!("{} ({})", sensor.path, sensor.name));
+        }
+    }
+    if quirks.contains(&Quirk::TempDecidegrees) {
+        line("Quirk", "battery temp always read as decidegrees");
+    }
+    println!();
+
+    println!(" \x1b[1mTime estimate:\x1b[0m");
+    let status = fs::read_to_string(Path::new(base).join("status")).unwrap_or_default();
+    let has_energy = has(base, "energy_now") || (has(base, "charge_now") && has(base, "voltage_now"));
+    let has_energy_full = has(base, "energy_full") || (has(base, "charge_full") && has(base, "voltage_now"));
+    let has_vi = has(base, "voltage_now") && has(base, "current_now");
+    let path = match status.trim() {
+        "Discharging" if has_energy => "energy now ÷ blended power".to_string(),
+        "Discharging" if has_vi => "capacity% × assumed 3 Ah × voltage ÷ V×I (rough fallback)".to_string(),
+        "Charging" if has_energy && has_energy_full => {
+            "(energy full − energy now) ÷ (blended power × charging-curve efficiency)".to_string()
+        }
+        "Charging" if has_vi => "missing capacity% × voltage-class capacity ÷ V×I (rough fallback)".to_string(),
+        "Discharging" | "Charging" => "\x1b[33mnone - no energy or voltage/current readings\x1b[0m".to_string(),
+        other => format!("none while {}", if other.is_empty() { "Unknown" } else { other }),
+    };
+    line("Now", &format!("{} ({})", path, status.trim()));
+    let sizes = monitor.window_sizes();
+    line("Blend", &format!(
+        "80/20 instant/smoothed under 5 samples, 50/50 under {}, then 20% instant + 30% smoothed ({}) + 50% rolling mean",
+        sizes.rolling, monitor.smoothing()
+    ));
+    line("Needs", &format!("{} samples and more than {} W", sizes.min_samples, MIN_POWER_THRESHOLD));
+    line("History", &format!("{} samples at {}s intervals", sizes.history, monitor.update_interval().as_secs()));
+}
@@ -0,0 +1,71 @@
+/*
+ * batfi.h — C interface to batfi's battery sampling and estimation.
+ *
+ * Build with `cargo build --release -p batfi_ffi`, then link against
+ * target/release/libbatfi_ffi.so (or libbatfi_ffi.a plus -lpthread -ldl -lm).
+ *
+ *     BatfiMonitor *monitor = batfi_monitor_open(NULL);
+ *     BatfiReading reading;
+ *     while (monitor && batfi_monitor_poll(monitor, &reading) == BATFI_OK) {
+ *         printf("%d%% %.1f W\n", reading.capacity_percent, reading.smoothed_power_w);
+ *         sleep(2);
+ *     }
+ *     batfi_monitor_free(monitor);
+ *
+ * Keep one monitor open and poll it: the time estimates and smoothed power come
+ * from the samples it has seen so far.
+ */
+#ifndef BATFI_H
+#define BATFI_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define BATFI_OK 0
+#define BATFI_ERR_NULL (-1)       /* a pointer argument was NULL */
+#define BATFI_ERR_NO_BATTERY (-2) /* the battery isn't present */
+#define BATFI_ERR_PANIC (-3)      /* internal error; free the monitor */
+
+#define BATFI_STATUS_UNKNOWN 0
+#define BATFI_STATUS_CHARGING 1
+#define BATFI_STATUS_DISCHARGING 2
+#define BATFI_STATUS_FULL 3
+#define BATFI_STATUS_NOT_CHARGING 4
+
+typedef struct BatfiMonitor BatfiMonitor;
+
+/* One sample. Unknown values are NaN for doubles and -1 for integers. */
+typedef struct {
+    int status; /* BATFI_STATUS_* */
+    int capacity_percent;
+    int time_remaining_minutes; /* to empty, or to full while charging */
+    double power_w;
+    double smoothed_power_w;
+    double voltage_v;
+    double energy_now_wh;
+    double energy_full_wh;
+    double health_percent;
+    double temperature_c;
+    double cpu_temperature_c;
+    double percent_per_hour; /* fitted capacity change, negative while draining */
+} BatfiReading;
+
+/* Monitor for `battery` (e.g. "BAT0"), or the first battery when NULL; NULL if none.
+ * What it learns is kept in memory only. */
+BatfiMonitor *batfi_monitor_open(const char *battery);
+
+/* As batfi_monitor_open, saving what it learns under `data_dir` (nothing when NULL). */
+BatfiMonitor *batfi_monitor_open_in(const char *battery, const char *data_dir);
+
+/* Take a sample into *out; returns BATFI_OK or BATFI_ERR_*, leaving *out untouched on error. */
+int batfi_monitor_poll(BatfiMonitor *monitor, BatfiReading *out);
+
+/* Release a monitor; NULL is ignored. */
+void batfi_monitor_free(BatfiMonitor *monitor);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* BATFI_H */
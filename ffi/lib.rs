@@ -0,0 +1,209 @@
+//! C interface to batfi's sampling and estimation, for status bars and other native tools
+//! that would otherwise shell out to `batfi --json` and parse the output.
+//!
+//! The matching declarations are in `batfi.h`. A monitor keeps the smoothing and history
+//! that the time estimates need, so open one and poll it at your update interval rather
+//! than opening a fresh monitor per reading.
+//!
+//! A panic never unwinds into the caller: it is caught at the boundary and reported as
+//! NULL or `BATFI_ERR_PANIC`.
+
+use std::ffi::{c_char, c_int, CStr};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::ptr;
+
+use batfi::{BatteryInfo, BatteryMonitor};
+
+pub const BATFI_OK: c_int = 0;
+/// A pointer argument was NULL
+pub const BATFI_ERR_NULL: c_int = -1;
+/// The battery isn't present (removed, or the name doesn't exist)
+pub const BATFI_ERR_NO_BATTERY: c_int = -2;
+/// batfi hit an internal error; the monitor should be freed rather than polled again
+pub const BATFI_ERR_PANIC: c_int = -3;
+
+pub const BATFI_STATUS_UNKNOWN: c_int = 0;
+pub const BATFI_STATUS_CHARGING: c_int = 1;
+pub const BATFI_STATUS_DISCHARGING: c_int = 2;
+pub const BATFI_STATUS_FULL: c_int = 3;
+pub const BATFI_STATUS_NOT_CHARGING: c_int = 4;
+
+/// Opaque handle from `batfi_monitor_open`
+pub struct BatfiMonitor {
+    monitor: BatteryMonitor,
+}
+
+/// One sample. Unknown values are NaN for doubles and -1 for integers.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct BatfiReading {
+    pub status: c_int,
+    pub capacity_percent: c_int,
+    pub time_remaining_minutes: c_int,
+    pub power_w: f64,
+    pub smoothed_power_w: f64,
+    pub voltage_v: f64,
+    pub energy_now_wh: f64,
+    pub energy_full_wh: f64,
+    pub health_percent: f64,
+    pub temperature_c: f64,
+    pub cpu_temperature_c: f64,
+    /// Fitted capacity change, negative while draining
+    pub percent_per_hour: f64,
+}
+
+fn status_code(status: &str) -> c_int {
+    match status {
+        "Charging" => BATFI_STATUS_CHARGING,
+        "Discharging" => BATFI_STATUS_DISCHARGING,
+        "Full" => BATFI_STATUS_FULL,
+        "Not charging" => BATFI_STATUS_NOT_CHARGING,
+        _ => BATFI_STATUS_UNKNOWN,
+    }
+}
+
+impl From<&BatteryInfo> for BatfiReading {
+    fn from(info: &BatteryInfo) -> Self {
+        let or_nan = |value: Option<f64>| value.unwrap_or(f64::NAN);
+        BatfiReading {
+            status: status_code(&info.status),
+            capacity_percent: info.capacity_percent as c_int,
+            time_remaining_minutes: info.time_remaining_minutes.map_or(-1, |m| m.min(c_int::MAX as u32) as c_int),
+            power_w: or_nan(info.power_w),
+            smoothed_power_w: or_nan(info.smoothed_power_w),
+            voltage_v: or_nan(info.voltage_v),
+            energy_now_wh: or_nan(info.energy_now_wh),
+            energy_full_wh: or_nan(info.energy_full_wh),
+            health_percent: info.health_percent,
+            temperature_c: or_nan(info.temperature_c),
+            cpu_temperature_c: or_nan(info.cpu_temperature_c),
+            percent_per_hour: or_nan(info.percent_per_hour),
+        }
+    }
+}
+
+/// UTF-8 contents of a NUL-terminated string, or None for NULL; Err if it isn't UTF-8
+///
+/// # Safety
+/// `value` must be NULL or point to a NUL-terminated string.
+unsafe fn optional_str<'a>(value: *const c_char) -> Result<Option<&'a str>, ()> {
+    if value.is_null() {
+        return Ok(None);
+    }
+    // SAFETY: the caller passes a NUL-terminated string
+    unsafe { CStr::from_ptr(value) }.to_str().map(Some).map_err(|_| ())
+}
+
+/// Open a monitor for `battery` (e.g. "BAT0"), or the first battery found when NULL. It
+/// learns only in memory; use `batfi_monitor_open_in` to keep what it learns across runs.
+/// Returns NULL when there is no such battery or the name isn't valid UTF-8.
+///
+/// # Safety
+/// `battery` must be NULL or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn batfi_monitor_open(battery: *const c_char) -> *mut BatfiMonitor {
+    // SAFETY: forwarded from our caller; a NULL data_dir is allowed
+    unsafe { batfi_monitor_open_in(battery, ptr::null()) }
+}
+
+/// Like `batfi_monitor_open`, keeping learned curves, sessions and history under
+/// `data_dir`, or nowhere when NULL. Returns NULL as `batfi_monitor_open` does, and when
+/// `data_dir` isn't valid UTF-8.
+///
+/// # Safety
+/// `battery` and `data_dir` must each be NULL or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn batfi_monitor_open_in(battery: *const c_char, data_dir: *const c_char) -> *mut BatfiMonitor {
+    // SAFETY: the caller passes NULL or NUL-terminated strings
+    let (Ok(battery), Ok(data_dir)) = (unsafe { optional_str(battery) }, unsafe { optional_str(data_dir) }) else {
+        return ptr::null_mut();
+    };
+    panic::catch_unwind(|| {
+        let name = match battery {
+            Some(name) => name.to_string(),
+            None => batfi::find_batteries().into_iter().next()?,
+        };
+        if !std::path::Path::new(&batfi::power_supply_path(&name)).exists() {
+            return None;
+        }
+        let mut monitor = BatteryMonitor::new(&name);
+        monitor.set_data_dir(data_dir.map(PathBuf::from));
+        Some(Box::into_raw(Box::new(BatfiMonitor { monitor })))
+    })
+    .ok()
+    .flatten()
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Take a sample into `*out`. Returns BATFI_OK, or BATFI_ERR_* with `*out` untouched.
+///
+/// # Safety
+/// `monitor` must come from `batfi_monitor_open` and not be freed; `out` must be NULL or
+/// point to writable memory for a `BatfiReading`.
+#[no_mangle]
+pub unsafe extern "C" fn batfi_monitor_poll(monitor: *mut BatfiMonitor, out: *mut BatfiReading) -> c_int {
+    if monitor.is_null() || out.is_null() {
+        return BATFI_ERR_NULL;
+    }
+    // SAFETY: checked non-NULL; the caller guarantees it is a live handle
+    let monitor = unsafe { &mut *monitor };
+    match panic::catch_unwind(AssertUnwindSafe(|| monitor.monitor.get_battery_info())) {
+        Ok(Some(info)) => {
+            // SAFETY: checked non-NULL; the caller guarantees it is writable
+            unsafe { out.write(BatfiReading::from(&info)) };
+            BATFI_OK
+        }
+        Ok(None) => BATFI_ERR_NO_BATTERY,
+        Err(_) => BATFI_ERR_PANIC,
+    }
+}
+
+/// Release a monitor; NULL is ignored.
+///
+/// # Safety
+/// `monitor` must be NULL or come from `batfi_monitor_open`, and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn batfi_monitor_free(monitor: *mut BatfiMonitor) {
+    if !monitor.is_null() {
+        // SAFETY: the handle was created by Box::into_raw in batfi_monitor_open_in
+        let monitor = unsafe { Box::from_raw(monitor) };
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(monitor)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[test]
+    fn open_poll_free_round_trip() {
+        batfi::set_sysfs_root(concat!(env!("CARGO_MANIFEST_DIR"), "/../tests/fixtures/intel-laptop"));
+
+        let missing = CString::new("BAT9").unwrap();
+        unsafe {
+            assert!(batfi_monitor_open(missing.as_ptr()).is_null());
+            let monitor = batfi_monitor_open(ptr::null());
+            assert!(!monitor.is_null());
+            assert_eq!((*monitor).monitor.data_dir(), None);
+            batfi_monitor_free(monitor);
+
+            let dir = tempfile::tempdir().unwrap();
+            let data_dir = CString::new(dir.path().to_str().unwrap()).unwrap();
+            let monitor = batfi_monitor_open_in(ptr::null(), data_dir.as_ptr());
+            assert_eq!((*monitor).monitor.data_dir(), Some(dir.path()));
+
+            let mut reading = std::mem::MaybeUninit::<BatfiReading>::uninit();
+            assert_eq!(batfi_monitor_poll(monitor, ptr::null_mut()), BATFI_ERR_NULL);
+            assert_eq!(batfi_monitor_poll(monitor, reading.as_mut_ptr()), BATFI_OK);
+            let reading = reading.assume_init();
+            assert!((0..=100).contains(&reading.capacity_percent));
+            assert_ne!(reading.status, BATFI_STATUS_UNKNOWN);
+            assert!(reading.voltage_v > 0.0);
+
+            batfi_monitor_free(monitor);
+            batfi_monitor_free(ptr::null_mut());
+        }
+    }
+}
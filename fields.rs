@@ -0,0 +1,86 @@
+use serde_json::{Map, Value};
+
+use crate::BatteryInfo;
+
+/// Short names accepted in --fields, mapped to BatteryInfo keys
+const ALIASES: [(&str, &str); 6] = [
+    ("capacity", "capacity_percent"),
+    ("health", "health_percent"),
+    ("time_remaining", "time_remaining_minutes"),
+    ("power", "smoothed_power_w"),
+    ("temperature", "temperature_c"),
+    ("cpu_temperature", "cpu_temperature_c"),
+];
+
+fn resolve(name: &str) -> &str {
+    ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map_or(name, |(_, key)| key)
+}
+
+fn info_map(info: &BatteryInfo) -> Map<String, Value> {
+    match serde_json::to_value(info) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+/// Every selectable field name (BatteryInfo keys plus short aliases)
+pub fn known_fields() -> Vec<String> {
+    let mut names: Vec<String> = info_map(&BatteryInfo::default()).keys().cloned().collect();
+    names.extend(ALIASES.iter().map(|(alias, _)| alias.to_string()));
+    names.sort();
+    names
+}
+
+/// Check requested field names, returning the first unknown one
+pub fn validate(fields: &[String]) -> Result<(), String> {
+    let known = info_map(&BatteryInfo::default());
+    match fields.iter().find(|f| !known.contains_key(resolve(f))) {
+        Some(unknown) => Err(unknown.clone()),
+        None => Ok(()),
+    }
+}
+
+/// Raw value of a field, without units or markup; empty when unavailable
+fn plain_value(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(Value::Number(n)) => match n.as_f64() {
+            Some(f) if n.is_f64() => format!("{:.2}", f),
+            _ => n.to_string(),
+        },
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Selected fields joined by `separator`, for conky, lemonbar and shell scripts
+pub fn render_plain(info: &BatteryInfo, fields: &[String], separator: &str) -> String {
+    let map = info_map(info);
+    fields
+        .iter()
+        .map(|field| plain_value(map.get(resolve(field))))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Only the selected keys, in the requested order and under the requested names
+pub fn select(info: &BatteryInfo, fields: &[String]) -> Value {
+    let map = info_map(info);
+    let selected: Map<String, Value> = fields
+        .iter()
+        .map(|field| (field.clone(), map.get(resolve(field)).cloned().unwrap_or(Value::Null)))
+        .collect();
+    Value::Object(selected)
+}
+
+/// Pretty JSON of either the whole BatteryInfo or just the selected fields
+pub fn to_json(info: &BatteryInfo, fields: Option<&[String]>) -> String {
+    let value = match fields {
+        Some(fields) => select(info, fields),
+        None => serde_json::to_value(info).unwrap_or_default(),
+    };
+    serde_json::to_string_pretty(&value).unwrap_or_else(|_| "{}".to_string())
+}
@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::server::{error_json, read_request, write_response};
+use crate::{client, format_minutes, BatteryInfo, BatteryMonitor, ChargeLevel};
+
+/// How often a pushing host reports to the aggregator
+const FLEET_PUSH_INTERVAL_SECS: u64 = 10;
+/// Hosts silent for longer than this are shown as stale
+const FLEET_STALE_SECS: u64 = 60;
+/// Hosts silent for longer than this are dropped from the table
+const FLEET_EXPIRE_SECS: u64 = 600;
+/// Most (host, battery) pairs the aggregator tracks at once
+const FLEET_MAX_REPORTS: usize = 256;
+/// Connections handled at once; more are turned away until one finishes
+const FLEET_MAX_CONNECTIONS: usize = 32;
+/// Aggregator table refresh rate
+const FLEET_REFRESH_SECS: u64 = 2;
+
+/// One host's latest battery state, as pushed to the aggregator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FleetReport {
+    pub host: String,
+    pub battery: String,
+    pub timestamp: u64,
+    pub info: BatteryInfo,
+}
+
+type FleetState = Arc<Mutex<BTreeMap<(String, String), FleetReport>>>;
+
+/// Where `batfi fleet --listen` accepts reports without an address: this machine only, since
+/// the aggregator has no authentication
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:9123";
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+/// This machine's hostname, used as the default fleet name
+pub fn hostname() -> String {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .or_else(|_| fs::read_to_string("/etc/hostname"))
+        .map(|h| h.trim().to_string())
+        .ok()
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
+fn handle_fleet_connection(mut stream: std::net::TcpStream, state: &FleetState) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let request = match read_request(&mut stream) {
+        Ok(request) => request,
+        Err(e) => {
+            let (status, body) = e.response();
+            write_response(&mut stream, status, &body);
+            return;
+        }
+    };
+
+    match (request.method.as_str(), request.target.trim_end_matches('/')) {
+        ("POST", "/v1/report") => match serde_json::from_str::<FleetReport>(&request.body) {
+            Ok(mut report) => {
+                // Trust our own clock over the reporter's for staleness
+                report.timestamp = now_secs();
+                let key = (report.host.clone(), report.battery.clone());
+                let mut reports = state.lock().unwrap();
+                expire_reports(&mut reports, report.timestamp);
+                if !reports.contains_key(&key) && reports.len() >= FLEET_MAX_REPORTS {
+                    drop(reports);
+                    write_response(&mut stream, "503 Service Unavailable", &error_json("too many hosts reporting"));
+                    return;
+                }
+                reports.insert(key, report);
+                drop(reports);
+                write_response(&mut stream, "200 OK", "{}");
+            }
+            Err(e) => write_response(&mut stream, "400 Bad Request", &error_json(&e.to_string())),
+        },
+        ("GET", "/v1/fleet") => {
+            let reports: Vec<FleetReport> = state.lock().unwrap().values().cloned().collect();
+            let body = serde_json::to_string_pretty(&reports).unwrap_or_else(|_| "[]".to_string());
+            write_response(&mut stream, "200 OK", &body);
+        }
+        _ => write_response(&mut stream, "404 Not Found", &error_json("unknown endpoint")),
+    }
+}
+
+/// Forget hosts that stopped reporting
+fn expire_reports(reports: &mut BTreeMap<(String, String), FleetReport>, now: u64) {
+    reports.retain(|_, report| now.saturating_sub(report.timestamp) <= FLEET_EXPIRE_SECS);
+}
+
+/// A reporter-supplied name, safe to print: control characters (escape sequences included)
+/// become `?`
+fn printable(text: &str) -> String {
+    text.chars().map(|c| if c.is_control() { '?' } else { c }).collect()
+}
+
+fn display_fleet(reports: &[FleetReport], addr: &str) {
+    print!("\x1b[2J\x1b[H");
+    println!("\x1b[1;36m╔══════════════════════════════════════════════════════════════╗\x1b[0m");
+    println!("\x1b[1;36m║\x1b[0m \x1b[1;37m🛰️  Batfi Fleet - {} host(s) reporting to {}\x1b[0m", reports.len(), addr);
+    println!("\x1b[1;36m╚══════════════════════════════════════════════════════════════╝\x1b[0m");
+    println!();
+    println!(" \x1b[1m{:<18} {:<8} {:>6}  {:<12} {:>9} {:>8} {:>7} {:>9}\x1b[0m",
+        "HOST", "BATTERY", "CHARGE", "STATUS", "ETA", "POWER", "HEALTH", "SEEN");
+
+    let now = now_secs();
+    for report in reports {
+        let info = &report.info;
+        let age = now.saturating_sub(report.timestamp);
+        let charge_color = match ChargeLevel::from_capacity(info.capacity_percent) {
+            ChargeLevel::Critical => "\x1b[31m",
+            ChargeLevel::Low => "\x1b[33m",
+            ChargeLevel::Normal | ChargeLevel::High => "\x1b[32m",
+        };
+        let eta = info.time_remaining_minutes.map(format_minutes).unwrap_or_else(|| "—".to_string());
+        let power = info.smoothed_power_w.or(info.power_w)
+            .map(|p| format!("{:.1}W", p))
+            .unwrap_or_else(|| "—".to_string());
+        let (seen_color, seen) = if age > FLEET_STALE_SECS {
+            ("\x1b[2m", format!("{}s ago!", age))
+        } else {
+            ("", format!("{}s ago", age))
+        };
+        println!(" {}{:<18} {:<8} {}{:>5}%\x1b[0m{}  {:<12} {:>9} {:>8} {:>6.0}% {:>9}\x1b[0m",
+            seen_color, printable(&report.host), printable(&report.battery), charge_color, info.capacity_percent, seen_color,
+            printable(&info.status), eta, power, info.health_percent, seen);
+    }
+    if reports.is_empty() {
+        println!(" \x1b[2mWaiting for hosts… run `batfi fleet --push {}` on each machine\x1b[0m", addr);
+    }
+    println!();
+    println!(" \x1b[2mPress Ctrl+C to exit • Refreshing every {}s\x1b[0m", FLEET_REFRESH_SECS);
+    let _ = std::io::stdout().flush();
+}
+
+/// Aggregate reports from pushing hosts and render them as a live table
+pub fn run_aggregator(addr: &str, json_output: bool) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    let state: FleetState = Arc::new(Mutex::new(BTreeMap::new()));
+
+    let listener_state = Arc::clone(&state);
+    let active = Arc::new(AtomicUsize::new(0));
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            if active.fetch_add(1, Ordering::SeqCst) >= FLEET_MAX_CONNECTIONS {
+                active.fetch_sub(1, Ordering::SeqCst);
+                write_response(&mut stream, "503 Service Unavailable", &error_json("too many connections"));
+                continue;
+            }
+            let state = Arc::clone(&listener_state);
+            let active = Arc::clone(&active);
+            thread::spawn(move || {
+                handle_fleet_connection(stream, &state);
+                active.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    });
+
+    loop {
+        let reports: Vec<FleetReport> = {
+            let mut reports = state.lock().unwrap();
+            expire_reports(&mut reports, now_secs());
+            reports.values().cloned().collect()
+        };
+        if json_output {
+            println!("{}", serde_json::to_string(&reports).unwrap_or_else(|_| "[]".to_string()));
+        } else {
+            display_fleet(&reports, addr);
+        }
+        thread::sleep(Duration::from_secs(FLEET_REFRESH_SECS));
+    }
+}
+
+/// Sample locally and push a report to the aggregator every few seconds
+pub fn run_push(mut monitor: BatteryMonitor, battery: &str, addr: &str, host: &str) {
+    println!("📡 Pushing {} on {} to {} every {}s", battery, host, addr, FLEET_PUSH_INTERVAL_SECS);
+    let mut last_push = 0;
+    loop {
+        if let Some(info) = monitor.get_battery_info() {
+            let now = now_secs();
+            if now.saturating_sub(last_push) >= FLEET_PUSH_INTERVAL_SECS {
+                let report = FleetReport {
+                    host: host.to_string(),
+                    battery: battery.to_string(),
+                    timestamp: now,
+                    info,
+                };
+                let body = serde_json::to_string(&report).unwrap_or_else(|_| "{}".to_string());
+                if let Err(e) = client::post_json(addr, "/v1/report", &body) {
+                    eprintln!("⚠️  Push to {} failed: {}", addr, e);
+                }
+                last_push = now;
+            }
+        }
+        thread::sleep(monitor.update_interval());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(host: &str, timestamp: u64) -> FleetReport {
+        FleetReport { host: host.to_string(), battery: "BAT0".to_string(), timestamp, info: BatteryInfo::default() }
+    }
+
+    #[test]
+    fn silent_hosts_expire_and_names_print_safely() {
+        let mut reports = BTreeMap::new();
+        for (host, timestamp) in [("fresh", 1_000), ("gone", 1_000 - FLEET_EXPIRE_SECS - 1)] {
+            reports.insert((host.to_string(), "BAT0".to_string()), report(host, timestamp));
+        }
+        expire_reports(&mut reports, 1_000);
+        assert_eq!(reports.keys().map(|(host, _)| host.as_str()).collect::<Vec<_>>(), ["fresh"]);
+
+        assert_eq!(printable("evil\x1b]0;pwned\x07host"), "evil?]0;pwned?host");
+    }
+}
@@ -0,0 +1,146 @@
+//! What batfi itself costs: per-sample CPU time, and CPU and energy overhead over a run.
+
+use std::fs;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::BatteryMonitor;
+
+/// Ticks `batfi bench-self` runs unless told otherwise
+pub const DEFAULT_BENCH_TICKS: u32 = 50;
+/// Kernel clock ticks per second for /proc/<pid>/stat start times (USER_HZ is 100 on every Linux arch)
+const USER_HZ: f64 = 100.0;
+/// Extra draw of one fully busy laptop core over idle; batfi's CPU share is charged at this rate
+const BUSY_CORE_W: f64 = 2.0;
+
+fn cpu_clock(clock: libc::clockid_t) -> Option<Duration> {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: `ts` is a valid, writable timespec for the duration of the call
+    let ok = unsafe { libc::clock_gettime(clock, &mut ts) } == 0;
+    ok.then(|| Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32))
+}
+
+/// CPU time the calling thread has used, to the nanosecond
+pub fn thread_cpu_time() -> Option<Duration> {
+    cpu_clock(libc::CLOCK_THREAD_CPUTIME_ID)
+}
+
+/// CPU time of the whole process, D-Bus and tray threads included
+pub fn process_cpu_time() -> Option<Duration> {
+    cpu_clock(libc::CLOCK_PROCESS_CPUTIME_ID)
+}
+
+/// How long this process has been running, from its start time and the system uptime
+pub fn process_uptime() -> Option<Duration> {
+    let stat = fs::read_to_string("/proc/self/stat").ok()?;
+    // The command name may contain spaces; `starttime` is the 20th field after its closing parenthesis
+    let started: u64 = stat.rsplit_once(')')?.1.split_whitespace().nth(19)?.parse().ok()?;
+    let uptime: f64 = fs::read_to_string("/proc/uptime").ok()?.split_whitespace().next()?.parse().ok()?;
+    Some(Duration::from_secs_f64((uptime - started as f64 / USER_HZ).max(0.0)))
+}
+
+/// What batfi itself costs the battery
+#[derive(Debug, Clone, Serialize)]
+pub struct Footprint {
+    pub cpu_secs: f64,
+    pub running_secs: f64,
+    /// Share of one core, averaged over `running_secs`
+    pub cpu_percent: f64,
+    /// Estimated drain per hour at that share, in mWh
+    pub mwh_per_hour: f64,
+}
+
+impl Footprint {
+    fn new(cpu_secs: f64, running_secs: f64) -> Self {
+        let share = if running_secs > 0.0 { cpu_secs / running_secs } else { 0.0 };
+        Footprint { cpu_secs, running_secs, cpu_percent: share * 100.0, mwh_per_hour: share * BUSY_CORE_W * 1000.0 }
+    }
+
+    /// Since this process started
+    pub fn current() -> Option<Self> {
+        Some(Self::new(process_cpu_time()?.as_secs_f64(), process_uptime()?.as_secs_f64()))
+    }
+
+    /// An hour of sampling at the measured per-tick cost
+    pub fn hourly(cost: &TickCost) -> Option<Self> {
+        let ticks_per_hour = 3600.0 / cost.interval_secs as f64;
+        Some(Self::new(cost.cpu_per_tick_us? / 1e6 * ticks_per_hour, 3600.0))
+    }
+
+    /// Share of a battery draw of `power_w` that is batfi's doing
+    pub fn share_of_draw(&self, power_w: f64) -> Option<f64> {
+        (power_w > 0.0).then(|| self.mwh_per_hour / 1000.0 / power_w * 100.0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TickCost {
+    pub battery: String,
+    pub ticks: u32,
+    pub interval_secs: u64,
+    pub cpu_per_tick_us: Option<f64>,
+    pub wall_min_us: f64,
+    pub wall_median_us: f64,
+    pub wall_max_us: f64,
+    /// Share of one core spent sampling at the configured interval
+    pub cpu_percent_at_interval: Option<f64>,
+}
+
+/// Run `ticks` samples back to back and time each one
+pub fn measure_ticks(monitor: &mut BatteryMonitor, ticks: u32) -> TickCost {
+    // The first sample discovers sensors and loads state; time steady-state ticks only
+    monitor.get_battery_info();
+
+    let cpu_before = thread_cpu_time();
+    let mut walls: Vec<f64> = (0..ticks.max(1))
+        .map(|_| {
+            let start = Instant::now();
+            monitor.get_battery_info();
+            start.elapsed().as_secs_f64() * 1e6
+        })
+        .collect();
+    let cpu_per_tick_us = match (cpu_before, thread_cpu_time()) {
+        (Some(before), Some(after)) => Some(after.saturating_sub(before).as_secs_f64() * 1e6 / walls.len() as f64),
+        _ => None,
+    };
+    walls.sort_by(f64::total_cmp);
+
+    let interval_secs = monitor.update_interval().as_secs().max(1);
+    TickCost {
+        battery: monitor.battery_name().to_string(),
+        ticks: walls.len() as u32,
+        interval_secs,
+        cpu_per_tick_us,
+        wall_min_us: walls[0],
+        wall_median_us: walls[walls.len() / 2],
+        wall_max_us: walls[walls.len() - 1],
+        cpu_percent_at_interval: cpu_per_tick_us.map(|us| us / (interval_secs as f64 * 1e6) * 100.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hourly_projection_scales_with_interval() {
+        let cost = |interval_secs| TickCost {
+            battery: "BAT0".to_string(),
+            ticks: 10,
+            interval_secs,
+            cpu_per_tick_us: Some(500.0),
+            wall_min_us: 500.0,
+            wall_median_us: 500.0,
+            wall_max_us: 500.0,
+            cpu_percent_at_interval: None,
+        };
+        // 1800 ticks × 0.5 ms = 0.9 s of one core in an hour: 0.025%, 0.5 mWh at 2 W
+        let every_two = Footprint::hourly(&cost(2)).unwrap();
+        assert!((every_two.cpu_secs - 0.9).abs() < 1e-9);
+        assert!((every_two.cpu_percent - 0.025).abs() < 1e-9);
+        assert!((every_two.mwh_per_hour - 0.5).abs() < 1e-9);
+        assert!((Footprint::hourly(&cost(10)).unwrap().mwh_per_hour - 0.1).abs() < 1e-9);
+        assert!((every_two.share_of_draw(5.0).unwrap() - 0.01).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,212 @@
+//! Cross-checks power and capacity between independent sources — the driver's power_now,
+//! voltage × current, UPower, and RAPL as a floor — and keeps the most plausible one.
+//! Some laptops report power_now several times off from V×I; which one is wrong varies.
+
+use std::fs;
+use std::ops::RangeInclusive;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use zbus::blocking::{proxy, Connection, Proxy};
+use zbus::proxy::CacheProperties;
+
+use crate::sysfs_path;
+
+/// Power sources further apart than this share of the larger one disagree
+const POWER_DISAGREE_RATIO: f64 = 0.2;
+/// Capacity sources further apart than this many points disagree
+const CAPACITY_DISAGREE_POINTS: f64 = 5.0;
+/// Battery power outside this range is a firmware glitch, not a reading
+const PLAUSIBLE_POWER_W: RangeInclusive<f64> = 0.05..=250.0;
+/// On battery, the pack feeds the CPU package and more; allow this much meter error below it
+const PACKAGE_FLOOR_TOLERANCE: f64 = 0.9;
+
+/// One source's figure for an attribute
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourceValue {
+    pub source: String,
+    pub value: f64,
+}
+
+impl SourceValue {
+    pub fn new(source: &str, value: f64) -> Self {
+        Self { source: source.to_string(), value }
+    }
+}
+
+/// Sources that disagreed about an attribute in this sample, and the one batfi went with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Disagreement {
+    pub attribute: String,
+    pub chosen: String,
+    pub values: Vec<SourceValue>,
+}
+
+/// Where a sample's power and capacity came from, and what didn't add up
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DataQuality {
+    pub power_source: Option<String>,
+    pub capacity_source: Option<String>,
+    pub disagreements: Vec<Disagreement>,
+}
+
+/// The most plausible power figure, and the disagreement if the sources didn't agree.
+///
+/// Readings out of range or below the CPU package's own draw (`package_floor_w`, only
+/// while discharging) are ruled out. Of the rest, the one closest to `reference_w` (the
+/// fitted energy rate) wins; without one, the median of three or more, else the first.
+pub fn reconcile_power(
+    candidates: &[SourceValue],
+    package_floor_w: Option<f64>,
+    reference_w: Option<f64>,
+) -> (Option<SourceValue>, Option<Disagreement>) {
+    let plausible: Vec<&SourceValue> = candidates
+        .iter()
+        .filter(|c| PLAUSIBLE_POWER_W.contains(&c.value))
+        .filter(|c| package_floor_w.is_none_or(|floor| c.value >= floor * PACKAGE_FLOOR_TOLERANCE))
+        .collect();
+    let closest_to = |target: f64| plausible.iter().min_by(|a, b| (a.value - target).abs().total_cmp(&(b.value - target).abs()));
+    let chosen = match (plausible.len(), reference_w) {
+        (0, _) => candidates.first(),
+        (1, _) => plausible.first().copied(),
+        (_, Some(reference)) => closest_to(reference).copied(),
+        (n, None) if n >= 3 => {
+            let mut values: Vec<f64> = plausible.iter().map(|c| c.value).collect();
+            values.sort_by(f64::total_cmp);
+            closest_to(values[n / 2]).copied()
+        }
+        _ => plausible.first().copied(),
+    };
+
+    let (low, high) = spread(candidates);
+    let disagree = candidates.len() > 1 && high > 0.0 && (high - low) / high > POWER_DISAGREE_RATIO;
+    let disagreement = disagree.then(|| Disagreement {
+        attribute: "power".to_string(),
+        chosen: chosen.map(|c| c.source.clone()).unwrap_or_default(),
+        values: candidates.to_vec(),
+    });
+    (chosen.cloned(), disagreement)
+}
+
+/// The capacity to report: the driver's figure unless both other sources agree it is off
+pub fn reconcile_capacity(driver: SourceValue, others: &[SourceValue]) -> (SourceValue, Option<Disagreement>) {
+    let near = |a: f64, b: f64| (a - b).abs() <= CAPACITY_DISAGREE_POINTS;
+    let outvoted = others.len() >= 2
+        && near(others[0].value, others[1].value)
+        && others.iter().all(|other| !near(other.value, driver.value));
+    let chosen = if outvoted { others[0].clone() } else { driver.clone() };
+
+    let all: Vec<SourceValue> = std::iter::once(driver).chain(others.iter().cloned()).collect();
+    let (low, high) = spread(&all);
+    let disagreement = (high - low > CAPACITY_DISAGREE_POINTS).then(|| Disagreement {
+        attribute: "capacity".to_string(),
+        chosen: chosen.source.clone(),
+        values: all,
+    });
+    (chosen, disagreement)
+}
+
+fn spread(values: &[SourceValue]) -> (f64, f64) {
+    values.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), c| (low.min(c.value), high.max(c.value)))
+}
+
+fn upower_device(battery: &str) -> Option<Proxy<'static>> {
+    let connection = Connection::system().ok()?;
+    let device: Proxy<'static> = proxy::Builder::new(&connection)
+        .destination("org.freedesktop.UPower")
+        .ok()?
+        .path(format!("/org/freedesktop/UPower/devices/battery_{}", battery))
+        .ok()?
+        .interface("org.freedesktop.UPower.Device")
+        .ok()?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .ok()?;
+    // A proxy builds without the device existing; check it answers
+    device.get_property::<bool>("IsPresent").unwrap_or(false).then_some(device)
+}
+
+/// Sources outside the battery's own sysfs directory: UPower on the system bus, and RAPL
+pub struct ExternalSources {
+    upower: Option<Proxy<'static>>,
+    last_package: Option<(u64, Instant)>,
+}
+
+impl ExternalSources {
+    /// No outside sources, for simulated and scripted batteries
+    pub fn none() -> Self {
+        Self { upower: None, last_package: None }
+    }
+
+    /// UPower's view of `battery`, when the daemon is running, plus RAPL
+    pub fn system(battery: &str) -> Self {
+        Self { upower: upower_device(battery), last_package: None }
+    }
+
+    fn upower_property(&self, name: &str) -> Option<f64> {
+        self.upower.as_ref()?.get_property::<f64>(name).ok()
+    }
+
+    /// UPower's energy rate (W)
+    pub fn upower_power(&self) -> Option<f64> {
+        self.upower_property("EnergyRate").filter(|watts| *watts > 0.0)
+    }
+
+    /// UPower's percentage
+    pub fn upower_capacity(&self) -> Option<f64> {
+        self.upower_property("Percentage")
+    }
+
+    /// CPU package power (W) since the previous call, from the RAPL energy counter
+    pub fn package_power(&mut self) -> Option<f64> {
+        let energy_uj: u64 = fs::read_to_string(sysfs_path("class/powercap/intel-rapl:0/energy_uj")).ok()?.trim().parse().ok()?;
+        let now = Instant::now();
+        let previous = self.last_package.replace((energy_uj, now));
+        let (before, then) = previous?;
+        let secs = now.duration_since(then).as_secs_f64();
+        // The counter wraps around; skip that sample
+        (energy_uj >= before && secs > 0.0).then(|| (energy_uj - before) as f64 / 1_000_000.0 / secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn implausible_power_is_ruled_out_and_reported() {
+        // power_now reads 4x high; V×I and UPower agree
+        let candidates = [SourceValue::new("power_now", 41.0), SourceValue::new("voltage_current", 10.2), SourceValue::new("upower", 10.5)];
+        let (chosen, disagreement) = reconcile_power(&candidates, None, None);
+        assert_eq!(chosen.unwrap().source, "upower");
+        let disagreement = disagreement.unwrap();
+        assert_eq!(disagreement.chosen, "upower");
+        assert_eq!(disagreement.values.len(), 3);
+
+        // Two sources: the fitted energy rate decides, and the package floor rules out a too-low reading
+        let pair = [SourceValue::new("power_now", 3.0), SourceValue::new("voltage_current", 9.0)];
+        assert_eq!(reconcile_power(&pair, None, Some(8.0)).0.unwrap().source, "voltage_current");
+        assert_eq!(reconcile_power(&pair, Some(6.0), None).0.unwrap().source, "voltage_current");
+        assert_eq!(reconcile_power(&pair, None, None).0.unwrap().source, "power_now");
+
+        let agreeing = [SourceValue::new("power_now", 10.0), SourceValue::new("voltage_current", 10.4)];
+        assert_eq!(reconcile_power(&agreeing, None, None).1, None);
+    }
+
+    #[test]
+    fn driver_capacity_stands_unless_outvoted() {
+        let driver = SourceValue::new("capacity", 72.0);
+        let (chosen, disagreement) = reconcile_capacity(driver.clone(), &[SourceValue::new("energy_ratio", 71.0)]);
+        assert_eq!((chosen.source.as_str(), disagreement), ("capacity", None));
+
+        let others = [SourceValue::new("energy_ratio", 51.0), SourceValue::new("upower", 52.0)];
+        let (chosen, disagreement) = reconcile_capacity(driver.clone(), &others);
+        assert_eq!(chosen.source, "energy_ratio");
+        assert!(disagreement.is_some());
+
+        // One dissenting source reports a disagreement but doesn't win
+        let (chosen, disagreement) = reconcile_capacity(driver, &[SourceValue::new("energy_ratio", 60.0)]);
+        assert_eq!(chosen.source, "capacity");
+        assert_eq!(disagreement.unwrap().chosen, "capacity");
+    }
+}
@@ -0,0 +1,581 @@
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::attr::Attrs;
+use crate::backend::PowerSupplyBackend;
+use crate::session::ChargeSession;
+use crate::standby::{self, StandbySummary, SuspendRecord};
+use crate::usage::{self, PlugStats};
+use crate::{charge_limit, identity, BatteryReading};
+
+/// Typical Li-ion capacity fade from calendar aging alone (% per year at room temperature)
+const CALENDAR_FADE_PER_YEAR: f64 = 2.5;
+/// Typical Li-ion capacity fade per full charge cycle (%)
+pub const CYCLE_FADE_PER_CYCLE: f64 = 0.025;
+/// Smallest current step (mA) that gives a usable ΔV/ΔI reading
+const MIN_CURRENT_STEP_MA: i32 = 200;
+/// Maximum gap between two readings for them to count as one load step
+const MAX_STEP_GAP_SECS: u64 = 10;
+/// Plausible range for pack internal resistance (Ω)
+const MIN_RESISTANCE_OHM: f64 = 0.005;
+const MAX_RESISTANCE_OHM: f64 = 1.0;
+/// Resistance growth (%) that is flagged as an early degradation signal
+pub const RESISTANCE_RISE_WARN_PERCENT: f64 = 25.0;
+/// Conventional end-of-life threshold (% of design capacity)
+const END_OF_LIFE_HEALTH_PERCENT: f64 = 80.0;
+/// Reported energy drop (Wh) that closes one gauge-drift comparison segment
+pub const DRIFT_SEGMENT_WH: f64 = 2.0;
+/// Readings further apart than this break the power integration
+const DRIFT_MAX_GAP_SECS: u64 = 60;
+/// Recent segments considered for the drift verdict
+const DRIFT_SEGMENTS_CONSIDERED: usize = 10;
+pub const DRIFT_MIN_SEGMENTS: usize = 3;
+/// Gauge/integration disagreement (%) that warrants a calibration cycle
+const DRIFT_CALIBRATE_PERCENT: f64 = 8.0;
+/// Minimum span of history before a degradation trend is reported
+pub const MIN_TREND_SPAN_DAYS: f64 = 14.0;
+const DAYS_PER_MONTH: f64 = 30.44;
+/// Window for the plug-habit statistics
+const PLUG_STATS_WINDOW_SECS: u64 = 30 * 86_400;
+/// Share of time parked at full on AC that is worth a charge-limit suggestion
+const FULL_ON_AC_ADVICE_PERCENT: f64 = 50.0;
+/// Health checks closer together than this don't add a new capacity point
+const CAPACITY_RECORD_INTERVAL_SECS: u64 = 3600;
+
+/// Long-term health snapshot of a battery pack, independent of the live monitoring view
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatteryHealth {
+    pub battery: String,
+    pub manufacturer: String,
+    pub model: String,
+    pub technology: String,
+    pub serial_number: Option<String>,
+    pub manufacture_date: Option<String>,
+    pub age_days: Option<u64>,
+    /// When batfi first saw this pack; the age fallback when no manufacture date is exposed
+    pub first_seen_date: Option<String>,
+    pub age_from_first_seen: bool,
+    pub unit: String, // "Wh" or "mAh", whichever the battery reports natively
+    pub design_capacity: Option<f64>,
+    pub full_capacity: Option<f64>,
+    pub health_percent: Option<f64>,
+    pub wear_percent: Option<f64>,
+    pub cycles: Option<u32>,
+    pub wear_per_100_cycles: Option<f64>,
+    pub expected_calendar_wear_percent: Option<f64>,
+    pub expected_cycle_wear_percent: Option<f64>,
+    pub internal_resistance_mohm: Option<f64>,
+    pub internal_resistance_change_percent: Option<f64>,
+    pub wear_rate_percent_per_month: Option<f64>,
+    pub months_to_end_of_life: Option<f64>,
+    pub gauge_drift_wh: Option<f64>,
+    pub gauge_drift_percent: Option<f64>,
+    pub calibration_recommended: bool,
+    /// Plug habits over the last 30 days, once anything was recorded
+    #[serde(default)]
+    pub plug_stats: Option<PlugStats>,
+    /// Charge stop threshold below 100%, if one is set
+    #[serde(default)]
+    pub charge_limit: Option<u8>,
+    /// Deep idle residency and drain over recent suspends
+    #[serde(default)]
+    pub standby: Option<StandbySummary>,
+    pub grade: char,
+}
+
+/// One persisted point of the long-term health history
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HealthSample {
+    pub timestamp: u64,
+    pub battery: String,
+    pub full_capacity: Option<f64>,
+    pub design_capacity: Option<f64>,
+    pub cycles: Option<u32>,
+    pub internal_resistance_mohm: Option<f64>,
+    #[serde(default)]
+    pub gauge_reported_wh: Option<f64>,
+    #[serde(default)]
+    pub gauge_integrated_wh: Option<f64>,
+    #[serde(default)]
+    pub charge_session: Option<ChargeSession>,
+    #[serde(default)]
+    pub suspend: Option<SuspendRecord>,
+}
+
+/// Energy drop over one discharge segment: what the gauge reported vs integrated power
+#[derive(Debug, Clone, Copy)]
+pub struct GaugeDriftSegment {
+    pub reported_wh: f64,
+    pub integrated_wh: f64,
+}
+
+/// Coulomb-counts discharge energy (∫P dt) to cross-check the fuel gauge
+#[derive(Debug, Default)]
+pub struct GaugeDriftTracker {
+    start_energy_wh: Option<f64>,
+    integrated_wh: f64,
+    last: Option<(u64, f64)>, // (timestamp, power_w)
+}
+
+impl GaugeDriftTracker {
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Feed one reading; returns a finished segment once the gauge reports enough energy drop
+    pub fn update(&mut self, timestamp: u64, status: &str, power_w: f64, energy_wh: f64) -> Option<GaugeDriftSegment> {
+        if status != "Discharging" {
+            self.reset();
+            return None;
+        }
+
+        match self.last {
+            Some((last_ts, _)) if timestamp.saturating_sub(last_ts) > DRIFT_MAX_GAP_SECS || timestamp <= last_ts => {
+                // Suspend or stalled sampling: the integral is no longer trustworthy
+                self.reset();
+            }
+            Some((last_ts, last_power)) => {
+                let hours = (timestamp - last_ts) as f64 / 3600.0;
+                self.integrated_wh += (last_power + power_w) / 2.0 * hours;
+            }
+            None => {}
+        }
+
+        let start = *self.start_energy_wh.get_or_insert(energy_wh);
+        self.last = Some((timestamp, power_w));
+
+        let reported_wh = start - energy_wh;
+        if reported_wh < DRIFT_SEGMENT_WH {
+            return None;
+        }
+        let segment = GaugeDriftSegment { reported_wh, integrated_wh: self.integrated_wh };
+        self.start_energy_wh = Some(energy_wh);
+        self.integrated_wh = 0.0;
+        Some(segment)
+    }
+}
+
+/// Location of the health history log in a data directory (XDG_DATA_HOME/batfi/health.jsonl for the user's)
+pub fn health_history_path(dir: Option<&Path>) -> Option<PathBuf> {
+    Some(dir?.join("health.jsonl"))
+}
+
+/// Load all health samples recorded for a battery in `dir`, oldest first
+pub fn load_health_history(dir: Option<&Path>, battery_name: &str) -> Vec<HealthSample> {
+    let Some(path) = health_history_path(dir) else {
+        return Vec::new();
+    };
+    let Ok(file) = fs::File::open(path) else {
+        return Vec::new();
+    };
+
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<HealthSample>(&line).ok())
+        .filter(|sample| sample.battery == battery_name)
+        .collect()
+}
+
+/// Append a sample to the health history log in `dir`
+pub fn append_health_sample(dir: Option<&Path>, sample: &HealthSample) -> std::io::Result<()> {
+    let path = health_history_path(dir)
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no data directory"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let line = serde_json::to_string(sample).map_err(std::io::Error::other)?;
+    writeln!(file, "{}", line)
+}
+
+/// Move a battery's samples out of the live history in `dir` (e.g. after a pack replacement).
+///
+/// The old samples are kept under dir/archive so nothing is lost.
+pub fn archive_health_history(dir: Option<&Path>, battery_name: &str) -> std::io::Result<Option<PathBuf>> {
+    let Some(path) = health_history_path(dir) else {
+        return Ok(None);
+    };
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+
+    let (archived, kept): (Vec<&str>, Vec<&str>) = contents.lines().partition(|line| {
+        serde_json::from_str::<HealthSample>(line).is_ok_and(|s| s.battery == battery_name)
+    });
+    if archived.is_empty() {
+        return Ok(None);
+    }
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let archive_dir = path.with_file_name("archive");
+    fs::create_dir_all(&archive_dir)?;
+    let archive_path = archive_dir.join(format!("health-{}-{}.jsonl", battery_name, now));
+    fs::write(&archive_path, archived.join("\n") + "\n")?;
+
+    let remaining = if kept.is_empty() { String::new() } else { kept.join("\n") + "\n" };
+    fs::write(&path, remaining)?;
+    Ok(Some(archive_path))
+}
+
+/// Add a capacity-only point to the history in `dir`, at most once per hour per battery
+pub fn record_capacity_sample(dir: Option<&Path>, health: &BatteryHealth) -> std::io::Result<()> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let recently_recorded = load_health_history(dir, &health.battery)
+        .iter()
+        .rev()
+        .find(|s| s.full_capacity.is_some())
+        .is_some_and(|s| now.saturating_sub(s.timestamp) < CAPACITY_RECORD_INTERVAL_SECS);
+    if recently_recorded || health.full_capacity.is_none() {
+        return Ok(());
+    }
+    append_health_sample(dir, &HealthSample {
+        timestamp: now,
+        battery: health.battery.clone(),
+        full_capacity: health.full_capacity,
+        design_capacity: health.design_capacity,
+        cycles: health.cycles,
+        ..Default::default()
+    })
+}
+
+/// Estimate effective internal resistance (mΩ) from voltage sag across load steps.
+///
+/// Each pair of consecutive discharging readings with a large enough current change
+/// yields R = ΔV / ΔI; the median of all plausible pairs is returned.
+pub fn estimate_internal_resistance(readings: &[BatteryReading]) -> Option<f64> {
+    let mut estimates: Vec<f64> = readings
+        .windows(2)
+        .filter_map(|w| {
+            let (prev, cur) = (&w[0], &w[1]);
+            if prev.status != "Discharging" || cur.status != "Discharging" {
+                return None;
+            }
+            if cur.timestamp.saturating_sub(prev.timestamp) > MAX_STEP_GAP_SECS {
+                return None;
+            }
+            let (v_prev, v_cur) = (prev.voltage_v?, cur.voltage_v?);
+            // Drivers disagree on the sign of current_now, so work with magnitudes
+            let delta_i_ma = cur.current_ma?.abs() - prev.current_ma?.abs();
+            if delta_i_ma.abs() < MIN_CURRENT_STEP_MA {
+                return None;
+            }
+            let resistance = (v_prev - v_cur) / (delta_i_ma as f64 / 1000.0);
+            (MIN_RESISTANCE_OHM..=MAX_RESISTANCE_OHM)
+                .contains(&resistance)
+                .then_some(resistance * 1000.0)
+        })
+        .collect();
+
+    if estimates.len() < 3 {
+        return None;
+    }
+    estimates.sort_by(|a, b| a.total_cmp(b));
+    Some(estimates[estimates.len() / 2])
+}
+
+/// Percent change between the oldest and newest resistance estimates in the history
+fn resistance_change_percent(history: &[HealthSample]) -> Option<(f64, f64)> {
+    let mut with_resistance = history.iter().filter_map(|s| s.internal_resistance_mohm.map(|r| (s.timestamp, r)));
+    let (first_ts, first) = with_resistance.next()?;
+    let (last_ts, last) = with_resistance.next_back()?;
+    if last_ts <= first_ts || first <= 0.0 {
+        return None;
+    }
+    Some((last, (last - first) / first * 100.0))
+}
+
+/// Summarize recent drift segments: (reported − integrated Wh, percent, calibration advised)
+fn summarize_gauge_drift(history: &[HealthSample]) -> Option<(f64, f64, bool)> {
+    let segments: Vec<(f64, f64)> = history
+        .iter()
+        .rev()
+        .filter_map(|s| Some((s.gauge_reported_wh?, s.gauge_integrated_wh?)))
+        .take(DRIFT_SEGMENTS_CONSIDERED)
+        .collect();
+    if segments.len() < DRIFT_MIN_SEGMENTS {
+        return None;
+    }
+
+    let reported: f64 = segments.iter().map(|s| s.0).sum();
+    let integrated: f64 = segments.iter().map(|s| s.1).sum();
+    if integrated <= 0.0 {
+        return None;
+    }
+    let drift_wh = reported - integrated;
+    let drift_percent = drift_wh / integrated * 100.0;
+
+    // Persistent means most segments disagree in the same direction
+    let same_sign = segments.iter().filter(|s| (s.0 - s.1).signum() == drift_wh.signum()).count();
+    let persistent = same_sign * 4 >= segments.len() * 3;
+    Some((drift_wh, drift_percent, persistent && drift_percent.abs() >= DRIFT_CALIBRATE_PERCENT))
+}
+
+/// Health % (full / design) over time from the persisted history, oldest first
+pub fn capacity_trend_points(history: &[HealthSample]) -> Vec<(u64, f64)> {
+    history
+        .iter()
+        .filter_map(|s| match (s.full_capacity, s.design_capacity) {
+            (Some(full), Some(design)) if design > 0.0 => Some((s.timestamp, full / design * 100.0)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Least-squares fit of health % over time; returns the slope in % per month.
+fn fit_degradation_rate(history: &[HealthSample]) -> Option<f64> {
+    let points: Vec<(f64, f64)> = capacity_trend_points(history)
+        .into_iter()
+        .map(|(ts, health)| (ts as f64 / 86_400.0, health))
+        .collect();
+
+    let (first, last) = (points.first()?.0, points.last()?.0);
+    if points.len() < 2 || last - first < MIN_TREND_SPAN_DAYS {
+        return None;
+    }
+
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let covariance: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    let variance: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    if variance <= 0.0 {
+        return None;
+    }
+    Some(covariance / variance * DAYS_PER_MONTH)
+}
+
+/// Months until health drops to the end-of-life threshold at the fitted rate
+fn project_months_to_end_of_life(health_percent: f64, rate_per_month: f64) -> Option<f64> {
+    if health_percent <= END_OF_LIFE_HEALTH_PERCENT {
+        return Some(0.0);
+    }
+    if rate_per_month >= 0.0 {
+        return None; // No measurable degradation
+    }
+    Some((health_percent - END_OF_LIFE_HEALTH_PERCENT) / -rate_per_month)
+}
+
+fn read_attr(backend: &dyn PowerSupplyBackend, name: &str) -> Option<String> {
+    backend.read_attr(name).filter(|s| !s.is_empty())
+}
+
+fn read_number<T: TryFrom<i64>>(backend: &dyn PowerSupplyBackend, name: &str) -> Option<T> {
+    Attrs::new(|name: &str| read_attr(backend, name)).integer(name)
+}
+
+/// Days since the Unix epoch for a proleptic Gregorian date
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Civil date (YYYY-MM-DD, UTC) for a Unix timestamp
+pub fn format_date(timestamp: u64) -> String {
+    let z = (timestamp / 86_400) as i64 + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Read manufacture date exposed by the driver (manufacture_year/month/day)
+fn read_manufacture_date(backend: &dyn PowerSupplyBackend) -> Option<(i64, u32, u32)> {
+    let year = read_number::<i64>(backend, "manufacture_year").filter(|y| *y > 1990)?;
+    let month = read_number::<u32>(backend, "manufacture_month")
+        .filter(|m| (1..=12).contains(m))
+        .unwrap_or(1);
+    let day = read_number::<u32>(backend, "manufacture_day")
+        .filter(|d| (1..=31).contains(d))
+        .unwrap_or(1);
+    Some((year, month, day))
+}
+
+/// Letter grade from remaining capacity relative to design
+pub fn health_grade(health_percent: Option<f64>) -> char {
+    match health_percent {
+        Some(h) if h >= 90.0 => 'A',
+        Some(h) if h >= 80.0 => 'B',
+        Some(h) if h >= 70.0 => 'C',
+        Some(h) if h >= 60.0 => 'D',
+        Some(_) => 'F',
+        None => '?',
+    }
+}
+
+/// Gather health metrics for a battery from its backend, with the history, plug log and pack
+/// registry kept in `dir`
+pub fn read_battery_health(dir: Option<&Path>, battery_name: &str, backend: &dyn PowerSupplyBackend) -> BatteryHealth {
+    // Prefer energy_* and fall back to charge_*
+    let attrs = Attrs::new(|name: &str| read_attr(backend, name));
+    let energy = |name: &str| attrs.number(name).map(|e| attrs.energy_scale().apply(e));
+    let (unit, design_capacity, full_capacity) = match (energy("energy_full_design"), energy("energy_full")) {
+        (design, full) if design.is_some() || full.is_some() => ("Wh", design, full),
+        _ => ("mAh", attrs.charge_mah("full_design"), attrs.charge_mah("full")),
+    };
+
+    let health_percent = match (full_capacity, design_capacity) {
+        (Some(full), Some(design)) if design > 0.0 => Some((full / design) * 100.0),
+        _ => None,
+    };
+    let wear_percent = health_percent.map(|h| (100.0 - h).max(0.0));
+
+    let cycles = read_number::<u32>(backend, "cycle_count").filter(|c| *c > 0);
+    let wear_per_100_cycles = match (wear_percent, cycles) {
+        (Some(wear), Some(c)) => Some(wear / c as f64 * 100.0),
+        _ => None,
+    };
+
+    let manufacture = read_manufacture_date(backend);
+    let manufacture_date = manufacture.map(|(y, m, d)| format!("{:04}-{:02}-{:02}", y, m, d));
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let today = (now / 86_400) as i64;
+    let manufacture_age_days = manufacture
+        .map(|(y, m, d)| today - days_from_civil(y, m, d))
+        .filter(|days| *days >= 0)
+        .map(|days| days as u64);
+    let first_seen = identity::load_registry(dir).get(battery_name).map(|record| record.first_seen);
+    let first_seen_date = first_seen
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs as i64, 0))
+        .map(|date| date.format("%Y-%m-%d").to_string());
+    let age_from_first_seen = manufacture_age_days.is_none() && first_seen.is_some();
+    let age_days = manufacture_age_days.or_else(|| first_seen.map(|secs| now.saturating_sub(secs) / 86_400));
+
+    // A first-seen age is only a lower bound, too weak to judge calendar wear against
+    let expected_calendar_wear_percent = manufacture_age_days.map(|days| days as f64 / 365.25 * CALENDAR_FADE_PER_YEAR);
+    let expected_cycle_wear_percent = cycles.map(|c| c as f64 * CYCLE_FADE_PER_CYCLE);
+
+    let history = load_health_history(dir, battery_name);
+    let (internal_resistance_mohm, internal_resistance_change_percent) = match resistance_change_percent(&history) {
+        Some((latest, change)) => (Some(latest), Some(change)),
+        None => (history.iter().rev().find_map(|s| s.internal_resistance_mohm), None),
+    };
+
+    let wear_rate_percent_per_month = fit_degradation_rate(&history);
+    let gauge_drift = summarize_gauge_drift(&history);
+    let months_to_end_of_life = match (health_percent, wear_rate_percent_per_month) {
+        (Some(h), Some(rate)) => project_months_to_end_of_life(h, rate),
+        (Some(h), None) if h <= END_OF_LIFE_HEALTH_PERCENT => Some(0.0),
+        _ => None,
+    };
+
+    BatteryHealth {
+        battery: battery_name.to_string(),
+        manufacturer: read_attr(backend, "manufacturer").unwrap_or_else(|| "Unknown".to_string()),
+        model: read_attr(backend, "model_name").unwrap_or_else(|| "Unknown".to_string()),
+        technology: read_attr(backend, "technology").unwrap_or_else(|| "Unknown".to_string()),
+        serial_number: read_attr(backend, "serial_number"),
+        manufacture_date,
+        age_days,
+        first_seen_date,
+        age_from_first_seen,
+        unit: unit.to_string(),
+        design_capacity,
+        full_capacity,
+        health_percent,
+        wear_percent,
+        cycles,
+        wear_per_100_cycles,
+        expected_calendar_wear_percent,
+        expected_cycle_wear_percent,
+        internal_resistance_mohm,
+        internal_resistance_change_percent,
+        wear_rate_percent_per_month,
+        months_to_end_of_life,
+        gauge_drift_wh: gauge_drift.map(|d| d.0),
+        gauge_drift_percent: gauge_drift.map(|d| d.1),
+        calibration_recommended: gauge_drift.is_some_and(|d| d.2),
+        plug_stats: Some(usage::plug_stats(dir, battery_name, now.saturating_sub(PLUG_STATS_WINDOW_SECS)))
+            .filter(|stats| stats.plugs + stats.unplugs > 0 || stats.percent_of_time(0).is_some()),
+        charge_limit: charge_limit::limit_from(backend).filter(|limit| *limit < 100),
+        standby: standby::summarize(history.iter().filter_map(|s| s.suspend.as_ref())),
+        grade: health_grade(health_percent),
+    }
+}
+
+/// Plain-text end-of-life projection, shared by the terminal view and exports
+pub fn end_of_life_summary(health: &BatteryHealth) -> Option<String> {
+    let months = health.months_to_end_of_life?;
+    if months <= 0.0 {
+        return Some(format!("Already below {:.0}% of design capacity", END_OF_LIFE_HEALTH_PERCENT));
+    }
+    let horizon = if months >= 24.0 {
+        format!("~{:.1} years", months / 12.0)
+    } else {
+        format!("~{:.0} months", months.max(1.0))
+    };
+    Some(format!(
+        "Projected to reach {:.0}% design capacity in {} at current usage",
+        END_OF_LIFE_HEALTH_PERCENT, horizon
+    ))
+}
+
+/// Actionable advice derived from the health metrics, most important first
+pub fn recommendations(health: &BatteryHealth) -> Vec<String> {
+    let mut advice = Vec::new();
+
+    if health.health_percent.is_some_and(|h| h < END_OF_LIFE_HEALTH_PERCENT) {
+        advice.push(format!(
+            "Capacity is below {:.0}% of design — the pack is at end of life; consider a replacement or warranty claim",
+            END_OF_LIFE_HEALTH_PERCENT
+        ));
+    } else if health.months_to_end_of_life.is_some_and(|m| m < 12.0) {
+        advice.push("Capacity is declining quickly — plan a replacement within the next year".to_string());
+    }
+    if health.calibration_recommended {
+        advice.push(format!(
+            "Fuel gauge is off by {:+.1} Wh — run a calibration cycle (full charge, discharge to ~5%, full charge)",
+            health.gauge_drift_wh.unwrap_or(0.0)
+        ));
+    }
+    if health.internal_resistance_change_percent.is_some_and(|c| c >= RESISTANCE_RISE_WARN_PERCENT) {
+        advice.push("Internal resistance is rising — expect voltage sag and early shutdowns under heavy load".to_string());
+    }
+    if let (Some(calendar), Some(wear)) = (health.expected_calendar_wear_percent, health.wear_percent) {
+        if wear > calendar + health.expected_cycle_wear_percent.unwrap_or(0.0) {
+            advice.push("Wear is faster than age and cycle count explain — avoid heat and long periods at 100%".to_string());
+        }
+    }
+    if let Some(percent) = health.plug_stats.as_ref().and_then(|s| s.percent_of_time(s.full_on_ac_secs)) {
+        if percent >= FULL_ON_AC_ADVICE_PERCENT && health.charge_limit.is_none() {
+            advice.push(format!(
+                "Your battery spends {:.0}% of its life at 100% on AC — a charge limit (`batfi limit 80`) slows wear",
+                percent
+            ));
+        }
+    }
+    if let Some(standby) = health.standby.as_ref().filter(|s| s.measured >= 2 && s.reached_deep_idle * 2 < s.measured) {
+        advice.push(format!(
+            "Only {} of {} suspends reached deep idle (S0ix) — a device is keeping the machine awake; see `batfi doctor`",
+            standby.reached_deep_idle, standby.measured
+        ));
+    }
+    if advice.is_empty() {
+        advice.push("No action needed — the battery is aging normally".to_string());
+    }
+    advice
+}
+
+pub fn format_age(days: u64) -> String {
+    let years = days / 365;
+    let months = (days % 365) / 30;
+    if years > 0 {
+        format!("{}y {}m", years, months)
+    } else {
+        format!("{}m {}d", months, days % 30)
+    }
+}
@@ -0,0 +1,253 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::health::{self, BatteryHealth, HealthSample};
+use crate::standby;
+
+const CHART_WIDTH: usize = 60;
+const CHART_HEIGHT: usize = 10;
+const SVG_WIDTH: f64 = 640.0;
+const SVG_HEIGHT: f64 = 240.0;
+const SVG_MARGIN: f64 = 40.0;
+/// Most recent charge sessions listed in the report
+const REPORT_SESSIONS: usize = 10;
+
+/// Metric rows shared by the Markdown and HTML documents
+fn metric_rows(report: &BatteryHealth) -> Vec<(&'static str, String)> {
+    let dash = || "—".to_string();
+    let cap = |v: Option<f64>| v.map(|v| format!("{:.1} {}", v, report.unit)).unwrap_or_else(dash);
+
+    vec![
+        ("Grade", match report.health_percent {
+            Some(h) => format!("{} ({:.1}% of design capacity)", report.grade, h),
+            None => "? (design capacity not reported)".to_string(),
+        }),
+        ("Pack", format!("{} {} ({})", report.manufacturer, report.model, report.technology)),
+        ("Serial number", report.serial_number.clone().unwrap_or_else(dash)),
+        ("Manufactured", match (&report.manufacture_date, report.age_days) {
+            (Some(date), Some(days)) => format!("{} ({} old)", date, health::format_age(days)),
+            _ => dash(),
+        }),
+        ("First seen", match (&report.first_seen_date, report.age_days) {
+            (Some(date), Some(days)) if report.age_from_first_seen => {
+                format!("{} (at least {} old)", date, health::format_age(days))
+            }
+            (Some(date), _) => date.clone(),
+            _ => dash(),
+        }),
+        ("Design capacity", cap(report.design_capacity)),
+        ("Full capacity", cap(report.full_capacity)),
+        ("Wear", report.wear_percent.map(|w| format!("{:.1}%", w)).unwrap_or_else(dash)),
+        ("Cycle count", report.cycles.map(|c| c.to_string()).unwrap_or_else(dash)),
+        ("Wear per 100 cycles", report.wear_per_100_cycles.map(|w| format!("{:.2}%", w)).unwrap_or_else(dash)),
+        ("Internal resistance", match (report.internal_resistance_mohm, report.internal_resistance_change_percent) {
+            (Some(r), Some(change)) => format!("{:.0} mΩ ({:+.0}% since first measured)", r, change),
+            (Some(r), None) => format!("{:.0} mΩ", r),
+            _ => dash(),
+        }),
+        ("Fuel gauge drift", match (report.gauge_drift_wh, report.gauge_drift_percent) {
+            (Some(wh), Some(pct)) => format!("{:+.2} Wh ({:+.1}%)", wh, pct),
+            _ => dash(),
+        }),
+        ("Time at full on AC", report.plug_stats
+            .as_ref()
+            .and_then(|s| s.percent_of_time(s.full_on_ac_secs))
+            .map(|p| format!("{:.0}% of the last 30 days", p))
+            .unwrap_or_else(dash)),
+        ("Standby", report.standby.as_ref().map(standby::summary_line).unwrap_or_else(dash)),
+        ("Degradation trend", report.wear_rate_percent_per_month
+            .map(|r| format!("{:+.2}% of design per month", r))
+            .unwrap_or_else(dash)),
+        ("Projection", health::end_of_life_summary(report).unwrap_or_else(dash)),
+    ]
+}
+
+/// Recent charging sessions, newest first, e.g. "2026-03-02 43%→71%: +18.3 Wh in 38m (avg 28.9 W)"
+fn session_lines(history: &[HealthSample]) -> Vec<String> {
+    history
+        .iter()
+        .rev()
+        .filter_map(|sample| sample.charge_session.as_ref())
+        .take(REPORT_SESSIONS)
+        .map(|session| format!(
+            "{} {}%→{}%: +{:.1} Wh in {} (avg {:.1} W)",
+            health::format_date(session.start),
+            session.start_percent,
+            session.end_percent,
+            session.energy_added_wh,
+            crate::format_minutes((session.duration_secs / 60) as u32),
+            session.average_power_w
+        ))
+        .collect()
+}
+
+fn generated_line() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    format!("Generated {} by batfi {}", health::format_date(now), env!("CARGO_PKG_VERSION"))
+}
+
+/// Plain-text line chart of health % over time for the Markdown document
+fn text_chart(points: &[(u64, f64)]) -> String {
+    if points.len() < 2 {
+        return "Not enough history yet — run `batfi health` periodically to build the chart.".to_string();
+    }
+
+    // Resample to the chart width, keeping the most recent point last
+    let columns: Vec<f64> = (0..CHART_WIDTH.min(points.len()))
+        .map(|i| points[i * (points.len() - 1) / (CHART_WIDTH.min(points.len()) - 1).max(1)].1)
+        .collect();
+    let max = columns.iter().cloned().fold(f64::NEG_INFINITY, f64::max).ceil();
+    let min = columns.iter().cloned().fold(f64::INFINITY, f64::min).floor();
+    let range = (max - min).max(1.0);
+
+    let mut lines = Vec::new();
+    for row in 0..CHART_HEIGHT {
+        let level = max - range * row as f64 / (CHART_HEIGHT - 1) as f64;
+        let cells: String = columns
+            .iter()
+            .map(|v| {
+                let cell = ((max - v) / range * (CHART_HEIGHT - 1) as f64).round() as usize;
+                if cell == row { '●' } else { ' ' }
+            })
+            .collect();
+        lines.push(format!("{:>6.1}% ┤{}", level, cells.trim_end()));
+    }
+    lines.push(format!("        └{}", "─".repeat(columns.len())));
+    lines.push(format!(
+        "         {} → {}",
+        health::format_date(points[0].0),
+        health::format_date(points[points.len() - 1].0)
+    ));
+    lines.join("\n")
+}
+
+pub fn render_markdown(report: &BatteryHealth, history: &[HealthSample]) -> String {
+    let mut doc = String::new();
+    doc.push_str(&format!("# Battery Health Report — {}\n\n", report.battery));
+    doc.push_str(&format!("_{}_\n\n", generated_line()));
+
+    doc.push_str("| Metric | Value |\n|---|---|\n");
+    for (name, value) in metric_rows(report) {
+        doc.push_str(&format!("| {} | {} |\n", name, value.replace('|', "\\|")));
+    }
+
+    doc.push_str("\n## Capacity history\n\n```text\n");
+    doc.push_str(&text_chart(&health::capacity_trend_points(history)));
+    doc.push_str("\n```\n\n## Charge sessions\n\n");
+    let sessions = session_lines(history);
+    if sessions.is_empty() {
+        doc.push_str("No charging sessions recorded yet.\n");
+    }
+    for line in sessions {
+        doc.push_str(&format!("- {}\n", line));
+    }
+    doc.push_str("\n## Recommendations\n\n");
+    for line in health::recommendations(report) {
+        doc.push_str(&format!("- {}\n", line));
+    }
+    doc
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Inline SVG of health % over time with the 80% end-of-life line
+fn svg_chart(points: &[(u64, f64)]) -> String {
+    if points.len() < 2 {
+        return "<p class=\"muted\">Not enough history yet — run <code>batfi health</code> periodically to build the chart.</p>".to_string();
+    }
+
+    let (t0, t1) = (points[0].0 as f64, points[points.len() - 1].0 as f64);
+    let max = points.iter().map(|p| p.1).fold(100.0_f64, f64::max);
+    let min = points.iter().map(|p| p.1).fold(75.0_f64, f64::min);
+    let x = |t: f64| SVG_MARGIN + (t - t0) / (t1 - t0).max(1.0) * (SVG_WIDTH - 2.0 * SVG_MARGIN);
+    let y = |v: f64| SVG_MARGIN + (max - v) / (max - min).max(1.0) * (SVG_HEIGHT - 2.0 * SVG_MARGIN);
+
+    let polyline: Vec<String> = points
+        .iter()
+        .map(|&(t, v)| format!("{:.1},{:.1}", x(t as f64), y(v)))
+        .collect();
+
+    format!(
+        r##"<svg viewBox="0 0 {w} {h}" width="{w}" height="{h}" role="img" aria-label="Capacity history">
+  <line x1="{m}" y1="{eol:.1}" x2="{xr}" y2="{eol:.1}" stroke="#c0392b" stroke-dasharray="6 4"/>
+  <text x="{xr}" y="{eol_label:.1}" text-anchor="end" font-size="11" fill="#c0392b">80% end of life</text>
+  <polyline points="{points}" fill="none" stroke="#2980b9" stroke-width="2"/>
+  <text x="{m}" y="{yb}" font-size="11">{start}</text>
+  <text x="{xr}" y="{yb}" font-size="11" text-anchor="end">{end}</text>
+  <text x="4" y="{ytop:.1}" font-size="11">{max:.0}%</text>
+  <text x="4" y="{ybot:.1}" font-size="11">{min:.0}%</text>
+</svg>"##,
+        w = SVG_WIDTH,
+        h = SVG_HEIGHT,
+        m = SVG_MARGIN,
+        xr = SVG_WIDTH - SVG_MARGIN,
+        eol = y(80.0),
+        eol_label = y(80.0) - 4.0,
+        points = polyline.join(" "),
+        yb = SVG_HEIGHT - 12.0,
+        start = health::format_date(points[0].0),
+        end = health::format_date(points[points.len() - 1].0),
+        ytop = y(max) + 4.0,
+        ybot = y(min) + 4.0,
+        max = max,
+        min = min,
+    )
+}
+
+pub fn render_html(report: &BatteryHealth, history: &[HealthSample]) -> String {
+    let rows: String = metric_rows(report)
+        .into_iter()
+        .map(|(name, value)| format!("      <tr><th>{}</th><td>{}</td></tr>\n", name, escape_html(&value)))
+        .collect();
+    let sessions: String = match session_lines(history) {
+        lines if lines.is_empty() => "    <p class=\"muted\">No charging sessions recorded yet.</p>\n".to_string(),
+        lines => format!(
+            "    <ul>\n{}    </ul>\n",
+            lines.iter().map(|line| format!("      <li>{}</li>\n", escape_html(line))).collect::<String>()
+        ),
+    };
+    let advice: String = health::recommendations(report)
+        .iter()
+        .map(|line| format!("      <li>{}</li>\n", escape_html(line)))
+        .collect();
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Battery Health Report — {battery}</title>
+  <style>
+    body {{ font-family: system-ui, sans-serif; max-width: 720px; margin: 2em auto; color: #222; }}
+    table {{ border-collapse: collapse; width: 100%; }}
+    th, td {{ text-align: left; padding: 4px 8px; border-bottom: 1px solid #ddd; }}
+    th {{ width: 40%; font-weight: 600; }}
+    .muted {{ color: #777; }}
+  </style>
+</head>
+<body>
+  <h1>Battery Health Report — {battery}</h1>
+  <p class="muted">{generated}</p>
+  <table>
+{rows}  </table>
+  <h2>Capacity history</h2>
+  {chart}
+  <h2>Charge sessions</h2>
+{sessions}  <h2>Recommendations</h2>
+  <ul>
+{advice}  </ul>
+</body>
+</html>
+"#,
+        battery = escape_html(&report.battery),
+        generated = generated_line(),
+        rows = rows,
+        chart = svg_chart(&health::capacity_trend_points(history)),
+        sessions = sessions,
+        advice = advice,
+    )
+}
@@ -0,0 +1,289 @@
+use std::fs;
+#[cfg(not(feature = "sqlite"))]
+use std::fs::OpenOptions;
+#[cfg(not(feature = "sqlite"))]
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::BatteryReading;
+
+/// Samples older than this are pruned, so the store stays quick to read on startup
+const RETENTION_SECS: u64 = 30 * 86_400;
+/// Without SQLite, the JSON lines are only rewritten once the file grows past this
+#[cfg(not(feature = "sqlite"))]
+const COMPACT_ABOVE_BYTES: u64 = 8 * 1024 * 1024;
+
+/// One persisted sample: the reading plus what the power history needs beyond it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredSample {
+    #[serde(flatten)]
+    pub reading: BatteryReading,
+    #[serde(default)]
+    pub cpu_temperature_c: Option<f64>,
+}
+
+/// Every sample of every battery, in XDG_DATA_HOME/batfi/history.db
+#[cfg(feature = "sqlite")]
+#[derive(Debug)]
+pub struct HistoryStore {
+    battery: String,
+    dir: PathBuf,
+    db: rusqlite::Connection,
+}
+
+#[cfg(feature = "sqlite")]
+impl HistoryStore {
+    pub fn open(dir: Option<&Path>, battery: &str) -> Option<Self> {
+        let dir = dir?;
+        fs::create_dir_all(dir).ok()?;
+        let db = rusqlite::Connection::open(dir.join("history.db")).ok()?;
+        // A daemon and a dashboard may both be writing
+        db.busy_timeout(std::time::Duration::from_secs(1)).ok()?;
+        db.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS readings (
+                 battery TEXT NOT NULL,
+                 timestamp INTEGER NOT NULL,
+                 capacity_percent INTEGER NOT NULL,
+                 energy_now_wh REAL,
+                 energy_full_wh REAL,
+                 power_now_w REAL,
+                 voltage_v REAL,
+                 current_ma INTEGER,
+                 status TEXT NOT NULL,
+                 temperature_c REAL,
+                 cpu_temperature_c REAL,
+                 context TEXT
+             );
+             CREATE INDEX IF NOT EXISTS readings_by_time ON readings (battery, timestamp);",
+        )
+        .ok()?;
+        Some(Self { battery: battery.to_string(), dir: dir.to_path_buf(), db })
+    }
+
+    pub fn append(&self, sample: &StoredSample) -> std::io::Result<()> {
+        let reading = &sample.reading;
+        let context = reading.context.as_ref().and_then(|c| serde_json::to_string(c).ok());
+        self.db
+            .execute(
+                "INSERT INTO readings VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                rusqlite::params![
+                    self.battery,
+                    reading.timestamp as i64,
+                    reading.capacity_percent,
+                    reading.energy_now_wh,
+                    reading.energy_full_wh,
+                    reading.power_now_w,
+                    reading.voltage_v,
+                    reading.current_ma,
+                    reading.status,
+                    reading.temperature_c,
+                    sample.cpu_temperature_c,
+                    context,
+                ],
+            )
+            .map(|_| ())
+            .map_err(std::io::Error::other)
+    }
+
+    /// Samples at or after `since`, oldest first
+    pub fn load(&self, since: u64) -> Vec<StoredSample> {
+        let Ok(mut statement) = self.db.prepare(
+            "SELECT timestamp, capacity_percent, energy_now_wh, energy_full_wh, power_now_w, voltage_v, current_ma,
+                    status, temperature_c, cpu_temperature_c, context
+             FROM readings WHERE battery = ?1 AND timestamp >= ?2 ORDER BY timestamp",
+        ) else {
+            return Vec::new();
+        };
+        let rows = statement.query_map(rusqlite::params![self.battery, since as i64], |row| {
+            let context: Option<String> = row.get(10)?;
+            Ok(StoredSample {
+                reading: BatteryReading {
+                    timestamp: row.get::<_, i64>(0)? as u64,
+                    capacity_percent: row.get(1)?,
+                    energy_now_wh: row.get(2)?,
+                    energy_full_wh: row.get(3)?,
+                    power_now_w: row.get(4)?,
+                    voltage_v: row.get(5)?,
+                    current_ma: row.get(6)?,
+                    status: row.get(7)?,
+                    temperature_c: row.get(8)?,
+                    context: context.and_then(|json| serde_json::from_str(&json).ok()),
+                },
+                cpu_temperature_c: row.get(9)?,
+            })
+        });
+        rows.map(|rows| rows.filter_map(Result::ok).collect()).unwrap_or_default()
+    }
+
+    /// Delete samples past the retention window
+    pub fn compact(&self, now: u64) -> std::io::Result<()> {
+        let cutoff = now.saturating_sub(RETENTION_SECS) as i64;
+        self.db
+            .execute("DELETE FROM readings WHERE timestamp < ?1", [cutoff])
+            .map(|_| ())
+            .map_err(std::io::Error::other)
+    }
+
+    /// Move this battery's samples out of the store (e.g. after a pack replacement), into
+    /// dir/archive as JSON lines so nothing is lost
+    pub fn archive(&self, now: u64) -> std::io::Result<Option<PathBuf>> {
+        let samples = self.load(0);
+        if samples.is_empty() {
+            return Ok(None);
+        }
+        let path = archive_path(&self.dir, &self.battery, now);
+        let mut text = String::new();
+        for sample in &samples {
+            text.push_str(&serde_json::to_string(sample).map_err(std::io::Error::other)?);
+            text.push('\n');
+        }
+        fs::create_dir_all(path.parent().unwrap_or(&self.dir))?;
+        fs::write(&path, text)?;
+        self.db
+            .execute("DELETE FROM readings WHERE battery = ?1", [&self.battery])
+            .map_err(std::io::Error::other)?;
+        Ok(Some(path))
+    }
+}
+
+/// Every sample of one battery, appended as JSON lines (XDG_DATA_HOME/batfi/history-BAT0.jsonl)
+#[cfg(not(feature = "sqlite"))]
+#[derive(Debug, Clone)]
+pub struct HistoryStore {
+    battery: String,
+    path: PathBuf,
+}
+
+#[cfg(not(feature = "sqlite"))]
+impl HistoryStore {
+    pub fn open(dir: Option<&Path>, battery: &str) -> Option<Self> {
+        Some(Self { battery: battery.to_string(), path: dir?.join(format!("history-{}.jsonl", battery)) })
+    }
+
+    pub fn append(&self, sample: &StoredSample) -> std::io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        let line = serde_json::to_string(sample).map_err(std::io::Error::other)?;
+        writeln!(file, "{}", line)
+    }
+
+    /// Samples at or after `since`, oldest first
+    pub fn load(&self, since: u64) -> Vec<StoredSample> {
+        let Ok(file) = fs::File::open(&self.path) else {
+            return Vec::new();
+        };
+        BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str::<StoredSample>(&line).ok())
+            .filter(|sample| sample.reading.timestamp >= since)
+            .collect()
+    }
+
+    /// Rewrite the file without samples past the retention window, once it has grown large
+    pub fn compact(&self, now: u64) -> std::io::Result<()> {
+        if fs::metadata(&self.path).map_or(true, |meta| meta.len() <= COMPACT_ABOVE_BYTES) {
+            return Ok(());
+        }
+        let mut text = String::new();
+        for sample in self.load(now.saturating_sub(RETENTION_SECS)) {
+            text.push_str(&serde_json::to_string(&sample).map_err(std::io::Error::other)?);
+            text.push('\n');
+        }
+        let temp = self.path.with_extension("jsonl.tmp");
+        fs::write(&temp, text)?;
+        fs::rename(temp, &self.path)
+    }
+
+    /// Move this battery's samples out of the store (e.g. after a pack replacement), into
+    /// dir/archive so nothing is lost
+    pub fn archive(&self, now: u64) -> std::io::Result<Option<PathBuf>> {
+        let Some(dir) = self.path.parent().filter(|_| self.path.exists()) else {
+            return Ok(None);
+        };
+        let path = archive_path(dir, &self.battery, now);
+        fs::create_dir_all(path.parent().unwrap_or(dir))?;
+        fs::rename(&self.path, &path)?;
+        Ok(Some(path))
+    }
+}
+
+/// Where `archive` puts a battery's samples, next to the archived health history
+fn archive_path(dir: &Path, battery: &str, now: u64) -> PathBuf {
+    dir.join("archive").join(format!("history-{}-{}.jsonl", battery, now))
+}
+
+/// The tail of `samples` that still describes the present: same status as the newest
+/// sample, and no gap longer than `max_gap_secs` between them or up to `now`
+pub fn resumable(samples: &[StoredSample], now: u64, max_gap_secs: u64) -> &[StoredSample] {
+    let Some(last) = samples.last() else {
+        return samples;
+    };
+    if now.saturating_sub(last.reading.timestamp) > max_gap_secs {
+        return &samples[samples.len()..];
+    }
+    let mut start = samples.len() - 1;
+    while start > 0 {
+        let previous = &samples[start - 1].reading;
+        let current = &samples[start].reading;
+        if previous.status != last.reading.status || current.timestamp.saturating_sub(previous.timestamp) > max_gap_secs {
+            break;
+        }
+        start -= 1;
+    }
+    &samples[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: u64, status: &str) -> StoredSample {
+        StoredSample {
+            reading: BatteryReading {
+                timestamp,
+                capacity_percent: 50,
+                energy_now_wh: Some(25.0),
+                energy_full_wh: Some(50.0),
+                power_now_w: Some(10.0),
+                voltage_v: None,
+                current_ma: None,
+                status: status.to_string(),
+                temperature_c: None,
+                context: None,
+            },
+            cpu_temperature_c: None,
+        }
+    }
+
+    #[test]
+    fn archiving_starts_the_battery_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = HistoryStore::open(Some(dir.path()), "BAT0").unwrap();
+        let other = HistoryStore::open(Some(dir.path()), "BAT1").unwrap();
+        store.append(&sample(100, "Discharging")).unwrap();
+        other.append(&sample(100, "Discharging")).unwrap();
+
+        let archived = store.archive(500).unwrap().expect("samples to archive");
+        assert!(archived.ends_with("archive/history-BAT0-500.jsonl"));
+        assert_eq!(fs::read_to_string(&archived).unwrap().lines().count(), 1);
+        assert!(store.load(0).is_empty());
+        assert_eq!(other.load(0).len(), 1);
+        assert!(store.archive(600).unwrap().is_none());
+    }
+
+    #[test]
+    fn resumes_only_the_current_stretch() {
+        let samples = [sample(100, "Charging"), sample(102, "Discharging"), sample(104, "Discharging"), sample(200, "Discharging"), sample(202, "Discharging")];
+        let tail = resumable(&samples, 205, 60);
+        assert_eq!(tail.iter().map(|s| s.reading.timestamp).collect::<Vec<_>>(), [200, 202]);
+        // Too long since the last sample: nothing carries over
+        assert!(resumable(&samples, 400, 60).is_empty());
+        assert!(resumable(&[], 400, 60).is_empty());
+    }
+}
@@ -0,0 +1,107 @@
+use std::env;
+use std::sync::OnceLock;
+
+use fluent_bundle::concurrent::FluentBundle;
+pub use fluent_bundle::FluentArgs;
+use fluent_bundle::FluentResource;
+use unic_langid::LanguageIdentifier;
+
+/// Bundled translations; English is the fallback for anything missing
+const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("locales/en.ftl")),
+    ("es", include_str!("locales/es.ftl")),
+    ("de", include_str!("locales/de.ftl")),
+];
+
+struct Catalog {
+    selected: Option<FluentBundle<FluentResource>>,
+    fallback: FluentBundle<FluentResource>,
+}
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+/// `t!("id")` or `t!("id", name = value, ...)`: look up a UI string
+#[macro_export]
+macro_rules! t {
+    ($id:expr) => {
+        $crate::i18n::translate($id, None)
+    };
+    ($id:expr, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = $crate::i18n::FluentArgs::new();
+        $(args.set(stringify!($name), $value);)+
+        $crate::i18n::translate($id, Some(&args))
+    }};
+}
+pub use t;
+
+fn load_bundle(lang: &str) -> Option<FluentBundle<FluentResource>> {
+    let (_, source) = LOCALES.iter().find(|(code, _)| *code == lang)?;
+    let langid: LanguageIdentifier = lang.parse().ok()?;
+    let resource = FluentResource::try_new(source.to_string()).ok()?;
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    // Bidi isolation marks only confuse terminals
+    bundle.set_use_isolating(false);
+    bundle.add_resource(resource).ok()?;
+    Some(bundle)
+}
+
+/// Language part of a locale like `de_DE.UTF-8`
+fn language_of(locale: &str) -> &str {
+    locale.split(['_', '.', '@', '-']).next().unwrap_or("")
+}
+
+/// Codes of the bundled translations, for --lang
+pub fn available() -> Vec<&'static str> {
+    LOCALES.iter().map(|(code, _)| *code).collect()
+}
+
+/// Pick the UI language from `lang`, else LC_ALL / LC_MESSAGES / LANG; call once at startup
+pub fn init(lang: Option<&str>) {
+    let requested = lang.map(str::to_string).or_else(|| {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|value| !value.is_empty())
+    });
+    let selected = requested
+        .as_deref()
+        .map(language_of)
+        .filter(|code| *code != "en")
+        .and_then(load_bundle);
+    let _ = CATALOG.set(Catalog {
+        selected,
+        fallback: load_bundle("en").expect("bundled English messages"),
+    });
+}
+
+fn format_message(bundle: &FluentBundle<FluentResource>, id: &str, args: Option<&FluentArgs>) -> Option<String> {
+    let pattern = bundle.get_message(id)?.value()?;
+    let mut errors = Vec::new();
+    Some(bundle.format_pattern(pattern, args, &mut errors).into_owned())
+}
+
+/// The message `id` in the selected language; prefer the `t!` macro
+pub fn translate(id: &str, args: Option<&FluentArgs>) -> String {
+    let catalog = CATALOG.get_or_init(|| Catalog {
+        selected: None,
+        fallback: load_bundle("en").expect("bundled English messages"),
+    });
+    catalog
+        .selected
+        .as_ref()
+        .and_then(|bundle| format_message(bundle, id, args))
+        .or_else(|| format_message(&catalog.fallback, id, args))
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// Display name for a sysfs `status` value; unknown values are shown as-is
+pub fn status_label(status: &str) -> String {
+    match status {
+        "Charging" => t!("status-charging"),
+        "Discharging" => t!("status-discharging"),
+        "Full" => t!("status-full"),
+        "Not charging" => t!("status-not-charging"),
+        "Unknown" => t!("status-unknown"),
+        other => other.to_string(),
+    }
+}
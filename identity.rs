@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// What the driver tells us about the physical pack
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackIdentity {
+    pub manufacturer: Option<String>,
+    pub model_name: Option<String>,
+    pub serial_number: Option<String>,
+}
+
+/// Identity last seen for a battery slot, and when that pack was first seen
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackRecord {
+    pub identity: PackIdentity,
+    pub first_seen: u64,
+}
+
+/// Outcome of comparing the current pack against the recorded one
+#[derive(Debug, Clone)]
+pub enum PackStatus {
+    New,
+    Unchanged,
+    Replaced { previous: PackIdentity },
+}
+
+impl PackIdentity {
+    pub fn read(base_path: &str) -> Self {
+        let read = |name: &str| {
+            fs::read_to_string(format!("{}/{}", base_path, name))
+                .ok()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+        Self {
+            manufacturer: read("manufacturer"),
+            model_name: read("model_name"),
+            serial_number: read("serial_number"),
+        }
+    }
+
+    /// Short human-readable description, e.g. "SMP 5B10W13975 #1234"
+    pub fn label(&self) -> String {
+        let mut parts: Vec<&str> = [&self.manufacturer, &self.model_name]
+            .iter()
+            .filter_map(|p| p.as_deref())
+            .collect();
+        let serial = self.serial_number.as_ref().map(|s| format!("#{}", s));
+        if let Some(ref serial) = serial {
+            parts.push(serial);
+        }
+        if parts.is_empty() {
+            "unknown pack".to_string()
+        } else {
+            parts.join(" ")
+        }
+    }
+
+    fn is_unknown(&self) -> bool {
+        self.manufacturer.is_none() && self.model_name.is_none() && self.serial_number.is_none()
+    }
+
+    /// Whether two identities describe different physical packs
+    fn differs_from(&self, other: &PackIdentity) -> bool {
+        if self.is_unknown() || other.is_unknown() {
+            return false; // Nothing to compare against
+        }
+        match (&self.serial_number, &other.serial_number) {
+            (Some(a), Some(b)) => a != b || self.model_name != other.model_name,
+            _ => self.manufacturer != other.manufacturer || self.model_name != other.model_name,
+        }
+    }
+}
+
+fn registry_path(dir: Option<&Path>) -> Option<PathBuf> {
+    Some(dir?.join("packs.json"))
+}
+
+/// The packs recorded in `dir`, by battery name
+pub fn load_registry(dir: Option<&Path>) -> HashMap<String, PackRecord> {
+    registry_path(dir)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_registry(dir: Option<&Path>, registry: &HashMap<String, PackRecord>) -> std::io::Result<()> {
+    let path = registry_path(dir)
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no data directory"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_string_pretty(registry).map_err(std::io::Error::other)?;
+    fs::write(path, json)
+}
+
+/// Compare the installed pack with the one recorded in `dir` and update the registry
+pub fn check_pack_identity(dir: Option<&Path>, battery: &str, base_path: &str) -> PackStatus {
+    let current = PackIdentity::read(base_path);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let mut registry = load_registry(dir);
+
+    let status = match registry.get(battery) {
+        None => PackStatus::New,
+        Some(record) if current.differs_from(&record.identity) => PackStatus::Replaced {
+            previous: record.identity.clone(),
+        },
+        Some(_) => PackStatus::Unchanged,
+    };
+
+    match status {
+        PackStatus::New | PackStatus::Replaced { .. } => {
+            registry.insert(battery.to_string(), PackRecord { identity: current, first_seen: now });
+        }
+        PackStatus::Unchanged => {
+            // Fill in details a driver may only expose after the first run
+            if let Some(record) = registry.get_mut(battery) {
+                if record.identity == current {
+                    return status;
+                }
+                record.identity = current;
+            }
+        }
+    }
+    let _ = save_registry(dir, &registry);
+    status
+}
@@ -0,0 +1,2115 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+pub mod adapter;
+pub mod alerts;
+pub mod attr;
+pub mod backend;
+pub mod backlight;
+#[cfg(feature = "chart")]
+pub mod chart;
+pub mod charge_curve;
+pub mod charge_limit;
+pub mod client;
+pub mod compositor;
+pub mod config;
+pub mod critical;
+pub mod dbus;
+#[cfg(feature = "notify")]
+pub mod desktop;
+pub mod discharge_curve;
+pub mod distribution;
+pub mod energy_per_percent;
+pub mod estimation;
+pub mod events;
+pub mod fields;
+pub mod fusion;
+pub mod footprint;
+pub mod health;
+pub mod health_export;
+pub mod history_store;
+pub mod i18n;
+pub mod identity;
+pub mod multi;
+#[cfg(feature = "notify")]
+pub mod ntfy;
+pub mod plasma;
+pub mod prometheus;
+pub mod provider;
+pub mod quirks;
+pub mod rpc;
+pub mod server;
+#[cfg(feature = "script")]
+pub mod script;
+pub mod session;
+pub mod simulate;
+#[cfg(feature = "notify")]
+pub mod sound;
+pub mod standby;
+pub mod statsd;
+pub mod statusbar;
+pub mod timefmt;
+pub mod usage;
+#[cfg(feature = "tui")]
+pub mod wakeups;
+#[cfg(feature = "notify")]
+pub mod webhook;
+
+use compositor::{Compositor, UsageContext};
+use charge_curve::ChargeCurve;
+use discharge_curve::DischargeCurve;
+use energy_per_percent::EnergyPerPercent;
+use estimation::{Smoother, Smoothing, WindowSizes};
+use fusion::{DataQuality, SourceValue};
+use provider::{ProvidedValues, Quantity};
+use quirks::Quirk;
+
+/// Configuration constants for smoothing and accuracy
+const UPDATE_INTERVAL_SECS: u64 = 2; // Update every 2 seconds
+const SAG_CONFIRM_SAMPLES: u32 = 3; // Consecutive sagging samples before flagging
+const SAG_EVENT_COOLDOWN_SECS: u64 = 600; // Minimum gap between logged voltage sag events
+const CURVE_SAVE_EVERY: u32 = 30; // Persist the discharge curve every N learned samples
+const SUSPEND_GAP_SECS: u64 = 60; // Sample gap that means the machine was asleep
+const THROTTLE_EVENT_COOLDOWN_SECS: u64 = 300; // Minimum gap between logged throttling events
+/// Sensor discovery narrates what it finds; off unless the CLI has a terminal to narrate to
+static DISCOVERY_LOG: AtomicBool = AtomicBool::new(false);
+
+/// Whether sensor discovery narrates to stderr
+pub fn set_discovery_log(enabled: bool) {
+    DISCOVERY_LOG.store(enabled, Ordering::Relaxed);
+}
+
+macro_rules! discovery_log {
+    ($($arg:tt)*) => {
+        if DISCOVERY_LOG.load(Ordering::Relaxed) {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+/// Receives what the library can't act on itself: failed notification pushes, backlight and
+/// logind errors, script errors. Nothing is reported until a handler is set.
+static WARNING_HANDLER: OnceLock<WarningHandler> = OnceLock::new();
+type WarningHandler = Box<dyn Fn(&str) + Send + Sync>;
+
+/// Pass the library's warnings to `handler`; only the first call counts
+pub fn set_warning_handler(handler: impl Fn(&str) + Send + Sync + 'static) {
+    let _ = WARNING_HANDLER.set(Box::new(handler));
+}
+
+/// Hand a warning to the handler from `set_warning_handler`, if there is one
+pub(crate) fn warn(message: &str) {
+    if let Some(handler) = WARNING_HANDLER.get() {
+        handler(message);
+    }
+}
+
+/// Where sysfs is mounted; set from `sysfs_root` for containers and recorded trees
+static SYSFS_ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// Read power supplies and sensors under `root` instead of /sys; only the first call counts
+pub fn set_sysfs_root(root: impl Into<PathBuf>) {
+    let _ = SYSFS_ROOT.set(root.into());
+}
+
+/// `relative` (e.g. "class/hwmon") under the configured sysfs root
+pub fn sysfs_path(relative: &str) -> PathBuf {
+    SYSFS_ROOT.get().map_or(Path::new("/sys"), PathBuf::as_path).join(relative)
+}
+
+/// Device directory of a power supply, e.g. /sys/class/power_supply/BAT0
+pub fn power_supply_path(name: &str) -> String {
+    sysfs_path("class/power_supply").join(name).display().to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatteryReading {
+    pub timestamp: u64,
+    pub capacity_percent: u8,
+    pub energy_now_wh: Option<f64>,
+    pub energy_full_wh: Option<f64>,
+    pub power_now_w: Option<f64>,
+    pub voltage_v: Option<f64>,
+    pub current_ma: Option<i32>,
+    pub status: String,
+    pub temperature_c: Option<f64>,
+    #[serde(default)]
+    pub context: Option<UsageContext>, // Display state from compositor IPC, when available
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatteryInfo {
+    pub status: String,
+    pub capacity_percent: u8,
+    pub health_percent: f64,
+    pub cycles: Option<u32>,
+    pub temperature_c: Option<f64>,
+    pub voltage_v: Option<f64>,
+    pub current_ma: Option<i32>,
+    pub power_w: Option<f64>,
+    pub smoothed_power_w: Option<f64>,
+    pub manufacturer: String,
+    pub model: String,
+    pub technology: String,
+    pub time_remaining_minutes: Option<u32>,
+    #[serde(default)]
+    pub eta_stability: Option<f64>, // 1 when the ETA held over the last few minutes, toward 0 as it jumps around
+    pub energy_now_wh: Option<f64>,
+    pub energy_full_wh: Option<f64>,
+    #[serde(default)]
+    pub energy_full_design_wh: Option<f64>,
+    pub power_trend: String, // "stable", "increasing", "decreasing"
+    pub cpu_temperature_c: Option<f64>,
+    pub voltage_sag_v: Option<f64>, // Volts below the learned discharge curve, when abnormal
+    #[serde(default)]
+    pub charge_now_mah: Option<f64>, // Only filled in with --units mah
+    #[serde(default)]
+    pub charge_full_mah: Option<f64>,
+    #[serde(default)]
+    pub last_full_secs_ago: Option<u64>,
+    #[serde(default)]
+    pub unplugged_secs: Option<u64>,
+    #[serde(default)]
+    pub energy_since_unplug_wh: Option<f64>,
+    #[serde(default)]
+    pub charge_session_added_wh: Option<f64>,
+    #[serde(default)]
+    pub charge_session_start_percent: Option<u8>,
+    #[serde(default)]
+    pub charge_session_secs: Option<u64>,
+    #[serde(default)]
+    pub percent_per_hour: Option<f64>, // Fitted capacity change, negative while draining
+    #[serde(default)]
+    pub time_to_80_minutes: Option<u32>, // While charging, from the learned charge-rate curve
+    #[serde(default)]
+    pub nickname: Option<String>, // Friendly label for the battery from [nicknames]
+    #[serde(default)]
+    pub data_quality: DataQuality, // Which source power and capacity came from, and where sources disagreed
+    #[serde(default)]
+    pub script: BTreeMap<String, serde_json::Value>, // Fields derived by the user script's fields()
+    #[serde(skip)]
+    pub script_lines: Vec<String>, // Extra display lines from the user script's display()
+    #[serde(default)]
+    pub ac_online: Option<bool>, // Whether a mains/USB-C adapter is online; None on machines without one
+}
+
+/// Capacity bands shared by the TUI bar and the status bar formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChargeLevel {
+    Critical,
+    Low,
+    Normal,
+    High,
+}
+
+impl ChargeLevel {
+    /// Band for `capacity` under the configured thresholds
+    pub fn from_capacity(capacity: u8) -> Self {
+        let limits = config::thresholds();
+        if capacity <= limits.capacity_crit {
+            ChargeLevel::Critical
+        } else if capacity <= limits.capacity_warn {
+            ChargeLevel::Low
+        } else if capacity <= limits.capacity_high {
+            ChargeLevel::Normal
+        } else {
+            ChargeLevel::High
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PowerSample {
+    pub timestamp: u64,
+    pub power_w: f64,
+    pub energy_wh: f64,
+    pub cpu_temperature_c: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TemperatureSensor {
+    pub sensor_type: String, // "coretemp", "k10temp", "battery", etc.
+    pub path: String,
+    pub label: Option<String>,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TemperatureReading {
+    pub raw_value: f64,
+    pub smoothed_value: f64,
+    pub sensor_info: TemperatureSensor,
+    pub timestamp: u64,
+}
+
+#[derive(Debug)]
+pub struct TemperatureMonitor {
+    pub cpu_sensors: Vec<TemperatureSensor>,
+    pub battery_sensors: Vec<TemperatureSensor>,
+    pub last_cpu_temp: Option<TemperatureReading>,
+    pub last_battery_temp: Option<TemperatureReading>,
+    pub battery_temp_decidegrees: bool, // Set by the TempDecidegrees quirk
+}
+
+impl Default for TemperatureMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TemperatureMonitor {
+    pub fn new() -> Self {
+        let mut monitor = Self {
+            cpu_sensors: Vec::new(),
+            battery_sensors: Vec::new(),
+            last_cpu_temp: None,
+            last_battery_temp: None,
+            battery_temp_decidegrees: false,
+        };
+        monitor.discover_sensors();
+        monitor
+    }
+
+    /// Comprehensive sensor discovery with detailed logging
+    fn discover_sensors(&mut self) {
+        discovery_log!("🔍 Discovering temperature sensors...");
+        
+        // Discover CPU sensors from hwmon
+        self.discover_cpu_sensors();
+        
+        // Discover battery sensors
+        self.discover_battery_sensors();
+        
+        // Log discovery results
+        if self.cpu_sensors.is_empty() && self.battery_sensors.is_empty() {
+            discovery_log!("⚠️  No temperature sensors found!");
+        } else {
+            discovery_log!("✅ Temperature sensor discovery complete:");
+            for sensor in &self.cpu_sensors {
+                discovery_log!("   CPU: {} ({})", sensor.name, sensor.path);
+            }
+            for sensor in &self.battery_sensors {
+                discovery_log!("   BAT: {} ({})", sensor.name, sensor.path);
+            }
+        }
+    }
+
+    /// Scan /sys/class/hwmon/hwmon*/name for CPU temperature sensors
+    fn discover_cpu_sensors(&mut self) {
+        let hwmon_path = sysfs_path("class/hwmon");
+        if !hwmon_path.exists() {
+            discovery_log!("❌ {} not found - ensure you're running on Linux", hwmon_path.display());
+            return;
+        }
+
+        discovery_log!("🔍 Scanning {}/ for temperature sensors...", hwmon_path.display());
+        
+        if let Ok(entries) = fs::read_dir(&hwmon_path) {
+            let mut hwmon_dirs: Vec<_> = entries.filter_map(|e| e.ok()).collect();
+            hwmon_dirs.sort_by_key(|e| e.file_name());
+            
+            for entry in hwmon_dirs {
+                if let Some(name) = entry.file_name().to_str() {
+                    if name.starts_with("hwmon") {
+                        discovery_log!("🔍 Found hwmon directory: {}", entry.path().display());
+                        self.scan_hwmon_device(&entry.path());
+                    }
+                }
+            }
+        } else {
+            discovery_log!("❌ Failed to read {} directory", hwmon_path.display());
+        }
+
+        if self.cpu_sensors.is_empty() {
+            discovery_log!("⚠️  No CPU temperature sensors found in {}/", hwmon_path.display());
+        } else {
+            // Sort CPU sensors by preference: coretemp > k10temp > others
+            self.cpu_sensors.sort_by(|a, b| {
+                let priority_a = match a.sensor_type.as_str() {
+                    "coretemp" => 1,   // Intel - highest priority
+                    "k10temp" => 2,    // AMD Ryzen
+                    "zenpower" => 3,   // AMD alternative
+                    "amdgpu" => 4,     // AMD GPU (if needed)
+                    _ => 9,            // Others - lowest priority
+                };
+                let priority_b = match b.sensor_type.as_str() {
+                    "coretemp" => 1,   // Intel - highest priority
+                    "k10temp" => 2,    // AMD Ryzen
+                    "zenpower" => 3,   // AMD alternative
+                    "amdgpu" => 4,     // AMD GPU (if needed)
+                    _ => 9,            // Others - lowest priority
+                };
+                priority_a.cmp(&priority_b)
+            });
+            
+            discovery_log!("📊 CPU sensors sorted by priority:");
+            for (i, sensor) in self.cpu_sensors.iter().enumerate() {
+                discovery_log!("   {}. {} [{}]", i+1, sensor.name, sensor.path);
+            }
+        }
+    }
+
+
+    fn scan_hwmon_device(&mut self, hwmon_path: &Path) {
+        // Read the device name
+        let name_path = hwmon_path.join("name");
+        let device_name = match fs::read_to_string(&name_path) {
+            Ok(name) => name.trim().to_string(),
+            Err(e) => {
+                discovery_log!("❌ Cannot read name from {}: {}", name_path.display(), e);
+                return;
+            }
+        };
+
+        discovery_log!("🔍 Scanning hwmon device: '{}' at {}", device_name, hwmon_path.display());
+
+        // Skip virtual/invalid sensors with explicit logging
+        if device_name == "acpitz" || device_name.contains("virtual") {
+            discovery_log!("🚫 Skipping virtual/ACPI sensor: '{}' (not a real temperature sensor)", device_name);
+            return;
+        }
+
+        // Look for temperature inputs with detailed scanning
+        if let Ok(entries) = fs::read_dir(hwmon_path) {
+            let mut temp_files: Vec<_> = entries.filter_map(|e| e.ok()).collect();
+            temp_files.sort_by_key(|e| e.file_name());
+            
+            let mut found_temp_inputs = Vec::new();
+            
+            for entry in temp_files {
+                let filename = entry.file_name();
+                let filename_str = filename.to_string_lossy();
+                
+                if filename_str.starts_with("temp") && filename_str.ends_with("_input") {
+                    found_temp_inputs.push(filename_str.to_string());
+                }
+            }
+            
+            if found_temp_inputs.is_empty() {
+                discovery_log!("   ❌ No temp*_input files found in {}", hwmon_path.display());
+                return;
+            }
+            
+            discovery_log!("   📊 Found temp inputs: {:?}", found_temp_inputs);
+            
+            for temp_input in found_temp_inputs {
+                // Extract temp number (e.g., temp1_input -> 1)
+                if let Some(temp_num) = temp_input.strip_prefix("temp").and_then(|s| s.strip_suffix("_input")) {
+                    let temp_input_path = hwmon_path.join(&temp_input);
+                    let label_path = hwmon_path.join(format!("temp{}_label", temp_num));
+                    
+                    // Read label if available
+                    let label = match fs::read_to_string(&label_path) {
+                        Ok(l) => {
+                            let label_str = l.trim().to_string();
+                            discovery_log!("   🏷️  temp{}_label = '{}'", temp_num, label_str);
+                            Some(label_str)
+                        }
+                        Err(_) => {
+                            discovery_log!("   ❌ No temp{}_label file (using temp{})", temp_num, temp_num);
+                            None
+                        }
+                    };
+                    
+                    // Check if this is a CPU temperature we want
+                    if self.is_cpu_temp_sensor(&device_name, &label) {
+                        let sensor = TemperatureSensor {
+                            sensor_type: device_name.clone(),
+                            path: temp_input_path.to_string_lossy().to_string(),
+                            label: label.clone(),
+                            name: format!("{} {}", device_name, label.unwrap_or_else(|| format!("temp{}", temp_num))),
+                        };
+                        
+                        // Test if we can actually read from this sensor
+                        discovery_log!("   🧪 Testing sensor: {} -> {}", sensor.name, sensor.path);
+                        match self.read_temperature_from_path(&sensor.path) {
+                            Some(raw_temp) => {
+                                let temp_celsius = raw_temp / 1000.0; // Convert millidegrees to Celsius
+                                if self.is_valid_temperature(temp_celsius) {
+                                    discovery_log!("   ✅ VALID CPU sensor: {} = {:.1}°C (raw: {})", sensor.name, temp_celsius, raw_temp);
+                                    self.cpu_sensors.push(sensor);
+                                } else {
+                                    discovery_log!("   🚫 INVALID temperature from {}: {:.1}°C (outside {}-{}°C range)", 
+                                        sensor.name, temp_celsius, config::thresholds().temp_valid_min, config::thresholds().temp_valid_max);
+                                }
+                            }
+                            None => {
+                                discovery_log!("   ❌ Cannot read from sensor: {} (file: {})", sensor.name, sensor.path);
+                            }
+                        }
+                    } else {
+                        discovery_log!("   🚫 Skipping temp{}: '{}' sensor '{}' with label '{:?}' (not a main CPU sensor)", 
+                            temp_num, device_name, temp_input, label);
+                    }
+                }
+            }
+        } else {
+            discovery_log!("   ❌ Cannot read directory contents of {}", hwmon_path.display());
+        }
+    }
+
+    fn is_cpu_temp_sensor(&self, device_name: &str, label: &Option<String>) -> bool {
+        discovery_log!("   🔍 Checking if '{}' with label '{:?}' is a CPU sensor", device_name, label);
+        
+        // Check device name first
+        match device_name {
+            "coretemp" => {
+                // Intel CPU temperature sensor
+                if let Some(ref label_str) = label {
+                    let label_lower = label_str.to_lowercase();
+                    // Look for package temperature specifically
+                    let is_package = label_lower.contains("package") || 
+                                   label_lower == "package id 0" ||
+                                   label_lower.contains("package id");
+                    discovery_log!("   📊 coretemp label '{}' -> package sensor: {}", label_str, is_package);
+                    is_package
+                } else {
+                    // If no label, assume temp1 is the main package sensor for coretemp
+                    discovery_log!("   📊 coretemp with no label -> assuming main package sensor");
+                    true
+                }
+            }
+            "k10temp" => {
+                // AMD Ryzen temperature sensor
+                if let Some(ref label_str) = label {
+                    let label_lower = label_str.to_lowercase();
+                    // Look for main die temperature (Tctl or Tdie)
+                    let is_main = label_lower.contains("tctl") || 
+                                label_lower.contains("tdie") ||
+                                label_lower == "tctl" ||
+                                label_lower == "tdie";
+                    discovery_log!("   📊 k10temp label '{}' -> main sensor: {}", label_str, is_main);
+                    is_main
+                } else {
+                    // If no label, assume temp1 is the main sensor for k10temp
+                    discovery_log!("   📊 k10temp with no label -> assuming main sensor");
+                    true
+                }
+            }
+            "zenpower" => {
+                // AMD alternative temperature sensor
+                if let Some(ref label_str) = label {
+                    let label_lower = label_str.to_lowercase();
+                    let is_main = label_lower.contains("tctl") || 
+                                label_lower.contains("tdie") ||
+                                label_lower.contains("die");
+                    discovery_log!("   📊 zenpower label '{}' -> main sensor: {}", label_str, is_main);
+                    is_main
+                } else {
+                    discovery_log!("   📊 zenpower with no label -> assuming main sensor");
+                    true
+                }
+            }
+            "amdgpu" => {
+                // AMD GPU temperature - only if specifically requested
+                if let Some(ref label_str) = label {
+                    let is_gpu = label_str.to_lowercase().contains("edge");
+                    discovery_log!("   📊 amdgpu label '{}' -> GPU edge sensor: {}", label_str, is_gpu);
+                    is_gpu
+                } else {
+                    discovery_log!("   🚫 amdgpu with no label -> skipping");
+                    false
+                }
+            }
+            _ => {
+                discovery_log!("   🚫 Unknown device type '{}' -> skipping", device_name);
+                false
+            }
+        }
+    }
+
+    /// Discover battery temperature sensors
+    fn discover_battery_sensors(&mut self) {
+        discovery_log!("🔍 Scanning for battery temperature sensors...");
+        
+        // Method 1: Direct battery power supply sensors
+        let power_supply_path = sysfs_path("class/power_supply");
+        discovery_log!("🔍 Checking {}/ for battery temp sensors...", power_supply_path.display());
+        
+        if let Ok(entries) = fs::read_dir(&power_supply_path) {
+            let mut power_entries: Vec<_> = entries.filter_map(|e| e.ok()).collect();
+            power_entries.sort_by_key(|e| e.file_name());
+            
+            for entry in power_entries {
+                let name = entry.file_name();
+                let name_str = name.to_string_lossy();
+                
+                if name_str.starts_with("BAT") || name_str.starts_with("battery") {
+                    discovery_log!("🔍 Found battery device: {}", name_str);
+                    let temp_path = entry.path().join("temp");
+                    
+                    if temp_path.exists() {
+                        discovery_log!("   📊 Found temp file: {}", temp_path.display());
+                        let sensor = TemperatureSensor {
+                            sensor_type: "battery".to_string(),
+                            path: temp_path.to_string_lossy().to_string(),
+                            label: Some(name_str.to_string()),
+                            name: format!("Battery {}", name_str),
+                        };
+                        
+                        // Test the sensor
+                        discovery_log!("   🧪 Testing battery sensor: {} -> {}", sensor.name, sensor.path);
+                        match self.read_temperature_from_path(&sensor.path) {
+                            Some(raw_temp) => {
+                                let normalized_temp = self.normalize_battery_temperature(raw_temp);
+                                discovery_log!("   📊 Raw temp: {}, normalized: {:.1}°C", raw_temp, normalized_temp);
+                                
+                                if self.is_valid_temperature(normalized_temp) {
+                                    discovery_log!("   ✅ VALID battery sensor: {} = {:.1}°C", sensor.name, normalized_temp);
+                                    self.battery_sensors.push(sensor);
+                                } else {
+                                    discovery_log!("   🚫 INVALID battery temperature: {:.1}°C (outside {}-{}°C range)", 
+                                        normalized_temp, config::thresholds().temp_valid_min, config::thresholds().temp_valid_max);
+                                }
+                            }
+                            None => {
+                                discovery_log!("   ❌ Cannot read from battery sensor: {}", sensor.path);
+                            }
+                        }
+                    } else {
+                        discovery_log!("   ❌ No temp file found for battery {}", name_str);
+                    }
+                } else {
+                    discovery_log!("🚫 Skipping non-battery device: {}", name_str);
+                }
+            }
+        } else {
+            discovery_log!("❌ Cannot read {} directory", power_supply_path.display());
+        }
+
+        // Method 2: Thermal zones with type=battery
+        let thermal_path = sysfs_path("class/thermal");
+        discovery_log!("🔍 Checking {}/ for battery thermal zones...", thermal_path.display());
+        
+        if let Ok(entries) = fs::read_dir(&thermal_path) {
+            let mut thermal_entries: Vec<_> = entries.filter_map(|e| e.ok()).collect();
+            thermal_entries.sort_by_key(|e| e.file_name());
+            
+            for entry in thermal_entries {
+                let name = entry.file_name();
+                let name_str = name.to_string_lossy();
+                
+                if name_str.starts_with("thermal_zone") {
+                    let type_path = entry.path().join("type");
+                    match fs::read_to_string(&type_path) {
+                        Ok(zone_type_raw) => {
+                            let zone_type = zone_type_raw.trim();
+                            discovery_log!("🔍 thermal_zone {} type: '{}'", name_str, zone_type);
+                            
+                            if zone_type == "battery" {
+                                let temp_path = entry.path().join("temp");
+                                if temp_path.exists() {
+                                    discovery_log!("   📊 Found battery thermal zone temp file: {}", temp_path.display());
+                                    let sensor = TemperatureSensor {
+                                        sensor_type: "thermal_zone".to_string(),
+                                        path: temp_path.to_string_lossy().to_string(),
+                                        label: Some(zone_type.to_string()),
+                                        name: format!("Battery Thermal {}", name_str),
+                                    };
+                                    
+                                    discovery_log!("   🧪 Testing thermal zone sensor: {} -> {}", sensor.name, sensor.path);
+                                    match self.read_temperature_from_path(&sensor.path) {
+                                        Some(raw_temp) => {
+                                            let normalized_temp = self.normalize_battery_temperature(raw_temp);
+                                            discovery_log!("   📊 Raw temp: {}, normalized: {:.1}°C", raw_temp, normalized_temp);
+                                            
+                                            if self.is_valid_temperature(normalized_temp) {
+                                                discovery_log!("   ✅ VALID battery thermal zone: {} = {:.1}°C", sensor.name, normalized_temp);
+                                                self.battery_sensors.push(sensor);
+                                            } else {
+                                                discovery_log!("   🚫 INVALID thermal zone temperature: {:.1}°C", normalized_temp);
+                                            }
+                                        }
+                                        None => {
+                                            discovery_log!("   ❌ Cannot read from thermal zone: {}", sensor.path);
+                                        }
+                                    }
+                                } else {
+                                    discovery_log!("   ❌ No temp file in thermal zone {}", name_str);
+                                }
+                            } else {
+                                discovery_log!("   🚫 Skipping thermal zone {} (type: '{}')", name_str, zone_type);
+                            }
+                        }
+                        Err(e) => {
+                            discovery_log!("   ❌ Cannot read type from {}: {}", type_path.display(), e);
+                        }
+                    }
+                }
+            }
+        } else {
+            discovery_log!("❌ Cannot read {} directory", thermal_path.display());
+        }
+        
+        if self.battery_sensors.is_empty() {
+            discovery_log!("⚠️  No battery temperature sensors found");
+        } else {
+            discovery_log!("📊 Found {} battery sensor(s):", self.battery_sensors.len());
+            for (i, sensor) in self.battery_sensors.iter().enumerate() {
+                discovery_log!("   {}. {} [{}]", i+1, sensor.name, sensor.path);
+            }
+        }
+    }
+
+    pub fn read_temperature_from_path(&self, path: &str) -> Option<f64> {
+        attr::parse_number(&fs::read_to_string(path).ok()?)
+    }
+
+    pub fn normalize_battery_temperature(&self, raw_value: f64) -> f64 {
+        if raw_value > 1000.0 {
+            // Millidegrees Celsius - divide by 1000
+            let normalized = raw_value / 1000.0;
+            discovery_log!("   🔄 Normalized battery temp: {} (millidegrees) -> {:.1}°C", raw_value, normalized);
+            normalized
+        } else if raw_value > 200.0 {
+            // Decidegrees Celsius - divide by 10
+            let normalized = raw_value / 10.0;
+            discovery_log!("   🔄 Normalized battery temp: {} (decidegrees) -> {:.1}°C", raw_value, normalized);
+            normalized
+        } else {
+            // Already in Celsius
+            discovery_log!("   ✅ Battery temp already in Celsius: {:.1}°C", raw_value);
+            raw_value
+        }
+    }
+
+    fn is_valid_temperature(&self, temp: f64) -> bool {
+        let t = config::thresholds();
+        (t.temp_valid_min..=t.temp_valid_max).contains(&temp)
+    }
+
+    /// Get current CPU temperature (raw value only)
+    pub fn get_cpu_temp(&mut self) -> Option<TemperatureReading> {
+        for sensor in &self.cpu_sensors {
+            if let Some(raw_temp) = self.read_temperature_from_path(&sensor.path) {
+                let temp_celsius = raw_temp / 1000.0; // Convert millidegrees to Celsius
+                
+                if self.is_valid_temperature(temp_celsius) {
+                    let reading = TemperatureReading {
+                        raw_value: temp_celsius,
+                        smoothed_value: temp_celsius, // BatteryMonitor smooths it after each sample
+                        sensor_info: sensor.clone(),
+                        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+                    };
+                    
+                    self.last_cpu_temp = Some(reading.clone());
+                    return Some(reading);
+                }
+            }
+        }
+        None
+    }
+
+    /// Get current battery temperature (raw value only)
+    pub fn get_battery_temp(&mut self) -> Option<TemperatureReading> {
+        for sensor in &self.battery_sensors {
+            if let Some(raw_temp) = self.read_temperature_from_path(&sensor.path) {
+                let temp_celsius = if self.battery_temp_decidegrees && sensor.sensor_type == "battery" {
+                    raw_temp / 10.0
+                } else {
+                    self.normalize_battery_temperature(raw_temp)
+                };
+                
+                if self.is_valid_temperature(temp_celsius) {
+                    let reading = TemperatureReading {
+                        raw_value: temp_celsius,
+                        smoothed_value: temp_celsius, // BatteryMonitor smooths it after each sample
+                        sensor_info: sensor.clone(),
+                        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+                    };
+                    
+                    self.last_battery_temp = Some(reading.clone());
+                    return Some(reading);
+                }
+            }
+        }
+        None
+    }
+}
+
+pub struct BatteryMonitor {
+    battery_name: String,
+    base_path: String,
+    backend: Box<dyn backend::PowerSupplyBackend>,
+    readings_history: VecDeque<BatteryReading>,
+    power_history: VecDeque<PowerSample>,
+    /// Where learned curves, the session, logs and the sample history are kept; None keeps nothing
+    data_dir: Option<PathBuf>,
+    /// Set after the first failed health history append, so the warning shows once
+    health_write_failed: AtomicBool,
+    /// Where every sample is appended, once `persist_history` turned it on
+    history_store: Option<history_store::HistoryStore>,
+    /// One smoother per series, all running the chosen method
+    power_smoother: Smoother,
+    current_smoother: Smoother,
+    battery_temp_smoother: Smoother,
+    cpu_temp_smoother: Smoother,
+    rolling_power_window: VecDeque<f64>,
+    /// (timestamp, minutes) of recent ETAs under the current status, for `eta_stability`
+    eta_history: VecDeque<(u64, u32)>,
+    temperature_monitor: TemperatureMonitor,
+    /// History and rolling-window spans (s); `sizes` turns them into sample counts
+    history_secs: u64,
+    rolling_secs: u64,
+    sizes: WindowSizes,
+    last_update: u64,
+    discharge_curve: DischargeCurve,
+    sag_streak: u32,
+    last_sag_event: u64,
+    curve_samples_unsaved: u32,
+    energy_per_percent: EnergyPerPercent,
+    charge_curve: ChargeCurve,
+    gauge_drift: health::GaugeDriftTracker,
+    quirks: Vec<Quirk>,
+    statsd: Option<statsd::StatsdEmitter>,
+    alerts: Option<alerts::AlertEngine>,
+    compositor: Option<Compositor>,
+    update_interval: Duration,
+    #[cfg(feature = "tui")]
+    animations: bool,
+    /// --duration, so the Pac-Cat can pace itself; None runs until stopped
+    #[cfg(feature = "tui")]
+    run_duration: Option<u64>,
+    charge_units: bool,
+    #[cfg(feature = "tui")]
+    energy_bar: bool,
+    #[cfg(feature = "tui")]
+    histogram: bool,
+    /// Interrupt and wakeup counts for the dashboard's wakeups panel, when it is shown
+    #[cfg(feature = "tui")]
+    wakeups: Option<wakeups::WakeupTracker>,
+    session: session::SessionTracker,
+    throttle_count: Option<u64>,
+    last_throttle_event: u64,
+    last_usage_record: u64,
+    /// Every discharging power reading since batfi started
+    session_power: distribution::PowerDistribution,
+    /// Third-party sensor sources that found something on this machine
+    providers: Vec<provider::ActiveProvider>,
+    /// AC adapters under /sys/class/power_supply; empty means the battery status decides
+    adapters: Vec<PathBuf>,
+    plug_debouncer: adapter::PlugDebouncer,
+    last_plug: Option<adapter::PlugChange>,
+    /// Undebounced adapter state at the previous sample; a flip restarts the power smoothing
+    last_ac_online: Option<bool>,
+    /// S0ix residency counter at the previous sample, to measure suspend gaps against
+    last_residency_us: Option<u64>,
+    /// UPower and RAPL, to cross-check the driver's figures against
+    sources: fusion::ExternalSources,
+    #[cfg(feature = "script")]
+    script: Option<script::Script>,
+}
+
+impl BatteryMonitor {
+    pub fn new(battery_name: &str) -> Self {
+        let base_path = power_supply_path(battery_name);
+        let mut monitor = Self::with_backend(battery_name, Box::new(backend::SysfsBackend::new(&base_path)));
+        monitor.adapters = adapter::find_adapters();
+        // UPower describes the live machine, not a recorded tree under --sysfs-root
+        if SYSFS_ROOT.get().is_none() {
+            monitor.sources = fusion::ExternalSources::system(battery_name);
+        }
+        monitor
+    }
+
+    /// Monitor fed by any attribute source, e.g. a mock with scripted readings
+    pub fn with_backend(battery_name: &str, backend: Box<dyn backend::PowerSupplyBackend>) -> Self {
+        let dir = data_dir();
+        let mut monitor = Self {
+            battery_name: battery_name.to_string(),
+            base_path: power_supply_path(battery_name),
+            backend,
+            readings_history: VecDeque::new(),
+            power_history: VecDeque::new(),
+            history_store: None,
+            power_smoother: Smoother::new(Smoothing::default()),
+            current_smoother: Smoother::new(Smoothing::default()),
+            battery_temp_smoother: Smoother::new(Smoothing::default()),
+            cpu_temp_smoother: Smoother::new(Smoothing::default()),
+            rolling_power_window: VecDeque::new(),
+            eta_history: VecDeque::new(),
+            temperature_monitor: TemperatureMonitor::new(),
+            history_secs: estimation::HISTORY_SECS,
+            rolling_secs: estimation::ROLLING_WINDOW_SECS,
+            sizes: WindowSizes::for_interval(estimation::HISTORY_SECS, estimation::ROLLING_WINDOW_SECS, UPDATE_INTERVAL_SECS),
+            last_update: 0,
+            discharge_curve: DischargeCurve::load(dir.as_deref(), battery_name),
+            sag_streak: 0,
+            last_sag_event: 0,
+            curve_samples_unsaved: 0,
+            energy_per_percent: EnergyPerPercent::load(dir.as_deref(), battery_name),
+            charge_curve: ChargeCurve::load(dir.as_deref(), battery_name),
+            gauge_drift: health::GaugeDriftTracker::default(),
+            quirks: Vec::new(),
+            statsd: None,
+            alerts: None,
+            compositor: Compositor::detect(),
+            update_interval: Duration::from_secs(UPDATE_INTERVAL_SECS),
+            #[cfg(feature = "tui")]
+            animations: true,
+            #[cfg(feature = "tui")]
+            run_duration: None,
+            charge_units: false,
+            #[cfg(feature = "tui")]
+            energy_bar: false,
+            #[cfg(feature = "tui")]
+            histogram: false,
+            #[cfg(feature = "tui")]
+            wakeups: None,
+            session: session::SessionTracker::load(dir.as_deref(), battery_name),
+            throttle_count: None,
+            last_throttle_event: 0,
+            last_usage_record: 0,
+            session_power: distribution::PowerDistribution::new(),
+            providers: provider::from_registry(),
+            adapters: Vec::new(),
+            plug_debouncer: adapter::PlugDebouncer::default(),
+            last_plug: None,
+            last_ac_online: None,
+            last_residency_us: None,
+            sources: fusion::ExternalSources::none(),
+            #[cfg(feature = "script")]
+            script: None,
+            data_dir: dir,
+            health_write_failed: AtomicBool::new(false),
+        };
+        for active in &monitor.providers {
+            discovery_log!("🔌 Sensor provider '{}': {} input(s)", active.provider.name(), active.sensors.len());
+        }
+
+        // Look up known firmware quirks for this pack
+        monitor.quirks = quirks::quirks_for(
+            &monitor.read_file("manufacturer").unwrap_or_default(),
+            &monitor.read_file("model_name").unwrap_or_default(),
+        );
+        monitor.temperature_monitor.battery_temp_decidegrees = monitor.has_quirk(Quirk::TempDecidegrees);
+        monitor
+    }
+
+    /// Feed this monitor from a third-party source too; false when it found nothing to measure
+    pub fn add_sensor_provider(&mut self, provider: Box<dyn provider::SensorProvider>) -> bool {
+        match provider::activate(provider) {
+            Some(active) => {
+                self.providers.push(active);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Active sensor providers and what each of them measures
+    pub fn sensor_providers(&self) -> impl Iterator<Item = (&str, &[provider::ProvidedSensor])> {
+        self.providers.iter().map(|active| (active.provider.name(), active.sensors.as_slice()))
+    }
+
+    pub fn compositor(&self) -> Option<&Compositor> {
+        self.compositor.as_ref()
+    }
+
+    /// Average discharge power per usage context, so docked and mobile use don't share one average
+    pub fn drain_by_context(&self) -> Vec<(UsageContext, f64)> {
+        UsageContext::ALL
+            .iter()
+            .filter_map(|&context| {
+                let powers: Vec<f64> = self
+                    .readings_history
+                    .iter()
+                    .filter(|r| r.status == "Discharging" && r.context == Some(context))
+                    .filter_map(|r| r.power_now_w)
+                    .collect();
+                (!powers.is_empty()).then(|| (context, powers.iter().sum::<f64>() / powers.len() as f64))
+            })
+            .collect()
+    }
+
+    /// Time between samples in every continuous mode
+    pub fn update_interval(&self) -> Duration {
+        self.update_interval
+    }
+
+    pub fn set_update_interval(&mut self, interval: Duration) {
+        self.update_interval = interval;
+        self.resize_windows();
+    }
+
+    /// How far back the histories and the rolling power average reach
+    pub fn set_history(&mut self, history_secs: u64, rolling_secs: u64) {
+        self.history_secs = history_secs;
+        self.rolling_secs = rolling_secs;
+        self.resize_windows();
+    }
+
+    /// Sample counts for the current interval
+    pub fn window_sizes(&self) -> WindowSizes {
+        self.sizes
+    }
+
+    /// Smoother for power, current and temperature; restarts every series
+    pub fn set_smoothing(&mut self, method: Smoothing) {
+        for smoother in [&mut self.power_smoother, &mut self.current_smoother, &mut self.battery_temp_smoother, &mut self.cpu_temp_smoother] {
+            *smoother = Smoother::new(method);
+        }
+    }
+
+    /// The smoother in use
+    pub fn smoothing(&self) -> Smoothing {
+        self.power_smoother.method()
+    }
+
+    /// Recompute the sample counts from the spans, dropping the oldest samples if they shrank
+    fn resize_windows(&mut self) {
+        self.sizes = WindowSizes::for_interval(self.history_secs, self.rolling_secs, self.update_interval.as_secs());
+        let excess = |len: usize, keep: usize| len.saturating_sub(keep);
+        self.readings_history.drain(..excess(self.readings_history.len(), self.sizes.history));
+        self.power_history.drain(..excess(self.power_history.len(), self.sizes.history));
+        self.rolling_power_window.drain(..excess(self.rolling_power_window.len(), self.sizes.rolling));
+    }
+
+    /// Keep learned curves, the session, logs and history under `dir` instead of the user's
+    /// data directory, or nowhere with None. Reloads what was learned there; call before
+    /// `persist_history`.
+    pub fn set_data_dir(&mut self, dir: Option<PathBuf>) {
+        self.discharge_curve = DischargeCurve::load(dir.as_deref(), &self.battery_name);
+        self.energy_per_percent = EnergyPerPercent::load(dir.as_deref(), &self.battery_name);
+        self.charge_curve = ChargeCurve::load(dir.as_deref(), &self.battery_name);
+        self.session = session::SessionTracker::load(dir.as_deref(), &self.battery_name);
+        if let Some(engine) = &mut self.alerts {
+            engine.set_data_dir(dir.clone());
+        }
+        self.data_dir = dir;
+    }
+
+    /// Where this monitor keeps its state, if anywhere
+    pub fn data_dir(&self) -> Option<&Path> {
+        self.data_dir.as_deref()
+    }
+
+    /// Long-term health of this pack, read through the backend against the history in this
+    /// monitor's data directory
+    pub fn read_health(&self) -> health::BatteryHealth {
+        health::read_battery_health(self.data_dir.as_deref(), &self.battery_name, self.backend.as_ref())
+    }
+
+    /// Append to the health history; a log this user can't write (say one a root-run hook
+    /// created) is reported once rather than on every event
+    fn record_health(&self, sample: &health::HealthSample) {
+        if let Err(e) = health::append_health_sample(self.data_dir.as_deref(), sample) {
+            if !self.health_write_failed.swap(true, Ordering::Relaxed) {
+                let path = health::health_history_path(self.data_dir.as_deref()).unwrap_or_default();
+                warn(&format!("Could not append to {}: {}", path.display(), e));
+            }
+        }
+    }
+
+    /// Keep samples on disk: pick up the history of a previous run that is still current, and
+    /// append every new sample. Call after the interval and history spans are set.
+    pub fn persist_history(&mut self) {
+        let Some(store) = history_store::HistoryStore::open(self.data_dir.as_deref(), &self.battery_name) else {
+            return;
+        };
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let _ = store.compact(now);
+        let samples = store.load(now.saturating_sub(self.history_secs));
+        let resumed = history_store::resumable(&samples, now, SUSPEND_GAP_SECS);
+        for sample in &resumed[resumed.len().saturating_sub(self.sizes.history)..] {
+            let reading = &sample.reading;
+            if let Some(power) = reading.power_now_w {
+                self.update_smoothed_power(power);
+                if let Some(energy) = reading.energy_now_wh {
+                    self.power_history.push_back(PowerSample {
+                        timestamp: reading.timestamp,
+                        power_w: power,
+                        energy_wh: energy,
+                        cpu_temperature_c: sample.cpu_temperature_c,
+                    });
+                }
+            }
+            self.readings_history.push_back(reading.clone());
+        }
+        self.history_store = Some(store);
+    }
+
+    /// Report charge in mAh and estimate time from current instead of power
+    pub fn set_charge_units(&mut self, enabled: bool) {
+        self.charge_units = enabled;
+    }
+
+    /// Fill the dashboard bar by energy against the design capacity instead of the percentage
+    #[cfg(feature = "tui")]
+    pub fn set_energy_bar(&mut self, enabled: bool) {
+        self.energy_bar = enabled;
+    }
+
+    /// Show the power distribution panel in the dashboard
+    #[cfg(feature = "tui")]
+    pub fn set_histogram(&mut self, enabled: bool) {
+        self.histogram = enabled;
+    }
+
+    /// Track interrupts and wakeup sources while discharging, for the dashboard's wakeups panel
+    #[cfg(feature = "tui")]
+    pub fn set_wakeups(&mut self, enabled: bool) {
+        self.wakeups = enabled.then(wakeups::WakeupTracker::default);
+    }
+
+    /// Show the Pac-Cat in the dashboard
+    #[cfg(feature = "tui")]
+    pub fn set_animations(&mut self, enabled: bool) {
+        self.animations = enabled;
+    }
+
+    /// How long the dashboard runs, in seconds; None for no end
+    #[cfg(feature = "tui")]
+    pub fn set_run_duration(&mut self, duration_secs: Option<u64>) {
+        self.run_duration = duration_secs;
+    }
+
+    pub fn battery_name(&self) -> &str {
+        &self.battery_name
+    }
+
+    pub fn base_path(&self) -> &str {
+        &self.base_path
+    }
+
+    pub fn alerts(&self) -> Option<&alerts::AlertEngine> {
+        self.alerts.as_ref()
+    }
+
+    /// Quirks applied to readings from this battery
+    pub fn quirks(&self) -> &[Quirk] {
+        &self.quirks
+    }
+
+    /// Whether readings are shown as charge and current (mAh, mA) rather than energy and power
+    pub fn charge_units(&self) -> bool {
+        self.charge_units
+    }
+
+    /// Recent power readings, oldest first
+    pub fn power_history(&self) -> &VecDeque<PowerSample> {
+        &self.power_history
+    }
+
+    /// Readings in the rolling power average so far
+    pub fn rolling_samples(&self) -> usize {
+        self.rolling_power_window.len()
+    }
+
+    /// When the latest sample was taken
+    pub fn last_update(&self) -> u64 {
+        self.last_update
+    }
+
+    /// The latest plug or unplug, once debounced
+    pub fn last_plug(&self) -> Option<adapter::PlugChange> {
+        self.last_plug
+    }
+
+    /// Energy per percent learned from past discharges
+    pub fn energy_per_percent(&self) -> &EnergyPerPercent {
+        &self.energy_per_percent
+    }
+
+    /// Charging speed by percentage learned from past charges
+    pub fn charge_curve(&self) -> &ChargeCurve {
+        &self.charge_curve
+    }
+
+    fn has_quirk(&self, quirk: Quirk) -> bool {
+        self.quirks.contains(&quirk)
+    }
+
+    /// Run a user script's hooks on every sample
+    #[cfg(feature = "script")]
+    pub fn set_script(&mut self, script: script::Script) {
+        self.script = Some(script);
+    }
+
+    /// Emit every sample as StatsD gauges
+    pub fn set_statsd(&mut self, emitter: statsd::StatsdEmitter) {
+        self.statsd = Some(emitter);
+    }
+
+    /// Evaluate alert rules on every sample
+    pub fn set_alerts(&mut self, mut engine: alerts::AlertEngine) {
+        engine.set_data_dir(self.data_dir.clone());
+        self.alerts = Some(engine);
+    }
+
+    /// Read raw values without any quirk corrections (--no-quirks)
+    pub fn disable_quirks(&mut self) {
+        self.quirks.clear();
+        self.temperature_monitor.battery_temp_decidegrees = false;
+    }
+
+    fn read_file(&self, filename: &str) -> Option<String> {
+        self.backend.read_attr(filename)
+    }
+
+    /// Numeric attributes, parsed and converted to base units whatever the firmware wrote
+    pub fn attrs(&self) -> attr::Attrs<impl Fn(&str) -> Option<String> + '_> {
+        attr::Attrs::new(|name: &str| self.read_file(name))
+    }
+
+    /// Read energy values with fallback between energy_* and charge_* files
+    fn read_energy_values(&self) -> (Option<f64>, Option<f64>) {
+        let attrs = self.attrs();
+        (attrs.energy_wh("now"), attrs.energy_wh("full"))
+    }
+
+    /// energy_full_design in Wh, falling back to charge_full_design * voltage_now
+    fn read_design_energy(&self) -> Option<f64> {
+        self.attrs().energy_wh("full_design").filter(|wh| *wh > 0.0)
+    }
+
+    /// energy_full over energy_full_design, or the charge_* ratio on charge-based packs
+    fn read_health_percent(&self, energy_full_wh: Option<f64>) -> f64 {
+        let attrs = self.attrs();
+        let design_wh = attrs.number("energy_full_design").map(|e| attrs.energy_scale().apply(e));
+        match (energy_full_wh, design_wh) {
+            (Some(full), Some(design)) if design > 0.0 => (full / design) * 100.0,
+            _ => match (attrs.charge_mah("full"), attrs.charge_mah("full_design")) {
+                (Some(full), Some(design)) if design > 0.0 => (full / design) * 100.0,
+                _ => 0.0,
+            },
+        }
+    }
+
+    /// Power and capacity, cross-checked between the driver, V×I and UPower; a sensor
+    /// provider's power only stands in when none of those has a figure
+    fn reconcile(
+        &mut self,
+        status: &str,
+        capacity: u8,
+        (voltage_v, current_ma): (Option<f64>, Option<i32>),
+        (energy_now_wh, energy_full_wh): (Option<f64>, Option<f64>),
+        provided_w: Option<f64>,
+    ) -> (Option<f64>, u8, DataQuality) {
+        let mut power = Vec::new();
+        if let Some(power_w) = self.attrs().power_w() {
+            power.push(SourceValue::new("power_now", power_w));
+        }
+        if let (Some(voltage), Some(current)) = (voltage_v, current_ma) {
+            power.push(SourceValue::new("voltage_current", voltage * (current.abs() as f64 / 1000.0))); // V * |A| = W
+        }
+        if let Some(power_w) = self.sources.upower_power() {
+            power.push(SourceValue::new("upower", power_w));
+        }
+        if let (true, Some(power_w)) = (power.is_empty(), provided_w) {
+            power.push(SourceValue::new("provider", power_w));
+        }
+        // The pack feeds at least the CPU package and drains at about the fitted rate, but
+        // only while discharging; charger power also covers the rest of the machine
+        let package_w = self.sources.package_power();
+        let discharging = status == "Discharging";
+        let reference_w = match (self.percent_per_hour(), energy_full_wh) {
+            (Some(rate), Some(full)) if discharging && rate < 0.0 => Some(-rate * full / 100.0),
+            _ => None,
+        };
+        let (power_w, power_disagreement) = fusion::reconcile_power(&power, package_w.filter(|_| discharging), reference_w);
+
+        let mut others = Vec::new();
+        if let (Some(now), Some(full)) = (energy_now_wh, energy_full_wh) {
+            if full > 0.0 {
+                others.push(SourceValue::new("energy_ratio", now / full * 100.0));
+            }
+        }
+        if let Some(percent) = self.sources.upower_capacity() {
+            others.push(SourceValue::new("upower", percent));
+        }
+        let (capacity_value, capacity_disagreement) = fusion::reconcile_capacity(SourceValue::new("capacity", capacity as f64), &others);
+
+        let quality = DataQuality {
+            power_source: power_w.as_ref().map(|p| p.source.clone()),
+            capacity_source: Some(capacity_value.source),
+            disagreements: power_disagreement.into_iter().chain(capacity_disagreement).collect(),
+        };
+        (power_w.map(|p| p.value), capacity_value.value.round().clamp(0.0, 100.0) as u8, quality)
+    }
+
+    /// Get CPU temperature using the new temperature monitor
+    pub fn get_cpu_temperature(&mut self) -> Option<TemperatureReading> {
+        self.temperature_monitor.get_cpu_temp()
+    }
+
+    /// Get battery temperature using the new temperature monitor
+    pub fn get_battery_temperature(&mut self) -> Option<TemperatureReading> {
+        self.temperature_monitor.get_battery_temp()
+    }
+
+    /// A sensor provider's temperature as a reading, for machines where sysfs has none
+    fn provided_temperature(&self, provided: &ProvidedValues, quantity: Quantity) -> Option<TemperatureReading> {
+        let (value, source) = provided.source(quantity)?;
+        let label = self.providers.iter()
+            .filter(|active| active.provider.name() == source)
+            .flat_map(|active| &active.sensors)
+            .find(|sensor| sensor.quantity == quantity)
+            .map(|sensor| sensor.label.clone());
+        Some(TemperatureReading {
+            raw_value: value,
+            smoothed_value: value,
+            sensor_info: TemperatureSensor {
+                sensor_type: "provider".to_string(),
+                path: format!("provider:{}", source),
+                name: label.clone().unwrap_or_else(|| source.to_string()),
+                label,
+            },
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        })
+    }
+
+    /// Update smoothed power with the chosen smoother and the rolling window
+    fn update_smoothed_power(&mut self, current_power: f64) {
+        self.power_smoother.update(current_power);
+
+        // Update rolling window for ultra-smooth estimates
+        self.rolling_power_window.push_back(current_power);
+        if self.rolling_power_window.len() > self.sizes.rolling {
+            self.rolling_power_window.pop_front();
+        }
+    }
+
+
+    /// Get rolling average power for ultra-stable estimates
+    #[cfg(feature = "tui")]
+    pub fn get_rolling_average_power(&self) -> Option<f64> {
+        let window: Vec<f64> = self.rolling_power_window.iter().copied().collect();
+        estimation::rolling_average(&window).or(self.power_smoother.value())
+    }
+
+    /// charge_now/charge_full in mAh, read directly or converted from energy at the present voltage
+    fn read_charge_values(&self, voltage_v: Option<f64>, energy_now_wh: Option<f64>, energy_full_wh: Option<f64>) -> (Option<f64>, Option<f64>) {
+        let from_energy = |wh: Option<f64>| Some(wh? / voltage_v.filter(|v| *v > 0.0)? * 1000.0);
+        let attrs = self.attrs();
+        let charge_now = attrs.charge_mah("now").or_else(|| from_energy(energy_now_wh));
+        let charge_full = attrs.charge_mah("full").or_else(|| from_energy(energy_full_wh));
+        (charge_now, charge_full)
+    }
+
+    /// ETA in the charge domain: mAh left (or to fill) over the smoothed current draw
+    fn calculate_charge_time_remaining(&self, status: &str, charge_now_mah: f64, charge_full_mah: Option<f64>) -> Option<u32> {
+        let current = self.current_smoother.value()?;
+        estimation::charge_time_remaining(status, charge_now_mah, charge_full_mah, current, self.readings_history.len(), self.sizes)
+    }
+
+    /// Calculate highly accurate time remaining using multiple smoothing techniques
+    fn calculate_time_remaining(&self, info: &BatteryReading) -> Option<u32> {
+        let window: Vec<f64> = self.rolling_power_window.iter().copied().collect();
+        // Capacity-only packs: stand the learned Wh per percent in for the missing energy readings
+        if info.energy_now_wh.is_none() {
+            let learned = (self.energy_per_percent.energy_below(info.capacity_percent), self.energy_per_percent.energy_below(100));
+            if let (Some(now), Some(full)) = learned {
+                let info = BatteryReading { energy_now_wh: Some(now), energy_full_wh: Some(full), ..info.clone() };
+                return estimation::time_remaining(&info, self.power_smoother.value(), &window, self.power_history.len(), self.sizes);
+            }
+        }
+        estimation::time_remaining(info, self.power_smoother.value(), &window, self.power_history.len(), self.sizes)
+    }
+
+    /// Journal plug changes, charge limits, suspend gaps and CPU throttling since the previous sample
+    fn log_transition_events(&mut self, reading: &BatteryReading) {
+        let log = |kind: &str, message: String| {
+            let _ = events::log_event(self.data_dir.as_deref(), &self.battery_name, kind, &message);
+        };
+
+        let residency_us = standby::read_s2idle_residency_us();
+        let last_residency_us = std::mem::replace(&mut self.last_residency_us, residency_us);
+
+        if let Some(previous) = self.readings_history.back() {
+            let gap = reading.timestamp.saturating_sub(previous.timestamp);
+            if gap >= SUSPEND_GAP_SECS.max(self.update_interval.as_secs() * 5) {
+                let on_battery = previous.status == "Discharging" && reading.status == "Discharging";
+                let record = standby::SuspendRecord::new(
+                    (previous.timestamp, reading.timestamp),
+                    (last_residency_us, residency_us),
+                    (previous.energy_now_wh, reading.energy_now_wh),
+                    (previous.capacity_percent, reading.capacity_percent),
+                    on_battery,
+                );
+                let deep_idle = record.deep_idle_percent.map(|p| format!(", {:.0}% in deep idle", p)).unwrap_or_default();
+                log("suspend", format!(
+                    "No samples for {} (suspended?); {}% → {}%{}",
+                    format_minutes((gap / 60) as u32), previous.capacity_percent, reading.capacity_percent, deep_idle
+                ));
+                // The sleep hook, when installed, has recorded this suspend more precisely
+                if !standby::sleep_hook_installed() {
+                    let sample = health::HealthSample {
+                        timestamp: reading.timestamp,
+                        battery: self.battery_name.clone(),
+                        suspend: Some(record),
+                        ..Default::default()
+                    };
+                    self.record_health(&sample);
+                }
+            }
+
+            let capacity = reading.capacity_percent;
+            match (previous.status.as_str(), reading.status.as_str()) {
+                (before, now) if before == now => {}
+                // Plugging in and out is journaled debounced, below
+                ("Discharging", _) | (_, "Discharging") => {}
+                (_, "Full") => log("full", "Fully charged".to_string()),
+                ("Charging", "Not charging") => {
+                    match charge_limit::read_limit(&self.base_path).filter(|limit| *limit < 100) {
+                        Some(limit) if capacity + 1 >= limit => {
+                            log("charge-limited", format!("Charging stopped at {}% (limit {}%)", capacity, limit))
+                        }
+                        _ => log("charge-stopped", format!("Charging stopped at {}% while plugged in", capacity)),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Adapters when the machine has them, else the battery status
+        let on_ac = adapter::any_online(&self.adapters).unwrap_or(reading.status != "Discharging");
+        if let Some(plugged) = self.plug_debouncer.update(reading.timestamp, on_ac) {
+            let adapter_w = adapter::online_rating_w(&self.adapters).filter(|_| plugged);
+            let change = adapter::PlugChange { plugged, timestamp: reading.timestamp, adapter_w };
+            log(if plugged { "plugged" } else { "unplugged" }, change.describe(reading.capacity_percent));
+            self.last_plug = Some(change);
+        }
+
+        let count = read_throttle_count();
+        if let (Some(before), Some(now)) = (self.throttle_count, count) {
+            if now > before && reading.timestamp.saturating_sub(self.last_throttle_event) >= THROTTLE_EVENT_COOLDOWN_SECS {
+                self.last_throttle_event = reading.timestamp;
+                let temperature = self.temperature_monitor.last_cpu_temp.as_ref()
+                    .map(|t| format!(" at {:.0}°C", t.raw_value))
+                    .unwrap_or_default();
+                let _ = events::log_event(self.data_dir.as_deref(), &self.battery_name, "thermal-throttle",
+                    &format!("CPU thermally throttled {} times{}", now - before, temperature));
+            }
+        }
+        self.throttle_count = count.or(self.throttle_count);
+    }
+
+    /// Compare voltage against the learned discharge curve; returns the sag (V) once confirmed
+    fn check_voltage_sag(&mut self, reading: &BatteryReading) -> Option<f64> {
+        let voltage = reading.voltage_v.filter(|_| reading.status == "Discharging");
+        let Some(voltage) = voltage else {
+            self.sag_streak = 0;
+            return None;
+        };
+
+        match self.discharge_curve.sag_below_expected(reading.capacity_percent, voltage) {
+            Some(sag) => {
+                self.sag_streak += 1;
+                if self.sag_streak < SAG_CONFIRM_SAMPLES {
+                    return None;
+                }
+                if reading.timestamp.saturating_sub(self.last_sag_event) >= SAG_EVENT_COOLDOWN_SECS {
+                    self.last_sag_event = reading.timestamp;
+                    let message = format!(
+                        "Voltage {:.2}V is {:.2}V below normal at {}% — possible aging or contact issue",
+                        voltage, sag, reading.capacity_percent
+                    );
+                    let _ = events::log_event(self.data_dir.as_deref(), &self.battery_name, "voltage-sag", &message);
+                }
+                Some(sag)
+            }
+            None => {
+                // Only learn from samples that look normal so sags don't drag the curve down
+                self.sag_streak = 0;
+                self.discharge_curve.learn(reading.capacity_percent, voltage);
+                self.curve_samples_unsaved += 1;
+                if self.curve_samples_unsaved >= CURVE_SAVE_EVERY {
+                    let _ = self.discharge_curve.save();
+                    self.curve_samples_unsaved = 0;
+                }
+                None
+            }
+        }
+    }
+
+    /// Determine power trend from recent history
+    fn get_power_trend(&self) -> String {
+        let powers: Vec<f64> = self.power_history.iter().map(|sample| sample.power_w).collect();
+        estimation::power_trend(&powers).as_str().to_string()
+    }
+
+    pub fn get_battery_info(&mut self) -> Option<BatteryInfo> {
+        self.backend.refresh();
+        if !self.backend.exists() {
+            return None;
+        }
+
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let provided = ProvidedValues::sample(&mut self.providers);
+        
+        // Read basic values
+        let status = self.read_file("status").unwrap_or_else(|| "Unknown".to_string());
+        let mut capacity = self.attrs().integer("capacity").unwrap_or(0u8);
+        if self.has_quirk(Quirk::CapacityStuckAt99) && capacity == 99 && status == "Full" {
+            capacity = 100;
+        }
+        let voltage_v = self.attrs().voltage_v().or(provided.get(Quantity::Voltage));
+        let current_ma = self.attrs().current_ma()
+            .map(|c| if self.has_quirk(Quirk::CurrentSignInverted) { -c } else { c })
+            .or(provided.get(Quantity::Current).map(|ma| ma.round() as i32));
+        let cycles = self.attrs().integer("cycle_count");
+
+        // Read energy values with fallbacks
+        let (energy_now_wh, energy_full_wh) = self.read_energy_values();
+
+        // Cross-check power and capacity between the sources that report them
+        let (power_w, capacity, data_quality) =
+            self.reconcile(&status, capacity, (voltage_v, current_ma), (energy_now_wh, energy_full_wh), provided.get(Quantity::Power));
+
+        // Get real-time temperatures using the new API, then from sensor providers
+        let cpu_temp_reading = self.get_cpu_temperature().or_else(|| {
+            let reading = self.provided_temperature(&provided, Quantity::CpuTemperature)?;
+            self.temperature_monitor.last_cpu_temp = Some(reading.clone());
+            Some(reading)
+        });
+        let battery_temp_reading = self.get_battery_temperature().or_else(|| {
+            let reading = self.provided_temperature(&provided, Quantity::BatteryTemperature)?;
+            self.temperature_monitor.last_battery_temp = Some(reading.clone());
+            Some(reading)
+        });
+        // Temperatures go through the same smoother as power; history keeps the raw values
+        if let (Some(reading), Some(last)) = (&cpu_temp_reading, self.temperature_monitor.last_cpu_temp.as_mut()) {
+            last.smoothed_value = self.cpu_temp_smoother.update(reading.raw_value);
+        }
+        if let (Some(reading), Some(last)) = (&battery_temp_reading, self.temperature_monitor.last_battery_temp.as_mut()) {
+            last.smoothed_value = self.battery_temp_smoother.update(reading.raw_value);
+        }
+        let cpu_temperature_c = cpu_temp_reading.as_ref().map(|r| r.raw_value);
+        let _temperature_c = battery_temp_reading.as_ref().map(|r| r.raw_value);
+
+        // Charge and discharge power must not blend: start smoothing over when the adapter flips
+        let ac_online = adapter::any_online(&self.adapters);
+        if ac_online.is_some() && self.last_ac_online.is_some() && ac_online != self.last_ac_online {
+            self.power_smoother.reset();
+            self.current_smoother.reset();
+            self.rolling_power_window.clear();
+        }
+        self.last_ac_online = ac_online.or(self.last_ac_online);
+
+        // Update smoothed values
+        if let Some(power) = power_w {
+            self.update_smoothed_power(power);
+            if status == "Discharging" {
+                self.session_power.record(power);
+            }
+            
+            // Add to power history
+            if let Some(energy) = energy_now_wh {
+                self.power_history.push_back(PowerSample {
+                    timestamp,
+                    power_w: power,
+                    energy_wh: energy,
+                    cpu_temperature_c,
+                });
+                
+                if self.power_history.len() > self.sizes.history {
+                    self.power_history.pop_front();
+                }
+
+                // Cross-check the fuel gauge against integrated power
+                if let Some(segment) = self.gauge_drift.update(timestamp, &status, power, energy) {
+                    let sample = health::HealthSample {
+                        timestamp,
+                        battery: self.battery_name.clone(),
+                        gauge_reported_wh: Some(segment.reported_wh),
+                        gauge_integrated_wh: Some(segment.integrated_wh),
+                        ..Default::default()
+                    };
+                    self.record_health(&sample);
+                }
+            }
+        }
+
+        self.last_update = timestamp;
+        #[cfg(feature = "tui")]
+        if let Some(tracker) = self.wakeups.as_mut() {
+            tracker.sample(timestamp, &status);
+        }
+
+        // Create reading for history
+        let reading = BatteryReading {
+            timestamp,
+            capacity_percent: capacity,
+            energy_now_wh,
+            energy_full_wh,
+            power_now_w: power_w,
+            voltage_v,
+            current_ma,
+            status: status.clone(),
+            temperature_c: battery_temp_reading.as_ref().map(|r| r.raw_value),
+            context: self.compositor.as_ref().and_then(|c| c.query_context()),
+        };
+
+        // Calculate time remaining, in the charge domain when asked to and the pack reports charge_*
+        let (charge_now_mah, charge_full_mah) = if self.charge_units {
+            if let Some(current) = current_ma {
+                let current = current.unsigned_abs() as f64;
+                self.current_smoother.update(current);
+            }
+            self.read_charge_values(voltage_v, energy_now_wh, energy_full_wh)
+        } else {
+            (None, None)
+        };
+        let charge_eta = match charge_now_mah {
+            Some(now) if self.charge_units && self.backend.has_attr("charge_now") => {
+                self.calculate_charge_time_remaining(&reading.status, now, charge_full_mah)
+            }
+            _ => None,
+        };
+        let time_remaining_minutes = charge_eta.or_else(|| self.calculate_time_remaining(&reading));
+        let eta_stability = self.track_eta(timestamp, &reading.status, time_remaining_minutes);
+        let voltage_sag_v = self.check_voltage_sag(&reading);
+        if self.energy_per_percent.learn(&reading) {
+            let _ = self.energy_per_percent.save();
+        }
+        if self.charge_curve.learn(&reading) {
+            let _ = self.charge_curve.save();
+        }
+        self.log_transition_events(&reading);
+        if timestamp.saturating_sub(self.last_usage_record) >= usage::USAGE_RECORD_INTERVAL_SECS {
+            self.last_usage_record = timestamp;
+            let _ = usage::append_usage_sample(self.data_dir.as_deref(), &self.battery_name, &reading);
+        }
+
+        // Add to readings history, and the on-disk store
+        if let Some(store) = &self.history_store {
+            let _ = store.append(&history_store::StoredSample { reading: reading.clone(), cpu_temperature_c });
+        }
+        self.readings_history.push_back(reading);
+        if self.readings_history.len() > self.sizes.history {
+            self.readings_history.pop_front();
+        }
+
+        let health_percent = self.read_health_percent(energy_full_wh);
+
+        let power_trend = self.get_power_trend();
+        if let Some(finished) = self.session.update(timestamp, &status, capacity, energy_now_wh, power_w) {
+            let sample = health::HealthSample {
+                timestamp,
+                battery: self.battery_name.clone(),
+                charge_session: Some(finished),
+                ..Default::default()
+            };
+            self.record_health(&sample);
+        }
+        let active_charge = self.session.active_charge();
+        let percent_per_hour = self.percent_per_hour();
+        let time_to_80_minutes = self.charge_eta_to(charge_curve::PROJECTION_MILESTONE_PERCENT, &status, capacity, percent_per_hour);
+
+        let info = BatteryInfo {
+            status,
+            capacity_percent: capacity,
+            health_percent,
+            cycles,
+            temperature_c: battery_temp_reading.as_ref().map(|r| r.raw_value),
+            voltage_v,
+            current_ma,
+            power_w,
+            smoothed_power_w: self.power_smoother.value(),
+            manufacturer: self.read_file("manufacturer").unwrap_or_else(|| "Unknown".to_string()),
+            model: self.read_file("model_name").unwrap_or_else(|| "Unknown".to_string()),
+            technology: self.read_file("technology").unwrap_or_else(|| "Unknown".to_string()),
+            time_remaining_minutes,
+            eta_stability,
+            energy_now_wh,
+            energy_full_wh,
+            energy_full_design_wh: self.read_design_energy(),
+            power_trend,
+            cpu_temperature_c,
+            voltage_sag_v,
+            charge_now_mah,
+            charge_full_mah,
+            last_full_secs_ago: self.session.secs_since_full(timestamp),
+            unplugged_secs: self.session.secs_unplugged(timestamp),
+            energy_since_unplug_wh: self.session.energy_since_unplug_wh(energy_now_wh),
+            charge_session_added_wh: active_charge.map(|c| c.energy_added_wh),
+            charge_session_start_percent: active_charge.map(|c| c.start_percent),
+            charge_session_secs: active_charge.map(|c| timestamp.saturating_sub(c.start)),
+            percent_per_hour,
+            time_to_80_minutes,
+            nickname: config::nickname(&self.battery_name).map(str::to_string),
+            data_quality,
+            script: BTreeMap::new(),
+            script_lines: Vec::new(),
+            ac_online,
+        };
+        #[cfg(feature = "script")]
+        let (info, script_alerts) = self.run_script(info);
+
+        if let Some(ref emitter) = self.statsd {
+            emitter.emit(&info);
+        }
+        if let Some(ref mut engine) = self.alerts {
+            if let Some(change) = self.last_plug.filter(|change| change.timestamp == timestamp) {
+                engine.notify_plug(&self.battery_name, &info, &change);
+            }
+            engine.evaluate(&self.battery_name, &info);
+            #[cfg(feature = "script")]
+            engine.evaluate_custom(&self.battery_name, &info, &script_alerts);
+        }
+        Some(info)
+    }
+
+    /// Add the user script's fields and lines to a sample; returns the alerts it raised
+    #[cfg(feature = "script")]
+    fn run_script(&mut self, mut info: BatteryInfo) -> (BatteryInfo, Vec<alerts::CustomAlert>) {
+        let Some(script) = self.script.as_mut() else {
+            return (info, Vec::new());
+        };
+        let output = script.run(&info);
+        info.script = output.fields;
+        info.script_lines = output.lines;
+        (info, output.alerts)
+    }
+
+    /// Capacity change in %/h fitted over the last DRAIN_RATE_WINDOW_SECS, negative while draining
+    pub fn percent_per_hour(&self) -> Option<f64> {
+        estimation::percent_per_hour(self.readings_history.iter(), estimation::DRAIN_RATE_WINDOW_SECS)
+    }
+
+    /// Record this sample's ETA and score how steady the ETAs of the last few minutes were;
+    /// a status change starts the series over, since charging and draining ETAs don't compare
+    fn track_eta(&mut self, timestamp: u64, status: &str, eta: Option<u32>) -> Option<f64> {
+        if self.readings_history.back().is_some_and(|previous| previous.status != status) {
+            self.eta_history.clear();
+        }
+        let since = timestamp.saturating_sub(estimation::ETA_STABILITY_WINDOW_SECS);
+        while self.eta_history.front().is_some_and(|(t, _)| *t < since) {
+            self.eta_history.pop_front();
+        }
+        self.eta_history.push_back((timestamp, eta?));
+        estimation::eta_stability(self.eta_history.make_contiguous())
+    }
+
+    /// Minutes until a charge reaches `target`%, projected along the learned charge-rate curve
+    fn charge_eta_to(&self, target: u8, status: &str, capacity: u8, percent_per_hour: Option<f64>) -> Option<u32> {
+        if status != "Charging" || capacity >= target {
+            return None;
+        }
+        let minutes = self.charge_curve.projection(capacity, percent_per_hour)?;
+        Some(minutes[(target - capacity) as usize - 1].round().max(1.0) as u32)
+    }
+
+    /// Recent readings, oldest first
+    pub fn readings_history(&self) -> &VecDeque<BatteryReading> {
+        &self.readings_history
+    }
+
+    /// How CPU temperature has tracked power draw over the recent history
+    pub fn thermal_correlation(&self) -> Option<estimation::ThermalCorrelation> {
+        let points: Vec<(f64, f64)> = self
+            .power_history
+            .iter()
+            .filter_map(|sample| Some((sample.power_w, sample.cpu_temperature_c?)))
+            .collect();
+        estimation::thermal_correlation(&points)
+    }
+
+    /// Discharging power draw over this session
+    pub fn session_power(&self) -> &distribution::PowerDistribution {
+        &self.session_power
+    }
+
+    pub fn temperature_monitor(&self) -> &TemperatureMonitor {
+        &self.temperature_monitor
+    }
+
+    /// Effective internal resistance (mΩ) from load steps in the reading history
+    pub fn estimate_internal_resistance(&self) -> Option<f64> {
+        let readings: Vec<BatteryReading> = self.readings_history.iter().cloned().collect();
+        health::estimate_internal_resistance(&readings)
+    }
+
+    pub fn to_json(&self, info: &BatteryInfo) -> String {
+        serde_json::to_string_pretty(info).unwrap_or_else(|_| "{}".to_string())
+    }
+}
+
+/// The interactive dashboard
+#[cfg(feature = "tui")]
+impl BatteryMonitor {
+    /// Whether the bar fills by energy against the design capacity
+    pub fn energy_bar(&self) -> bool {
+        self.energy_bar
+    }
+
+    /// Whether the power distribution panel is shown
+    pub fn histogram(&self) -> bool {
+        self.histogram
+    }
+
+    /// Interrupt and wakeup counts, when the wakeups panel is shown
+    pub fn wakeups(&self) -> Option<&wakeups::WakeupTracker> {
+        self.wakeups.as_ref()
+    }
+
+    /// Whether the Pac-Cat is shown
+    pub fn animations(&self) -> bool {
+        self.animations
+    }
+
+    /// How long the dashboard runs, in seconds; None for no end
+    pub fn run_duration(&self) -> Option<u64> {
+        self.run_duration
+    }
+
+    /// Bar filled to `fill_percent`, colored by the capacity band
+    pub fn get_battery_bar(&self, capacity: u8, fill_percent: f64, width: usize) -> String {
+        let filled = ((fill_percent.clamp(0.0, 100.0) / 100.0 * width as f64) as usize).min(width);
+        let empty = width - filled;
+        
+        let color = match ChargeLevel::from_capacity(capacity) {
+            ChargeLevel::Critical => "\x1b[31m", // Red
+            ChargeLevel::Low => "\x1b[33m",      // Yellow
+            ChargeLevel::Normal => "\x1b[32m",   // Green
+            ChargeLevel::High => "\x1b[36m",     // Cyan
+        };
+        
+        format!("{}{}{}{}",
+            color,
+            "█".repeat(filled),
+            "░".repeat(empty),
+            "\x1b[0m"
+        )
+    }
+
+    /// Energy left as a share of the design capacity, so wear shows up in the bar
+    pub fn design_energy_percent(&self, info: &BatteryInfo) -> Option<f64> {
+        Some(info.energy_now_wh? / info.energy_full_design_wh? * 100.0)
+    }
+
+    pub fn get_trend_indicator(&self) -> String {
+        let capacities: Vec<u8> = self.readings_history.iter().map(|r| r.capacity_percent).collect();
+        let trend = match self.percent_per_hour() {
+            Some(rate) => estimation::rate_trend(rate),
+            None => estimation::capacity_trend(&capacities),
+        };
+        match trend {
+            estimation::Trend::Increasing => "\x1b[32m↗\x1b[0m".to_string(), // Green up
+            estimation::Trend::Decreasing => "\x1b[31m↘\x1b[0m".to_string(), // Red down
+            estimation::Trend::Stable if capacities.len() < 2 => "━".to_string(),
+            estimation::Trend::Stable => "\x1b[37m━\x1b[0m".to_string(), // Gray stable
+        }
+    }
+
+    pub fn get_power_graph(&self, width: usize) -> String {
+        if self.power_history.len() < 2 {
+            return " ".repeat(width);
+        }
+
+        let values: Vec<f64> = self.power_history.iter().map(|p| p.power_w).collect();
+        let min_val = values.iter().fold(f64::INFINITY, |a, &b| a.min(b));
+        let max_val = values.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
+        let range = if (max_val - min_val).abs() < 0.1 { 0.1 } else { max_val - min_val };
+
+        let bars = ["▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+        
+        values.iter()
+            .rev()
+            .take(width)
+            .map(|&val| {
+                let normalized = ((val - min_val) / range * (bars.len() - 1) as f64) as usize;
+                bars[normalized.min(bars.len() - 1)]
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .collect()
+    }
+
+    /// Journal events under the matching columns of `get_power_graph(width)`, plus the kinds
+    /// marked, in order of first appearance. Alerts win when several events share a column.
+    pub fn get_power_graph_annotations(&self, width: usize) -> (String, Vec<String>) {
+        let shown: Vec<u64> = self.power_history.iter().skip(self.power_history.len().saturating_sub(width)).map(|s| s.timestamp).collect();
+        let Some(&first) = shown.first() else {
+            return (String::new(), Vec::new());
+        };
+        let journal: Vec<events::Event> =
+            events::load_events(self.data_dir.as_deref(), first).into_iter().filter(|event| event.battery == self.battery_name).collect();
+
+        let mut columns: Vec<Option<&events::Event>> = vec![None; shown.len()];
+        for event in &journal {
+            // Events are logged while taking the sample they belong to, or just after it
+            let column = shown.iter().position(|&ts| ts >= event.timestamp).unwrap_or(shown.len() - 1);
+            if columns[column].is_none_or(|earlier| events::graph_marker(&earlier.kind) != '!') {
+                columns[column] = Some(event);
+            }
+        }
+
+        let mut kinds: Vec<String> = Vec::new();
+        let row = columns
+            .iter()
+            .map(|event| match event {
+                Some(event) => {
+                    if !kinds.contains(&event.kind) {
+                        kinds.push(event.kind.clone());
+                    }
+                    format!("{}{}\x1b[0m", events::kind_color(&event.kind), events::graph_marker(&event.kind))
+                }
+                None => " ".to_string(),
+            })
+            .collect();
+        (row, kinds)
+    }
+
+    /// Power (•, left axis) and CPU temperature (×, right axis) over the same samples, one row per line
+    pub fn get_thermal_chart(&self, width: usize, height: usize) -> Vec<String> {
+        let samples: Vec<(f64, f64)> = self
+            .power_history
+            .iter()
+            .filter_map(|sample| Some((sample.power_w, sample.cpu_temperature_c?)))
+            .collect();
+        let samples = &samples[samples.len().saturating_sub(width)..];
+        if samples.len() < 2 || height < 2 {
+            return Vec::new();
+        }
+
+        let span = |values: &mut dyn Iterator<Item = f64>| {
+            let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(v), hi.max(v)));
+            (min, if max - min < 0.1 { min + 0.1 } else { max })
+        };
+        let (min_w, max_w) = span(&mut samples.iter().map(|s| s.0));
+        let (min_c, max_c) = span(&mut samples.iter().map(|s| s.1));
+        let row = |value: f64, min: f64, max: f64| ((max - value) / (max - min) * (height - 1) as f64).round() as usize;
+
+        (0..height)
+            .map(|line| {
+                let cells: String = samples
+                    .iter()
+                    .map(|&(watts, celsius)| match (row(watts, min_w, max_w) == line, row(celsius, min_c, max_c) == line) {
+                        (true, true) => "\x1b[35m◆\x1b[0m",
+                        (true, false) => "\x1b[33m•\x1b[0m",
+                        (false, true) => "\x1b[31m×\x1b[0m",
+                        (false, false) => " ",
+                    })
+                    .collect();
+                let padding = " ".repeat(width - samples.len());
+                match line {
+                    0 => format!("{:>6.1}W ┤{}{}├ {:.0}°C", max_w, cells, padding, max_c),
+                    l if l == height - 1 => format!("{:>6.1}W ┤{}{}├ {:.0}°C", min_w, cells, padding, min_c),
+                    _ => format!("{:>7} │{}{}│", "", cells, padding),
+                }
+            })
+            .collect()
+    }
+
+    pub fn format_time(&self, minutes: u32) -> String {
+        format_minutes(minutes)
+    }
+}
+
+/// Directory for persisted state (XDG_DATA_HOME/batfi, falling back to ~/.local/share/batfi)
+pub fn data_dir() -> Option<PathBuf> {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+    Some(data_home.join("batfi"))
+}
+
+/// Format minutes as "2h 41m" / "41m"
+pub fn format_minutes(minutes: u32) -> String {
+    timefmt::eta(minutes)
+}
+
+/// Parse a duration like "90", "30s", "10m", "2h" or "7d" into seconds
+pub fn parse_duration_secs(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let (number, multiplier) = match text.chars().last()? {
+        's' => (&text[..text.len() - 1], 1),
+        'm' => (&text[..text.len() - 1], 60),
+        'h' => (&text[..text.len() - 1], 3600),
+        'd' => (&text[..text.len() - 1], 86_400),
+        'w' => (&text[..text.len() - 1], 604_800),
+        _ => (text, 1),
+    };
+    number.trim().parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+/// Sum of the per-CPU thermal throttle counters, where the platform exposes them
+fn read_throttle_count() -> Option<u64> {
+    let counts: Vec<u64> = fs::read_dir(sysfs_path("devices/system/cpu"))
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .flat_map(|entry| {
+            let dir = entry.path().join("thermal_throttle");
+            ["core_throttle_count", "package_throttle_count"].map(|name| {
+                fs::read_to_string(dir.join(name)).ok().and_then(|text| text.trim().parse::<u64>().ok())
+            })
+        })
+        .flatten()
+        .collect();
+    (!counts.is_empty()).then(|| counts.iter().sum())
+}
+
+pub fn find_batteries() -> Vec<String> {
+    let power_supply_path = sysfs_path("class/power_supply");
+    if !power_supply_path.exists() {
+        return vec![];
+    }
+
+    let mut batteries: Vec<String> = fs::read_dir(&power_supply_path)
+        .unwrap_or_else(|_| fs::read_dir(".").unwrap())
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if name.starts_with("BAT") || name.starts_with("battery") {
+                Some(name)
+            } else {
+                None
+            }
+        })
+        .collect();
+    batteries.sort(); // read_dir order is arbitrary; keep "first battery" stable
+    batteries
+}
+
+/// Column order of `batfi log` CSV output
+pub const LOG_CSV_HEADER: &str =
+    "timestamp,capacity_percent,status,power_w,voltage_v,current_ma,energy_now_wh,temperature_c,time_remaining_minutes";
+
+/// A JSON log line: the (selected) sample plus its timestamp
+pub fn log_json_line(timestamp: u64, info: &BatteryInfo, fields: Option<&[String]>) -> String {
+    let value = match fields {
+        Some(fields) => fields::select(info, fields),
+        None => serde_json::to_value(info).unwrap_or_default(),
+    };
+    let mut line = serde_json::Map::new();
+    line.insert("timestamp".to_string(), timefmt::timestamp_json(timestamp));
+    if let serde_json::Value::Object(mut map) = value {
+        line.append(&mut map);
+    }
+    serde_json::Value::Object(line).to_string()
+}
+
+/// A `batfi log` CSV row in LOG_CSV_HEADER order
+pub fn log_csv_row(timestamp: u64, info: &BatteryInfo) -> String {
+    fn opt<T: std::fmt::Display>(value: Option<T>) -> String {
+        value.map(|v| v.to_string()).unwrap_or_default()
+    }
+    format!(
+        "{},{},{},{},{},{},{},{},{}",
+        timefmt::timestamp(timestamp),
+        info.capacity_percent,
+        info.status,
+        opt(info.power_w.map(|p| format!("{:.3}", p))),
+        opt(info.voltage_v.map(|v| format!("{:.3}", v))),
+        opt(info.current_ma),
+        opt(info.energy_now_wh.map(|e| format!("{:.3}", e))),
+        opt(info.temperature_c.map(|t| format!("{:.1}", t))),
+        opt(info.time_remaining_minutes),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backend::{mock_monitor, MockBackend};
+    use estimation::{MIN_SAMPLES_FOR_ESTIMATE, ROLLING_WINDOW_SIZE};
+
+    fn steady_discharge(samples: usize, energy_wh: f64, power_w: f64) -> MockBackend {
+        (0..samples).fold(MockBackend::new(), |backend, i| {
+            backend.discharging(energy_wh - i as f64 * 0.01, 50.0, power_w)
+        })
+    }
+
+    #[test]
+    fn no_battery_without_readings() {
+        assert!(mock_monitor(MockBackend::new()).0.get_battery_info().is_none());
+    }
+
+    #[test]
+    fn eta_waits_for_enough_samples() {
+        let (mut monitor, _dir) = mock_monitor(steady_discharge(MIN_SAMPLES_FOR_ESTIMATE, 40.0, 10.0));
+        for _ in 1..MIN_SAMPLES_FOR_ESTIMATE {
+            assert_eq!(monitor.get_battery_info().unwrap().time_remaining_minutes, None);
+        }
+        let eta = monitor.get_battery_info().unwrap().time_remaining_minutes.unwrap();
+        assert!((235..=240).contains(&eta), "40 Wh at 10 W should be ~240 min, got {}", eta);
+    }
+
+    #[test]
+    fn smoothing_damps_a_power_spike() {
+        let backend = steady_discharge(ROLLING_WINDOW_SIZE + 2, 40.0, 10.0).discharging(39.8, 50.0, 40.0);
+        let (mut monitor, _dir) = mock_monitor(backend);
+        for _ in 0..ROLLING_WINDOW_SIZE + 2 {
+            monitor.get_battery_info();
+        }
+        let info = monitor.get_battery_info().unwrap();
+        let smoothed = info.smoothed_power_w.unwrap();
+        assert!(smoothed > 10.0 && smoothed < 20.0, "EMA should move only part way to the spike, got {}", smoothed);
+        // A 40 W reading alone would say 60 minutes
+        let eta = info.time_remaining_minutes.unwrap();
+        assert!(eta > 100 && eta < 239, "spike should pull the ETA down only partly, got {}", eta);
+    }
+
+    #[test]
+    fn charging_eta_counts_to_full() {
+        let charging = |energy: f64| {
+            vec![
+                ("status", "Charging".to_string()),
+                ("capacity", ((energy / 50.0 * 100.0) as u8).to_string()),
+                ("energy_now", ((energy * 1_000_000.0) as u64).to_string()),
+                ("energy_full", "50000000".to_string()),
+                ("power_now", "20000000".to_string()),
+            ]
+        };
+        let backend = (0..MIN_SAMPLES_FOR_ESTIMATE).fold(MockBackend::new(), |b, i| b.push(&charging(20.0 + i as f64 * 0.01)));
+        let (mut monitor, _dir) = mock_monitor(backend);
+        let mut info = None;
+        for _ in 0..MIN_SAMPLES_FOR_ESTIMATE {
+            info = monitor.get_battery_info();
+        }
+        // 30 Wh to go at 20 W and 90% efficiency
+        let eta = info.unwrap().time_remaining_minutes.unwrap();
+        assert!((95..=105).contains(&eta), "expected ~100 min, got {}", eta);
+    }
+
+    struct FixedProvider;
+
+    impl provider::SensorProvider for FixedProvider {
+        fn name(&self) -> &str {
+            "bench-meter"
+        }
+        fn discover(&mut self) -> Vec<provider::ProvidedSensor> {
+            vec![
+                provider::ProvidedSensor::new(Quantity::Voltage, "Shunt"),
+                provider::ProvidedSensor::new(Quantity::CpuTemperature, "EC CPU"),
+            ]
+        }
+        fn sample(&mut self) -> Vec<provider::SensorValue> {
+            vec![
+                provider::SensorValue { quantity: Quantity::Voltage, value: 11.5 },
+                provider::SensorValue { quantity: Quantity::CpuTemperature, value: 61.0 },
+            ]
+        }
+    }
+
+    #[test]
+    fn sensor_providers_fill_in_missing_readings() {
+        // energy and power but no voltage_now; the meter's voltage stands in, sysfs power is kept
+        let backend = MockBackend::new().push(&[
+            ("status", "Discharging".to_string()),
+            ("capacity", "80".to_string()),
+            ("energy_now", "40000000".to_string()),
+            ("energy_full", "50000000".to_string()),
+            ("power_now", "10000000".to_string()),
+        ]);
+        let (mut monitor, _dir) = mock_monitor(backend);
+        assert!(monitor.add_sensor_provider(Box::new(FixedProvider)));
+        let info = monitor.get_battery_info().unwrap();
+        assert_eq!(info.voltage_v, Some(11.5));
+        assert_eq!(info.power_w, Some(10.0));
+        if monitor.temperature_monitor().cpu_sensors.is_empty() {
+            assert_eq!(info.cpu_temperature_c, Some(61.0));
+            assert_eq!(monitor.temperature_monitor().last_cpu_temp.as_ref().unwrap().sensor_info.name, "EC CPU");
+        }
+        assert_eq!(monitor.sensor_providers().map(|(name, _)| name).collect::<Vec<_>>(), ["bench-meter"]);
+    }
+}
@@ -0,0 +1,139 @@
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::{config, set_discovery_log, sysfs_path, TemperatureMonitor, TemperatureSensor};
+
+/// One entry of /sys/class/power_supply
+#[derive(Debug, Serialize)]
+pub struct PowerSupplyDevice {
+    pub name: String,
+    /// battery, ac, ups, usb or peripheral (device-scoped batteries like mice)
+    pub kind: String,
+    pub path: String,
+    pub model: Option<String>,
+    pub reading: Option<String>,
+    /// From the config's [nicknames]
+    pub nickname: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SensorDevice {
+    /// What batfi uses the sensor for: cpu or battery
+    pub role: String,
+    pub name: String,
+    pub sensor_type: String,
+    pub path: String,
+    pub celsius: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeviceList {
+    pub power_supplies: Vec<PowerSupplyDevice>,
+    pub temperature_sensors: Vec<SensorDevice>,
+}
+
+fn read_attr(path: &Path, attr: &str) -> Option<String> {
+    fs::read_to_string(path.join(attr)).ok().map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+fn describe_power_supply(path: &Path) -> Option<PowerSupplyDevice> {
+    let name = path.file_name()?.to_str()?.to_string();
+    let supply_type = read_attr(path, "type").unwrap_or_else(|| "Unknown".to_string());
+    let device_scoped = read_attr(path, "scope").is_some_and(|scope| scope == "Device");
+    let kind = match supply_type.as_str() {
+        "Battery" if device_scoped => "peripheral".to_string(),
+        "Battery" => "battery".to_string(),
+        "Mains" => "ac".to_string(),
+        other => other.to_lowercase(),
+    };
+
+    let reading = match (read_attr(path, "capacity"), read_attr(path, "online")) {
+        (Some(capacity), _) => Some(match read_attr(path, "status") {
+            Some(status) => format!("{}% {}", capacity, status),
+            None => format!("{}%", capacity),
+        }),
+        (None, Some(online)) => Some(if online == "1" { "online" } else { "offline" }.to_string()),
+        (None, None) => None,
+    };
+
+    Some(PowerSupplyDevice {
+        kind,
+        path: path.display().to_string(),
+        model: read_attr(path, "model_name"),
+        reading,
+        nickname: config::nickname(&name).map(str::to_string),
+        name,
+    })
+}
+
+fn sensor_device(role: &str, sensor: &TemperatureSensor, monitor: &TemperatureMonitor) -> SensorDevice {
+    let raw = monitor.read_temperature_from_path(&sensor.path);
+    let celsius = match role {
+        "battery" => raw.map(|r| monitor.normalize_battery_temperature(r)),
+        _ => raw.map(|r| r / 1000.0),
+    };
+    SensorDevice {
+        role: role.to_string(),
+        name: sensor.name.clone(),
+        sensor_type: sensor.sensor_type.clone(),
+        path: sensor.path.clone(),
+        celsius,
+    }
+}
+
+pub fn list_devices() -> DeviceList {
+    let mut power_supplies: Vec<PowerSupplyDevice> = fs::read_dir(sysfs_path("class/power_supply"))
+        .map(|entries| entries.filter_map(|e| e.ok()).filter_map(|e| describe_power_supply(&e.path())).collect())
+        .unwrap_or_default();
+    power_supplies.sort_by(|a, b| a.name.cmp(&b.name));
+
+    // The discovery narration is what this command replaces
+    set_discovery_log(false);
+    let temps = TemperatureMonitor::new();
+    let temperature_sensors = temps
+        .cpu_sensors
+        .iter()
+        .map(|sensor| sensor_device("cpu", sensor, &temps))
+        .chain(temps.battery_sensors.iter().map(|sensor| sensor_device("battery", sensor, &temps)))
+        .collect();
+
+    DeviceList { power_supplies, temperature_sensors }
+}
+
+/// `batfi list`: everything batfi can see, with a sample reading each
+pub fn run_list(json_output: bool) {
+    let devices = list_devices();
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&devices).unwrap_or_else(|_| "{}".to_string()));
+        return;
+    }
+
+    println!(" \x1b[1mPower supplies:\x1b[0m");
+    if devices.power_supplies.is_empty() {
+        println!(" └─ \x1b[2mnone found in {}\x1b[0m", sysfs_path("class/power_supply").display());
+    }
+    for (i, device) in devices.power_supplies.iter().enumerate() {
+        let branch = if i + 1 == devices.power_supplies.len() { "└─" } else { "├─" };
+        let nickname = device.nickname.as_ref().map(|nick| format!(" \x1b[36m\"{}\"\x1b[0m", nick)).unwrap_or_default();
+        println!(" {} \x1b[1m{:<12}\x1b[0m {:<10} {:<18} {}{}",
+            branch,
+            device.name,
+            device.kind,
+            device.reading.as_deref().unwrap_or("—"),
+            device.model.as_deref().unwrap_or(""),
+            nickname);
+    }
+
+    println!();
+    println!(" \x1b[1mTemperature sensors:\x1b[0m");
+    if devices.temperature_sensors.is_empty() {
+        println!(" └─ \x1b[2mnone found\x1b[0m");
+    }
+    for (i, sensor) in devices.temperature_sensors.iter().enumerate() {
+        let branch = if i + 1 == devices.temperature_sensors.len() { "└─" } else { "├─" };
+        let celsius = sensor.celsius.map(|c| format!("{:.1}°C", c)).unwrap_or_else(|| "—".to_string());
+        println!(" {} \x1b[1m{:<8}\x1b[0m {:<24} {:>8}  \x1b[2m{}\x1b[0m", branch, sensor.role, sensor.name, celsius, sensor.path);
+    }
+}
@@ -0,0 +1,111 @@
+# Deutsche Oberflächentexte
+
+app-title = Batfi v2.0 - Erweiterter Akkumonitor
+header-paused = ⏸ Pausiert
+banner-plugged = ⚡ Netzteil angeschlossen
+banner-plugged-adapter = ⚡ Netzteil angeschlossen — { $watts }W
+banner-unplugged = 🔋 Netzteil getrennt — Akkubetrieb bei { $capacity }%
+
+status-charging = Lädt
+status-discharging = Entlädt
+status-full = Voll
+status-not-charging = Lädt nicht
+status-unknown = Unbekannt
+
+bar-of-design = { $percent }% der Nennkapazität
+label-packs = Akkus
+label-combined = Zusammen
+label-drain-order = Entladereihenfolge
+label-status = Status
+label-device = Gerät
+label-source = Strom
+source-ac = Netzbetrieb
+source-battery = Akkubetrieb
+label-time = Zeit
+time-to-full = bis voll
+time-remaining = verbleibend
+phase-trickle = (Erhaltungsladung)
+phase-slowing = (verlangsamt)
+phase-fast = (Schnellladung)
+time-calculating = Berechne
+
+panel-battery = Akku
+panel-power = Leistungsanalyse in Echtzeit
+power-current = Aktuell
+power-percent-per-hour = { $rate } %/h
+power-smoothed = Geglättet
+power-trend = Trend: { $arrow }
+power-rolling = Gleitend
+power-rolling-window = { $secs }s Mittel
+power-spread = Streuung
+power-spread-detail = Sitzung, { $samples } Messungen
+power-voltage = Spannung
+power-sag = ⚠️  { $sag }V unter Normal bei { $capacity }% — mögliche Alterung oder Kontaktproblem
+power-sources-disagree = ⚠️  Quellen uneinig bei { $attribute }: { $values } — verwende { $chosen }
+current-draw-abs = Verbrauch
+current-draw = Strom
+drain-by-context = Verbrauch nach Kontext
+
+panel-energy = Energiedetails
+energy-now = Aktuell
+energy-full-of-design = { $full } von { $design } Nennkapazität ({ $health }% Zustand)
+energy-full = Voll
+energy-unplugged = Ohne Netzteil
+energy-used-in = { $energy } verbraucht in { $duration }
+energy-charging = Laden
+energy-added = { $energy } geladen ({ $from }%→{ $to }%) in { $duration }
+energy-per-percent = Pro 1%
+energy-per-percent-learned = { $energy } (gelernt bei { $from }–{ $to }%)
+energy-last-full = Zuletzt voll
+panel-charge-projection = Ladeprognose
+charge-rate = Tempo
+charge-to-percent = Bis { $percent }%
+charge-curve-span = jetzt → voll
+panel-what-if = Was wäre wenn (Dauerlast)
+what-if-now = jetzt
+last-full-ago = vor { $duration }
+last-full-now = jetzt
+
+panel-temperature = Temperatur in Echtzeit (alle { $secs }s)
+temp-battery = Akku
+temp-cpu = CPU
+temp-no-sensor = (kein Sensor gefunden)
+temp-none-valid = Keine gültigen Temperatursensoren gefunden (Bereich: { $min }-{ $max }°C)
+
+panel-power-history = Leistungsverlauf (letzte { $count } Messungen)
+panel-power-distribution = Leistungsverteilung (Entladen, { $count } Messungen)
+panel-wakeups = Aufwecker im Akkubetrieb (letzte { $minutes } min)
+wakeups-interrupts = Interrupts
+wakeups-events = Weckereignisse
+panel-thermal = CPU-Temperatur vs. Leistung (letzte { $count } Messungen)
+thermal-legend = • Leistung (links)  × CPU-Temperatur (rechts)  ◆ beide
+thermal-strong = stark
+thermal-moderate = mäßig
+thermal-weak = schwach
+thermal-per-watt = pro zusätzlichem Watt
+thermal-hint-throttled = ⚠️  Die Leistung sinkt, wenn die CPU heiß wird — vermutlich thermische Drosselung
+thermal-hint-cooling = ⚠️  Die CPU ist unabhängig von der Last heiß — Lüfter, Lüftungsschlitze und Wärmeleitpaste prüfen
+
+panel-events = Letzte Ereignisse
+panel-history = Verlauf
+panel-script = Skript
+
+accuracy-ultra = Sehr hohe Genauigkeit
+accuracy-ultra-detail = ({ $samples } Messungen, { $secs }s gleitend)
+accuracy-high = Hohe Genauigkeit
+accuracy-medium = Mittlere Genauigkeit
+accuracy-samples = ({ $samples } Messungen)
+accuracy-building = Genauigkeit wird aufgebaut
+accuracy-building-detail = ({ $samples }/{ $needed } Messungen)
+footer-last-update = Letzte Messung: { $when }
+footer-ago = vor { $secs }s
+footer-starting = startet
+footer-keys = ←/→ oder 1-9 Akku • p Pause • u Einheiten (W/mAh) • q Beenden
+footer-updates = Aktualisierung alle { $secs }s
+footer-self = batfi selbst: { $cpu } CPU ({ $percent } % eines Kerns) • ~{ $mwh } mWh pro Stunde
+
+summary-to-full = { $time } bis voll
+summary-remaining = { $time } verbleibend
+source-daemon = vom Daemon
+source-local = lokale Messung
+source-combined = alle Akkus zusammen
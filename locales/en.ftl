@@ -0,0 +1,111 @@
+# English UI strings; also the fallback for messages missing from other locales
+
+app-title = Batfi v2.0 - Advanced Battery Monitor
+header-paused = ⏸ Paused
+banner-plugged = ⚡ Plugged in
+banner-plugged-adapter = ⚡ Plugged in — adapter { $watts }W
+banner-unplugged = 🔋 Unplugged — on battery at { $capacity }%
+
+status-charging = Charging
+status-discharging = Discharging
+status-full = Full
+status-not-charging = Not charging
+status-unknown = Unknown
+
+bar-of-design = { $percent }% of design
+label-packs = Packs
+label-combined = Combined
+label-drain-order = Drain order
+label-status = Status
+label-device = Device
+label-source = Power
+source-ac = On AC
+source-battery = On battery
+label-time = Time
+time-to-full = to full
+time-remaining = remaining
+phase-trickle = (trickle charge)
+phase-slowing = (slowing down)
+phase-fast = (fast charge)
+time-calculating = Calculating
+
+panel-battery = Battery
+panel-power = Real-Time Power Analytics
+power-current = Current
+power-percent-per-hour = { $rate } %/h
+power-smoothed = Smoothed
+power-trend = trend: { $arrow }
+power-rolling = Rolling
+power-rolling-window = { $secs }s avg
+power-spread = Spread
+power-spread-detail = session, { $samples } samples
+power-voltage = Voltage
+power-sag = ⚠️  Sagging { $sag }V below normal for { $capacity }% — possible aging/contact issue
+power-sources-disagree = ⚠️  Sources disagree on { $attribute }: { $values } — using { $chosen }
+current-draw-abs = Draw
+current-draw = Current
+drain-by-context = Drain by context
+
+panel-energy = Energy Details
+energy-now = Current
+energy-full-of-design = { $full } of { $design } design ({ $health }% health)
+energy-full = Full
+energy-unplugged = Unplugged
+energy-used-in = { $energy } used in { $duration }
+energy-charging = Charging
+energy-added = Added { $energy } ({ $from }%→{ $to }%) in { $duration }
+energy-per-percent = Per 1%
+energy-per-percent-learned = { $energy } (learned at { $from }–{ $to }%)
+energy-last-full = Last full
+panel-charge-projection = Charge Projection
+charge-rate = Rate
+charge-to-percent = To { $percent }%
+charge-curve-span = now → full
+panel-what-if = What If (sustained load)
+what-if-now = now
+last-full-ago = { $duration } ago
+last-full-now = now
+
+panel-temperature = Real-Time Temperature ({ $secs }s updates)
+temp-battery = Battery
+temp-cpu = CPU
+temp-no-sensor = (no sensor found)
+temp-none-valid = No valid temperature sensors found (range: { $min }-{ $max }°C)
+
+panel-power-history = Power History (last { $count } samples)
+panel-power-distribution = Power Distribution (discharging, { $count } samples)
+panel-wakeups = Wakeups on Battery (last { $minutes } min)
+wakeups-interrupts = interrupts
+wakeups-events = wakeup events
+panel-thermal = CPU Temperature vs Power (last { $count } samples)
+thermal-legend = • power (left)  × CPU temperature (right)  ◆ both
+thermal-strong = strong
+thermal-moderate = moderate
+thermal-weak = weak
+thermal-per-watt = per extra watt
+thermal-hint-throttled = ⚠️  Power drops as the CPU heats up — it is likely being thermally throttled
+thermal-hint-cooling = ⚠️  The CPU runs hot regardless of load — check fans, vents and thermal paste
+
+panel-events = Recent Events
+panel-history = History
+panel-script = Script
+
+accuracy-ultra = Ultra-high accuracy
+accuracy-ultra-detail = ({ $samples } samples, { $secs }s rolling)
+accuracy-high = High accuracy
+accuracy-medium = Medium accuracy
+accuracy-samples = ({ $samples } samples)
+accuracy-building = Building accuracy
+accuracy-building-detail = ({ $samples }/{ $needed } samples)
+footer-last-update = Last update: { $when }
+footer-ago = { $secs }s ago
+footer-starting = starting
+footer-keys = ←/→ or 1-9 battery • p pause • u units (W/mAh) • q quit
+footer-updates = Real-time { $secs }s updates
+footer-self = batfi itself: { $cpu } CPU ({ $percent }% of a core) • ~{ $mwh } mWh per hour
+
+summary-to-full = { $time } to full
+summary-remaining = { $time } remaining
+source-daemon = from daemon
+source-local = local sample
+source-combined = all packs combined
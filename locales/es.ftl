@@ -0,0 +1,111 @@
+# Cadenas de la interfaz en español
+
+app-title = Batfi v2.0 - Monitor de batería avanzado
+header-paused = ⏸ En pausa
+banner-plugged = ⚡ Cargador conectado
+banner-plugged-adapter = ⚡ Cargador conectado — adaptador de { $watts }W
+banner-unplugged = 🔋 Cargador desconectado — con batería al { $capacity }%
+
+status-charging = Cargando
+status-discharging = Descargando
+status-full = Completa
+status-not-charging = Sin cargar
+status-unknown = Desconocido
+
+bar-of-design = { $percent }% del diseño
+label-packs = Packs
+label-combined = Combinado
+label-drain-order = Orden de descarga
+label-status = Estado
+label-device = Dispositivo
+label-source = Energía
+source-ac = Con cargador
+source-battery = Con batería
+label-time = Tiempo
+time-to-full = hasta completar
+time-remaining = restante
+phase-trickle = (carga lenta)
+phase-slowing = (frenando)
+phase-fast = (carga rápida)
+time-calculating = Calculando
+
+panel-battery = Batería
+panel-power = Análisis de potencia en tiempo real
+power-current = Actual
+power-percent-per-hour = { $rate } %/h
+power-smoothed = Suavizada
+power-trend = tendencia: { $arrow }
+power-rolling = Móvil
+power-rolling-window = media de { $secs }s
+power-spread = Dispersión
+power-spread-detail = sesión, { $samples } muestras
+power-voltage = Voltaje
+power-sag = ⚠️  { $sag }V por debajo de lo normal al { $capacity }% — posible desgaste o mal contacto
+power-sources-disagree = ⚠️  Las fuentes no coinciden en { $attribute }: { $values } — se usa { $chosen }
+current-draw-abs = Consumo
+current-draw = Corriente
+drain-by-context = Consumo por contexto
+
+panel-energy = Detalles de energía
+energy-now = Actual
+energy-full-of-design = { $full } de { $design } de diseño ({ $health }% de salud)
+energy-full = Completa
+energy-unplugged = Sin cargador
+energy-used-in = { $energy } usados en { $duration }
+energy-charging = Cargando
+energy-added = { $energy } añadidos ({ $from }%→{ $to }%) en { $duration }
+energy-per-percent = Por 1%
+energy-per-percent-learned = { $energy } (aprendido en { $from }–{ $to }%)
+energy-last-full = Carga 100%
+panel-charge-projection = Proyección de carga
+charge-rate = Ritmo
+charge-to-percent = Hasta { $percent }%
+charge-curve-span = ahora → lleno
+panel-what-if = Qué pasaría si (carga sostenida)
+what-if-now = ahora
+last-full-ago = hace { $duration }
+last-full-now = ahora
+
+panel-temperature = Temperatura en tiempo real (cada { $secs }s)
+temp-battery = Batería
+temp-cpu = CPU
+temp-no-sensor = (sin sensor)
+temp-none-valid = No hay sensores de temperatura válidos (rango: { $min }-{ $max }°C)
+
+panel-power-history = Historial de potencia (últimas { $count } muestras)
+panel-power-distribution = Distribución de potencia (descargando, { $count } muestras)
+panel-wakeups = Despertares con batería (últimos { $minutes } min)
+wakeups-interrupts = interrupciones
+wakeups-events = eventos de activación
+panel-thermal = Temperatura de CPU vs. potencia (últimas { $count } muestras)
+thermal-legend = • potencia (izq.)  × temperatura de CPU (der.)  ◆ ambas
+thermal-strong = fuerte
+thermal-moderate = moderada
+thermal-weak = débil
+thermal-per-watt = por vatio adicional
+thermal-hint-throttled = ⚠️  La potencia baja cuando la CPU se calienta — probablemente limitación térmica
+thermal-hint-cooling = ⚠️  La CPU está caliente sin importar la carga — revisa ventiladores, rejillas y pasta térmica
+
+panel-events = Eventos recientes
+panel-history = Historial
+panel-script = Script
+
+accuracy-ultra = Precisión muy alta
+accuracy-ultra-detail = ({ $samples } muestras, media móvil de { $secs }s)
+accuracy-high = Precisión alta
+accuracy-medium = Precisión media
+accuracy-samples = ({ $samples } muestras)
+accuracy-building = Calibrando precisión
+accuracy-building-detail = ({ $samples }/{ $needed } muestras)
+footer-last-update = Última lectura: { $when }
+footer-ago = hace { $secs }s
+footer-starting = iniciando
+footer-keys = ←/→ o 1-9 batería • p pausa • u unidades (W/mAh) • q salir
+footer-updates = Actualización cada { $secs }s
+footer-self = batfi en sí: { $cpu } de CPU ({ $percent } % de un núcleo) • ~{ $mwh } mWh por hora
+
+summary-to-full = { $time } hasta completar
+summary-remaining = quedan { $time }
+source-daemon = del daemon
+source-local = lectura local
+source-combined = todas las baterías juntas
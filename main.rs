@@ -1,23 +1,64 @@
-use std::collections::VecDeque;
+//! The `batfi` command line: argument parsing, the subcommands and the watch loop, on top of
+//! the library's monitor. Nothing here is part of the library.
+
 use std::fs;
-use std::io::{self, Write};
+use std::io::{self, BufRead, BufReader, IsTerminal, Seek, SeekFrom, Write};
 use std::path::Path;
-use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::thread;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use batfi::*;
+use batfi::backend::SysfsBackend;
+use batfi::charge_curve::ChargeCurve;
+use batfi::client::Remote;
+use batfi::discharge_curve::DischargeCurve;
+use batfi::energy_per_percent::EnergyPerPercent;
+use batfi::events::{events_log_path, kind_color, load_events, Event};
+use batfi::footprint::{measure_ticks, Footprint, DEFAULT_BENCH_TICKS};
+use batfi::i18n::t;
+use batfi::usage::{load_usage, summarize};
+
+mod advise;
+mod cli;
+#[cfg(feature = "tui")]
+mod dashboard;
+mod doctor;
+mod eval;
+mod explain;
+mod fleet;
+mod list;
+mod menu;
+#[cfg(feature = "tui")]
+mod packs;
+mod raw;
+mod service;
+#[cfg(feature = "tui")]
+mod top;
+mod tray;
+
+const HEALTH_RECORD_INTERVAL_SECS: u64 = 600; // Persist a health sample every 10 minutes
 
-use clap::{Arg, Command};
-use serde::{Deserialize, Serialize};
+fn main() {
+    run();
+}
 
 /// Convert Celsius to Fahrenheit
+#[cfg(feature = "tui")]
 fn celsius_to_fahrenheit(celsius: f64) -> f64 {
     (celsius * 9.0 / 5.0) + 32.0
 }
 
 /// Generate Pac-Man cat animation based on elapsed time
-fn generate_pacman_cat_animation(elapsed_secs: u64) -> String {
-    // Calculate dots eaten based on elapsed seconds (1 dot per second)
-    let dots_eaten = (elapsed_secs as usize).min(TOTAL_DOTS);
+#[cfg(feature = "tui")]
+fn generate_pacman_cat_animation(elapsed_secs: u64, duration_secs: Option<u64>) -> String {
+    // The cat clears the row over a --duration run; without one it eats a dot a second and starts over
+    let dots_eaten = match duration_secs {
+        Some(duration) => (elapsed_secs.saturating_mul(TOTAL_DOTS as u64) / duration.max(1)) as usize,
+        None => elapsed_secs as usize % (TOTAL_DOTS + 1),
+    }
+    .min(TOTAL_DOTS);
     let remaining_dots = TOTAL_DOTS - dots_eaten;
     
     // Animated cat with moving mouth - more frames for smoother animation
@@ -32,1351 +73,1557 @@ fn generate_pacman_cat_animation(elapsed_secs: u64) -> String {
     let remaining_dots_str = "●".repeat(remaining_dots);
     
     if remaining_dots == 0 {
-        format!("All dots eaten!")
+        "All dots eaten!".to_string()
     } else {
         format!("{}{}", cat, remaining_dots_str)
     }
 }
 
-/// Generate countdown dots that disappear one by one
-fn generate_countdown_dots(elapsed_secs: u64) -> String {
-    let remaining_seconds = PROGRAM_DURATION_SECS.saturating_sub(elapsed_secs);
-    let remaining_dots = remaining_seconds as usize;
-    let disappeared_dots = (PROGRAM_DURATION_SECS - remaining_seconds) as usize;
+/// Generate countdown dots that disappear one by one over a `duration_secs` run
+#[cfg(feature = "tui")]
+fn generate_countdown_dots(elapsed_secs: u64, duration_secs: u64) -> String {
+    let remaining_seconds = duration_secs.saturating_sub(elapsed_secs);
+    let remaining_dots = remaining_seconds.saturating_mul(TOTAL_DOTS as u64).div_ceil(duration_secs.max(1)) as usize;
+    let disappeared_dots = TOTAL_DOTS - remaining_dots.min(TOTAL_DOTS);
     
     let disappeared_spaces = " ".repeat(disappeared_dots);
     let remaining_dots_str = "●".repeat(remaining_dots);
     
-    format!("{}[{}] {}s remaining", disappeared_spaces, remaining_dots_str, remaining_seconds)
+    format!("{}[{}] {} remaining", disappeared_spaces, remaining_dots_str, format_run_time(remaining_seconds))
 }
 
 
 
-/// Configuration constants for smoothing and accuracy
-const POWER_SMOOTHING_ALPHA: f64 = 0.25; // Exponential moving average factor (optimized)
-const MIN_POWER_THRESHOLD: f64 = 0.05; // Minimum power in watts for calculations (more sensitive)
-const MAX_HISTORY_SIZE: usize = 300; // 5 minutes at 1s intervals
-const UPDATE_INTERVAL_SECS: u64 = 2; // Update every 2 seconds
-const PROGRAM_DURATION_SECS: u64 = 20; // Stop program after 20 seconds
-const MIN_SAMPLES_FOR_ESTIMATE: usize = 3; // Minimum samples before showing estimate
-const ROLLING_WINDOW_SIZE: usize = 10; // Rolling average window for ultra-smooth estimates
-const MIN_VALID_TEMP: f64 = 10.0; // Minimum valid temperature in Celsius
-const MAX_VALID_TEMP: f64 = 110.0; // Maximum valid temperature in Celsius
-const TOTAL_DOTS: usize = 20; // Total dots for Pac-Man cat animation
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BatteryReading {
-    pub timestamp: u64,
-    pub capacity_percent: u8,
-    pub energy_now_wh: Option<f64>,
-    pub energy_full_wh: Option<f64>,
-    pub power_now_w: Option<f64>,
-    pub voltage_v: Option<f64>,
-    pub current_ma: Option<i32>,
-    pub status: String,
-    pub temperature_c: Option<f64>,
+#[cfg(feature = "tui")]
+const TOTAL_DOTS: usize = 20; // Total dots for Pac-Man cat animation and the countdown
+#[cfg(feature = "tui")]
+const RECENT_EVENTS_SHOWN: usize = 5; // Events in the dashboard's journal panel
+const HISTORY_HISTOGRAM_BUCKETS: usize = 12; // Rows of `batfi history --histogram`
+#[cfg(feature = "tui")]
+const THERMAL_CHART_ROWS: usize = 6; // Height of the dashboard's power/temperature chart
+#[cfg(feature = "tui")]
+const HISTOGRAM_BUCKETS: usize = 8; // Rows of the dashboard's power distribution panel
+#[cfg(feature = "tui")]
+const CHARGE_CURVE_WIDTH: usize = 32; // Columns of the dashboard's projected charge curve
+
+/// Set once Ctrl+C, SIGTERM or SIGHUP arrives during `watch`; the loop winds down at its next wait
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether `watch` was asked to stop
+fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BatteryInfo {
-    pub status: String,
-    pub capacity_percent: u8,
-    pub health_percent: f64,
-    pub cycles: Option<u32>,
-    pub temperature_c: Option<f64>,
-    pub voltage_v: Option<f64>,
-    pub current_ma: Option<i32>,
-    pub power_w: Option<f64>,
-    pub smoothed_power_w: Option<f64>,
-    pub manufacturer: String,
-    pub model: String,
-    pub technology: String,
-    pub time_remaining_minutes: Option<u32>,
-    pub energy_now_wh: Option<f64>,
-    pub energy_full_wh: Option<f64>,
-    pub power_trend: String, // "stable", "increasing", "decreasing"
-    pub cpu_temperature_c: Option<f64>,
+/// Turn Ctrl+C into a clean stop. A second one while winding down exits right away.
+fn install_interrupt_handler() {
+    let _ = ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::Relaxed) {
+            #[cfg(feature = "tui")]
+            dashboard::restore_terminal();
+            std::process::exit(130);
+        }
+    });
 }
 
-#[derive(Debug)]
-pub struct PowerSample {
-    pub timestamp: u64,
-    pub power_w: f64,
-    pub energy_wh: f64,
+/// Sleep for `duration` unless interrupted first; returns whether it was
+fn sleep_unless_interrupted(duration: Duration) -> bool {
+    let deadline = Instant::now() + duration;
+    while !interrupted() {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return false;
+        }
+        thread::sleep(left.min(Duration::from_millis(100)));
+    }
+    true
 }
 
-#[derive(Debug, Clone)]
-pub struct TemperatureSensor {
-    pub sensor_type: String, // "coretemp", "k10temp", "battery", etc.
-    pub path: String,
-    pub label: Option<String>,
-    pub name: String,
+/// Compile the user script and hand it to the monitor, exiting when it doesn't compile
+#[cfg(feature = "script")]
+fn load_script(monitor: &mut BatteryMonitor, path: &str) {
+    match script::Script::load(Path::new(path)) {
+        Ok(script) => monitor.set_script(script),
+        Err(e) => {
+            eprintln!("❌ Script {}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
-pub struct TemperatureReading {
-    pub raw_value: f64,
-    pub smoothed_value: f64,
-    pub sensor_info: TemperatureSensor,
-    pub timestamp: u64,
+#[cfg(not(feature = "script"))]
+fn load_script(_monitor: &mut BatteryMonitor, _path: &str) {
+    missing_feature("--script", "script");
 }
 
-#[derive(Debug)]
-pub struct TemperatureMonitor {
-    pub cpu_sensors: Vec<TemperatureSensor>,
-    pub battery_sensors: Vec<TemperatureSensor>,
-    pub last_cpu_temp: Option<TemperatureReading>,
-    pub last_battery_temp: Option<TemperatureReading>,
+/// Exit with a hint when asked for something this binary was built without
+#[allow(dead_code)] // Only reachable when a feature is turned off
+fn missing_feature(what: &str, feature: &str) -> ! {
+    eprintln!("❌ {} needs batfi built with the `{}` feature (cargo build --features {})", what, feature, feature);
+    std::process::exit(1);
 }
 
-impl TemperatureMonitor {
-    pub fn new() -> Self {
-        let mut monitor = Self {
-            cpu_sensors: Vec::new(),
-            battery_sensors: Vec::new(),
-            last_cpu_temp: None,
-            last_battery_temp: None,
-        };
-        monitor.discover_sensors();
-        monitor
-    }
-
-    /// Comprehensive sensor discovery with detailed logging
-    fn discover_sensors(&mut self) {
-        println!("🔍 Discovering temperature sensors...");
-        
-        // Discover CPU sensors from hwmon
-        self.discover_cpu_sensors();
-        
-        // Discover battery sensors
-        self.discover_battery_sensors();
-        
-        // Log discovery results
-        if self.cpu_sensors.is_empty() && self.battery_sensors.is_empty() {
-            println!("⚠️  No temperature sensors found!");
-        } else {
-            println!("✅ Temperature sensor discovery complete:");
-            for sensor in &self.cpu_sensors {
-                println!("   CPU: {} ({})", sensor.name, sensor.path);
-            }
-            for sensor in &self.battery_sensors {
-                println!("   BAT: {} ({})", sensor.name, sensor.path);
-            }
-        }
+/// A --duration span: seconds while short, then like an ETA
+fn format_run_time(secs: u64) -> String {
+    match secs {
+        0..=119 => format!("{}s", secs),
+        _ => format_minutes((secs / 60) as u32),
     }
+}
 
-    /// Scan /sys/class/hwmon/hwmon*/name for CPU temperature sensors
-    fn discover_cpu_sensors(&mut self) {
-        let hwmon_path = Path::new("/sys/class/hwmon");
-        if !hwmon_path.exists() {
-            println!("❌ /sys/class/hwmon not found - ensure you're running on Linux");
-            return;
+/// Shell-style match supporting `*` and `?`, enough for names like `BAT*`
+fn glob_match(pattern: &str, name: &str) -> bool {
+    fn matches(pattern: &[char], name: &[char]) -> bool {
+        match (pattern.first(), name.first()) {
+            (None, None) => true,
+            (Some('*'), _) => matches(&pattern[1..], name) || (!name.is_empty() && matches(pattern, &name[1..])),
+            (Some('?'), Some(_)) => matches(&pattern[1..], &name[1..]),
+            (Some(p), Some(n)) if p == n => matches(&pattern[1..], &name[1..]),
+            _ => false,
         }
+    }
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    matches(&pattern, &name)
+}
 
-        println!("🔍 Scanning /sys/class/hwmon/ for temperature sensors...");
-        
-        if let Ok(entries) = fs::read_dir(hwmon_path) {
-            let mut hwmon_dirs: Vec<_> = entries.filter_map(|e| e.ok()).collect();
-            hwmon_dirs.sort_by_key(|e| e.file_name());
-            
-            for entry in hwmon_dirs {
-                if let Some(name) = entry.file_name().to_str() {
-                    if name.starts_with("hwmon") {
-                        println!("🔍 Found hwmon directory: {}", entry.path().display());
-                        self.scan_hwmon_device(&entry.path());
-                    }
-                }
-            }
+/// Batteries selected with --battery (names, globs or `all`), or the first one found
+fn select_batteries(settings: &config::Settings) -> Vec<String> {
+    // Find available batteries
+    let batteries = find_batteries();
+    if batteries.is_empty() {
+        eprintln!("❌ No batteries found in {}/", sysfs_path("class/power_supply").display());
+        eprintln!("   Make sure you're running this on a laptop with battery support.");
+        std::process::exit(1);
+    }
+
+    if settings.battery.is_empty() {
+        return vec![batteries[0].clone()]; // Use first battery found
+    }
+    let mut selected: Vec<String> = Vec::new();
+    for pattern in &settings.battery {
+        let matching: Vec<&String> = if pattern == "all" {
+            batteries.iter().collect()
         } else {
-            println!("❌ Failed to read /sys/class/hwmon directory");
+            batteries.iter().filter(|name| glob_match(pattern, name)).collect()
+        };
+        if matching.is_empty() {
+            eprintln!("❌ Battery '{}' not found. Available batteries: {}", pattern, batteries.join(", "));
+            std::process::exit(1);
         }
+        for name in matching {
+            if !selected.contains(name) {
+                selected.push(name.clone());
+            }
+        }
+    }
+    selected
+}
 
-        if self.cpu_sensors.is_empty() {
-            println!("⚠️  No CPU temperature sensors found in /sys/class/hwmon/");
-        } else {
-            // Sort CPU sensors by preference: coretemp > k10temp > others
-            self.cpu_sensors.sort_by(|a, b| {
-                let priority_a = match a.sensor_type.as_str() {
-                    "coretemp" => 1,   // Intel - highest priority
-                    "k10temp" => 2,    // AMD Ryzen
-                    "zenpower" => 3,   // AMD alternative
-                    "amdgpu" => 4,     // AMD GPU (if needed)
-                    _ => 9,            // Others - lowest priority
-                };
-                let priority_b = match b.sensor_type.as_str() {
-                    "coretemp" => 1,   // Intel - highest priority
-                    "k10temp" => 2,    // AMD Ryzen
-                    "zenpower" => 3,   // AMD alternative
-                    "amdgpu" => 4,     // AMD GPU (if needed)
-                    _ => 9,            // Others - lowest priority
-                };
-                priority_a.cmp(&priority_b)
-            });
-            
-            println!("📊 CPU sensors sorted by priority:");
-            for (i, sensor) in self.cpu_sensors.iter().enumerate() {
-                println!("   {}. {} [{}]", i+1, sensor.name, sensor.path);
+/// The combined estimate, with each pack's fields (or the --fields subset) under "batteries"
+fn multi_battery_json(monitors: &mut multi::MultiBatteryMonitor, fields: Option<&[String]>) -> String {
+    let Some(combined) = monitors.sample() else {
+        return "{}".to_string();
+    };
+    let entries: Vec<serde_json::Value> = combined
+        .batteries
+        .iter()
+        .filter_map(|pack| {
+            let mut value = match fields {
+                Some(fields) => fields::select(&pack.info, fields),
+                None => serde_json::to_value(&pack.info).ok()?,
+            };
+            let mut entry = serde_json::to_value(pack).ok()?;
+            if let (Some(entry), Some(map)) = (entry.as_object_mut(), value.as_object_mut()) {
+                entry.append(map);
             }
+            Some(entry)
+        })
+        .collect();
+    let mut value = serde_json::to_value(&combined).unwrap_or_default();
+    value["batteries"] = entries.into();
+    serde_json::to_string_pretty(&value).unwrap_or_else(|_| "{}".to_string())
+}
+
+/// Persist capacity and the current resistance estimate to the health history
+fn record_health_sample(monitor: &BatteryMonitor, battery_name: &str) {
+    let report = monitor.read_health();
+    let sample = health::HealthSample {
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        battery: battery_name.to_string(),
+        full_capacity: report.full_capacity,
+        design_capacity: report.design_capacity,
+        cycles: report.cycles,
+        internal_resistance_mohm: monitor.estimate_internal_resistance(),
+        ..Default::default()
+    };
+    if let Err(e) = health::append_health_sample(monitor.data_dir(), &sample) {
+        eprintln!("⚠️  Could not record health sample: {}", e);
+    }
+}
+
+/// Detect a swapped pack and start a fresh health history for it in `dir`
+fn check_pack_change(dir: Option<&Path>, battery_name: &str, quiet: bool) {
+    let base_path = power_supply_path(battery_name);
+    let identity::PackStatus::Replaced { previous } = identity::check_pack_identity(dir, battery_name, &base_path) else {
+        return;
+    };
+
+    let current = identity::PackIdentity::read(&base_path);
+    let message = format!("Pack changed from {} to {}", previous.label(), current.label());
+    let archived = health::archive_health_history(dir, battery_name).ok().flatten();
+    // The samples behind `batfi history` belong to the old pack too
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let archived_samples = history_store::HistoryStore::open(dir, battery_name).and_then(|store| store.archive(now).ok().flatten());
+    DischargeCurve::discard(dir, battery_name);
+    EnergyPerPercent::discard(dir, battery_name);
+    ChargeCurve::discard(dir, battery_name);
+    let _ = events::log_event(dir, battery_name, "pack-replaced", &message);
+
+    if !quiet {
+        println!("🔄 {} on {} — starting a fresh health history", message, battery_name);
+        if let Some(path) = archived {
+            println!("   Previous history archived to {}", path.display());
+        }
+        if let Some(path) = archived_samples {
+            println!("   Previous samples archived to {}", path.display());
         }
     }
+}
 
+/// Compact one-shot status used by `batfi status`
+fn print_status_summary(info: &BatteryInfo, source: &str) {
+    println!("🔋 {} \x1b[2m({})\x1b[0m", status_summary(info), source);
+    for line in &info.script_lines {
+        println!("   {}", line);
+    }
+}
 
-    fn scan_hwmon_device(&mut self, hwmon_path: &Path) {
-        // Read the device name
-        let name_path = hwmon_path.join("name");
-        let device_name = match fs::read_to_string(&name_path) {
-            Ok(name) => name.trim().to_string(),
-            Err(e) => {
-                println!("❌ Cannot read name from {}: {}", name_path.display(), e);
-                return;
-            }
-        };
+/// Capacity, status, ETA and power on one line, without markup
+fn status_summary(info: &BatteryInfo) -> String {
+    let time = match info.time_remaining_minutes {
+        Some(minutes) if info.status == "Charging" => t!("summary-to-full", time = format_minutes(minutes)),
+        Some(minutes) => t!("summary-remaining", time = format_minutes(minutes)),
+        None => "—".to_string(),
+    };
+    let power = info.smoothed_power_w.or(info.power_w)
+        .map(|p| format!("{:.2}W", p))
+        .unwrap_or_else(|| "—".to_string());
+    format!("{}% {} • {} • {}", info.capacity_percent, i18n::status_label(&info.status), time, power)
+}
 
-        println!("🔍 Scanning hwmon device: '{}' at {}", device_name, hwmon_path.display());
+/// `batfi status --remote`: ask a running server instead of reading sysfs
+/// Announce what `batfi serve` and `batfi daemon` are listening on
+fn print_serve_status(status: server::ServeStatus) {
+    match status {
+        server::ServeStatus::Http(addr) => {
+            println!("🌐 Serving battery data on http://{}/v1/battery", addr);
+            println!("   Endpoints: /v1/battery, /v1/history?since=10m, /v1/sensors, /v1/events, /v1/plasma");
+        }
+        server::ServeStatus::Prometheus(addr) => println!("📈 Serving Prometheus metrics on http://{}/metrics", addr),
+        server::ServeStatus::Socket(path) => println!("🔌 Listening on {}", path.display()),
+        server::ServeStatus::Stopped(path) => println!("👋 Stopped; removed {}", path.display()),
+    }
+}
 
-        // Skip virtual/invalid sensors with explicit logging
-        if device_name == "acpitz" || device_name.contains("virtual") {
-            println!("🚫 Skipping virtual/ACPI sensor: '{}' (not a real temperature sensor)", device_name);
-            return;
+fn run_remote_status(remote: &client::Remote, json_output: bool, fields: Option<&[String]>) {
+    match client::fetch_json::<BatteryInfo>(remote, "/v1/battery") {
+        Ok(info) if json_output => println!("{}", fields::to_json(&info, fields)),
+        Ok(info) => print_status_summary(&info, &t!("source-daemon")),
+        Err(e) => {
+            eprintln!("❌ Could not reach batfi server: {}", e);
+            std::process::exit(1);
         }
+    }
+}
 
-        // Look for temperature inputs with detailed scanning
-        if let Ok(entries) = fs::read_dir(hwmon_path) {
-            let mut temp_files: Vec<_> = entries.filter_map(|e| e.ok()).collect();
-            temp_files.sort_by_key(|e| e.file_name());
-            
-            let mut found_temp_inputs = Vec::new();
-            
-            for entry in temp_files {
-                let filename = entry.file_name();
-                let filename_str = filename.to_string_lossy();
-                
-                if filename_str.starts_with("temp") && filename_str.ends_with("_input") {
-                    found_temp_inputs.push(filename_str.to_string());
-                }
-            }
-            
-            if found_temp_inputs.is_empty() {
-                println!("   ❌ No temp*_input files found in {}", hwmon_path.display());
-                return;
-            }
-            
-            println!("   📊 Found temp inputs: {:?}", found_temp_inputs);
-            
-            for temp_input in found_temp_inputs {
-                // Extract temp number (e.g., temp1_input -> 1)
-                if let Some(temp_num) = temp_input.strip_prefix("temp").and_then(|s| s.strip_suffix("_input")) {
-                    let temp_input_path = hwmon_path.join(&temp_input);
-                    let label_path = hwmon_path.join(format!("temp{}_label", temp_num));
-                    
-                    // Read label if available
-                    let label = match fs::read_to_string(&label_path) {
-                        Ok(l) => {
-                            let label_str = l.trim().to_string();
-                            println!("   🏷️  temp{}_label = '{}'", temp_num, label_str);
-                            Some(label_str)
-                        }
-                        Err(_) => {
-                            println!("   ❌ No temp{}_label file (using temp{})", temp_num, temp_num);
-                            None
-                        }
-                    };
-                    
-                    // Check if this is a CPU temperature we want
-                    if self.is_cpu_temp_sensor(&device_name, &label) {
-                        let sensor = TemperatureSensor {
-                            sensor_type: device_name.clone(),
-                            path: temp_input_path.to_string_lossy().to_string(),
-                            label: label.clone(),
-                            name: format!("{} {}", device_name, label.unwrap_or_else(|| format!("temp{}", temp_num))),
-                        };
-                        
-                        // Test if we can actually read from this sensor
-                        println!("   🧪 Testing sensor: {} -> {}", sensor.name, sensor.path);
-                        match self.read_temperature_from_path(&sensor.path) {
-                            Some(raw_temp) => {
-                                let temp_celsius = raw_temp / 1000.0; // Convert millidegrees to Celsius
-                                if self.is_valid_temperature(temp_celsius) {
-                                    println!("   ✅ VALID CPU sensor: {} = {:.1}°C (raw: {})", sensor.name, temp_celsius, raw_temp);
-                                    self.cpu_sensors.push(sensor);
-                                } else {
-                                    println!("   🚫 INVALID temperature from {}: {:.1}°C (outside {}-{}°C range)", 
-                                        sensor.name, temp_celsius, MIN_VALID_TEMP, MAX_VALID_TEMP);
-                                }
-                            }
-                            None => {
-                                println!("   ❌ Cannot read from sensor: {} (file: {})", sensor.name, sensor.path);
-                            }
-                        }
-                    } else {
-                        println!("   🚫 Skipping temp{}: '{}' sensor '{}' with label '{:?}' (not a main CPU sensor)", 
-                            temp_num, device_name, temp_input, label);
-                    }
-                }
-            }
-        } else {
-            println!("   ❌ Cannot read directory contents of {}", hwmon_path.display());
+/// Build the alert engine from the configured rules and channels.
+///
+/// Without channels the engine is only kept when `track_state` is set, for
+/// outputs that report which alerts are active.
+fn build_alert_engine(settings: &config::AlertSettings, track_state: bool, base_path: &str) -> Option<alerts::AlertEngine> {
+    let rules = if settings.rules.is_empty() {
+        alerts::default_rules()
+    } else {
+        // Already checked by cli::load_settings
+        settings.rules.iter().filter_map(|spec| alerts::AlertRule::parse(spec).ok()).collect()
+    };
+
+    let mut engine = alerts::AlertEngine::new(rules);
+    add_notify_channels(&mut engine, settings);
+    engine.set_plug_notifications(settings.plug);
+    if let Some(target) = settings.charge_target {
+        // Already checked by cli::load_settings
+        let reminder_secs = settings.charge_reminder.as_deref().and_then(parse_duration_secs);
+        let hardware_limit = charge_limit::read_limit(base_path).filter(|limit| *limit < 100);
+        engine.set_charge_target(alerts::ChargeTarget::new(target, reminder_secs, hardware_limit));
+    }
+
+    if let Some(action) = &settings.critical_action {
+        let grace_secs = settings
+            .critical_grace
+            .as_deref()
+            .and_then(parse_duration_secs)
+            .unwrap_or(critical::DEFAULT_GRACE_SECS);
+        engine.set_critical_action(critical::CriticalAction::new(action, settings.critical_action_percent, grace_secs, base_path));
+    }
+
+    if engine.has_actions() {
+        engine.set_backlight(backlight::Backlight::new(settings.backlight.as_deref(), settings.backlight_command.as_deref()));
+    }
+
+    let acts = engine.has_actions() || settings.charge_target.is_some() || settings.critical_action.is_some();
+    (track_state || engine.has_channels() || acts).then_some(engine)
+}
+
+/// Webhook, ntfy, desktop and sound channels for alerts
+#[cfg(feature = "notify")]
+fn add_notify_channels(engine: &mut alerts::AlertEngine, settings: &config::AlertSettings) {
+    let host = fleet::hostname();
+    for url in &settings.webhooks {
+        engine.add_channel(Box::new(webhook::WebhookChannel::new(url, settings.webhook_template.clone(), &host)));
+    }
+    if let Some(topic) = &settings.ntfy_topic {
+        engine.add_channel(Box::new(ntfy::NtfyChannel::new(&settings.ntfy_server, topic, &host)));
+    }
+    if settings.desktop {
+        engine.add_channel(Box::new(desktop::DesktopChannel));
+    }
+    // Already checked by cli::load_settings
+    let audible_severity = alerts::Severity::parse(&settings.audible_severity).unwrap_or(alerts::Severity::Info);
+    if settings.bell {
+        engine.add_channel(Box::new(sound::BellChannel::new(audible_severity)));
+    }
+    if let Some(file) = &settings.sound {
+        match sound::SoundChannel::new(file, settings.sound_player.as_deref(), audible_severity) {
+            Some(channel) => engine.add_channel(Box::new(channel)),
+            None => eprintln!("⚠️  No sound player found (install paplay, pw-play or aplay, or set alerts.sound_player)"),
         }
     }
+}
 
-    fn is_cpu_temp_sensor(&self, device_name: &str, label: &Option<String>) -> bool {
-        println!("   🔍 Checking if '{}' with label '{:?}' is a CPU sensor", device_name, label);
-        
-        // Check device name first
-        match device_name {
-            "coretemp" => {
-                // Intel CPU temperature sensor
-                if let Some(ref label_str) = label {
-                    let label_lower = label_str.to_lowercase();
-                    // Look for package temperature specifically
-                    let is_package = label_lower.contains("package") || 
-                                   label_lower == "package id 0" ||
-                                   label_lower.contains("package id");
-                    println!("   📊 coretemp label '{}' -> package sensor: {}", label_str, is_package);
-                    is_package
-                } else {
-                    // If no label, assume temp1 is the main package sensor for coretemp
-                    println!("   📊 coretemp with no label -> assuming main package sensor");
-                    true
-                }
-            }
-            "k10temp" => {
-                // AMD Ryzen temperature sensor
-                if let Some(ref label_str) = label {
-                    let label_lower = label_str.to_lowercase();
-                    // Look for main die temperature (Tctl or Tdie)
-                    let is_main = label_lower.contains("tctl") || 
-                                label_lower.contains("tdie") ||
-                                label_lower == "tctl" ||
-                                label_lower == "tdie";
-                    println!("   📊 k10temp label '{}' -> main sensor: {}", label_str, is_main);
-                    is_main
-                } else {
-                    // If no label, assume temp1 is the main sensor for k10temp
-                    println!("   📊 k10temp with no label -> assuming main sensor");
-                    true
-                }
-            }
-            "zenpower" => {
-                // AMD alternative temperature sensor
-                if let Some(ref label_str) = label {
-                    let label_lower = label_str.to_lowercase();
-                    let is_main = label_lower.contains("tctl") || 
-                                label_lower.contains("tdie") ||
-                                label_lower.contains("die");
-                    println!("   📊 zenpower label '{}' -> main sensor: {}", label_str, is_main);
-                    is_main
-                } else {
-                    println!("   📊 zenpower with no label -> assuming main sensor");
-                    true
-                }
-            }
-            "amdgpu" => {
-                // AMD GPU temperature - only if specifically requested
-                if let Some(ref label_str) = label {
-                    let is_gpu = label_str.to_lowercase().contains("edge");
-                    println!("   📊 amdgpu label '{}' -> GPU edge sensor: {}", label_str, is_gpu);
-                    is_gpu
-                } else {
-                    println!("   🚫 amdgpu with no label -> skipping");
-                    false
-                }
-            }
-            _ => {
-                println!("   🚫 Unknown device type '{}' -> skipping", device_name);
-                false
+/// Built without notifications: alerts still show up in outputs and the journal
+#[cfg(not(feature = "notify"))]
+fn add_notify_channels(_engine: &mut alerts::AlertEngine, settings: &config::AlertSettings) {
+    let configured = !settings.webhooks.is_empty() || settings.ntfy_topic.is_some() || settings.desktop || settings.bell || settings.sound.is_some();
+    if configured {
+        eprintln!("⚠️  Alert notifications are configured, but batfi was built without the `notify` feature");
+    }
+}
+
+/// The synthetic battery for --simulate
+fn simulation_backend(scenario: &str, matches: &clap::ArgMatches, interval_secs: u64) -> simulate::SimulatedBackend {
+    let rate = matches.get_one::<String>("rate").map(|text| {
+        simulate::parse_rate(text).unwrap_or_else(|| {
+            eprintln!("❌ Invalid --rate '{}' (e.g. 12W)", text);
+            std::process::exit(1);
+        })
+    });
+    let default_from = if scenario == "charge" { 20 } else { 90 };
+    let from = matches.get_one::<u8>("from").copied().unwrap_or(default_from);
+    let speed = matches.get_one::<u32>("speed").copied().unwrap_or(1);
+    simulate::SimulatedBackend::new(scenario, from as f64, rate, (interval_secs * speed as u64) as f64)
+}
+
+/// `batfi snooze [DURATION|off]`: pause charge-target reminders
+fn run_snooze(snooze_matches: &clap::ArgMatches) {
+    let duration = snooze_matches.get_one::<String>("duration").map(String::as_str).unwrap_or("1h");
+    let until = if duration == "off" {
+        None
+    } else {
+        let Some(secs) = parse_duration_secs(duration) else {
+            eprintln!("❌ Invalid duration '{}' (e.g. 30m, 2h, or off)", duration);
+            std::process::exit(1);
+        };
+        Some(SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() + secs)
+    };
+    if let Err(e) = alerts::set_snooze(until) {
+        eprintln!("❌ Could not save the snooze: {}", e);
+        std::process::exit(1);
+    }
+    match until {
+        Some(until) => println!("😴 Charge reminders snoozed until {}", timefmt::date_time(until)),
+        None => println!("🔔 Charge reminders resumed"),
+    }
+}
+
+/// Render a health report, with the history kept in `dir`, as Markdown or HTML to a file or stdout
+fn export_health_report(dir: Option<&Path>, report: &health::BatteryHealth, format: &str, output: Option<&String>) {
+    let history = health::load_health_history(dir, &report.battery);
+    let document = match format {
+        "html" => health_export::render_html(report, &history),
+        _ => health_export::render_markdown(report, &history),
+    };
+    match output {
+        Some(path) => {
+            if let Err(e) = fs::write(path, document) {
+                eprintln!("❌ Could not write {}: {}", path, e);
+                std::process::exit(1);
             }
+            println!("✅ Health report written to {}", path);
         }
+        None => print!("{}", document),
     }
+}
 
-    /// Discover battery temperature sensors
-    fn discover_battery_sensors(&mut self) {
-        println!("🔍 Scanning for battery temperature sensors...");
-        
-        // Method 1: Direct battery power supply sensors
-        let power_supply_path = Path::new("/sys/class/power_supply");
-        println!("🔍 Checking /sys/class/power_supply/ for battery temp sensors...");
-        
-        if let Ok(entries) = fs::read_dir(power_supply_path) {
-            let mut power_entries: Vec<_> = entries.filter_map(|e| e.ok()).collect();
-            power_entries.sort_by_key(|e| e.file_name());
-            
-            for entry in power_entries {
-                let name = entry.file_name();
-                let name_str = name.to_string_lossy();
-                
-                if name_str.starts_with("BAT") || name_str.starts_with("battery") {
-                    println!("🔍 Found battery device: {}", name_str);
-                    let temp_path = entry.path().join("temp");
-                    
-                    if temp_path.exists() {
-                        println!("   📊 Found temp file: {}", temp_path.display());
-                        let sensor = TemperatureSensor {
-                            sensor_type: "battery".to_string(),
-                            path: temp_path.to_string_lossy().to_string(),
-                            label: Some(name_str.to_string()),
-                            name: format!("Battery {}", name_str),
-                        };
-                        
-                        // Test the sensor
-                        println!("   🧪 Testing battery sensor: {} -> {}", sensor.name, sensor.path);
-                        match self.read_temperature_from_path(&sensor.path) {
-                            Some(raw_temp) => {
-                                let normalized_temp = self.normalize_battery_temperature(raw_temp);
-                                println!("   📊 Raw temp: {}, normalized: {:.1}°C", raw_temp, normalized_temp);
-                                
-                                if self.is_valid_temperature(normalized_temp) {
-                                    println!("   ✅ VALID battery sensor: {} = {:.1}°C", sensor.name, normalized_temp);
-                                    self.battery_sensors.push(sensor);
-                                } else {
-                                    println!("   🚫 INVALID battery temperature: {:.1}°C (outside {}-{}°C range)", 
-                                        normalized_temp, MIN_VALID_TEMP, MAX_VALID_TEMP);
-                                }
-                            }
-                            None => {
-                                println!("   ❌ Cannot read from battery sensor: {}", sensor.path);
-                            }
-                        }
-                    } else {
-                        println!("   ❌ No temp file found for battery {}", name_str);
-                    }
-                } else {
-                    println!("🚫 Skipping non-battery device: {}", name_str);
-                }
+/// `batfi report`: the shareable health report on its own
+fn run_report(battery_name: &str, report_matches: &clap::ArgMatches) {
+    let dir = data_dir();
+    let report = health::read_battery_health(dir.as_deref(), battery_name, &SysfsBackend::new(&power_supply_path(battery_name)));
+    let format = report_matches.get_one::<String>("kind").map(String::as_str).unwrap_or("html");
+    export_health_report(dir.as_deref(), &report, format, report_matches.get_one::<String>("output"));
+}
+
+/// `batfi limit [PERCENT|off]`: show or set the charge stop threshold
+fn run_limit(battery_name: &str, limit_matches: &clap::ArgMatches, json_output: bool) {
+    let base_path = power_supply_path(battery_name);
+    let Some(value) = limit_matches.get_one::<String>("percent") else {
+        match charge_limit::read_limit(&base_path) {
+            Some(limit) if json_output => println!("{{\"battery\": \"{}\", \"charge_limit\": {}}}", battery_name, limit),
+            Some(limit) => println!("🔋 {} stops charging at {}%", battery_name, limit),
+            None => {
+                eprintln!("❌ {} has no charge threshold control", battery_name);
+                std::process::exit(1);
             }
-        } else {
-            println!("❌ Cannot read /sys/class/power_supply directory");
         }
+        return;
+    };
 
-        // Method 2: Thermal zones with type=battery
-        let thermal_path = Path::new("/sys/class/thermal");
-        println!("🔍 Checking /sys/class/thermal/ for battery thermal zones...");
-        
-        if let Ok(entries) = fs::read_dir(thermal_path) {
-            let mut thermal_entries: Vec<_> = entries.filter_map(|e| e.ok()).collect();
-            thermal_entries.sort_by_key(|e| e.file_name());
-            
-            for entry in thermal_entries {
-                let name = entry.file_name();
-                let name_str = name.to_string_lossy();
-                
-                if name_str.starts_with("thermal_zone") {
-                    let type_path = entry.path().join("type");
-                    match fs::read_to_string(&type_path) {
-                        Ok(zone_type_raw) => {
-                            let zone_type = zone_type_raw.trim();
-                            println!("🔍 thermal_zone {} type: '{}'", name_str, zone_type);
-                            
-                            if zone_type == "battery" {
-                                let temp_path = entry.path().join("temp");
-                                if temp_path.exists() {
-                                    println!("   📊 Found battery thermal zone temp file: {}", temp_path.display());
-                                    let sensor = TemperatureSensor {
-                                        sensor_type: "thermal_zone".to_string(),
-                                        path: temp_path.to_string_lossy().to_string(),
-                                        label: Some(zone_type.to_string()),
-                                        name: format!("Battery Thermal {}", name_str),
-                                    };
-                                    
-                                    println!("   🧪 Testing thermal zone sensor: {} -> {}", sensor.name, sensor.path);
-                                    match self.read_temperature_from_path(&sensor.path) {
-                                        Some(raw_temp) => {
-                                            let normalized_temp = self.normalize_battery_temperature(raw_temp);
-                                            println!("   📊 Raw temp: {}, normalized: {:.1}°C", raw_temp, normalized_temp);
-                                            
-                                            if self.is_valid_temperature(normalized_temp) {
-                                                println!("   ✅ VALID battery thermal zone: {} = {:.1}°C", sensor.name, normalized_temp);
-                                                self.battery_sensors.push(sensor);
-                                            } else {
-                                                println!("   🚫 INVALID thermal zone temperature: {:.1}°C", normalized_temp);
-                                            }
-                                        }
-                                        None => {
-                                            println!("   ❌ Cannot read from thermal zone: {}", sensor.path);
-                                        }
-                                    }
-                                } else {
-                                    println!("   ❌ No temp file in thermal zone {}", name_str);
-                                }
-                            } else {
-                                println!("   🚫 Skipping thermal zone {} (type: '{}')", name_str, zone_type);
-                            }
-                        }
-                        Err(e) => {
-                            println!("   ❌ Cannot read type from {}: {}", type_path.display(), e);
-                        }
-                    }
-                }
-            }
-        } else {
-            println!("❌ Cannot read /sys/class/thermal directory");
+    let percent = if value == "off" { Some(100) } else { value.parse::<u8>().ok() };
+    let Some(percent) = percent else {
+        eprintln!("❌ Invalid limit '{}': use 1-100 or 'off'", value);
+        std::process::exit(1);
+    };
+    match charge_limit::set_limit(&base_path, percent) {
+        Ok(()) => {
+            let _ = events::log_event(data_dir().as_deref(), battery_name, "charge-limit", &format!("charge limit set to {}%", percent));
+            println!("✅ {} will stop charging at {}%", battery_name, percent);
         }
-        
-        if self.battery_sensors.is_empty() {
-            println!("⚠️  No battery temperature sensors found");
-        } else {
-            println!("📊 Found {} battery sensor(s):", self.battery_sensors.len());
-            for (i, sensor) in self.battery_sensors.iter().enumerate() {
-                println!("   {}. {} [{}]", i+1, sensor.name, sensor.path);
-            }
+        Err(e) => {
+            eprintln!("❌ Could not set charge limit: {} (writing thresholds usually needs root)", e);
+            std::process::exit(1);
         }
     }
+}
 
-    fn read_temperature_from_path(&self, path: &str) -> Option<f64> {
-        fs::read_to_string(path)
-            .ok()?
-            .trim()
-            .parse::<f64>()
-            .ok()
-    }
-
-    fn normalize_battery_temperature(&self, raw_value: f64) -> f64 {
-        if raw_value > 1000.0 {
-            // Millidegrees Celsius - divide by 1000
-            let normalized = raw_value / 1000.0;
-            println!("   🔄 Normalized battery temp: {} (millidegrees) -> {:.1}°C", raw_value, normalized);
-            normalized
-        } else if raw_value > 200.0 {
-            // Decidegrees Celsius - divide by 10
-            let normalized = raw_value / 10.0;
-            println!("   🔄 Normalized battery temp: {} (decidegrees) -> {:.1}°C", raw_value, normalized);
-            normalized
-        } else {
-            // Already in Celsius
-            println!("   ✅ Battery temp already in Celsius: {:.1}°C", raw_value);
-            raw_value
+/// `batfi log`: one row per sample, to stdout or appended to a file
+fn run_log(mut monitor: BatteryMonitor, log_matches: &clap::ArgMatches, json_output: bool, fields: Option<Vec<String>>) {
+    let (mut out, needs_header): (Box<dyn Write>, bool) = match log_matches.get_one::<String>("output") {
+        Some(path) => {
+            let file = fs::OpenOptions::new().create(true).append(true).open(path).unwrap_or_else(|e| {
+                eprintln!("❌ Could not open {}: {}", path, e);
+                std::process::exit(1);
+            });
+            let empty = file.metadata().map(|m| m.len() == 0).unwrap_or(true);
+            (Box::new(file), empty)
         }
-    }
+        None => (Box::new(io::stdout()), true),
+    };
 
-    fn is_valid_temperature(&self, temp: f64) -> bool {
-        temp >= MIN_VALID_TEMP && temp <= MAX_VALID_TEMP
-    }
-
-    /// Get current CPU temperature (raw value only)
-    pub fn get_cpu_temp(&mut self) -> Option<TemperatureReading> {
-        for sensor in &self.cpu_sensors {
-            if let Some(raw_temp) = self.read_temperature_from_path(&sensor.path) {
-                let temp_celsius = raw_temp / 1000.0; // Convert millidegrees to Celsius
-                
-                if self.is_valid_temperature(temp_celsius) {
-                    let reading = TemperatureReading {
-                        raw_value: temp_celsius,
-                        smoothed_value: temp_celsius, // No averaging - same as raw
-                        sensor_info: sensor.clone(),
-                        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
-                    };
-                    
-                    self.last_cpu_temp = Some(reading.clone());
-                    return Some(reading);
-                }
+    if needs_header && !json_output {
+        let header = fields.as_ref().map(|f| f.join(",")).unwrap_or_else(|| LOG_CSV_HEADER.to_string());
+        let _ = writeln!(out, "{}", header);
+    }
+    loop {
+        if let Some(info) = monitor.get_battery_info() {
+            let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+            let line = match (&fields, json_output) {
+                (fields, true) => log_json_line(timestamp, &info, fields.as_deref()),
+                (Some(fields), false) => fields::render_plain(&info, fields, ","),
+                (None, false) => log_csv_row(timestamp, &info),
+            };
+            if writeln!(out, "{}", line).and_then(|_| out.flush()).is_err() {
+                // Reader went away (e.g. `batfi log | head`)
+                return;
             }
         }
-        None
+        thread::sleep(monitor.update_interval());
     }
+}
 
-    /// Get current battery temperature (raw value only)
-    pub fn get_battery_temp(&mut self) -> Option<TemperatureReading> {
-        for sensor in &self.battery_sensors {
-            if let Some(raw_temp) = self.read_temperature_from_path(&sensor.path) {
-                let temp_celsius = self.normalize_battery_temperature(raw_temp);
-                
-                if self.is_valid_temperature(temp_celsius) {
-                    let reading = TemperatureReading {
-                        raw_value: temp_celsius,
-                        smoothed_value: temp_celsius, // No averaging - same as raw
-                        sensor_info: sensor.clone(),
-                        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
-                    };
-                    
-                    self.last_battery_temp = Some(reading.clone());
-                    return Some(reading);
-                }
-            }
+/// `batfi history --histogram`: discharging samples bucketed by wattage
+fn print_power_histogram(readings: &[BatteryReading], since: &str, json_output: bool) {
+    let power: distribution::PowerDistribution = readings
+        .iter()
+        .filter(|r| r.status == "Discharging")
+        .filter_map(|r| r.power_now_w)
+        .collect();
+    let buckets = power.histogram(HISTORY_HISTOGRAM_BUCKETS);
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&buckets).unwrap_or_else(|_| "[]".to_string()));
+        return;
+    }
+    if buckets.is_empty() {
+        println!(" \x1b[2mNo discharging samples in the last {}\x1b[0m", since);
+        return;
+    }
+    println!(" \x1b[1mPower distribution, last {} ({} discharging samples):\x1b[0m", since, power.len());
+    for line in distribution::histogram_lines(&buckets, 40) {
+        println!(" {}", line);
+    }
+    if let Some(p) = power.percentiles() {
+        println!(" \x1b[2mp50 {:.1} W · p90 {:.1} W · p99 {:.1} W\x1b[0m", p.p50, p.p90, p.p99);
+    }
+}
+
+/// `batfi history --chart METRIC --output FILE`
+#[cfg(feature = "chart")]
+fn export_history_chart(readings: &[BatteryReading], metric: &str, since: &str, output: &str) {
+    let path = Path::new(output);
+    if path.extension().is_some_and(|ext| !ext.eq_ignore_ascii_case("svg")) {
+        eprintln!("❌ Charts are written as SVG; use a .svg file name (convert with e.g. `rsvg-convert chart.svg -o chart.png`)");
+        std::process::exit(1);
+    }
+    // Already checked by clap
+    let metric = chart::Metric::parse(metric).unwrap_or(chart::Metric::Power);
+    match chart::render_svg(readings, metric, since, path) {
+        Ok(points) => println!("📈 Wrote {} ({} samples)", path.display(), points),
+        Err(e) => {
+            eprintln!("❌ Could not write chart: {}", e);
+            std::process::exit(1);
         }
-        None
     }
 }
 
-pub struct BatteryMonitor {
-    base_path: String,
-    readings_history: VecDeque<BatteryReading>,
-    power_history: VecDeque<PowerSample>,
-    smoothed_power: Option<f64>,
-    rolling_power_window: VecDeque<f64>,
-    temperature_monitor: TemperatureMonitor,
-    max_history: usize,
-    last_update: u64,
+#[cfg(not(feature = "chart"))]
+fn export_history_chart(_readings: &[BatteryReading], _metric: &str, _since: &str, _output: &str) {
+    missing_feature("batfi history --chart", "chart");
+}
+
+/// `batfi query`: one daemon endpoint's JSON, passed through as is for scripts
+fn run_query(query_matches: &clap::ArgMatches) {
+    if !client::daemon_available() {
+        eprintln!("❌ No batfi daemon is running; start one with `batfi daemon`");
+        std::process::exit(1);
+    }
+    let what = query_matches.get_one::<String>("what").map(String::as_str).unwrap_or("battery");
+    let path = match query_matches.get_one::<String>("since") {
+        Some(since) => format!("/v1/{}?since={}", what, since),
+        None => format!("/v1/{}", what),
+    };
+    match client::fetch_local(&path) {
+        Ok(body) => println!("{}", body.trim_end()),
+        Err(e) => {
+            eprintln!("❌ Could not query the daemon: {}", e);
+            std::process::exit(1);
+        }
+    }
 }
 
-impl BatteryMonitor {
-    pub fn new(battery_name: &str) -> Self {
-        Self {
-            base_path: format!("/sys/class/power_supply/{}", battery_name),
-            readings_history: VecDeque::new(),
-            power_history: VecDeque::new(),
-            smoothed_power: None,
-            rolling_power_window: VecDeque::new(),
-            temperature_monitor: TemperatureMonitor::new(),
-            max_history: MAX_HISTORY_SIZE,
-            last_update: 0,
+/// `batfi history`: one pack's samples from the history store, falling back to a running
+/// daemon's in-memory readings when nothing was recorded
+fn run_history(history_matches: &clap::ArgMatches, settings: &config::Settings, json_output: bool) {
+    let since = history_matches.get_one::<String>("since").map(String::as_str).unwrap_or("1h");
+    let Some(since_timestamp) = server::parse_since(since) else {
+        eprintln!("❌ Invalid --since '{}' (use e.g. 10m, 2h, 7d, @<Unix seconds> or an RFC 3339 time)", since);
+        std::process::exit(1);
+    };
+    // A pack that has since been removed still has history, so a plain name isn't checked
+    let battery = match settings.battery.as_slice() {
+        [name] if name != "all" && !name.contains(['*', '?']) => name.clone(),
+        _ => {
+            let mut batteries = select_batteries(settings);
+            if batteries.len() > 1 {
+                eprintln!("❌ --battery matches {}; history shows one pack at a time", batteries.join(", "));
+                std::process::exit(1);
+            }
+            batteries.swap_remove(0)
         }
+    };
+    let mut readings: Vec<BatteryReading> = history_store::HistoryStore::open(data_dir().as_deref(), &battery)
+        .map(|store| store.load(since_timestamp).into_iter().map(|sample| sample.reading).collect())
+        .unwrap_or_default();
+    // Nothing recorded (e.g. only a --simulate daemon ran): the daemon's in-memory history
+    if readings.is_empty() && client::daemon_available() {
+        readings = match client::fetch_json(&client::Remote::Socket, &format!("/v1/history?since={}", since)) {
+            Ok(readings) => readings,
+            Err(e) => {
+                eprintln!("❌ Could not fetch history: {}", e);
+                std::process::exit(1);
+            }
+        };
+    }
+
+    if history_matches.get_flag("histogram") {
+        print_power_histogram(&readings, since, json_output);
+        return;
+    }
+    if let Some(metric) = history_matches.get_one::<String>("chart") {
+        let output = history_matches.get_one::<String>("output").map(String::as_str).unwrap_or_default();
+        export_history_chart(&readings, metric, since, output);
+        return;
+    }
+    if json_output {
+        let entries: Vec<serde_json::Value> = readings
+            .iter()
+            .filter_map(|reading| {
+                let mut value = serde_json::to_value(reading).ok()?;
+                value["timestamp"] = timefmt::timestamp_json(reading.timestamp);
+                Some(value)
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&entries).unwrap_or_else(|_| "[]".to_string()));
+        return;
+    }
+    println!(" \x1b[1m{:<9} {:>5}  {:<12} {:>8} {:>8} {:>7}\x1b[0m", "TIME", "CAP", "STATUS", "POWER", "VOLTAGE", "TEMP");
+    for reading in &readings {
+        let dash = || "—".to_string();
+        println!(" {:<9} {:>4}%  {:<12} {:>8} {:>8} {:>7}",
+            timefmt::time_of_day(reading.timestamp),
+            reading.capacity_percent,
+            reading.status,
+            reading.power_now_w.map(|p| format!("{:.2}W", p)).unwrap_or_else(dash),
+            reading.voltage_v.map(|v| format!("{:.2}V", v)).unwrap_or_else(dash),
+            reading.temperature_c.map(|t| format!("{:.1}°C", t)).unwrap_or_else(dash));
+    }
+    if readings.is_empty() {
+        println!(" \x1b[2mNo samples of {} in the last {}\x1b[0m", battery, since);
     }
+}
 
-    fn read_file(&self, filename: &str) -> Option<String> {
-        let path = format!("{}/{}", self.base_path, filename);
-        fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+/// `batfi health`: long-term pack health, separate from the live monitoring view
+fn run_health(battery_name: &str, json_output: bool, health_matches: &clap::ArgMatches) {
+    let dir = data_dir();
+    let report = health::read_battery_health(dir.as_deref(), battery_name, &SysfsBackend::new(&power_supply_path(battery_name)));
+    if let Err(e) = health::record_capacity_sample(dir.as_deref(), &report) {
+        eprintln!("⚠️  Could not record health sample: {}", e);
     }
 
-    fn read_as_number<T: std::str::FromStr>(&self, filename: &str) -> Option<T> {
-        self.read_file(filename)?.parse().ok()
+    if let Some(format) = health_matches.get_one::<String>("export") {
+        export_health_report(dir.as_deref(), &report, format, health_matches.get_one::<String>("output"));
+    } else if json_output {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap_or_else(|_| "{}".to_string()));
+    } else {
+        display_health_report(&report);
     }
+}
 
-    /// Read energy values with fallback between energy_* and charge_* files
-    fn read_energy_values(&self) -> (Option<f64>, Option<f64>) {
-        // Try energy_* first (preferred for modern systems)
-        let energy_now = self.read_as_number::<f64>("energy_now")
-            .map(|e| e / 1_000_000.0) // Convert µWh to Wh
-            .or_else(|| {
-                // Fallback: charge_now * voltage_now
-                let charge = self.read_as_number::<f64>("charge_now")?;
-                let voltage = self.read_as_number::<f64>("voltage_now")?;
-                Some((charge * voltage) / 1_000_000_000_000.0) // µAh * µV to Wh
-            });
+/// `batfi health` as a terminal report
+fn display_health_report(report: &health::BatteryHealth) {
+    let grade_color = match report.grade {
+        'A' => "\x1b[32m",
+        'B' => "\x1b[36m",
+        'C' => "\x1b[33m",
+        'D' | 'F' => "\x1b[31m",
+        _ => "\x1b[37m",
+    };
 
-        let energy_full = self.read_as_number::<f64>("energy_full")
-            .map(|e| e / 1_000_000.0) // Convert µWh to Wh
-            .or_else(|| {
-                // Fallback: charge_full * voltage_now
-                let charge = self.read_as_number::<f64>("charge_full")?;
-                let voltage = self.read_as_number::<f64>("voltage_now")?;
-                Some((charge * voltage) / 1_000_000_000_000.0) // µAh * µV to Wh
-            });
+    println!("\x1b[1;36m╔══════════════════════════════════════════════════════════════╗\x1b[0m");
+    println!("\x1b[1;36m║\x1b[0m \x1b[1;37m🩺 Batfi - Battery Health Report\x1b[0m                             \x1b[1;36m║\x1b[0m");
+    println!("\x1b[1;36m╚══════════════════════════════════════════════════════════════╝\x1b[0m");
+    println!();
 
-        (energy_now, energy_full)
+    match report.health_percent {
+        Some(h) => println!(" Grade: {}\x1b[1m{}\x1b[0m  ({:.1}% of design capacity)", grade_color, report.grade, h),
+        None => println!(" Grade: \x1b[2m?\x1b[0m  (design capacity not reported)"),
     }
+    println!();
+
+    // Identity
+    println!(" \x1b[1mPack:\x1b[0m");
+    println!(" ├─ Device:       {} ({} {}, {})", report.battery, report.manufacturer, report.model, report.technology);
+    println!(" ├─ Serial:       {}", report.serial_number.as_deref().unwrap_or("\x1b[2m—\x1b[0m"));
+    match (&report.manufacture_date, report.age_days) {
+        (Some(date), Some(days)) => println!(" ├─ Manufactured: {} ({} old)", date, health::format_age(days)),
+        _ => println!(" ├─ Manufactured: \x1b[2m— (not exposed by driver)\x1b[0m"),
+    }
+    match (&report.first_seen_date, report.age_days) {
+        (Some(date), Some(days)) if report.age_from_first_seen => {
+            println!(" └─ First seen:   {} (at least {} old)", date, health::format_age(days))
+        }
+        (Some(date), _) => println!(" └─ First seen:   {}", date),
+        (None, _) => println!(" └─ First seen:   \x1b[2m— (not recorded yet)\x1b[0m"),
+    }
+    println!();
 
-    /// Read power with multiple fallback methods using instantaneous values
-    fn read_power(&self, voltage_v: Option<f64>, current_ma: Option<i32>) -> Option<f64> {
-        // Method 1: Direct power reading (most accurate)
-        if let Some(power_uw) = self.read_as_number::<f64>("power_now") {
-            return Some(power_uw / 1_000_000.0); // Convert µW to W
+    // Capacity
+    println!(" \x1b[1mCapacity:\x1b[0m");
+    let fmt_cap = |v: Option<f64>| match v {
+        Some(v) => format!("{:.1} {}", v, report.unit),
+        None => "\x1b[2m—\x1b[0m".to_string(),
+    };
+    println!(" ├─ Design:       \x1b[1m{}\x1b[0m", fmt_cap(report.design_capacity));
+    println!(" ├─ Full now:     \x1b[1m{}\x1b[0m", fmt_cap(report.full_capacity));
+    match report.wear_percent {
+        Some(wear) => println!(" └─ Wear:         \x1b[1m{:.1}%\x1b[0m", wear),
+        None => println!(" └─ Wear:         \x1b[2m—\x1b[0m"),
+    }
+    println!();
+
+    // Aging
+    println!(" \x1b[1mAging:\x1b[0m");
+    match report.cycles {
+        Some(c) => println!(" ├─ Cycles:       \x1b[1m{}\x1b[0m", c),
+        None => println!(" ├─ Cycles:       \x1b[2m— (not reported)\x1b[0m"),
+    }
+    match report.wear_per_100_cycles {
+        Some(rate) => println!(" ├─ Wear rate:    \x1b[1m{:.2}%\x1b[0m per 100 cycles (typical: {:.1}%)", rate, health::CYCLE_FADE_PER_CYCLE * 100.0),
+        None => println!(" ├─ Wear rate:    \x1b[2m—\x1b[0m"),
+    }
+    match (report.expected_calendar_wear_percent, report.wear_percent) {
+        (Some(calendar), Some(wear)) => {
+            let cycle_part = report.expected_cycle_wear_percent.unwrap_or(0.0);
+            let verdict = if wear <= calendar + cycle_part {
+                "\x1b[32mwithin expected aging\x1b[0m"
+            } else {
+                "\x1b[33mfaster than expected\x1b[0m"
+            };
+            println!(" └─ Calendar age: ~{:.1}% expected from age alone, {:.1}% from cycling ({})",
+                calendar, cycle_part, verdict);
         }
+        (Some(calendar), None) => println!(" └─ Calendar age: ~{:.1}% expected from age alone", calendar),
+        _ => println!(" └─ Calendar age: \x1b[2m— (manufacture date unknown)\x1b[0m"),
+    }
+    println!();
 
-        // Method 2: Instantaneous Power = Voltage × Current (most reliable for time estimation)
-        if let (Some(voltage), Some(current)) = (voltage_v, current_ma) {
-            let power_w = voltage * (current.abs() as f64 / 1000.0); // V * |A| = W
-            return Some(power_w);
+    // Degradation trend from persisted history
+    println!(" \x1b[1mProjection:\x1b[0m");
+    match report.wear_rate_percent_per_month {
+        Some(rate) => println!(" ├─ Trend:        \x1b[1m{:+.2}%\x1b[0m of design capacity per month", rate),
+        None => println!(" ├─ Trend:        \x1b[2m— (needs {:.0}+ days of history)\x1b[0m", health::MIN_TREND_SPAN_DAYS),
+    }
+    match health::end_of_life_summary(report) {
+        Some(summary) => println!(" └─ End of life:  \x1b[1m{}\x1b[0m", summary),
+        None => println!(" └─ End of life:  \x1b[2m— (no measurable degradation yet)\x1b[0m"),
+    }
+    println!();
+
+    // Fuel gauge accuracy
+    println!(" \x1b[1mFuel Gauge:\x1b[0m");
+    match (report.gauge_drift_wh, report.gauge_drift_percent) {
+        (Some(drift), Some(percent)) => {
+            println!(" ├─ Drift:        \x1b[1m{:+.2} Wh\x1b[0m ({:+.1}%) reported vs measured over recent discharges", drift, percent);
+            if report.calibration_recommended {
+                println!(" └─ \x1b[33mGauge is drifting — run a calibration cycle (full charge, discharge to ~5%, full charge)\x1b[0m");
+            } else {
+                println!(" └─ \x1b[32mGauge agrees with measured energy\x1b[0m");
+            }
         }
+        _ => println!(" └─ Drift:        \x1b[2m— (needs {} discharge segments of {:.0} Wh)\x1b[0m", health::DRIFT_MIN_SEGMENTS, health::DRIFT_SEGMENT_WH),
+    }
+    println!();
+
+    // Internal resistance
+    println!(" \x1b[1mInternal Resistance:\x1b[0m");
+    match (report.internal_resistance_mohm, report.internal_resistance_change_percent) {
+        (Some(r), Some(change)) => {
+            let trend = if change >= health::RESISTANCE_RISE_WARN_PERCENT {
+                format!("\x1b[33m{:+.0}% since first measured — early sign of degradation\x1b[0m", change)
+            } else {
+                format!("\x1b[32m{:+.0}% since first measured\x1b[0m", change)
+            };
+            println!(" └─ Estimated:    \x1b[1m{:.0} mΩ\x1b[0m ({})", r, trend);
+        }
+        (Some(r), None) => println!(" └─ Estimated:    \x1b[1m{:.0} mΩ\x1b[0m \x1b[2m(no trend yet)\x1b[0m", r),
+        _ => println!(" └─ Estimated:    \x1b[2m— (run the monitor on battery to collect load steps)\x1b[0m"),
+    }
+    println!();
+
+    if let Some(stats) = &report.plug_stats {
+        println!(" \x1b[1mPlug Habits (30 days):\x1b[0m");
+        let buckets: Vec<String> = usage::TIME_OF_DAY_BUCKETS
+            .iter()
+            .zip(stats.plugs_by_time_of_day)
+            .filter(|(_, count)| *count > 0)
+            .map(|(label, count)| format!("{} {}", label, count))
+            .collect();
+        if buckets.is_empty() {
+            println!(" ├─ Plugged in:   \x1b[1m{}×\x1b[0m", stats.plugs);
+        } else {
+            println!(" ├─ Plugged in:   \x1b[1m{}×\x1b[0m ({})", stats.plugs, buckets.join(", "));
+        }
+        println!(" ├─ Unplugged:    \x1b[1m{}×\x1b[0m", stats.unplugs);
+        let share = |secs: u64| stats.percent_of_time(secs).map(|p| format!("{:.0}%", p)).unwrap_or_else(|| "—".to_string());
+        let full_label = match report.charge_limit {
+            Some(limit) => format!("held at {}%", limit),
+            None => "full on AC".to_string(),
+        };
+        println!(" └─ Time split:   {} {} · {} charging · {} on battery",
+            share(stats.full_on_ac_secs), full_label, share(stats.charging_secs), share(stats.discharging_secs));
+        println!();
+    }
 
-        None
+    println!(" \x1b[1mRecommendations:\x1b[0m");
+    let advice = health::recommendations(report);
+    for (i, line) in advice.iter().enumerate() {
+        let branch = if i + 1 == advice.len() { "└─" } else { "├─" };
+        println!(" {} {}", branch, line);
     }
+}
 
-    /// Get CPU temperature using the new temperature monitor
-    pub fn get_cpu_temperature(&mut self) -> Option<TemperatureReading> {
-        self.temperature_monitor.get_cpu_temp()
+/// `--samples N`: prime the estimators with `warmup` samples, then merge the next
+/// `samples` readings into one with their mean power and the settled ETA
+fn sample_snapshot(monitor: &mut BatteryMonitor, samples: u32, warmup: u32) -> Option<BatteryInfo> {
+    for _ in 0..warmup {
+        monitor.get_battery_info()?;
+        thread::sleep(monitor.update_interval());
     }
 
-    /// Get battery temperature using the new temperature monitor
-    pub fn get_battery_temperature(&mut self) -> Option<TemperatureReading> {
-        self.temperature_monitor.get_battery_temp()
+    let mut powers = Vec::new();
+    let mut last = None;
+    for i in 0..samples {
+        if i > 0 {
+            thread::sleep(monitor.update_interval());
+        }
+        let info = monitor.get_battery_info()?;
+        powers.extend(info.power_w);
+        last = Some(info);
     }
 
-    /// Update smoothed power using exponential moving average and rolling window
-    fn update_smoothed_power(&mut self, current_power: f64) {
-        // Update exponential moving average
-        self.smoothed_power = Some(match self.smoothed_power {
-            Some(prev) => POWER_SMOOTHING_ALPHA * current_power + (1.0 - POWER_SMOOTHING_ALPHA) * prev,
-            None => current_power,
-        });
+    let mut info = last?;
+    if !powers.is_empty() {
+        info.power_w = Some(powers.iter().sum::<f64>() / powers.len() as f64);
+    }
+    Some(info)
+}
+
+/// A single reading, or the `--samples` snapshot when one was asked for
+fn one_shot_info(monitor: &mut BatteryMonitor, matches: &clap::ArgMatches) -> Option<BatteryInfo> {
+    match matches.get_one::<u32>("samples") {
+        Some(&samples) => sample_snapshot(monitor, samples, *matches.get_one::<u32>("warmup").unwrap_or(&3)),
+        None => monitor.get_battery_info(),
+    }
+}
 
-        // Update rolling window for ultra-smooth estimates
-        self.rolling_power_window.push_back(current_power);
-        if self.rolling_power_window.len() > ROLLING_WINDOW_SIZE {
-            self.rolling_power_window.pop_front();
+/// `batfi watch --json` with several batteries: one array per update
+/// `--json` or `--format plasma` with several batteries selected: one document per update covering every pack
+fn run_multi_watch(batteries: &[String], settings: &config::Settings, run_once: bool) {
+    let plasma_output = settings.format.as_deref() == Some("plasma");
+    let mut monitors = multi::MultiBatteryMonitor::with_monitors(
+        batteries
+            .iter()
+            .map(|name| {
+                let mut monitor = BatteryMonitor::new(name);
+                // The Plasma document lists each pack's active alerts
+                if let Some(engine) = build_alert_engine(&settings.alerts, true, monitor.base_path()).filter(|_| plasma_output) {
+                    monitor.set_alerts(engine);
+                }
+                monitor
+            })
+            .collect(),
+    );
+    let start_time = SystemTime::now();
+    loop {
+        if plasma_output {
+            let document = monitors.sample().map(|combined| plasma::combined_document(&monitors, &combined));
+            println!("{}", document.and_then(|document| serde_json::to_string(&document).ok()).unwrap_or_else(|| "{}".to_string()));
+        } else {
+            println!("{}", multi_battery_json(&mut monitors, settings.fields.as_deref()));
+        }
+        let _ = std::io::stdout().flush();
+        let elapsed = start_time.elapsed().unwrap().as_secs();
+        if run_once || settings.duration_secs().is_some_and(|duration| elapsed >= duration) {
+            break;
         }
+        thread::sleep(Duration::from_secs(settings.interval));
     }
+}
 
+/// Intervals, smoothing, units and the dashboard options from the settings
+fn configure_monitor(monitor: &mut BatteryMonitor, settings: &config::Settings) {
+    monitor.set_update_interval(Duration::from_secs(settings.interval));
+    monitor.set_history(settings.history_secs(), settings.rolling_window_secs());
+    monitor.set_smoothing(settings.smoothing());
+    monitor.set_charge_units(settings.units == "mah");
+    #[cfg(feature = "tui")]
+    {
+        monitor.set_animations(settings.animations);
+        monitor.set_run_duration(settings.duration_secs());
+        monitor.set_energy_bar(settings.bar == "energy");
+        monitor.set_histogram(settings.histogram);
+        monitor.set_wakeups(settings.wakeups);
+    }
+    if settings.no_quirks {
+        monitor.disable_quirks();
+    }
+}
+
+/// A battery the dashboard can switch to; alerts, scripts and StatsD stay with the watched one
+#[cfg(feature = "tui")]
+fn watch_monitor(battery_name: &str, settings: &config::Settings) -> BatteryMonitor {
+    let mut monitor = BatteryMonitor::new(battery_name);
+    configure_monitor(&mut monitor, settings);
+    monitor.persist_history();
+    monitor
+}
 
-    /// Get rolling average power for ultra-stable estimates
-    fn get_rolling_average_power(&self) -> Option<f64> {
-        if self.rolling_power_window.len() < 3 {
-            return self.smoothed_power;
+/// `batfi watch`: the live dashboard, or one machine-readable line per update
+fn run_watch(mut monitor: BatteryMonitor, battery_name: &str, matches: &clap::ArgMatches, settings: &config::Settings) {
+    let json_output = matches.get_flag("json");
+    let run_once = matches.get_flag("once") || matches.contains_id("samples");
+    let plasma_output = settings.format.as_deref() == Some("plasma");
+    let bar_format = settings.format.as_deref().and_then(statusbar::BarFormat::parse);
+    let selected_fields = settings.fields.clone();
+    let separator = settings.separator.as_str();
+    // Piped or redirected, the dashboard's screen clears and colours would only garble the
+    // file: print one timestamped plain line per sample instead
+    let plain_output = !json_output && !io::stdout().is_terminal();
+    // Single-line outputs are consumed by other programs: no banner
+    let line_output = bar_format.is_some() || plasma_output || selected_fields.is_some() || plain_output;
+
+    if !json_output && !run_once && !line_output {
+        println!("🔋 Starting Batfi v2.0...");
+    println!("   Found battery: {}", battery_name);
+        for quirk in monitor.quirks() {
+            println!("   Applying quirk: {}", quirk.description());
         }
-        
-        let sum: f64 = self.rolling_power_window.iter().sum();
-        Some(sum / self.rolling_power_window.len() as f64)
-    }
-
-    /// Calculate highly accurate time remaining using multiple smoothing techniques
-    fn calculate_time_remaining(&self, info: &BatteryReading) -> Option<u32> {
-        let instantaneous_power = info.power_now_w?;
-        let smoothed_power = self.smoothed_power?;
-        let rolling_power = self.get_rolling_average_power()?;
-        
-        // Skip calculation if power is too low (likely noise or system idle)
-        if instantaneous_power.abs() < MIN_POWER_THRESHOLD {
-            return None;
+        if let Some(compositor) = monitor.compositor() {
+            println!("   Tagging samples with {} display state", compositor.name());
         }
-
-        // Need enough samples for reliable estimate
-        if self.power_history.len() < MIN_SAMPLES_FOR_ESTIMATE {
-            return None;
+        match settings.duration_secs() {
+            Some(duration) => println!("   Will run for {} with {}s updates", format_run_time(duration), monitor.update_interval().as_secs()),
+            None => println!("   Updating every {}s until stopped", monitor.update_interval().as_secs()),
         }
-
-        // Advanced weighted power calculation for maximum accuracy
-        let weighted_power = if self.power_history.len() < 5 {
-            // Very early: mostly instantaneous for quick adaptation
-            0.8 * instantaneous_power + 0.2 * smoothed_power
-        } else if self.power_history.len() < ROLLING_WINDOW_SIZE {
-            // Early: balance instantaneous and smoothed
-            0.5 * instantaneous_power + 0.5 * smoothed_power
-        } else {
-            // Mature: use all three methods for ultra-stable estimates
-            0.2 * instantaneous_power + 0.3 * smoothed_power + 0.5 * rolling_power
+        #[cfg(feature = "tui")]
+        if settings.animations {
+            println!("   🐱 Watch the cat eat {} dots!", TOTAL_DOTS);
+            println!("   Pac-Cat Progress: {}", "●".repeat(TOTAL_DOTS));
+        }
+        thread::sleep(Duration::from_millis(1000));
+    }
+    // On a terminal the dashboard draws and reads keys; it also samples the machine's other
+    // batteries so they can be switched to
+    #[cfg(feature = "tui")]
+    let mut dashboard = (!json_output && !run_once && !line_output).then(|| {
+        let others = match matches.contains_id("simulate") {
+            true => Vec::new(),
+            false => find_batteries().into_iter().filter(|name| name != battery_name).map(|name| watch_monitor(&name, settings)).collect(),
         };
+        dashboard::Dashboard::enter(others).unwrap_or_else(|e| {
+            eprintln!("❌ Could not switch the terminal to raw mode: {}", e);
+            std::process::exit(1);
+        })
+    });
 
-        match info.status.as_str() {
-            "Discharging" => {
-                if let Some(energy_now) = info.energy_now_wh {
-                    if weighted_power > 0.0 {
-                        // Time to drain = Current Energy / Power Consumption
-                        let hours = energy_now / weighted_power;
-                        Some((hours * 60.0).max(1.0) as u32) // At least 1 minute
-                    } else {
-                        None
-                    }
+    // Ctrl+C ends the run like --duration does: terminal restored, summary, on_exit, status 0
+    if !run_once {
+        install_interrupt_handler();
+    }
+
+    // Record start time for --duration
+    let start_time = SystemTime::now();
+    let mut last_health_record: Option<u64> = None;
+    let mut summary = session::RunSummary::default();
+
+    // Main monitoring loop, until --duration runs out
+    loop {
+        let sample = if run_once { one_shot_info(&mut monitor, matches) } else { monitor.get_battery_info() };
+        match sample {
+            Some(info) => {
+                summary.record(SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(), &info);
+                if json_output && selected_fields.is_some() {
+                    println!("{}", fields::to_json(&info, selected_fields.as_deref()));
+                } else if let Some(fields) = &selected_fields {
+                    println!("{}", fields::render_plain(&info, fields, separator));
+                    let _ = std::io::stdout().flush();
+                } else if plasma_output {
+                    let document = plasma::document(&[(&monitor, &info)]);
+                    println!("{}", serde_json::to_string(&document).unwrap_or_else(|_| "{}".to_string()));
+                    let _ = std::io::stdout().flush();
+                } else if let Some(format) = bar_format {
+                    println!("{}", statusbar::render(format, &info));
+                    let _ = std::io::stdout().flush();
+                } else if json_output {
+                    println!("{}", monitor.to_json(&info));
+                } else if plain_output {
+                    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+                    println!("{} {} {}", timefmt::date_time(now), monitor.battery_name(), status_summary(&info));
+                    let _ = std::io::stdout().flush();
                 } else {
-                    // Fallback: use capacity percentage if energy not available
-                    if let (Some(voltage), Some(current)) = (info.voltage_v, info.current_ma) {
-                        if current < 0 && voltage > 0.0 {
-                            // Estimate based on capacity and current draw
-                            let capacity_fraction = info.capacity_percent as f64 / 100.0;
-                            let estimated_energy = voltage * 3.0 * capacity_fraction; // Rough 3Ah estimate
-                            let power = voltage * ((-current) as f64 / 1000.0);
-                            if power > MIN_POWER_THRESHOLD {
-                                let hours = estimated_energy / power;
-                                return Some((hours * 60.0).max(1.0) as u32);
-                            }
-                        }
+                    #[cfg(feature = "tui")]
+                    if let Some(dashboard) = dashboard.as_mut() {
+                        dashboard.update(&mut monitor, &info);
                     }
-                    None
+                    // Built without the dashboard: one compact line per update
+                    #[cfg(not(feature = "tui"))]
+                    print_status_summary(&info, monitor.battery_name());
                 }
             }
-            "Charging" => {
-                if let (Some(energy_now), Some(energy_full)) = (info.energy_now_wh, info.energy_full_wh) {
-                    if weighted_power > 0.0 {
-                        let energy_to_charge = energy_full - energy_now;
-                        
-                        // Advanced charging calculation considering charging curve
-                        let charge_progress = energy_now / energy_full;
-                        let charging_efficiency = if charge_progress > 0.8 {
-                            // Charging slows down significantly above 80%
-                            0.6 + (0.9 - charge_progress) * 2.0 // Efficiency drops as we approach 100%
-                        } else if charge_progress > 0.95 {
-                            // Trickle charge phase
-                            0.3
-                        } else {
-                            // Normal charging phase
-                            0.9
-                        };
-                        
-                        let effective_power = weighted_power * charging_efficiency;
-                        let hours = energy_to_charge / effective_power;
-                        Some((hours * 60.0).max(1.0) as u32) // At least 1 minute
-                    } else {
-                        None
-                    }
+            None => {
+                #[cfg(feature = "tui")]
+                drop(dashboard.take());
+                if json_output {
+                    eprintln!("{{\"error\": \"Could not read battery information\"}}");
                 } else {
-                    // Enhanced fallback for systems without energy readings
-                    if let (Some(voltage), Some(current)) = (info.voltage_v, info.current_ma) {
-                        if current > 0 && voltage > 0.0 {
-                            let remaining_capacity = (100 - info.capacity_percent) as f64 / 100.0;
-                            
-                            // Better capacity estimation based on voltage
-                            let estimated_full_capacity = match voltage {
-                                v if v > 12.0 => 4.0, // Larger battery
-                                v if v > 7.0 => 3.0,  // Standard laptop battery
-                                _ => 2.0,             // Smaller battery
-                            };
-                            
-                            let estimated_energy_needed = voltage * estimated_full_capacity * remaining_capacity;
-                            let power = voltage * (current as f64 / 1000.0);
-                            
-                            // Apply charging curve to fallback calculation too
-                            let charge_progress = info.capacity_percent as f64 / 100.0;
-                            let efficiency = if charge_progress > 0.8 { 0.7 } else { 0.9 };
-                            let effective_power = power * efficiency;
-                            
-                            if effective_power > MIN_POWER_THRESHOLD {
-                                let hours = estimated_energy_needed / effective_power;
-                                return Some((hours * 60.0).max(1.0) as u32);
-                            }
-                        }
-                    }
-                    None
+                println!("❌ Could not read battery information");
+                println!("   Make sure {} exists and is readable", monitor.base_path());
                 }
+                std::process::exit(1);
             }
-            "Not charging" | "Full" => {
-                // Battery is full or not charging
-                None
-            }
-            _ => None,
         }
-    }
 
-    /// Determine power trend from recent history
-    fn get_power_trend(&self) -> String {
-        if self.power_history.len() < 5 {
-            return "stable".to_string();
+        // Periodically persist resistance estimates for the health trend
+        if monitor.estimate_internal_resistance().is_some() {
+            let now = start_time.elapsed().unwrap().as_secs();
+            if last_health_record.is_none_or(|last| now - last >= HEALTH_RECORD_INTERVAL_SECS) {
+                record_health_sample(&monitor, battery_name);
+                last_health_record = Some(now);
+            }
         }
 
-        let recent: Vec<&PowerSample> = self.power_history.iter().rev().take(5).collect();
-        let power_changes: Vec<f64> = recent.windows(2)
-            .map(|w| w[0].power_w - w[1].power_w)
-            .collect();
-
-        let avg_change: f64 = power_changes.iter().sum::<f64>() / power_changes.len() as f64;
-
-        if avg_change > 0.5 {
-            "increasing".to_string()
-        } else if avg_change < -0.5 {
-            "decreasing".to_string()
-        } else {
-            "stable".to_string()
+        if run_once {
+            break;
         }
-    }
 
-    pub fn get_battery_info(&mut self) -> Option<BatteryInfo> {
-        if !Path::new(&self.base_path).exists() {
-            return None;
+        let elapsed = start_time.elapsed().unwrap().as_secs();
+        if settings.duration_secs().is_some_and(|duration| elapsed >= duration) {
+            #[cfg(feature = "tui")]
+            drop(dashboard.take());
+            if !json_output && !line_output {
+                println!("⏰ Program completed after {} seconds", elapsed);
+                print_run_summary(&summary);
+            }
+            break;
         }
 
-        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-        
-        // Read basic values
-        let status = self.read_file("status").unwrap_or_else(|| "Unknown".to_string());
-        let capacity = self.read_as_number("capacity").unwrap_or(0u8);
-        let voltage_v = self.read_as_number::<f64>("voltage_now").map(|v| v / 1_000_000.0);
-        let current_ma = self.read_as_number::<i32>("current_now").map(|c| c / 1000);
-        let _temperature_c = self.read_as_number::<f64>("temp").map(|t| t / 10.0);
-        let cycles = self.read_as_number("cycle_count");
-
-        // Read energy values with fallbacks
-        let (energy_now_wh, energy_full_wh) = self.read_energy_values();
-
-        // Read power with fallbacks
-        let power_w = self.read_power(voltage_v, current_ma);
-
-        // Get real-time temperatures using the new API
-        let cpu_temp_reading = self.get_cpu_temperature();
-        let battery_temp_reading = self.get_battery_temperature();
-        let cpu_temperature_c = cpu_temp_reading.as_ref().map(|r| r.raw_value);
-        let _temperature_c = battery_temp_reading.as_ref().map(|r| r.raw_value);
-
-        // Update smoothed values
-        if let Some(power) = power_w {
-            self.update_smoothed_power(power);
-            
-            // Add to power history
-            if let Some(energy) = energy_now_wh {
-                self.power_history.push_back(PowerSample {
-                    timestamp,
-                    power_w: power,
-                    energy_wh: energy,
-                });
-                
-                if self.power_history.len() > self.max_history {
-                    self.power_history.pop_front();
-                }
+        // Wait before next update; the dashboard handles keys meanwhile
+        #[cfg(feature = "tui")]
+        let stopped = match dashboard.as_mut() {
+            Some(screen) => screen.wait(&mut monitor) == dashboard::Wake::Quit,
+            None => sleep_unless_interrupted(monitor.update_interval()),
+        };
+        #[cfg(not(feature = "tui"))]
+        let stopped = sleep_unless_interrupted(monitor.update_interval());
+        if stopped {
+            #[cfg(feature = "tui")]
+            drop(dashboard.take()); // Back on the normal screen for the summary
+            if !json_output && !line_output {
+                println!("⏹  Stopped after {} seconds", start_time.elapsed().unwrap().as_secs());
+                print_run_summary(&summary);
             }
+            break;
         }
+    }
+    if let Some(command) = &settings.on_exit {
+        run_on_exit(command, battery_name, &summary);
+    }
+}
 
-        self.last_update = timestamp;
-
-        // Create reading for history
-        let reading = BatteryReading {
-            timestamp,
-            capacity_percent: capacity,
-            energy_now_wh,
-            energy_full_wh,
-            power_now_w: power_w,
-            voltage_v,
-            current_ma,
-            status: status.clone(),
-            temperature_c: battery_temp_reading.as_ref().map(|r| r.raw_value),
-        };
+/// What a `watch` run saw, printed when it ends
+fn print_run_summary(summary: &session::RunSummary) {
+    println!();
+    println!("📋 Session summary:");
+    println!("   Duration:     {}", format_run_time(summary.duration_secs()));
+    println!("   Samples:      {}", summary.samples);
+    if let (Some(start), Some(end)) = (summary.start_percent, summary.end_percent) {
+        println!("   Capacity:     {}% → {}%", start, end);
+    }
+    println!("   Energy used:  {:.2} Wh", summary.energy_used_wh);
+    if let Some(power) = summary.average_power_w() {
+        println!("   Avg power:    {:.2} W", power);
+    }
+}
 
-        // Calculate time remaining
-        let time_remaining_minutes = self.calculate_time_remaining(&reading);
+/// Run the `on_exit` command through the shell, with the run's totals in BATFI_* variables
+fn run_on_exit(command: &str, battery_name: &str, summary: &session::RunSummary) {
+    let mut hook = std::process::Command::new("sh");
+    hook.arg("-c")
+        .arg(command)
+        .env("BATFI_BATTERY", battery_name)
+        .env("BATFI_DURATION_SECS", summary.duration_secs().to_string())
+        .env("BATFI_SAMPLES", summary.samples.to_string())
+        .env("BATFI_ENERGY_USED_WH", format!("{:.3}", summary.energy_used_wh));
+    if let Some(power) = summary.average_power_w() {
+        hook.env("BATFI_AVG_POWER_W", format!("{:.3}", power));
+    }
+    if let Some(capacity) = summary.end_percent {
+        hook.env("BATFI_CAPACITY", capacity.to_string());
+    }
+    match hook.status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => eprintln!("⚠️  on_exit command exited with {}", status),
+        Err(e) => eprintln!("❌ Could not run on_exit command: {}", e),
+    }
+}
 
-        // Add to readings history
-        self.readings_history.push_back(reading);
-        if self.readings_history.len() > self.max_history {
-            self.readings_history.pop_front();
+/// The command line: parse arguments, load the config and run the chosen subcommand
+pub fn run() {
+    let matches = cli::build_cli().get_matches();
+    let json_output = matches.get_flag("json");
+    // Sensor discovery narration is for people watching a terminal, not for logs
+    set_discovery_log(io::stderr().is_terminal());
+    set_warning_handler(|message| eprintln!("⚠️  {}", message));
+
+    // Runs before loading the config so a broken file can still be diagnosed
+    if let Some(config_matches) = matches.subcommand_matches("config") {
+        cli::run_config(config_matches, &matches);
+        return;
+    }
+    let settings = cli::load_settings(&matches);
+    i18n::init(settings.lang.as_deref());
+    timefmt::init(&settings.time_format, &settings.timestamp_format);
+    set_sysfs_root(&settings.sysfs_root);
+    let simulation = matches.get_one::<String>("simulate").map(|scenario| simulation_backend(scenario, &matches, settings.interval));
+
+    // Prefer a running server so every consumer sees the same smoothed values
+    if let Some(status_matches) = matches.subcommand_matches("status").filter(|_| simulation.is_none()) {
+        let remote = match status_matches.get_one::<String>("remote") {
+            Some(addr) if addr.is_empty() => Some(client::Remote::Socket),
+            Some(addr) => Some(client::Remote::Http(addr.clone())),
+            None if !status_matches.get_flag("local") && client::daemon_available() => Some(client::Remote::Socket),
+            None => None,
+        };
+        if let Some(remote) = remote {
+            run_remote_status(&remote, json_output, settings.fields.as_deref());
+            return;
         }
+    }
 
-        // Calculate health
-        let health_percent = match (energy_full_wh, self.read_as_number::<f64>("energy_full_design").map(|e| e / 1_000_000.0)) {
-            (Some(full), Some(design)) if design > 0.0 => (full / design) * 100.0,
-            _ => {
-                // Fallback to charge-based calculation
-                match (
-                    self.read_as_number::<f64>("charge_full"),
-                    self.read_as_number::<f64>("charge_full_design")
-                ) {
-                    (Some(full), Some(design)) if design > 0.0 => (full / design) * 100.0,
-            _ => 0.0,
-                }
+    // The aggregator only listens; it doesn't need a local battery
+    if let Some(fleet_matches) = matches.subcommand_matches("fleet") {
+        if fleet_matches.get_one::<String>("push").is_none() {
+            let addr = fleet_matches.get_one::<String>("listen").map(String::as_str).unwrap_or(fleet::DEFAULT_LISTEN);
+            if let Err(e) = fleet::run_aggregator(addr, json_output) {
+                eprintln!("❌ Could not listen on {}: {}", addr, e);
+                std::process::exit(1);
             }
-        };
+            return;
+        }
+    }
 
-        let power_trend = self.get_power_trend();
-
-        Some(BatteryInfo {
-            status,
-            capacity_percent: capacity,
-            health_percent,
-            cycles,
-            temperature_c: battery_temp_reading.as_ref().map(|r| r.raw_value),
-            voltage_v,
-            current_ma,
-            power_w,
-            smoothed_power_w: self.smoothed_power,
-            manufacturer: self.read_file("manufacturer").unwrap_or_else(|| "Unknown".to_string()),
-            model: self.read_file("model_name").unwrap_or_else(|| "Unknown".to_string()),
-            technology: self.read_file("technology").unwrap_or_else(|| "Unknown".to_string()),
-            time_remaining_minutes,
-            energy_now_wh,
-            energy_full_wh,
-            power_trend,
-            cpu_temperature_c,
-        })
+    if matches.subcommand_matches("list").is_some() {
+        list::run_list(json_output);
+        return;
     }
 
-    pub fn get_battery_bar(&self, capacity: u8, width: usize) -> String {
-        let filled = (capacity as f32 / 100.0 * width as f32) as usize;
-        let empty = width - filled;
-        
-        let color = match capacity {
-            0..=15 => "\x1b[31m",   // Red
-            16..=30 => "\x1b[33m",  // Yellow
-            31..=80 => "\x1b[32m",  // Green
-            _ => "\x1b[36m",        // Cyan
-        };
-        
-        format!("{}{}{}{}",
-            color,
-            "█".repeat(filled),
-            "░".repeat(empty),
-            "\x1b[0m"
-        )
-    }
-
-    pub fn get_trend_indicator(&self) -> String {
-        if self.readings_history.len() < 2 {
-            return "━".to_string();
-        }
-        
-        let recent: Vec<&BatteryReading> = self.readings_history.iter().rev().take(5).collect();
-        if recent.len() < 2 {
-            return "━".to_string();
-        }
+    if matches.subcommand_matches("top").is_some() {
+        #[cfg(feature = "tui")]
+        return top::run_top(Duration::from_secs(settings.interval), json_output);
+        #[cfg(not(feature = "tui"))]
+        missing_feature("batfi top", "tui");
+    }
 
-        let trend: i32 = recent.windows(2)
-            .map(|w| w[0].capacity_percent as i32 - w[1].capacity_percent as i32)
-            .sum();
-        
-        match trend {
-            t if t > 0 => "\x1b[32m↗\x1b[0m".to_string(),  // Green up
-            t if t < 0 => "\x1b[31m↘\x1b[0m".to_string(),  // Red down
-            _ => "\x1b[37m━\x1b[0m".to_string(),           // Gray stable
-        }
+    if let Some(history_matches) = matches.subcommand_matches("history") {
+        run_history(history_matches, &settings, json_output);
+        return;
     }
 
-    pub fn get_power_graph(&self, width: usize) -> String {
-        if self.power_history.len() < 2 {
-            return " ".repeat(width);
-        }
+    if let Some(query_matches) = matches.subcommand_matches("query") {
+        run_query(query_matches);
+        return;
+    }
 
-        let values: Vec<f64> = self.power_history.iter().map(|p| p.power_w).collect();
-        let min_val = values.iter().fold(f64::INFINITY, |a, &b| a.min(b));
-        let max_val = values.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-        let range = if (max_val - min_val).abs() < 0.1 { 0.1 } else { max_val - min_val };
-
-        let bars = ["▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
-        
-        values.iter()
-            .rev()
-            .take(width)
-            .map(|&val| {
-                let normalized = ((val - min_val) / range * (bars.len() - 1) as f64) as usize;
-                bars[normalized.min(bars.len() - 1)]
-            })
-            .collect::<Vec<_>>()
-            .into_iter()
-            .rev()
-            .collect()
+    if let Some(events_matches) = matches.subcommand_matches("events") {
+        run_events(events_matches, json_output);
+        return;
     }
 
-    pub fn format_time(&self, minutes: u32) -> String {
-        let hours = minutes / 60;
-        let mins = minutes % 60;
-        if hours > 0 {
-            format!("{}h {:02}m", hours, mins)
-        } else {
-            format!("{}m", mins)
-        }
+    if let Some(eval_matches) = matches.subcommand_matches("eval") {
+        eval::run_eval(eval_matches, json_output);
+        return;
     }
 
-    pub fn display_battery_info(&mut self, info: &BatteryInfo, elapsed_secs: u64) {
-        // Clear screen and move to top
-        print!("\x1b[2J\x1b[H");
-        
-        // Header
-        println!("\x1b[1;36m╔══════════════════════════════════════════════════════════════╗\x1b[0m");
-        println!("\x1b[1;36m║\x1b[0m \x1b[1;37m🔋 Batfi v2.0 - Advanced Battery Monitor\x1b[0m                \x1b[1;36m║\x1b[0m");
-        println!("\x1b[1;36m╚══════════════════════════════════════════════════════════════╝\x1b[0m");
-        println!();
+    match matches.subcommand() {
+        Some(("install-service", install_matches)) => return service::run_install(install_matches, &matches),
+        Some(("uninstall-service", uninstall_matches)) => return service::run_uninstall(uninstall_matches),
+        _ => {}
+    }
 
-        // Main battery display
-        let bar_width = 40;
-        let battery_bar = self.get_battery_bar(info.capacity_percent, bar_width);
-        let trend = self.get_trend_indicator();
-        
-        println!(" \x1b[1m{}%\x1b[0m [{}] {}", info.capacity_percent, battery_bar, trend);
-        println!(" Status: \x1b[1m{}\x1b[0m", match info.status.as_str() {
-            "Charging" => format!("\x1b[32m{} ⚡\x1b[0m", info.status),
-            "Discharging" => format!("\x1b[33m{} 🔋\x1b[0m", info.status),
-            "Full" => format!("\x1b[36m{} ✓\x1b[0m", info.status),
-            _ => format!("\x1b[37m{}\x1b[0m", info.status),
-        });
-
-        // Enhanced time display with real-time precision
-        if let Some(time) = info.time_remaining_minutes {
-            let time_str = self.format_time(time);
-            let (icon, status_text) = match info.status.as_str() {
-                "Charging" => {
-                    let charge_phase = if info.capacity_percent > 95 {
-                        " (trickle charge)"
-                    } else if info.capacity_percent > 80 {
-                        " (slowing down)"
-                    } else {
-                        " (fast charge)"
-                    };
-                    ("⚡", format!("to full{}", charge_phase))
-                },
-                "Discharging" => ("🔋", "remaining".to_string()),
-                _ => ("🔋", "remaining".to_string()),
-            };
-            
-            let accuracy = if self.rolling_power_window.len() >= ROLLING_WINDOW_SIZE {
-                "\x1b[32m●●●\x1b[0m" // Three green dots for ultra-high accuracy
-            } else if self.power_history.len() >= MIN_SAMPLES_FOR_ESTIMATE * 3 {
-                "\x1b[32m●●\x1b[0m" // Two green dots for high accuracy
-            } else if self.power_history.len() >= MIN_SAMPLES_FOR_ESTIMATE {
-                "\x1b[33m●\x1b[0m" // One yellow dot for basic accuracy
-            } else {
-                "\x1b[31m○\x1b[0m" // Red circle for low confidence
-            };
-            
-            println!(" Time:   \x1b[1m{} {} {}\x1b[0m {}", time_str, icon, status_text, accuracy);
-        } else {
-            let calculating_dots = match SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() % 4 {
-                0 => "   ",
-                1 => "●  ",
-                2 => "●● ",
-                _ => "●●●",
-            };
-            println!(" Time:   \x1b[2mCalculating{}\x1b[0m", calculating_dots);
-        }
+    if let Some(snooze_matches) = matches.subcommand_matches("snooze") {
+        run_snooze(snooze_matches);
+        return;
+    }
 
-        println!();
+    let batteries = match simulation {
+        Some(_) => vec![simulate::BATTERY_NAME.to_string()],
+        None => select_batteries(&settings),
+    };
+    let battery_name = batteries[0].as_str();
+    let machine_output = settings.format.is_some() || settings.fields.is_some();
+    if simulation.is_none() {
+        check_pack_change(data_dir().as_deref(), battery_name, json_output || machine_output);
+    } else if let Some(command @ ("health" | "report" | "summary" | "limit" | "sleep-hook" | "raw")) = matches.subcommand_name() {
+        eprintln!("❌ `batfi {}` reads the real battery and can't run with --simulate", command);
+        std::process::exit(1);
+    }
 
-        // Cat animation
-        let cat_animation = generate_pacman_cat_animation(elapsed_secs);
-        println!(" {}", cat_animation);
+    // Commands that only read sysfs attributes
+    match matches.subcommand() {
+        Some(("health", health_matches)) => return run_health(battery_name, json_output, health_matches),
+        Some(("report", report_matches)) => return run_report(battery_name, report_matches),
+        Some(("summary", summary_matches)) => return run_summary(battery_name, summary_matches, json_output),
+        Some(("limit", limit_matches)) => return run_limit(battery_name, limit_matches, json_output),
+        Some(("sleep-hook", hook_matches)) => return service::run_sleep_hook(battery_name, hook_matches),
+        Some(("raw", raw_matches)) => {
+            let device = raw_matches.get_one::<String>("device").map_or(battery_name, String::as_str);
+            return raw::run_raw(device, json_output);
+        }
+        _ => {}
+    }
 
-        println!();
+    let simulated = simulation.is_some();
+    let mut monitor = match simulation {
+        Some(backend) => {
+            // Nothing is saved, so every run replays from the same clean state
+            let mut monitor = BatteryMonitor::with_backend(battery_name, Box::new(backend));
+            monitor.set_data_dir(None);
+            monitor
+        }
+        None => BatteryMonitor::new(battery_name),
+    };
+    configure_monitor(&mut monitor, &settings);
+    // Simulations stay out of the real history; one-shot commands still benefit from it
+    if !simulated {
+        monitor.persist_history();
+    }
+    if matches.get_flag("explain") {
+        explain::run_explain(&monitor);
+        return;
+    }
+    let plasma_output = settings.format.as_deref() == Some("plasma");
+    // Script alerts need an engine even without notification channels, for the journal
+    let track_alerts = plasma_output || settings.script.is_some() || matches!(matches.subcommand_name(), Some("serve" | "daemon"));
+    if let Some(engine) = build_alert_engine(&settings.alerts, track_alerts, monitor.base_path()) {
+        monitor.set_alerts(engine);
+    }
+    if let Some(path) = &settings.script {
+        load_script(&mut monitor, path);
+    }
+    if let Some(addr) = &settings.statsd.address {
+        match statsd::StatsdEmitter::new(addr, &settings.statsd.prefix, settings.statsd.tags.clone()) {
+            Ok(emitter) => monitor.set_statsd(emitter),
+            Err(e) => {
+                eprintln!("❌ Invalid StatsD address {}: {}", addr, e);
+                std::process::exit(1);
+            }
+        }
+    }
 
-        // Enhanced power information with real-time analytics
-        println!(" \x1b[1mReal-Time Power Analytics:\x1b[0m");
-        if let Some(power) = info.power_w {
-            let power_color = if info.status == "Charging" { "\x1b[32m" } else { "\x1b[33m" };
-            println!(" ├─ Current:   {}{:.2}W\x1b[0m", power_color, power);
+    match matches.subcommand() {
+        Some(("serve", serve_matches)) => {
+            let addr = serve_matches.get_one::<String>("http").map(String::as_str);
+            if addr.is_some() && cfg!(not(feature = "http")) {
+                missing_feature("batfi serve --http", "http");
+            }
+            let dbus = serve_matches.get_flag("dbus").then(|| {
+                dbus::DbusPublisher::start().unwrap_or_else(|e| {
+                    eprintln!("❌ Could not register {} on the session bus: {}", dbus::BUS_NAME, e);
+                    std::process::exit(1);
+                })
+            });
+            let prometheus = serve_matches.get_one::<String>("prometheus").map(String::as_str);
+            if let Err(e) = server::serve(monitor, addr, prometheus, dbus, print_serve_status) {
+                eprintln!("❌ Could not start server: {}", e);
+                std::process::exit(1);
+            }
         }
-        if let Some(smoothed) = info.smoothed_power_w {
-            let rolling_avg = self.get_rolling_average_power().unwrap_or(smoothed);
-            println!(" ├─ Smoothed:  \x1b[1m{:.2}W\x1b[0m (trend: {})", 
-                smoothed, 
-                match info.power_trend.as_str() {
-                    "increasing" => "\x1b[31m↑\x1b[0m",
-                    "decreasing" => "\x1b[32m↓\x1b[0m",
-                    _ => "\x1b[37m→\x1b[0m",
-                }
-            );
-            if self.rolling_power_window.len() >= 3 {
-                println!(" ├─ Rolling:   \x1b[1m{:.2}W\x1b[0m ({}s avg)", 
-                    rolling_avg, 
-                    self.rolling_power_window.len() * UPDATE_INTERVAL_SECS as usize
-                );
+        Some(("daemon", _)) => {
+            if let Err(e) = server::serve(monitor, None, None, None, print_serve_status) {
+                eprintln!("❌ Could not start daemon: {}", e);
+                std::process::exit(1);
             }
         }
-        if let Some(voltage) = info.voltage_v {
-            println!(" ├─ Voltage:   \x1b[1m{:.2}V\x1b[0m", voltage);
+        Some(("rpc", _)) => run_rpc(monitor),
+        Some(("doctor", _)) => doctor::run_doctor(&mut monitor),
+        Some(("advise", _)) => advise::run_advise(&mut monitor, json_output),
+        Some(("bench-self", bench_matches)) => run_bench_self(&mut monitor, bench_matches, json_output),
+        Some(("log", log_matches)) => run_log(monitor, log_matches, json_output, settings.fields.clone()),
+        Some(("menu", menu_matches)) => {
+            menu::run_menu(monitor, menu_matches.get_one::<String>("selection").map(String::as_str));
         }
-        if let Some(current) = info.current_ma {
-            let current_str = if current >= 0 {
-                format!("\x1b[32m+{} mA\x1b[0m", current)
-            } else {
-                format!("\x1b[31m{} mA\x1b[0m", current)
-            };
-            println!(" └─ Current:   {}", current_str);
+        Some(("tray", _)) => {
+            if let Err(e) = tray::run_tray(monitor) {
+                eprintln!("❌ Could not create tray icon (is a StatusNotifierItem host running?): {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(("fleet", fleet_matches)) => {
+            let addr = fleet_matches.get_one::<String>("push").unwrap();
+            let host = fleet_matches.get_one::<String>("name").cloned().unwrap_or_else(fleet::hostname);
+            fleet::run_push(monitor, battery_name, addr, &host);
+        }
+        Some(("status", _)) if batteries.len() > 1 => {
+            let mut monitors = multi::MultiBatteryMonitor::new(&batteries);
+            if json_output {
+                println!("{}", multi_battery_json(&mut monitors, settings.fields.as_deref()));
+            } else if let Some(combined) = monitors.sample() {
+                print_status_summary(&combined.as_info(), &t!("source-combined"));
+                for pack in &combined.batteries {
+                    print_status_summary(&pack.info, &format!("{}, {}", config::display_name(&pack.battery), t!("source-local")));
+                }
+            }
+        }
+        Some(("status", _)) => {
+            // Local fallback: take a sample (or a --samples snapshot) ourselves
+            match one_shot_info(&mut monitor, &matches) {
+                Some(info) if json_output => println!("{}", fields::to_json(&info, settings.fields.as_deref())),
+                Some(info) => print_status_summary(&info, &t!("source-local")),
+                None => {
+                    eprintln!("❌ Could not read battery information");
+                    std::process::exit(1);
+                }
+            }
         }
+        // `watch`, or no subcommand at all (`batfi`, `batfi --once`, `batfi --json`)
+        _ if batteries.len() > 1 && (json_output || plasma_output) => run_multi_watch(&batteries, &settings, matches.get_flag("once")),
+        _ => run_watch(monitor, battery_name, &matches, &settings),
+    }
+}
 
-        println!();
+/// How often `batfi events --follow` checks the journal for new lines
+const FOLLOW_POLL_SECS: u64 = 1;
 
-        // Energy information
-        println!(" \x1b[1mEnergy Details:\x1b[0m");
-        if let (Some(now), Some(full)) = (info.energy_now_wh, info.energy_full_wh) {
-            println!(" ├─ Current:   \x1b[1m{:.1} Wh\x1b[0m", now);
-            println!(" └─ Full:      \x1b[1m{:.1} Wh\x1b[0m", full);
-        }
+fn event_json(event: &Event) -> serde_json::Value {
+    let mut value = serde_json::to_value(event).unwrap_or_default();
+    value["timestamp"] = timefmt::timestamp_json(event.timestamp);
+    value
+}
 
-        println!();
+fn event_row(event: &Event) -> String {
+    format!(" {:<19} {:<7} {}{:<16}\x1b[0m {}",
+        timefmt::date_time(event.timestamp),
+        event.battery,
+        kind_color(&event.kind),
+        event.kind,
+        event.message)
+}
 
-        // Real-time temperature monitoring (2s updates, raw values only)
-        let mut has_temp = false;
-        println!(" \x1b[1mReal-Time Temperature (2s updates):\x1b[0m");
-        
-        // Battery temperature - raw values only
-        if let Some(battery_reading) = self.temperature_monitor.last_battery_temp.as_ref() {
-            let temp_c = battery_reading.raw_value;
-            let temp_f = celsius_to_fahrenheit(temp_c);
-            let temp_color = match temp_c as u32 {
-                0..=35 => "\x1b[36m",   // Cyan (cool)
-                36..=45 => "\x1b[32m",  // Green (normal) 
-                46..=55 => "\x1b[33m",  // Yellow (warm)
-                _ => "\x1b[31m",        // Red (hot)
-            };
-            println!(" ├─ Battery:   {}{:.1}°C ({:.1}°F)\x1b[0m [{}]", 
-                temp_color, temp_c, temp_f, battery_reading.sensor_info.sensor_type);
-            has_temp = true;
-        } else {
-            println!(" ├─ Battery:   \x1b[2m—\x1b[0m (no sensor found)");
+/// New complete lines of the journal past `offset`; starts over if the file was truncated
+fn read_new_events(offset: &mut u64) -> Vec<Event> {
+    let Some(mut file) = events_log_path(data_dir().as_deref()).and_then(|path| fs::File::open(path).ok()) else {
+        return Vec::new();
+    };
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    if len < *offset {
+        *offset = 0;
+    }
+    if file.seek(SeekFrom::Start(*offset)).is_err() {
+        return Vec::new();
+    }
+    let mut events = Vec::new();
+    let mut reader = BufReader::new(&mut file);
+    let mut line = String::new();
+    while let Ok(n) = reader.read_line(&mut line) {
+        // A line without its newline is still being written; pick it up next time
+        if n == 0 || !line.ends_with('\n') {
+            break;
         }
-        
-        // CPU temperature - raw values only with Fahrenheit
-        if let Some(cpu_reading) = self.temperature_monitor.last_cpu_temp.as_ref() {
-            let temp_c = cpu_reading.raw_value;
-            let temp_f = celsius_to_fahrenheit(temp_c);
-            let temp_color = match temp_c as u32 {
-                0..=45 => "\x1b[36m",   // Cyan (cool)
-                46..=60 => "\x1b[32m",  // Green (normal)
-                61..=75 => "\x1b[33m",  // Yellow (warm)
-                76..=85 => "\x1b[31m",  // Red (hot)
-                _ => "\x1b[41m\x1b[37m", // Red background (critical)
-            };
-            println!(" └─ CPU:       {}{:.1}°C ({:.1}°F)\x1b[0m [{}]", 
-                temp_color, temp_c, temp_f, cpu_reading.sensor_info.sensor_type);
-            has_temp = true;
-        } else {
-            println!(" └─ CPU:       \x1b[2m—\x1b[0m (no sensor found)");
+        *offset += n as u64;
+        events.extend(serde_json::from_str::<Event>(&line).ok());
+        line.clear();
+    }
+    events
+}
+
+/// Events after `since` from a running server, skipping `seen` already printed at that second
+fn fetch_remote_events(remote: &Remote, since: u64, seen: usize) -> Vec<Event> {
+    match client::fetch_json::<Vec<Event>>(remote, &format!("/v1/events?since=@{}", since)) {
+        Ok(events) => {
+            let mut skip = seen;
+            events
+                .into_iter()
+                .filter(|event| {
+                    if event.timestamp == since && skip > 0 {
+                        skip -= 1;
+                        return false;
+                    }
+                    true
+                })
+                .collect()
         }
-        
-        if !has_temp {
-            println!(" └─ No valid temperature sensors found (range: {:.0}-{:.0}°C)", MIN_VALID_TEMP, MAX_VALID_TEMP);
+        Err(e) => {
+            eprintln!("⚠️  Could not fetch events: {}", e);
+            Vec::new()
         }
+    }
+}
 
-        println!();
+/// `--follow`: print matching events as they are journaled, one line each
+fn follow(remote: Option<Remote>, since: u64, matches: impl Fn(&Event) -> bool, json_output: bool) -> ! {
+    let print = |event: &Event| {
+        if matches(event) {
+            if json_output {
+                println!("{}", event_json(event));
+            } else {
+                println!("{}", event_row(event));
+            }
+            let _ = std::io::stdout().flush();
+        }
+    };
 
-        // Power consumption graph
-        if self.power_history.len() > 1 {
-            println!(" \x1b[1mPower History (last {} samples):\x1b[0m", self.power_history.len());
-            let graph = self.get_power_graph(60);
-            println!(" {}", graph);
-            println!();
+    match remote {
+        Some(remote) => {
+            let (mut last, mut seen) = (since, 0);
+            loop {
+                for event in fetch_remote_events(&remote, last, seen) {
+                    if event.timestamp == last {
+                        seen += 1;
+                    } else {
+                        (last, seen) = (event.timestamp, 1);
+                    }
+                    print(&event);
+                }
+                thread::sleep(Duration::from_secs(FOLLOW_POLL_SECS));
+            }
+        }
+        None => {
+            let mut offset = 0;
+            for event in read_new_events(&mut offset).iter().filter(|event| event.timestamp >= since) {
+                print(event);
+            }
+            loop {
+                thread::sleep(Duration::from_secs(FOLLOW_POLL_SECS));
+                for event in read_new_events(&mut offset) {
+                    print(&event);
+                }
+            }
         }
+    }
+}
 
-        // Enhanced footer with real-time stats
-        let samples = self.power_history.len();
-        let rolling_samples = self.rolling_power_window.len();
-        let accuracy_text = if rolling_samples >= ROLLING_WINDOW_SIZE {
-            format!("\x1b[32mUltra-high accuracy\x1b[0m ({} samples, {}s rolling)", samples, rolling_samples * UPDATE_INTERVAL_SECS as usize)
-        } else if samples >= MIN_SAMPLES_FOR_ESTIMATE * 3 {
-            format!("\x1b[32mHigh accuracy\x1b[0m ({} samples)", samples)
-        } else if samples >= MIN_SAMPLES_FOR_ESTIMATE {
-            format!("\x1b[33mMedium accuracy\x1b[0m ({} samples)", samples)
-        } else {
-            format!("\x1b[31mBuilding accuracy\x1b[0m ({}/{} samples)", samples, MIN_SAMPLES_FOR_ESTIMATE)
-        };
-        
-        let elapsed = if self.last_update > 0 {
-            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-            format!("{}s ago", now - self.last_update)
-        } else {
-            "starting".to_string()
+/// `batfi events`: query the journal by age and kind, or follow it live
+pub fn run_events(matches: &clap::ArgMatches, json_output: bool) {
+    let since_text = matches.get_one::<String>("since").map(String::as_str).unwrap_or("24h");
+    let Some(since_secs) = parse_duration_secs(since_text) else {
+        eprintln!("❌ Invalid duration '{}': use e.g. 90s, 30m, 2h or 7d", since_text);
+        std::process::exit(1);
+    };
+    let kinds: Vec<&String> = matches.get_many::<String>("kind").map(|k| k.collect()).unwrap_or_default();
+    let wanted = |event: &Event| kinds.is_empty() || kinds.contains(&&event.kind);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let since = now.saturating_sub(since_secs);
+
+    if matches.get_flag("follow") {
+        let remote = match matches.get_one::<String>("remote") {
+            Some(addr) if addr.is_empty() => Some(Remote::Socket),
+            Some(addr) => Some(Remote::Http(addr.clone())),
+            None => None,
         };
-        
-        println!(" {} • \x1b[2mLast update: {} • Press Ctrl+C to exit • Real-time {}s updates\x1b[0m", 
-            accuracy_text, elapsed, UPDATE_INTERVAL_SECS);
-        
-        io::stdout().flush().unwrap();
+        follow(remote, since, wanted, json_output);
     }
 
-    pub fn to_json(&self, info: &BatteryInfo) -> String {
-        serde_json::to_string_pretty(info).unwrap_or_else(|_| "{}".to_string())
+    let events: Vec<Event> = load_events(data_dir().as_deref(), since).into_iter().filter(|event| wanted(event)).collect();
+    if json_output {
+        let entries: Vec<serde_json::Value> = events.iter().map(event_json).collect();
+        println!("{}", serde_json::to_string_pretty(&entries).unwrap_or_else(|_| "[]".to_string()));
+        return;
     }
-}
-
-pub fn find_batteries() -> Vec<String> {
-    let power_supply_path = Path::new("/sys/class/power_supply");
-    if !power_supply_path.exists() {
-        return vec![];
+    println!(" \x1b[1m{:<19} {:<7} {:<16} MESSAGE\x1b[0m", "TIME", "BATTERY", "KIND");
+    for event in &events {
+        println!("{}", event_row(event));
+    }
+    if events.is_empty() {
+        println!(" \x1b[2mNo events in the last {}\x1b[0m", since_text);
     }
-
-    fs::read_dir(power_supply_path)
-        .unwrap_or_else(|_| fs::read_dir(".").unwrap())
-        .filter_map(|entry| entry.ok())
-        .filter_map(|entry| {
-            let name = entry.file_name().into_string().ok()?;
-            if name.starts_with("BAT") || name.starts_with("battery") {
-                Some(name)
-            } else {
-                None
-            }
-        })
-        .collect()
 }
 
-fn main() {
-    let matches = Command::new("batfi")
-        .version("2.0.0")
-        .author("Your Name <your.email@example.com>")
-        .about("Advanced battery monitoring tool with accurate time estimation")
-        .arg(
-            Arg::new("json")
-                .long("json")
-                .short('j')
-                .help("Output in JSON format")
-                .action(clap::ArgAction::SetTrue),
-        )
-        .arg(
-            Arg::new("once")
-                .long("once")
-                .short('o')
-                .help("Run once and exit")
-                .action(clap::ArgAction::SetTrue),
-        )
-        .arg(
-            Arg::new("battery")
-                .long("battery")
-                .short('b')
-                .value_name("NAME")
-                .help("Specify battery name (e.g., BAT0, BAT1)")
-                .action(clap::ArgAction::Set),
-        )
-        .get_matches();
+/// `batfi summary --period day|week`: usage totals from the persisted samples
+pub fn run_summary(battery: &str, matches: &clap::ArgMatches, json_output: bool) {
+    let period = matches.get_one::<String>("period").map(String::as_str).unwrap_or("day");
+    let span_secs = if period == "week" { 7 * 86_400 } else { 86_400 };
+    let since = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs().saturating_sub(span_secs);
+    let summary = summarize(battery, period, since, &load_usage(data_dir().as_deref(), battery, since));
 
-    // Find available batteries
-    let batteries = find_batteries();
-    if batteries.is_empty() {
-        eprintln!("❌ No batteries found in /sys/class/power_supply/");
-        eprintln!("   Make sure you're running this on a laptop with battery support.");
-        std::process::exit(1);
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&summary).unwrap_or_else(|_| "{}".to_string()));
+        return;
     }
 
-    // Select battery
-    let battery_name = if let Some(name) = matches.get_one::<String>("battery") {
-        if batteries.contains(name) {
-            name
-        } else {
-            eprintln!("❌ Battery '{}' not found. Available batteries: {}", name, batteries.join(", "));
-            std::process::exit(1);
+    let title = if period == "week" { "Last 7 days" } else { "Last 24 hours" };
+    println!("\x1b[1;36m╔══════════════════════════════════════════════════════════════╗\x1b[0m");
+    println!("\x1b[1;36m║\x1b[0m \x1b[1;37m📅 Batfi Summary - {} ({})\x1b[0m", battery, title);
+    println!("\x1b[1;36m╚══════════════════════════════════════════════════════════════╝\x1b[0m");
+    println!();
+    if summary.samples < 2 {
+        println!(" \x1b[2mNot enough samples yet — batfi records usage while `batfi daemon` or the dashboard runs\x1b[0m");
+        return;
+    }
+
+    let dash = || "\x1b[2m—\x1b[0m".to_string();
+    println!(" \x1b[1mUsage:\x1b[0m");
+    println!(" ├─ On battery:   \x1b[1m{}\x1b[0m (screen-on estimate)", format_minutes(summary.on_battery_minutes as u32));
+    println!(" ├─ Energy used:  \x1b[1m{:.1} Wh\x1b[0m", summary.energy_used_wh);
+    println!(" ├─ Cycles used:  \x1b[1m{:.2}\x1b[0m (sum of depth of discharge)", summary.cycles_consumed);
+    println!(" ├─ Avg power:    {}", summary.average_power_w.map(|p| format!("\x1b[1m{:.1} W\x1b[0m", p)).unwrap_or_else(dash));
+    let spread = summary
+        .power_percentiles
+        .map(|p| format!("p50 \x1b[1m{:.1} W\x1b[0m · p90 \x1b[1m{:.1} W\x1b[0m · p99 \x1b[1m{:.1} W\x1b[0m", p.p50, p.p90, p.p99));
+    println!(" └─ Power spread: {}", spread.unwrap_or_else(dash));
+    println!();
+    println!(" \x1b[1mBattery temperature:\x1b[0m");
+    match (summary.temperature_min_c, summary.temperature_avg_c, summary.temperature_max_c) {
+        (Some(min), Some(avg), Some(max)) => {
+            println!(" ├─ Min:          {:.1}°C", min);
+            println!(" ├─ Average:      {:.1}°C", avg);
+            println!(" └─ Max:          {:.1}°C", max);
         }
-    } else {
-        &batteries[0] // Use first battery found
-    };
+        _ => println!(" └─ {}", dash()),
+    }
+    println!();
+    println!(" \x1b[2m{} samples since {}\x1b[0m", summary.samples, crate::timefmt::date_time(summary.since));
+}
 
-    let mut monitor = BatteryMonitor::new(battery_name);
-    let json_output = matches.get_flag("json");
-    let run_once = matches.get_flag("once");
+/// `batfi bench-self`: what one sample costs on this machine
+pub fn run_bench_self(monitor: &mut BatteryMonitor, bench_matches: &clap::ArgMatches, json_output: bool) {
+    let ticks = bench_matches.get_one::<u32>("ticks").copied().unwrap_or(DEFAULT_BENCH_TICKS);
+    let cost = measure_ticks(monitor, ticks);
 
-    if !json_output && !run_once {
-        println!("🔋 Starting Batfi v2.0...");
-    println!("   Found battery: {}", battery_name);
-        println!("   Will run for {} seconds with {}s updates", PROGRAM_DURATION_SECS, UPDATE_INTERVAL_SECS);
-        println!("   🐱 Watch the cat eat {} dots!", TOTAL_DOTS);
-        println!("   Pac-Cat Progress: {}", "●".repeat(TOTAL_DOTS));
-        thread::sleep(Duration::from_millis(1000));
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&cost).unwrap_or_else(|_| "{}".to_string()));
+        return;
     }
+    let dash = || "\x1b[2m—\x1b[0m".to_string();
+    println!("⏱️  {} ticks on {}", cost.ticks, cost.battery);
+    println!(" ├─ CPU per tick:  {}", cost.cpu_per_tick_us.map(|us| format!("\x1b[1m{:.0} µs\x1b[0m", us)).unwrap_or_else(dash));
+    println!(" ├─ Wall per tick: {:.0} µs median ({:.0}–{:.0})", cost.wall_median_us, cost.wall_min_us, cost.wall_max_us);
+    let hourly = Footprint::hourly(&cost);
+    println!(
+        " └─ At {}s updates: {}",
+        cost.interval_secs,
+        hourly
+            .map(|h| format!("\x1b[1m{:.3}%\x1b[0m of one core, ~{:.2} mWh per hour", h.cpu_percent, h.mwh_per_hour))
+            .unwrap_or_else(dash)
+    );
+}
 
-    // Record start time for auto-stop
-    let start_time = SystemTime::now();
-    let mut update_count = 0;
+fn send(message: &serde_json::Value) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "{}", message)?;
+    stdout.flush()
+}
 
-    // Main monitoring loop with auto-stop
-    loop {
-        match monitor.get_battery_info() {
-            Some(info) => {
-                if json_output {
-                    println!("{}", monitor.to_json(&info));
-                } else {
-                    update_count += 1;
-                    let elapsed = start_time.elapsed().unwrap().as_secs();
-                    
-                    // Show Pac-Man cat animation and countdown
-                    let animation = generate_pacman_cat_animation(elapsed);
-                    let countdown = generate_countdown_dots(elapsed);
-                    println!("🔋 Update #{} ({}s elapsed)", update_count, elapsed);
-                    println!("🐱 Pac-Cat: {}", animation);
-                    println!("⏰ Countdown: {}", countdown);
-                    println!();
-                    
-                monitor.display_battery_info(&info, elapsed);
-                }
-            }
-            None => {
-                if json_output {
-                    eprintln!("{{\"error\": \"Could not read battery information\"}}");
-                } else {
-                println!("❌ Could not read battery information");
-                println!("   Make sure {} exists and is readable", monitor.base_path);
-                }
-                std::process::exit(1);
+/// Serve JSON-RPC on stdin/stdout until stdin closes or the parent stops reading
+pub fn run_rpc(monitor: BatteryMonitor) {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if tx.send(line).is_err() {
+                break;
             }
         }
+    });
 
-        if run_once {
-            break;
-        }
-
-        // Check if we should stop (20 seconds elapsed)
-        let elapsed = start_time.elapsed().unwrap().as_secs();
-        if elapsed >= PROGRAM_DURATION_SECS {
-            println!("⏰ Program completed after {} seconds", elapsed);
-            println!("\nPress Enter to exit...");
-            // Run the curl command to get ASCII art immediately
-            use std::io;
-            use std::process::Command;
-            
-            let mut input = String::new();
-            io::stdin().read_line(&mut input).unwrap();
-            
-            let status = Command::new("curl")
-                .arg("ascii.live/rick")
-                .status()
-                .expect("Failed to run curl");
-            
-            if !status.success() {
-                eprintln!("Error: curl command failed.");
+    let mut session = rpc::Session::new(monitor);
+    let mut next_tick = Instant::now();
+    loop {
+        let message = match rx.recv_timeout(next_tick.saturating_duration_since(Instant::now())) {
+            Ok(line) if line.trim().is_empty() => continue,
+            Ok(line) => session.handle_line(&line),
+            Err(RecvTimeoutError::Timeout) => {
+                next_tick = Instant::now() + session.update_interval();
+                session.tick()
+            }
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        if let Some(message) = message {
+            if send(&message).is_err() {
+                return;
             }
-            
         }
-
-        // Wait before next update
-        thread::sleep(Duration::from_secs(UPDATE_INTERVAL_SECS));
     }
 }
 
-
-
+#[cfg(all(test, feature = "tui"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn countdown_and_pac_cat_pace_to_the_duration() {
+        assert_eq!(generate_countdown_dots(0, 600).matches('●').count(), TOTAL_DOTS);
+        assert_eq!(generate_countdown_dots(300, 600).matches('●').count(), TOTAL_DOTS / 2);
+        assert_eq!(generate_countdown_dots(600, 600).matches('●').count(), 0);
+        assert_eq!(generate_pacman_cat_animation(600, Some(600)), "All dots eaten!");
+        // Without --duration the cat starts over instead of stopping at an empty row
+        assert_eq!(generate_pacman_cat_animation(TOTAL_DOTS as u64 + 1, None).matches('●').count(), TOTAL_DOTS);
+    }
+}
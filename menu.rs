@@ -0,0 +1,125 @@
+use std::fs;
+use std::process::Command;
+
+use crate::charge_limit::{self, DEFAULT_CHARGE_LIMIT};
+use crate::client::{self, Remote};
+use crate::{events, format_minutes, health, health_export, BatteryInfo, BatteryMonitor};
+
+/// Actions offered below the stats; the printed label is what rofi hands back
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MenuAction {
+    LimitCharge,
+    RemoveLimit,
+    StartCalibration,
+    OpenReport,
+}
+
+impl MenuAction {
+    fn label(&self) -> String {
+        match self {
+            MenuAction::LimitCharge => format!("🔒 Limit charging to {}%", DEFAULT_CHARGE_LIMIT),
+            MenuAction::RemoveLimit => "🔓 Charge to 100%".to_string(),
+            MenuAction::StartCalibration => "🎯 Start calibration".to_string(),
+            MenuAction::OpenReport => "📄 Open health report".to_string(),
+        }
+    }
+}
+
+fn available_actions(base_path: &str) -> Vec<MenuAction> {
+    let mut actions = Vec::new();
+    match charge_limit::read_limit(base_path) {
+        Some(limit) if limit < 100 => actions.push(MenuAction::RemoveLimit),
+        Some(_) => actions.push(MenuAction::LimitCharge),
+        None => {}
+    }
+    actions.push(MenuAction::StartCalibration);
+    actions.push(MenuAction::OpenReport);
+    actions
+}
+
+/// Prefer the daemon's smoothed reading; fall back to a single local sample
+fn current_info(monitor: &mut BatteryMonitor) -> Option<BatteryInfo> {
+    if client::daemon_available() {
+        if let Ok(info) = client::fetch_json::<BatteryInfo>(&Remote::Socket, "/v1/battery") {
+            return Some(info);
+        }
+    }
+    monitor.get_battery_info()
+}
+
+fn stat_lines(info: &BatteryInfo) -> Vec<String> {
+    let mut lines = vec![match info.time_remaining_minutes {
+        Some(minutes) => format!("🔋 {}% · {} · {} left", info.capacity_percent, info.status, format_minutes(minutes)),
+        None => format!("🔋 {}% · {}", info.capacity_percent, info.status),
+    }];
+    if let Some(power) = info.smoothed_power_w.or(info.power_w) {
+        lines.push(format!("⚡ {:.1}W", power));
+    }
+    if let Some(temp) = info.temperature_c {
+        lines.push(format!("🌡️ {:.1}°C", temp));
+    }
+    lines.push(format!("❤️ Health {:.0}%", info.health_percent));
+    lines
+}
+
+fn set_limit(monitor: &BatteryMonitor, percent: u8) {
+    match charge_limit::set_limit(monitor.base_path(), percent) {
+        Ok(()) => println!("✅ Charge limit set to {}%", percent),
+        Err(e) => {
+            eprintln!("❌ Could not set charge limit: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn start_calibration(monitor: &BatteryMonitor) {
+    // A calibration cycle needs a true full charge
+    if charge_limit::read_limit(monitor.base_path()).is_some_and(|limit| limit < 100) {
+        set_limit(monitor, 100);
+    }
+    let _ = events::log_event(monitor.data_dir(), monitor.battery_name(), "calibration", "calibration cycle started");
+    println!("🎯 Calibration started: charge to 100%, then discharge to ~5% in one go");
+}
+
+fn open_report(monitor: &BatteryMonitor) {
+    let report = monitor.read_health();
+    let history = health::load_health_history(monitor.data_dir(), monitor.battery_name());
+    let Some(dir) = monitor.data_dir() else {
+        eprintln!("❌ Could not determine the data directory (HOME unset)");
+        std::process::exit(1);
+    };
+    let path = dir.join("health-report.html");
+    if let Err(e) = fs::create_dir_all(dir).and_then(|_| fs::write(&path, health_export::render_html(&report, &history))) {
+        eprintln!("❌ Could not write {}: {}", path.display(), e);
+        std::process::exit(1);
+    }
+    if let Err(e) = Command::new("xdg-open").arg(&path).spawn() {
+        eprintln!("⚠️  Could not open {}: {}", path.display(), e);
+    }
+}
+
+/// `batfi menu`: list stats and actions for rofi/wofi, or run the chosen line
+pub fn run_menu(mut monitor: BatteryMonitor, selection: Option<&str>) {
+    let actions = available_actions(monitor.base_path());
+
+    let Some(selection) = selection else {
+        if let Some(info) = current_info(&mut monitor) {
+            for line in stat_lines(&info) {
+                println!("{}", line);
+            }
+        }
+        for action in &actions {
+            println!("{}", action.label());
+        }
+        return;
+    };
+
+    // Stat lines are informational; selecting one does nothing
+    match actions.iter().find(|action| action.label() == selection.trim()) {
+        Some(MenuAction::LimitCharge) => set_limit(&monitor, DEFAULT_CHARGE_LIMIT),
+        Some(MenuAction::RemoveLimit) => set_limit(&monitor, 100),
+        Some(MenuAction::StartCalibration) => start_calibration(&monitor),
+        Some(MenuAction::OpenReport) => open_report(&monitor),
+        None => {}
+    }
+}
@@ -0,0 +1,181 @@
+use serde::Serialize;
+
+use crate::estimation::{self, charging_efficiency};
+use crate::{BatteryInfo, BatteryMonitor};
+
+/// One pack's place in the combined estimate
+#[derive(Debug, Clone, Serialize)]
+pub struct PackShare {
+    pub battery: String,
+    /// 1 for the pack draining now; the others follow once it is empty
+    pub drain_order: usize,
+    /// Minutes until this pack is empty at the combined draw, counting the packs before it
+    pub empties_in_minutes: Option<u32>,
+    #[serde(skip)]
+    pub info: BatteryInfo,
+}
+
+/// Several packs seen as one battery (ThinkPad-style BAT0 + BAT1)
+#[derive(Debug, Clone, Serialize)]
+pub struct CombinedInfo {
+    pub status: String,
+    pub capacity_percent: u8,
+    pub energy_now_wh: Option<f64>,
+    pub energy_full_wh: Option<f64>,
+    pub power_w: Option<f64>,
+    pub smoothed_power_w: Option<f64>,
+    pub time_remaining_minutes: Option<u32>,
+    pub ac_online: Option<bool>,
+    pub batteries: Vec<PackShare>,
+}
+
+impl CombinedInfo {
+    /// The totals as a BatteryInfo, for the one-line summaries
+    pub fn as_info(&self) -> BatteryInfo {
+        BatteryInfo {
+            status: self.status.clone(),
+            capacity_percent: self.capacity_percent,
+            energy_now_wh: self.energy_now_wh,
+            energy_full_wh: self.energy_full_wh,
+            power_w: self.power_w,
+            smoothed_power_w: self.smoothed_power_w,
+            time_remaining_minutes: self.time_remaining_minutes,
+            ac_online: self.ac_online,
+            ..Default::default()
+        }
+    }
+}
+
+/// Watches every pack and sums energy and power into one estimate.
+///
+/// Firmware drains one pack at a time: the packs discharging now go first, the
+/// others after them in name order, so the per-pack "empties in" accumulates.
+pub struct MultiBatteryMonitor {
+    monitors: Vec<BatteryMonitor>,
+}
+
+impl MultiBatteryMonitor {
+    pub fn new(batteries: &[String]) -> Self {
+        Self::with_monitors(batteries.iter().map(|name| BatteryMonitor::new(name)).collect())
+    }
+
+    pub fn with_monitors(monitors: Vec<BatteryMonitor>) -> Self {
+        Self { monitors }
+    }
+
+    pub fn monitors(&self) -> &[BatteryMonitor] {
+        &self.monitors
+    }
+
+    /// Sample every pack; None when none of them could be read
+    pub fn sample(&mut self) -> Option<CombinedInfo> {
+        let packs: Vec<(String, BatteryInfo)> = self
+            .monitors
+            .iter_mut()
+            .filter_map(|monitor| Some((monitor.battery_name().to_string(), monitor.get_battery_info()?)))
+            .collect();
+        (!packs.is_empty()).then(|| combine(packs))
+    }
+}
+
+fn sum(values: impl Iterator<Item = Option<f64>>) -> Option<f64> {
+    values.flatten().fold(None, |total, value| Some(total.unwrap_or(0.0) + value))
+}
+
+/// Fold per-pack readings into the combined estimate
+pub fn combine(mut packs: Vec<(String, BatteryInfo)>) -> CombinedInfo {
+    let any = |status: &str| packs.iter().any(|(_, info)| info.status == status);
+    let status = if any("Discharging") {
+        "Discharging"
+    } else if any("Charging") {
+        "Charging"
+    } else {
+        packs[0].1.status.as_str()
+    }
+    .to_string();
+
+    let active = |info: &BatteryInfo| info.status == status;
+    let energy_now_wh = sum(packs.iter().map(|(_, info)| info.energy_now_wh));
+    let energy_full_wh = sum(packs.iter().map(|(_, info)| info.energy_full_wh));
+    let power_w = sum(packs.iter().filter(|(_, info)| active(info)).map(|(_, info)| info.power_w.map(f64::abs)));
+    let smoothed_power_w = sum(packs.iter().filter(|(_, info)| active(info)).map(|(_, info)| info.smoothed_power_w.or(info.power_w).map(f64::abs)));
+    let capacity_percent = match (energy_now_wh, energy_full_wh) {
+        (Some(now), Some(full)) if full > 0.0 => (now / full * 100.0).round().min(100.0) as u8,
+        _ => (packs.iter().map(|(_, info)| info.capacity_percent as u32).sum::<u32>() / packs.len() as u32) as u8,
+    };
+
+    let time_remaining_minutes = match (status.as_str(), energy_now_wh, energy_full_wh, smoothed_power_w) {
+        ("Discharging", Some(now), _, Some(power)) => estimation::runtime_at(now, power),
+        ("Charging", Some(now), Some(full), Some(power)) => {
+            estimation::runtime_at(full - now, power * charging_efficiency(now / full))
+        }
+        _ => None,
+    };
+
+    // Every pack sees the same adapters
+    let ac_online = packs.iter().find_map(|(_, info)| info.ac_online);
+
+    // Draining packs first (hardest-working first), then the rest by name
+    packs.sort_by(|(a_name, a), (b_name, b)| {
+        let draining = |info: &BatteryInfo| info.status == "Discharging";
+        draining(b)
+            .cmp(&draining(a))
+            .then(b.power_w.unwrap_or(0.0).abs().total_cmp(&a.power_w.unwrap_or(0.0).abs()))
+            .then(a_name.cmp(b_name))
+    });
+    let mut drained_wh = 0.0;
+    let batteries = packs
+        .into_iter()
+        .enumerate()
+        .map(|(i, (battery, info))| {
+            drained_wh += info.energy_now_wh.unwrap_or(0.0);
+            let empties_in_minutes = match (status.as_str(), smoothed_power_w) {
+                ("Discharging", Some(power)) => estimation::runtime_at(drained_wh, power),
+                _ => None,
+            };
+            PackShare { battery, drain_order: i + 1, empties_in_minutes, info }
+        })
+        .collect();
+
+    CombinedInfo {
+        status,
+        capacity_percent,
+        energy_now_wh,
+        energy_full_wh,
+        power_w,
+        smoothed_power_w,
+        time_remaining_minutes,
+        ac_online,
+        batteries,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pack(status: &str, energy_now: f64, energy_full: f64, power: f64) -> BatteryInfo {
+        BatteryInfo {
+            status: status.to_string(),
+            energy_now_wh: Some(energy_now),
+            energy_full_wh: Some(energy_full),
+            power_w: Some(power),
+            smoothed_power_w: Some(power),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sums_packs_and_drains_the_active_one_first() {
+        let combined = combine(vec![
+            ("BAT0".to_string(), pack("Unknown", 40.0, 50.0, 0.0)),
+            ("BAT1".to_string(), pack("Discharging", 10.0, 20.0, 10.0)),
+        ]);
+        assert_eq!(combined.status, "Discharging");
+        assert_eq!(combined.capacity_percent, 71);
+        // 50 Wh left at 10 W
+        assert_eq!(combined.time_remaining_minutes, Some(300));
+        let order: Vec<(&str, Option<u32>)> = combined.batteries.iter().map(|p| (p.battery.as_str(), p.empties_in_minutes)).collect();
+        assert_eq!(order, [("BAT1", Some(60)), ("BAT0", Some(300))]);
+    }
+}
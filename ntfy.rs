@@ -0,0 +1,55 @@
+use std::thread;
+
+use crate::alerts::{Alert, AlertChannel, Severity};
+use crate::config;
+use crate::webhook::post_with_retry;
+
+/// Publishes alerts to an ntfy topic so they arrive as phone push notifications
+pub struct NtfyChannel {
+    url: String,
+    host: String,
+}
+
+impl NtfyChannel {
+    pub fn new(server: &str, topic: &str, host: &str) -> Self {
+        Self {
+            url: format!("{}/{}", server.trim_end_matches('/'), topic.trim_matches('/')),
+            host: host.to_string(),
+        }
+    }
+}
+
+/// ntfy priorities run 1 (min) to 5 (urgent); critical alerts bypass Do Not Disturb
+fn priority(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Info => "3",
+        Severity::Warning => "4",
+        Severity::Critical => "5",
+    }
+}
+
+/// Emoji shortcodes ntfy renders in front of the title
+fn tags(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Info => "battery",
+        Severity::Warning => "warning,battery",
+        Severity::Critical => "rotating_light,battery",
+    }
+}
+
+impl AlertChannel for NtfyChannel {
+    fn send(&self, alert: &Alert) {
+        let url = self.url.clone();
+        let headers = vec![
+            ("Title".to_string(), format!("{}: {}", self.host, alert.rule)),
+            ("Priority".to_string(), priority(alert.severity).to_string()),
+            ("Tags".to_string(), tags(alert.severity).to_string()),
+        ];
+        let body = format!("{} ({})", alert.message, config::display_name(&alert.battery));
+        thread::spawn(move || {
+            if let Err(e) = post_with_retry(&url, "text/plain", &headers, &body) {
+                crate::warn(&format!("ntfy push to {} failed: {}", url, e));
+            }
+        });
+    }
+}
@@ -0,0 +1,81 @@
+use std::fs;
+use std::path::Path;
+
+use crate::attr::Attrs;
+use crate::{find_batteries, power_supply_path};
+
+/// Energy snapshot of one pack, for machines that drain several in turn
+#[derive(Debug, Clone)]
+pub struct PackEnergy {
+    pub name: String,
+    pub status: String,
+    pub energy_now_wh: f64,
+    pub energy_full_wh: f64,
+}
+
+impl PackEnergy {
+    pub fn percent(&self) -> f64 {
+        (self.energy_now_wh / self.energy_full_wh * 100.0).clamp(0.0, 100.0)
+    }
+}
+
+fn read_pack(name: &str) -> Option<PackEnergy> {
+    let path = power_supply_path(name);
+    let base = Path::new(&path);
+    let attrs = Attrs::new(|attr: &str| fs::read_to_string(base.join(attr)).ok());
+    let energy_full_wh = attrs.energy_wh("full").filter(|wh| *wh > 0.0)?;
+    Some(PackEnergy {
+        name: name.to_string(),
+        status: fs::read_to_string(base.join("status")).map(|s| s.trim().to_string()).unwrap_or_default(),
+        energy_now_wh: attrs.energy_wh("now")?,
+        energy_full_wh,
+    })
+}
+
+/// Every pack with readable energy, or None on single-battery machines
+pub fn read_packs() -> Option<Vec<PackEnergy>> {
+    let packs: Vec<PackEnergy> = find_batteries().iter().filter_map(|name| read_pack(name)).collect();
+    (packs.len() > 1).then_some(packs)
+}
+
+/// One segment per pack, sized by its share of total full energy; the draining pack is yellow
+pub fn render_bar(packs: &[PackEnergy], width: usize) -> String {
+    let total_full: f64 = packs.iter().map(|p| p.energy_full_wh).sum();
+    // Dividers between segments take one column each
+    let usable = width.saturating_sub(packs.len() - 1).max(packs.len());
+    let mut segments = Vec::new();
+    let mut used = 0;
+    for (i, pack) in packs.iter().enumerate() {
+        let segment_width = if i + 1 == packs.len() {
+            usable.saturating_sub(used).max(1)
+        } else {
+            ((pack.energy_full_wh / total_full * usable as f64).round() as usize).max(1)
+        };
+        used += segment_width;
+        let filled = ((pack.percent() / 100.0 * segment_width as f64).round() as usize).min(segment_width);
+        let color = match pack.status.as_str() {
+            "Discharging" => "\x1b[33m",
+            "Charging" => "\x1b[32m",
+            _ => "\x1b[36m",
+        };
+        segments.push(format!("{}{}\x1b[0m\x1b[2m{}\x1b[0m",
+            color, "█".repeat(filled), "░".repeat(segment_width - filled)));
+    }
+    segments.join("│")
+}
+
+/// Per-pack legend, e.g. `BAT0 72% ↓  BAT1 100%`
+pub fn legend(packs: &[PackEnergy]) -> String {
+    packs
+        .iter()
+        .map(|pack| {
+            let arrow = match pack.status.as_str() {
+                "Discharging" => " \x1b[33m↓\x1b[0m",
+                "Charging" => " \x1b[32m↑\x1b[0m",
+                _ => "",
+            };
+            format!("{} {:.0}% ({:.1} Wh){}", pack.name, pack.percent(), pack.energy_now_wh, arrow)
+        })
+        .collect::<Vec<_>>()
+        .join("  ")
+}
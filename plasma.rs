@@ -0,0 +1,83 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::multi::{CombinedInfo, MultiBatteryMonitor};
+use crate::{BatteryInfo, BatteryMonitor};
+
+/// Bumped whenever a field is removed or changes meaning; additions keep the version
+pub const PLASMA_CONTRACT_VERSION: u32 = 1;
+
+/// One battery entry of the Plasma contract
+#[derive(Debug, Clone, Serialize)]
+pub struct PlasmaBattery {
+    pub name: String,
+    pub capacity: u8,
+    pub status: String,
+    pub time_remaining_minutes: Option<u32>,
+    pub power_w: Option<f64>,
+    pub health_percent: f64,
+    pub temperature_c: Option<f64>,
+}
+
+/// A currently active alert rule
+#[derive(Debug, Clone, Serialize)]
+pub struct PlasmaAlert {
+    pub rule: String,
+    pub severity: String,
+    pub battery: String,
+}
+
+/// The document a Plasmoid reads, either from `--format plasma` or `GET /v1/plasma`
+#[derive(Debug, Clone, Serialize)]
+pub struct PlasmaDocument {
+    pub version: u32,
+    pub timestamp: u64,
+    pub batteries: Vec<PlasmaBattery>,
+    pub alerts: Vec<PlasmaAlert>,
+}
+
+/// Build the document from each watched pack and the monitor that read it
+pub fn document(packs: &[(&BatteryMonitor, &BatteryInfo)]) -> PlasmaDocument {
+    let mut batteries = Vec::with_capacity(packs.len());
+    let mut alerts = Vec::new();
+    for (monitor, info) in packs {
+        let battery = monitor.battery_name().to_string();
+        if let Some(engine) = monitor.alerts() {
+            alerts.extend(engine.active_rules().map(|rule| PlasmaAlert {
+                rule: rule.name.clone(),
+                severity: rule.severity.as_str().to_string(),
+                battery: battery.clone(),
+            }));
+        }
+        batteries.push(PlasmaBattery {
+            name: battery,
+            capacity: info.capacity_percent,
+            status: info.status.clone(),
+            time_remaining_minutes: info.time_remaining_minutes,
+            power_w: info.smoothed_power_w.or(info.power_w),
+            health_percent: info.health_percent,
+            temperature_c: info.temperature_c,
+        });
+    }
+
+    PlasmaDocument {
+        version: PLASMA_CONTRACT_VERSION,
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        batteries,
+        alerts,
+    }
+}
+
+/// Build the document from every pack of a multi-battery sample
+pub fn combined_document(monitors: &MultiBatteryMonitor, combined: &CombinedInfo) -> PlasmaDocument {
+    let packs: Vec<(&BatteryMonitor, &BatteryInfo)> = combined
+        .batteries
+        .iter()
+        .filter_map(|pack| {
+            let monitor = monitors.monitors().iter().find(|monitor| monitor.battery_name() == pack.battery)?;
+            Some((monitor, &pack.info))
+        })
+        .collect();
+    document(&packs)
+}
@@ -0,0 +1,33 @@
+use std::fmt::Write;
+
+use crate::BatteryInfo;
+
+/// Content type of the text exposition format
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Prometheus text exposition of one sample, one gauge per BatteryInfo field
+pub fn exposition(battery: &str, info: &BatteryInfo) -> String {
+    let gauges = [
+        ("capacity_percent", "Remaining capacity (%)", Some(info.capacity_percent as f64)),
+        ("health_percent", "Full charge capacity against design (%)", Some(info.health_percent)),
+        ("power_watts", "Instantaneous power draw or charge rate (W)", info.power_w),
+        ("smoothed_power_watts", "Smoothed power behind the time estimate (W)", info.smoothed_power_w),
+        ("voltage_volts", "Pack voltage (V)", info.voltage_v),
+        ("energy_now_watthours", "Energy left in the pack (Wh)", info.energy_now_wh),
+        ("energy_full_watthours", "Energy when full (Wh)", info.energy_full_wh),
+        ("time_remaining_seconds", "Time to empty, or to full while charging (s)", info.time_remaining_minutes.map(|t| t as f64 * 60.0)),
+        ("temperature_celsius", "Battery temperature (°C)", info.temperature_c),
+        ("cpu_temperature_celsius", "CPU temperature (°C)", info.cpu_temperature_c),
+        ("charging", "1 while charging, 0 otherwise", Some(if info.status == "Charging" { 1.0 } else { 0.0 })),
+    ];
+
+    let battery = battery.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut text = String::new();
+    for (name, help, value) in gauges {
+        let Some(value) = value else { continue };
+        let _ = writeln!(text, "# HELP batfi_{} {}", name, help);
+        let _ = writeln!(text, "# TYPE batfi_{} gauge", name);
+        let _ = writeln!(text, "batfi_{}{{battery=\"{}\"}} {}", name, battery, value);
+    }
+    text
+}
@@ -0,0 +1,137 @@
+//! Third-party sensor sources — vendor CLIs, embedded controllers, USB battery testers —
+//! that feed the same pipeline as sysfs without forking batfi.
+//!
+//! Implement [`SensorProvider`] in your own crate and register it before creating monitors:
+//!
+//! ```no_run
+//! use batfi::provider::{self, ProvidedSensor, Quantity, SensorProvider, SensorValue};
+//!
+//! struct UsbTester;
+//!
+//! impl SensorProvider for UsbTester {
+//!     fn name(&self) -> &str {
+//!         "usb-tester"
+//!     }
+//!     fn discover(&mut self) -> Vec<ProvidedSensor> {
+//!         vec![ProvidedSensor::new(Quantity::Voltage, "UM25C")]
+//!     }
+//!     fn sample(&mut self) -> Vec<SensorValue> {
+//!         vec![SensorValue { quantity: Quantity::Voltage, value: 12.1 }]
+//!     }
+//! }
+//!
+//! provider::register(|| Box::new(UsbTester));
+//! let mut monitor = batfi::BatteryMonitor::new("BAT0");
+//! let info = monitor.get_battery_info();
+//! ```
+//!
+//! Provided values fill in what the battery driver and hwmon don't report; they never
+//! override a sysfs reading.
+
+use std::sync::Mutex;
+
+/// What a provider can measure, in the units batfi uses everywhere
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantity {
+    /// °C
+    CpuTemperature,
+    /// °C
+    BatteryTemperature,
+    /// W, always positive; the battery status says which way it flows
+    Power,
+    /// V
+    Voltage,
+    /// mA, positive while charging
+    Current,
+}
+
+/// One input a provider found during discovery
+#[derive(Debug, Clone)]
+pub struct ProvidedSensor {
+    pub quantity: Quantity,
+    /// Shown in `batfi doctor` and the sensor list, e.g. "EC fan board"
+    pub label: String,
+}
+
+impl ProvidedSensor {
+    pub fn new(quantity: Quantity, label: &str) -> Self {
+        Self { quantity, label: label.to_string() }
+    }
+}
+
+/// One value from a sample
+#[derive(Debug, Clone, Copy)]
+pub struct SensorValue {
+    pub quantity: Quantity,
+    pub value: f64,
+}
+
+/// A source of sensor values outside sysfs
+pub trait SensorProvider: Send {
+    /// Short identifier, e.g. "usb-tester"
+    fn name(&self) -> &str;
+
+    /// Find what this source can measure here; called once per monitor. Providers that
+    /// find nothing are dropped.
+    fn discover(&mut self) -> Vec<ProvidedSensor>;
+
+    /// Current values, called once per sample; leave out whatever couldn't be read
+    fn sample(&mut self) -> Vec<SensorValue>;
+}
+
+type Factory = fn() -> Box<dyn SensorProvider>;
+
+/// Providers every new BatteryMonitor starts with
+static REGISTRY: Mutex<Vec<Factory>> = Mutex::new(Vec::new());
+
+/// Give every monitor created from now on its own instance of a provider
+pub fn register(factory: Factory) {
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner()).push(factory);
+}
+
+/// A provider that found something, with what it found
+pub(crate) struct ActiveProvider {
+    pub provider: Box<dyn SensorProvider>,
+    pub sensors: Vec<ProvidedSensor>,
+}
+
+/// Discover a provider; None when it has nothing to offer on this machine
+pub(crate) fn activate(mut provider: Box<dyn SensorProvider>) -> Option<ActiveProvider> {
+    let sensors = provider.discover();
+    (!sensors.is_empty()).then_some(ActiveProvider { provider, sensors })
+}
+
+/// Instances of every registered provider that found something
+pub(crate) fn from_registry() -> Vec<ActiveProvider> {
+    let factories = REGISTRY.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    factories.into_iter().filter_map(|factory| activate(factory())).collect()
+}
+
+/// The latest values across providers; the first provider to report a quantity wins
+#[derive(Debug, Default)]
+pub(crate) struct ProvidedValues {
+    values: Vec<(Quantity, f64, String)>,
+}
+
+impl ProvidedValues {
+    pub fn sample(providers: &mut [ActiveProvider]) -> Self {
+        let mut values: Vec<(Quantity, f64, String)> = Vec::new();
+        for active in providers {
+            for value in active.provider.sample() {
+                if value.value.is_finite() && !values.iter().any(|(quantity, ..)| *quantity == value.quantity) {
+                    values.push((value.quantity, value.value, active.provider.name().to_string()));
+                }
+            }
+        }
+        Self { values }
+    }
+
+    pub fn get(&self, quantity: Quantity) -> Option<f64> {
+        self.values.iter().find(|(q, ..)| *q == quantity).map(|(_, value, _)| *value)
+    }
+
+    /// The value and the name of the provider it came from
+    pub fn source(&self, quantity: Quantity) -> Option<(f64, &str)> {
+        self.values.iter().find(|(q, ..)| *q == quantity).map(|(_, value, name)| (*value, name.as_str()))
+    }
+}
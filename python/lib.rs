@@ -0,0 +1,204 @@
+//! The `batfi` Python module: the same monitor and estimators the CLI runs, for scripting
+//! battery experiments and plotting them in notebooks.
+//!
+//! Readings cross the boundary as plain dicts with the keys of `batfi --json`, so
+//! `pandas.DataFrame(monitor.history())` just works.
+
+use batfi::simulate::{self, SimulatedBackend};
+use batfi::{estimation, BatteryMonitor, BatteryReading};
+use pyo3::exceptions::{PyLookupError, PyValueError};
+use pyo3::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Serialize through JSON, so dicts carry exactly the keys of the CLI's JSON output
+fn to_python<'py, T: Serialize>(py: Python<'py>, value: &T) -> PyResult<Bound<'py, PyAny>> {
+    let text = serde_json::to_string(value).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    py.import("json")?.call_method1("loads", (text,))
+}
+
+fn from_python<T: DeserializeOwned>(value: &Bound<'_, PyAny>) -> PyResult<T> {
+    let text: String = value.py().import("json")?.call_method1("dumps", (value,))?.extract()?;
+    serde_json::from_str(&text).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// A battery monitor; keeps the smoothing and history that the estimates are built from.
+#[pyclass(name = "BatteryMonitor", unsendable)]
+struct PyBatteryMonitor {
+    monitor: BatteryMonitor,
+}
+
+#[pymethods]
+impl PyBatteryMonitor {
+    /// Monitor `battery` (e.g. "BAT0"), or the first battery found
+    #[new]
+    #[pyo3(signature = (battery=None))]
+    fn new(battery: Option<String>) -> PyResult<Self> {
+        let name = match battery {
+            Some(name) => name,
+            None => batfi::find_batteries().into_iter().next().ok_or_else(|| PyLookupError::new_err("no battery found"))?,
+        };
+        if !std::path::Path::new(&batfi::power_supply_path(&name)).exists() {
+            return Err(PyLookupError::new_err(format!("battery '{}' not found", name)));
+        }
+        Ok(Self { monitor: BatteryMonitor::new(&name) })
+    }
+
+    /// A synthetic battery ("discharge", "charge" or "cycle") that advances `step_secs` per
+    /// poll. Like `batfi --simulate`, it saves nothing and starts fresh every time.
+    #[staticmethod]
+    #[pyo3(signature = (scenario="discharge", from_percent=None, rate_w=None, step_secs=2.0))]
+    fn simulated(scenario: &str, from_percent: Option<f64>, rate_w: Option<f64>, step_secs: f64) -> PyResult<Self> {
+        if !simulate::SCENARIOS.contains(&scenario) {
+            return Err(PyValueError::new_err(format!("scenario must be one of {:?}", simulate::SCENARIOS)));
+        }
+        let from = from_percent.unwrap_or(if scenario == "charge" { 20.0 } else { 90.0 });
+        let backend = SimulatedBackend::new(scenario, from, rate_w, step_secs);
+        let mut monitor = BatteryMonitor::with_backend(simulate::BATTERY_NAME, Box::new(backend));
+        // Nothing is saved, so every simulation starts from the same clean state
+        monitor.set_data_dir(None);
+        Ok(Self { monitor })
+    }
+
+    #[getter]
+    fn battery(&self) -> &str {
+        self.monitor.battery_name()
+    }
+
+    /// Take a sample: a dict like `batfi --json`, or None if the battery went away
+    fn poll<'py>(&mut self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        self.monitor.get_battery_info().map(|info| to_python(py, &info)).transpose()
+    }
+
+    /// Readings kept so far (up to the last 300), oldest first
+    fn history<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let readings: Vec<&BatteryReading> = self.monitor.readings_history().iter().collect();
+        to_python(py, &readings)
+    }
+
+    /// Capacity change in %/h over the recent history, negative while draining
+    fn percent_per_hour(&self) -> Option<f64> {
+        self.monitor.percent_per_hour()
+    }
+
+    /// Estimated internal resistance (Ω) from voltage and current swings
+    fn internal_resistance(&self) -> Option<f64> {
+        self.monitor.estimate_internal_resistance()
+    }
+
+    /// Power (W) at the 50th, 90th and 99th percentile of this session's discharging samples
+    fn power_percentiles<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        self.monitor.session_power().percentiles().map(|p| to_python(py, &p)).transpose()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("BatteryMonitor('{}')", self.monitor.battery_name())
+    }
+}
+
+/// Names of the batteries under /sys/class/power_supply
+#[pyfunction]
+fn find_batteries() -> Vec<String> {
+    batfi::find_batteries()
+}
+
+/// Minutes to empty (or full) for a reading dict, as the monitor computes them
+#[pyfunction]
+#[pyo3(signature = (reading, smoothed_power, power_window, samples))]
+fn time_remaining(reading: &Bound<'_, PyAny>, smoothed_power: Option<f64>, power_window: Vec<f64>, samples: usize) -> PyResult<Option<u32>> {
+    let reading: BatteryReading = from_python(reading)?;
+    Ok(estimation::time_remaining(&reading, smoothed_power, &power_window, samples, estimation::WindowSizes::default()))
+}
+
+/// Least-squares %/h over reading dicts (oldest first) from the last `window_secs`
+#[pyfunction]
+#[pyo3(signature = (readings, window_secs=estimation::DRAIN_RATE_WINDOW_SECS))]
+fn percent_per_hour(readings: &Bound<'_, PyAny>, window_secs: u64) -> PyResult<Option<f64>> {
+    let readings: Vec<BatteryReading> = from_python(readings)?;
+    Ok(estimation::percent_per_hour(readings.iter(), window_secs))
+}
+
+/// Minutes `energy_wh` lasts at a constant `watts`
+#[pyfunction]
+fn runtime_at(energy_wh: f64, watts: f64) -> Option<u32> {
+    estimation::runtime_at(energy_wh, watts)
+}
+
+/// One step of the exponential moving average used for smoothed power
+#[pyfunction]
+#[pyo3(signature = (previous, value, alpha=estimation::POWER_SMOOTHING_ALPHA))]
+fn ema(previous: Option<f64>, value: f64, alpha: f64) -> f64 {
+    estimation::ema(previous, value, alpha)
+}
+
+/// "increasing", "decreasing" or "stable" for power readings, oldest first
+#[pyfunction]
+fn power_trend(powers: Vec<f64>) -> &'static str {
+    estimation::power_trend(&powers).as_str()
+}
+
+/// Share of charger power that ends up in the pack at a fill of `progress` (0.0–1.0)
+#[pyfunction]
+fn charging_efficiency(progress: f64) -> f64 {
+    estimation::charging_efficiency(progress)
+}
+
+/// (slope, intercept) of the least-squares line through (x, y) points
+#[pyfunction]
+fn linear_fit(points: Vec<(f64, f64)>) -> Option<(f64, f64)> {
+    estimation::linear_fit(&points)
+}
+
+/// Pearson correlation of (x, y) points
+#[pyfunction]
+fn correlation(points: Vec<(f64, f64)>) -> Option<f64> {
+    estimation::correlation(&points)
+}
+
+#[pymodule]
+#[pyo3(name = "batfi")]
+fn batfi_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyBatteryMonitor>()?;
+    m.add_function(wrap_pyfunction!(find_batteries, m)?)?;
+
+    let estimators = PyModule::new(m.py(), "estimation")?;
+    estimators.add_function(wrap_pyfunction!(time_remaining, &estimators)?)?;
+    estimators.add_function(wrap_pyfunction!(percent_per_hour, &estimators)?)?;
+    estimators.add_function(wrap_pyfunction!(runtime_at, &estimators)?)?;
+    estimators.add_function(wrap_pyfunction!(ema, &estimators)?)?;
+    estimators.add_function(wrap_pyfunction!(power_trend, &estimators)?)?;
+    estimators.add_function(wrap_pyfunction!(charging_efficiency, &estimators)?)?;
+    estimators.add_function(wrap_pyfunction!(linear_fit, &estimators)?)?;
+    estimators.add_function(wrap_pyfunction!(correlation, &estimators)?)?;
+    m.add_submodule(&estimators)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simulated_monitor_feeds_the_estimators() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = PyModule::new(py, "batfi").unwrap();
+            batfi_module(&module).unwrap();
+            let monitor = module.getattr("BatteryMonitor").unwrap().call_method1("simulated", ("discharge",)).unwrap();
+            for _ in 0..5 {
+                monitor.call_method0("poll").unwrap();
+            }
+            let info = monitor.call_method0("poll").unwrap();
+            assert_eq!(info.get_item("status").unwrap().extract::<String>().unwrap(), "Discharging");
+
+            // History dicts go back into the estimators unchanged
+            let history = monitor.call_method0("history").unwrap();
+            assert_eq!(history.len().unwrap(), 6);
+            let estimators = module.getattr("estimation").unwrap();
+            let latest = history.get_item(5).unwrap();
+            let eta = estimators.call_method1("time_remaining", (latest, 12.0, vec![12.0; 10], 20)).unwrap();
+            assert!(eta.extract::<Option<u32>>().unwrap().is_some());
+            assert_eq!(estimators.call_method1("runtime_at", (36.0, 12.0)).unwrap().extract::<u32>().unwrap(), 180);
+        });
+    }
+}
@@ -0,0 +1,51 @@
+/// Firmware/EC misbehaviours that the reading layer can correct for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quirk {
+    /// Battery `temp` is always in decidegrees, even when the value looks like Celsius
+    TempDecidegrees,
+    /// Capacity never goes past 99% even when the pack reports Full
+    CapacityStuckAt99,
+    /// current_now is positive while discharging and negative while charging
+    CurrentSignInverted,
+}
+
+impl Quirk {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Quirk::TempDecidegrees => "temperature reported in decidegrees",
+            Quirk::CapacityStuckAt99 => "capacity sticks at 99% when full",
+            Quirk::CurrentSignInverted => "current_now sign inverted",
+        }
+    }
+}
+
+struct QuirkEntry {
+    manufacturer: &'static str,
+    model_prefix: &'static str, // Empty matches every model from the manufacturer
+    quirks: &'static [Quirk],
+}
+
+/// Built-in quirks table, matched case-insensitively on manufacturer and model prefix
+const QUIRKS: &[QuirkEntry] = &[
+    QuirkEntry { manufacturer: "SMP", model_prefix: "DELL", quirks: &[Quirk::TempDecidegrees] },
+    QuirkEntry { manufacturer: "Samsung SDI", model_prefix: "DELL", quirks: &[Quirk::TempDecidegrees] },
+    QuirkEntry { manufacturer: "ASUSTeK", model_prefix: "", quirks: &[Quirk::CapacityStuckAt99] },
+    QuirkEntry { manufacturer: "Hewlett-Packard", model_prefix: "Primary", quirks: &[Quirk::CapacityStuckAt99] },
+    QuirkEntry { manufacturer: "Google", model_prefix: "", quirks: &[Quirk::CurrentSignInverted] },
+    QuirkEntry { manufacturer: "Surface", model_prefix: "", quirks: &[Quirk::CurrentSignInverted] },
+];
+
+/// Quirks that apply to a pack with this manufacturer and model name
+pub fn quirks_for(manufacturer: &str, model: &str) -> Vec<Quirk> {
+    let manufacturer = manufacturer.trim().to_lowercase();
+    let model = model.trim().to_lowercase();
+
+    let mut quirks: Vec<Quirk> = QUIRKS
+        .iter()
+        .filter(|entry| entry.manufacturer.to_lowercase() == manufacturer)
+        .filter(|entry| model.starts_with(&entry.model_prefix.to_lowercase()))
+        .flat_map(|entry| entry.quirks.iter().copied())
+        .collect();
+    quirks.dedup();
+    quirks
+}
@@ -0,0 +1,96 @@
+//! `batfi raw`: every readable attribute of one power supply, as the kernel wrote it and
+//! with batfi's units applied, for pasting into bug reports.
+
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::attr::Attrs;
+use crate::power_supply_path;
+
+/// One file of the device directory
+#[derive(Debug, Serialize)]
+pub struct RawAttribute {
+    pub name: String,
+    /// File contents, trimmed; multi-line files keep their line breaks
+    pub raw: String,
+    /// The value in `unit`, for attributes batfi knows how to scale
+    pub value: Option<f64>,
+    pub unit: Option<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RawDump {
+    pub device: String,
+    pub path: String,
+    pub attributes: Vec<RawAttribute>,
+    /// Files that exist but couldn't be read (write-only knobs, I/O errors from the EC)
+    pub unreadable: Vec<String>,
+}
+
+/// Read every regular file in the device directory, sorted by name
+pub fn dump(device: &str) -> std::io::Result<RawDump> {
+    let path = power_supply_path(device);
+    let dir = Path::new(&path);
+    let read = |name: &str| fs::read_to_string(dir.join(name)).ok().map(|s| s.trim().to_string());
+    let attrs = Attrs::new(read);
+
+    // Skip `device`, `subsystem`, `power/` and other links or directories; uevent repeats the rest
+    let mut names: Vec<String> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name != "uevent")
+        .collect();
+    names.sort();
+
+    let mut attributes = Vec::new();
+    let mut unreadable = Vec::new();
+    for name in names {
+        match fs::read_to_string(dir.join(&name)) {
+            Ok(contents) => {
+                let interpreted = attrs.interpreted(&name);
+                attributes.push(RawAttribute {
+                    raw: contents.trim_matches(|c: char| c.is_whitespace() || c == '\0').to_string(),
+                    value: interpreted.map(|(value, _)| value),
+                    unit: interpreted.map(|(_, unit)| unit),
+                    name,
+                });
+            }
+            Err(_) => unreadable.push(name),
+        }
+    }
+    Ok(RawDump { device: device.to_string(), path, attributes, unreadable })
+}
+
+/// `batfi raw [DEVICE]`: the dump as an aligned table or JSON
+pub fn run_raw(device: &str, json_output: bool) {
+    let dump = match dump(device) {
+        Ok(dump) => dump,
+        Err(e) => {
+            eprintln!("❌ Could not read {}: {}", power_supply_path(device), e);
+            std::process::exit(1);
+        }
+    };
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&dump).unwrap_or_else(|_| "{}".to_string()));
+        return;
+    }
+
+    println!(" \x1b[1m{}\x1b[0m \x1b[2m{}\x1b[0m", dump.device, dump.path);
+    let width = dump.attributes.iter().map(|a| a.name.len()).max().unwrap_or(0);
+    for attribute in &dump.attributes {
+        let interpreted = match (attribute.value, attribute.unit) {
+            (Some(value), Some(unit)) => format!("  \x1b[36m= {} {}\x1b[0m", (value * 1000.0).round() / 1000.0, unit),
+            _ => String::new(),
+        };
+        // Continuation lines of multi-line files line up under the first
+        let raw = attribute.raw.replace('\n', &format!("\n {:width$}   ", "", width = width));
+        println!(" {:<width$}   {}{}", attribute.name, raw, interpreted, width = width);
+    }
+    if !dump.unreadable.is_empty() {
+        println!();
+        println!(" \x1b[2mUnreadable: {}\x1b[0m", dump.unreadable.join(", "));
+    }
+}
@@ -0,0 +1,170 @@
+//! JSON-RPC 2.0 sessions for `batfi rpc`, which speaks them on stdin/stdout one message per
+//! line, for editor plugins and desktop apps that spawn batfi as a child process rather than
+//! talk to the daemon socket.
+//!
+//! Methods: `get_status`, `subscribe` (`{"enabled": false}` to stop), `set_interval`
+//! (`{"seconds": N}`) and `list_sensors`. While subscribed, every sample is pushed as a
+//! `status` notification. The session ends when stdin closes.
+
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::server::SensorsSnapshot;
+use crate::{BatteryInfo, BatteryMonitor};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// Implementation-defined server error: the battery couldn't be read
+const NO_BATTERY: i64 = -32000;
+
+/// One client's session: its monitor, latest sample and subscription
+pub struct Session {
+    monitor: BatteryMonitor,
+    latest: Option<BatteryInfo>,
+    subscribed: bool,
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// A named parameter, whether params came as an object or as a positional array
+fn param<'a>(params: Option<&'a Value>, name: &str) -> Option<&'a Value> {
+    match params? {
+        Value::Object(map) => map.get(name),
+        Value::Array(values) => values.first(),
+        _ => None,
+    }
+}
+
+impl Session {
+    pub fn new(monitor: BatteryMonitor) -> Self {
+        Self { monitor, latest: None, subscribed: false }
+    }
+
+    fn sample(&mut self) -> Option<&BatteryInfo> {
+        self.latest = self.monitor.get_battery_info();
+        self.latest.as_ref()
+    }
+
+    /// How often `tick` should be called
+    pub fn update_interval(&self) -> Duration {
+        self.monitor.update_interval()
+    }
+
+    /// Answer one line of input; None when nothing should be written back (notifications)
+    pub fn handle_line(&mut self, line: &str) -> Option<Value> {
+        match serde_json::from_str::<Value>(line) {
+            Ok(Value::Array(batch)) if batch.is_empty() => Some(error(Value::Null, INVALID_REQUEST, "empty batch")),
+            Ok(Value::Array(batch)) => {
+                let responses: Vec<Value> = batch.into_iter().filter_map(|request| self.handle(request)).collect();
+                (!responses.is_empty()).then_some(Value::Array(responses))
+            }
+            Ok(request) => self.handle(request),
+            Err(e) => Some(error(Value::Null, PARSE_ERROR, &e.to_string())),
+        }
+    }
+
+    fn handle(&mut self, request: Value) -> Option<Value> {
+        let id = request.get("id").cloned();
+        let method = request.get("method").and_then(Value::as_str);
+        let (Some(method), Some("2.0")) = (method, request.get("jsonrpc").and_then(Value::as_str)) else {
+            return Some(error(id.unwrap_or(Value::Null), INVALID_REQUEST, "expected a JSON-RPC 2.0 request"));
+        };
+        let result = self.call(method, request.get("params"));
+        // Requests without an id are notifications and get no response, not even errors
+        let id = id?;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error(id, code, &message),
+        })
+    }
+
+    fn call(&mut self, method: &str, params: Option<&Value>) -> Result<Value, (i64, String)> {
+        match method {
+            "get_status" => {
+                let info = match self.latest.as_ref() {
+                    Some(info) => info,
+                    None => self.sample().ok_or((NO_BATTERY, "could not read battery information".to_string()))?,
+                };
+                Ok(serde_json::to_value(info).unwrap_or(Value::Null))
+            }
+            "subscribe" => {
+                let enabled = match param(params, "enabled") {
+                    None => true,
+                    Some(value) => value.as_bool().ok_or((INVALID_PARAMS, "enabled must be a boolean".to_string()))?,
+                };
+                self.subscribed = enabled;
+                Ok(Value::Bool(enabled))
+            }
+            "set_interval" => {
+                let seconds = param(params, "seconds")
+                    .and_then(Value::as_u64)
+                    .filter(|&secs| secs >= 1)
+                    .ok_or((INVALID_PARAMS, "seconds must be a whole number of at least 1".to_string()))?;
+                self.monitor.set_update_interval(Duration::from_secs(seconds));
+                Ok(json!(seconds))
+            }
+            "list_sensors" => Ok(serde_json::to_value(SensorsSnapshot::of(self.monitor.temperature_monitor())).unwrap_or(Value::Null)),
+            _ => Err((METHOD_NOT_FOUND, format!("no method '{}'", method))),
+        }
+    }
+
+    /// Take the periodic sample; the `status` notification to push when subscribed
+    pub fn tick(&mut self) -> Option<Value> {
+        let subscribed = self.subscribed;
+        let info = self.sample()?;
+        subscribed.then(|| json!({ "jsonrpc": "2.0", "method": "status", "params": info }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{mock_monitor, MockBackend};
+
+    fn session() -> (Session, tempfile::TempDir) {
+        let backend = (0..3).fold(MockBackend::new(), |backend, _| backend.discharging(36.0, 50.0, 9.0));
+        let (monitor, dir) = mock_monitor(backend);
+        (Session::new(monitor), dir)
+    }
+
+    #[test]
+    fn answers_requests_and_reports_errors() {
+        let (mut session, _dir) = session();
+        let status = session.handle_line(r#"{"jsonrpc":"2.0","id":1,"method":"get_status"}"#).unwrap();
+        assert_eq!(status["id"], 1);
+        assert_eq!(status["result"]["capacity_percent"], 72);
+
+        let interval = session.handle_line(r#"{"jsonrpc":"2.0","id":"a","method":"set_interval","params":{"seconds":5}}"#).unwrap();
+        assert_eq!(interval["result"], 5);
+        assert_eq!(session.monitor.update_interval(), Duration::from_secs(5));
+        let bad = session.handle_line(r#"{"jsonrpc":"2.0","id":2,"method":"set_interval","params":[0]}"#).unwrap();
+        assert_eq!(bad["error"]["code"], INVALID_PARAMS);
+
+        assert_eq!(session.handle_line(r#"{"jsonrpc":"2.0","id":3,"method":"reboot"}"#).unwrap()["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(session.handle_line("{oops").unwrap()["error"]["code"], PARSE_ERROR);
+        assert_eq!(session.handle_line(r#"{"id":4,"method":"get_status"}"#).unwrap()["error"]["code"], INVALID_REQUEST);
+        // Notifications are acted on silently
+        assert_eq!(session.handle_line(r#"{"jsonrpc":"2.0","method":"subscribe"}"#), None);
+        assert!(session.subscribed);
+    }
+
+    #[test]
+    fn subscription_pushes_status_notifications() {
+        let (mut session, _dir) = session();
+        assert_eq!(session.tick(), None);
+        let batch = session
+            .handle_line(r#"[{"jsonrpc":"2.0","id":1,"method":"subscribe"},{"jsonrpc":"2.0","id":2,"method":"list_sensors"}]"#)
+            .unwrap();
+        assert_eq!(batch[0]["result"], true);
+        assert!(batch[1]["result"]["cpu_sensors"].is_array());
+        let notification = session.tick().unwrap();
+        assert_eq!(notification["method"], "status");
+        assert!(notification.get("id").is_none());
+        assert_eq!(notification["params"]["status"], "Discharging");
+    }
+}
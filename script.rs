@@ -0,0 +1,194 @@
+//! User scripts in Rhai: a middle ground between config options and forking batfi.
+//!
+//! A script may define any of three functions, each called with the sample as a map
+//! with the keys of `batfi --json`:
+//!
+//! ```text
+//! fn fields(info) { #{ wh_per_hour: info.energy_now_wh / 2.0 } }   // extra JSON keys under "script"
+//! fn alerts(info) { if info.temperature_c > 42.0 { ["Pack is warm"] } else { [] } }
+//! fn display(info) { `Cost so far: ${info.energy_since_unplug_wh * 0.3} ct` }
+//! ```
+//!
+//! `alerts` returns messages, or maps with `message`, `name` and `severity`; each fires
+//! once while it keeps being returned with the same name and message. `display` returns
+//! a line or an array of lines for the dashboard and `batfi status`.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST};
+
+use crate::alerts::{CustomAlert, Severity};
+use crate::BatteryInfo;
+
+/// Rhai operations one hook may run per sample, so a runaway loop can't stall sampling
+const MAX_OPERATIONS: u64 = 200_000;
+/// The hooks a script may define
+const HOOKS: [&str; 3] = ["fields", "alerts", "display"];
+
+/// What a script made of one sample
+#[derive(Debug, Default)]
+pub struct ScriptOutput {
+    pub fields: BTreeMap<String, serde_json::Value>,
+    pub alerts: Vec<CustomAlert>,
+    pub lines: Vec<String>,
+}
+
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    /// Last runtime error reported, so a broken hook doesn't repeat itself every sample
+    last_error: Option<String>,
+}
+
+impl Script {
+    /// Compile a script, checking that it defines at least one hook
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let ast = engine.compile_file(path.to_path_buf()).map_err(|e| e.to_string())?;
+        if !ast.iter_functions().any(|f| HOOKS.contains(&f.name) && f.params.len() == 1) {
+            return Err(format!("defines none of {}", HOOKS.map(|hook| format!("{}(info)", hook)).join(", ")));
+        }
+        Ok(Self { engine, ast, last_error: None })
+    }
+
+    fn defines(&self, hook: &str) -> bool {
+        self.ast.iter_functions().any(|f| f.name == hook && f.params.len() == 1)
+    }
+
+    fn call(&self, hook: &str, info: &Dynamic) -> Result<Option<Dynamic>, String> {
+        if !self.defines(hook) {
+            return Ok(None);
+        }
+        let result = self.engine.call_fn::<Dynamic>(&mut Scope::new(), &self.ast, hook, (info.clone(),));
+        result.map(Some).map_err(|e| format!("{}(): {}", hook, e))
+    }
+
+    /// Run every defined hook on a sample; hook errors are reported once and skipped
+    pub fn run(&mut self, info: &BatteryInfo) -> ScriptOutput {
+        let mut output = ScriptOutput::default();
+        let info = match rhai::serde::to_dynamic(info) {
+            Ok(info) => info,
+            Err(e) => {
+                self.report(e.to_string());
+                return output;
+            }
+        };
+        let outcome = (|| -> Result<(), String> {
+            if let Some(fields) = self.call("fields", &info)? {
+                output.fields = parse_fields(fields)?;
+            }
+            if let Some(alerts) = self.call("alerts", &info)? {
+                output.alerts = parse_alerts(alerts)?;
+            }
+            if let Some(lines) = self.call("display", &info)? {
+                output.lines = parse_lines(lines)?;
+            }
+            Ok(())
+        })();
+        match outcome {
+            Ok(()) => self.last_error = None,
+            Err(e) => self.report(e),
+        }
+        output
+    }
+
+    fn report(&mut self, error: String) {
+        if self.last_error.as_ref() != Some(&error) {
+            crate::warn(&format!("Script error: {}", error));
+            self.last_error = Some(error);
+        }
+    }
+}
+
+fn parse_fields(value: Dynamic) -> Result<BTreeMap<String, serde_json::Value>, String> {
+    if value.is_unit() {
+        return Ok(BTreeMap::new());
+    }
+    let map = value.try_cast::<Map>().ok_or("fields() must return a map")?;
+    map.into_iter()
+        .map(|(key, value)| {
+            let json = rhai::serde::from_dynamic::<serde_json::Value>(&value).map_err(|e| format!("fields().{}: {}", key, e))?;
+            Ok((key.to_string(), json))
+        })
+        .collect()
+}
+
+fn parse_alerts(value: Dynamic) -> Result<Vec<CustomAlert>, String> {
+    let items = match value {
+        value if value.is_unit() => Array::new(),
+        value if value.is_array() => value.into_array()?,
+        value => vec![value],
+    };
+    items
+        .into_iter()
+        .map(|item| {
+            if item.is_string() {
+                let message = item.into_string()?;
+                return Ok(CustomAlert { name: "script".to_string(), severity: Severity::Warning, message });
+            }
+            let map = item.try_cast::<Map>().ok_or("alerts() items must be strings or maps")?;
+            let text = |key: &str| map.get(key).filter(|v| v.is_string()).map(|v| v.to_string());
+            let message = text("message").ok_or("alerts() maps need a \"message\"")?;
+            let severity = match text("severity") {
+                Some(name) => Severity::parse(&name).ok_or(format!("unknown severity '{}'", name))?,
+                None => Severity::Warning,
+            };
+            let name = text("name").unwrap_or_else(|| "script".to_string());
+            Ok(CustomAlert { name, severity, message })
+        })
+        .collect()
+}
+
+fn parse_lines(value: Dynamic) -> Result<Vec<String>, String> {
+    match value {
+        value if value.is_unit() => Ok(Vec::new()),
+        value if value.is_array() => Ok(value.into_array()?.into_iter().map(|line| line.to_string()).collect()),
+        value => Ok(vec![value.to_string()]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(source: &str) -> Script {
+        let path = std::env::temp_dir().join(format!("batfi-script-{}-{}.rhai", std::process::id(), source.len()));
+        std::fs::write(&path, source).unwrap();
+        let script = Script::load(&path);
+        let _ = std::fs::remove_file(&path);
+        script.unwrap()
+    }
+
+    #[test]
+    fn hooks_derive_fields_alerts_and_lines() {
+        let mut script = script(
+            r#"
+            fn fields(info) { #{ double: info.capacity_percent * 2 } }
+            fn alerts(info) {
+                if info.capacity_percent < 50 { [#{ name: "half", message: "Below half", severity: "critical" }, "plain"] } else { [] }
+            }
+            fn display(info) { `${info.capacity_percent}% left` }
+            "#,
+        );
+        let info = BatteryInfo { capacity_percent: 40, ..Default::default() };
+        let output = script.run(&info);
+        assert_eq!(output.fields["double"], 80);
+        assert_eq!(output.alerts[0], CustomAlert { name: "half".into(), severity: Severity::Critical, message: "Below half".into() });
+        assert_eq!(output.alerts[1].name, "script");
+        assert_eq!(output.lines, ["40% left"]);
+    }
+
+    #[test]
+    fn runaway_and_broken_hooks_are_contained() {
+        let mut looping = script("fn display(info) { loop {} }");
+        assert!(looping.run(&BatteryInfo::default()).lines.is_empty());
+        assert!(looping.last_error.as_ref().unwrap().contains("display()"));
+
+        let path = std::env::temp_dir().join(format!("batfi-script-{}-empty.rhai", std::process::id()));
+        std::fs::write(&path, "let x = 1;").unwrap();
+        assert!(Script::load(&path).err().unwrap().contains("defines none"));
+        let _ = std::fs::remove_file(&path);
+    }
+}
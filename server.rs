@@ -0,0 +1,542 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::SocketAddr;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, UnixListener};
+use tokio::sync::watch;
+use tokio::task::{self, JoinSet};
+use tokio::{runtime, signal, time};
+
+use crate::dbus::DbusPublisher;
+use crate::plasma::{self, PlasmaDocument};
+use crate::prometheus;
+use crate::{
+    events, parse_duration_secs, BatteryInfo, BatteryMonitor, BatteryReading, TemperatureMonitor,
+    TemperatureReading, TemperatureSensor,
+};
+
+/// Largest request head we are willing to read
+const MAX_REQUEST_BYTES: usize = 8192;
+/// Largest request body we accept (fleet reports)
+const MAX_BODY_BYTES: usize = 65_536;
+/// Clients get this long to send their request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// The daemon's sampler and listeners are light; two workers keep its footprint small
+const RUNTIME_WORKERS: usize = 2;
+
+/// Temperature sensors and their latest readings (`/v1/sensors`)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SensorsSnapshot {
+    pub cpu_sensors: Vec<TemperatureSensor>,
+    pub battery_sensors: Vec<TemperatureSensor>,
+    pub cpu_temperature: Option<TemperatureReading>,
+    pub battery_temperature: Option<TemperatureReading>,
+}
+
+impl SensorsSnapshot {
+    pub fn of(temps: &TemperatureMonitor) -> Self {
+        Self {
+            cpu_sensors: temps.cpu_sensors.clone(),
+            battery_sensors: temps.battery_sensors.clone(),
+            cpu_temperature: temps.last_cpu_temp.clone(),
+            battery_temperature: temps.last_battery_temp.clone(),
+        }
+    }
+}
+
+/// Latest sampler output shared with the request handlers
+#[derive(Default)]
+struct ServerState {
+    battery_name: String,
+    /// The monitor's data directory, whose event journal `/v1/events` serves
+    data_dir: Option<PathBuf>,
+    battery: Option<BatteryInfo>,
+    history: VecDeque<BatteryReading>,
+    sensors: SensorsSnapshot,
+    plasma: Option<PlasmaDocument>,
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: &'a str,
+}
+
+/// Per-user socket the server listens on ($XDG_RUNTIME_DIR/batfi.sock, else under
+/// /run/user/<uid>). Without a private runtime directory there is nowhere safe to put it.
+pub fn socket_path() -> std::io::Result<PathBuf> {
+    if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+        return Ok(PathBuf::from(dir).join("batfi.sock"));
+    }
+    let uid = fs::metadata("/proc/self")?.uid();
+    let dir = PathBuf::from(format!("/run/user/{}", uid));
+    match fs::metadata(&dir) {
+        Ok(meta) if meta.is_dir() && meta.uid() == uid && meta.mode() & 0o077 == 0 => Ok(dir.join("batfi.sock")),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("XDG_RUNTIME_DIR is unset and {} is not a private directory", dir.display()),
+        )),
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+/// Resolve `since=` as a duration back from now ("10m", "2h"), or an absolute time given as
+/// `@<Unix seconds>` or RFC 3339. A bare number could be either, so it is rejected.
+pub fn parse_since(value: &str) -> Option<u64> {
+    let value = value.trim();
+    if let Some(timestamp) = value.strip_prefix('@') {
+        return timestamp.parse().ok();
+    }
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(value) {
+        return u64::try_from(time.timestamp()).ok();
+    }
+    if !value.ends_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    Some(now_secs().saturating_sub(parse_duration_secs(value)?))
+}
+
+/// `since=` from the query, 0 when absent; an invalid value is a 400 response
+fn since_param(query: &str) -> Result<u64, (&'static str, String)> {
+    match query_param(query, "since") {
+        Some(value) => parse_since(value).ok_or_else(|| {
+            ("400 Bad Request", error_json("invalid since (use e.g. 10m, 2h, 7d, @<Unix seconds> or an RFC 3339 time)"))
+        }),
+        None => Ok(0),
+    }
+}
+
+fn query_param<'a>(query: &'a str, key: &str) -> Option<&'a str> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| v)
+}
+
+pub fn write_response<S: Write>(stream: &mut S, status: &str, body: &str) {
+    write_typed_response(stream, status, "application/json", body);
+}
+
+fn write_typed_response<S: Write>(stream: &mut S, status: &str, content_type: &str, body: &str) {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
+
+fn json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|_| "{}".to_string())
+}
+
+pub fn error_json(message: &str) -> String {
+    json(&ErrorBody { error: message })
+}
+
+/// Route a GET request to the matching endpoint; returns (status line, body)
+fn route(path: &str, query: &str, state: &Mutex<ServerState>) -> (&'static str, String) {
+    let state = state.lock().unwrap();
+    match path {
+        "/v1/battery" => match &state.battery {
+            Some(info) => ("200 OK", json(info)),
+            None => ("503 Service Unavailable", error_json("no battery reading yet")),
+        },
+        "/v1/history" => {
+            let since = match since_param(query) {
+                Ok(since) => since,
+                Err(response) => return response,
+            };
+            let readings: Vec<&BatteryReading> = state.history.iter().filter(|r| r.timestamp >= since).collect();
+            ("200 OK", json(&readings))
+        }
+        "/v1/sensors" => ("200 OK", json(&state.sensors)),
+        "/v1/plasma" => match &state.plasma {
+            Some(document) => ("200 OK", json(document)),
+            None => ("503 Service Unavailable", error_json("no battery reading yet")),
+        },
+        "/v1/events" => {
+            let since = match since_param(query) {
+                Ok(since) => since,
+                Err(response) => return response,
+            };
+            ("200 OK", json(&events::load_events(state.data_dir.as_deref(), since)))
+        }
+        _ => ("404 Not Found", error_json("unknown endpoint")),
+    }
+}
+
+/// What a listener answers: the JSON API, or only `/metrics` on the Prometheus port
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Endpoints {
+    Api,
+    Metrics,
+}
+
+/// `/metrics` for Prometheus scrapers; the scrape port serves nothing else
+fn metrics(path: &str, state: &Mutex<ServerState>) -> (&'static str, &'static str, String) {
+    if path != "/metrics" {
+        return ("404 Not Found", prometheus::CONTENT_TYPE, "# only /metrics is served here\n".to_string());
+    }
+    let state = state.lock().unwrap();
+    match &state.battery {
+        Some(info) => ("200 OK", prometheus::CONTENT_TYPE, prometheus::exposition(&state.battery_name, info)),
+        None => ("503 Service Unavailable", prometheus::CONTENT_TYPE, "# no battery reading yet\n".to_string()),
+    }
+}
+
+/// Answer a GET on `endpoints`; returns (status line, content type, body)
+fn respond(endpoints: Endpoints, path: &str, query: &str, state: &Mutex<ServerState>) -> (&'static str, &'static str, String) {
+    match endpoints {
+        Endpoints::Api => {
+            let (status, body) = route(path, query, state);
+            (status, "application/json", body)
+        }
+        Endpoints::Metrics => metrics(path, state),
+    }
+}
+
+/// A parsed HTTP request (just what our endpoints need)
+pub struct HttpRequest {
+    pub method: String,
+    pub target: String,
+    pub body: String,
+}
+
+/// Why a request could not be read
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RequestError {
+    Malformed,
+    /// The body is larger than `MAX_BODY_BYTES`
+    TooLarge,
+}
+
+impl RequestError {
+    /// Status line and JSON body to answer with
+    pub fn response(self) -> (&'static str, String) {
+        match self {
+            RequestError::Malformed => ("400 Bad Request", error_json("malformed request")),
+            RequestError::TooLarge => ("413 Content Too Large", error_json("request body too large")),
+        }
+    }
+}
+
+/// Content-Length of a header line, if that is what it is
+fn content_length(header: &str) -> Option<usize> {
+    let (name, value) = header.split_once(':')?;
+    name.trim().eq_ignore_ascii_case("content-length").then(|| value.trim().parse().unwrap_or(0))
+}
+
+/// Read one request head and, when Content-Length is given, its body
+pub fn read_request<S: Read>(stream: &mut S) -> Result<HttpRequest, RequestError> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).map_err(|_| RequestError::Malformed)?;
+
+    let mut length = 0usize;
+    let mut consumed = request_line.len();
+    let mut header = String::new();
+    while consumed < MAX_REQUEST_BYTES {
+        header.clear();
+        match reader.read_line(&mut header) {
+            Ok(0) | Err(_) => break,
+            Ok(_) if header.trim().is_empty() => break,
+            Ok(n) => {
+                consumed += n;
+                length = content_length(&header).unwrap_or(length);
+            }
+        }
+    }
+
+    if length > MAX_BODY_BYTES {
+        return Err(RequestError::TooLarge);
+    }
+    let mut body = vec![0u8; length];
+    reader.read_exact(&mut body).map_err(|_| RequestError::Malformed)?;
+
+    let mut parts = request_line.split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => Ok(HttpRequest {
+            method: method.to_string(),
+            target: target.to_string(),
+            body: String::from_utf8_lossy(&body).into_owned(),
+        }),
+        _ => Err(RequestError::Malformed),
+    }
+}
+
+/// `read_request` for the daemon's async listeners: buffer the head and body, then parse
+async fn read_request_async<S: AsyncRead + Unpin>(stream: &mut S) -> Result<HttpRequest, RequestError> {
+    let mut reader = tokio::io::BufReader::new(stream);
+    let mut raw = Vec::new();
+    let mut length = 0usize;
+    while raw.len() < MAX_REQUEST_BYTES {
+        let start = raw.len();
+        if reader.read_until(b'\n', &mut raw).await.map_err(|_| RequestError::Malformed)? == 0 {
+            break;
+        }
+        let line = String::from_utf8_lossy(&raw[start..]);
+        if line.trim().is_empty() && start > 0 {
+            break;
+        }
+        length = content_length(&line).unwrap_or(length);
+    }
+    if length > MAX_BODY_BYTES {
+        return Err(RequestError::TooLarge);
+    }
+    let mut body = vec![0u8; length];
+    reader.read_exact(&mut body).await.map_err(|_| RequestError::Malformed)?;
+    raw.extend_from_slice(&body);
+    read_request(&mut raw.as_slice())
+}
+
+async fn handle_connection<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S, endpoints: Endpoints, state: &Mutex<ServerState>) {
+    let request = time::timeout(REQUEST_TIMEOUT, read_request_async(&mut stream))
+        .await
+        .unwrap_or(Err(RequestError::Malformed));
+    let (status, content_type, body) = match request {
+        Err(e) => {
+            let (status, body) = e.response();
+            (status, "application/json", body)
+        }
+        Ok(request) if request.method != "GET" => ("405 Method Not Allowed", "application/json", error_json("only GET is supported")),
+        Ok(request) => {
+            let target = request.target.as_str();
+            let (path, query) = target.split_once('?').unwrap_or((target, ""));
+            respond(endpoints, path.trim_end_matches('/'), query, state)
+        }
+    };
+    let mut response = Vec::new();
+    write_typed_response(&mut response, status, content_type, &body);
+    let _ = stream.write_all(&response).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Take a sample and publish it to the request handlers and D-Bus
+fn sample_once(monitor: &mut BatteryMonitor, dbus: Option<&DbusPublisher>, state: &Mutex<ServerState>) {
+    let info = monitor.get_battery_info();
+    if let (Some(publisher), Some(info)) = (dbus, &info) {
+        if let Err(e) = publisher.publish(info) {
+            crate::warn(&format!("D-Bus update failed: {}", e));
+        }
+    }
+    let mut state = state.lock().unwrap();
+    if state.battery_name.is_empty() {
+        state.battery_name = monitor.battery_name().to_string();
+        state.data_dir = monitor.data_dir().map(PathBuf::from);
+        // Once, for the samples the monitor resumed; after that each sample only appends its reading
+        state.history = monitor.readings_history().clone();
+    } else if info.is_some() {
+        let history = monitor.readings_history();
+        state.history.extend(history.back().cloned());
+        let excess = state.history.len().saturating_sub(history.len());
+        state.history.drain(..excess);
+    }
+    if let Some(info) = info {
+        state.plasma = Some(plasma::document(&[(monitor, &info)]));
+        state.battery = Some(info);
+    }
+    state.sensors = SensorsSnapshot::of(monitor.temperature_monitor());
+}
+
+/// Sample every update interval until shutdown
+async fn run_sampler(
+    mut monitor: BatteryMonitor,
+    dbus: Option<DbusPublisher>,
+    state: Arc<Mutex<ServerState>>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    loop {
+        // sysfs reads and D-Bus calls block; keep them off the other tasks' worker
+        task::block_in_place(|| sample_once(&mut monitor, dbus.as_ref(), &state));
+        tokio::select! {
+            _ = shutdown.changed() => return,
+            _ = time::sleep(monitor.update_interval()) => {}
+        }
+    }
+}
+
+/// Accept connections from any of the daemon's listeners until shutdown
+macro_rules! accept_until_shutdown {
+    ($listener:expr, $endpoints:expr, $state:expr, $shutdown:expr) => {{
+        let (listener, endpoints, state, mut shutdown) = ($listener, $endpoints, $state, $shutdown);
+        async move {
+            loop {
+                tokio::select! {
+                    _ = shutdown.changed() => return,
+                    accepted = listener.accept() => {
+                        let Ok((stream, _)) = accepted else { continue };
+                        let state = Arc::clone(&state);
+                        tokio::spawn(async move { handle_connection(stream, endpoints, &state).await });
+                    }
+                }
+            }
+        }
+    }};
+}
+
+/// Resolves on Ctrl+C or SIGTERM (systemd stop)
+async fn shutdown_signal() {
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut stream) => {
+                stream.recv().await;
+            }
+            Err(_) => std::future::pending().await,
+        }
+    };
+    tokio::select! {
+        _ = signal::ctrl_c() => {}
+        _ = terminate => {}
+    }
+}
+
+/// What `serve` has bound or cleaned up, for the caller to announce
+#[derive(Debug, Clone, Copy)]
+pub enum ServeStatus<'a> {
+    /// The JSON API over TCP
+    Http(SocketAddr),
+    /// `/metrics` over TCP
+    Prometheus(SocketAddr),
+    /// The per-user Unix socket
+    Socket(&'a Path),
+    /// Shut down, with the socket removed
+    Stopped(&'a Path),
+}
+
+/// Sample continuously and serve the latest data until Ctrl+C or SIGTERM.
+///
+/// The API is always available on the per-user Unix socket, and additionally over
+/// TCP when `http_addr` is given (with the `http` feature). `prometheus_addr` serves only
+/// `/metrics`, never the JSON API. The sampler and every listener are tasks on one runtime that stop
+/// together, removing the socket on the way out. Each listener bound and the shutdown are
+/// passed to `status`.
+pub fn serve(
+    monitor: BatteryMonitor,
+    http_addr: Option<&str>,
+    prometheus_addr: Option<&str>,
+    dbus: Option<DbusPublisher>,
+    status: impl Fn(ServeStatus),
+) -> std::io::Result<()> {
+    let runtime = runtime::Builder::new_multi_thread().worker_threads(RUNTIME_WORKERS).enable_all().build()?;
+    runtime.block_on(serve_async(monitor, http_addr, prometheus_addr, dbus, &status))
+}
+
+#[cfg_attr(not(feature = "http"), allow(unused_variables))]
+async fn serve_async(
+    monitor: BatteryMonitor,
+    http_addr: Option<&str>,
+    prometheus_addr: Option<&str>,
+    dbus: Option<DbusPublisher>,
+    status: &dyn Fn(ServeStatus),
+) -> std::io::Result<()> {
+    // A socket left by a crashed run refuses connections and would make bind fail; one that
+    // accepts them belongs to a live daemon, which may just not have its first sample yet
+    let path = socket_path()?;
+    match UnixStream::connect(&path) {
+        Ok(_) => {
+            let message = format!("another batfi daemon is listening on {}", path.display());
+            return Err(std::io::Error::new(std::io::ErrorKind::AddrInUse, message));
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+            let _ = fs::remove_file(&path);
+        }
+        Err(_) => {}
+    }
+
+    let state = Arc::new(Mutex::new(ServerState::default()));
+    let (stop, shutdown) = watch::channel(false);
+    let mut tasks = JoinSet::new();
+
+    #[cfg(feature = "http")]
+    if let Some(addr) = http_addr {
+        let listener = TcpListener::bind(addr).await?;
+        status(ServeStatus::Http(listener.local_addr()?));
+        tasks.spawn(accept_until_shutdown!(listener, Endpoints::Api, Arc::clone(&state), shutdown.clone()));
+    }
+
+    if let Some(addr) = prometheus_addr {
+        let listener = TcpListener::bind(addr).await?;
+        status(ServeStatus::Prometheus(listener.local_addr()?));
+        tasks.spawn(accept_until_shutdown!(listener, Endpoints::Metrics, Arc::clone(&state), shutdown.clone()));
+    }
+
+    let listener = UnixListener::bind(&path)?;
+    status(ServeStatus::Socket(&path));
+    tasks.spawn(accept_until_shutdown!(listener, Endpoints::Api, Arc::clone(&state), shutdown.clone()));
+    tasks.spawn(run_sampler(monitor, dbus, Arc::clone(&state), shutdown));
+
+    shutdown_signal().await;
+    let _ = stop.send(true);
+    while tasks.join_next().await.is_some() {}
+    let _ = fs::remove_file(&path);
+    status(ServeStatus::Stopped(&path));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn exchange(request: &str, endpoints: Endpoints, state: &Mutex<ServerState>) -> String {
+        let (mut client, server) = tokio::io::duplex(MAX_REQUEST_BYTES);
+        client.write_all(request.as_bytes()).await.unwrap();
+        handle_connection(server, endpoints, state).await;
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn async_handler_routes_and_rejects() {
+        let state = Mutex::new(ServerState::default());
+        let response = exchange("GET /v1/sensors HTTP/1.1\r\nHost: x\r\n\r\n", Endpoints::Api, &state).await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        assert!(response.contains("\"cpu_sensors\""));
+
+        let response = exchange("GET /v1/battery/ HTTP/1.1\r\n\r\n", Endpoints::Api, &state).await;
+        assert!(response.starts_with("HTTP/1.1 503"));
+        state.lock().unwrap().battery = Some(BatteryInfo { capacity_percent: 42, power_w: Some(7.5), ..Default::default() });
+        let response = exchange("GET /metrics HTTP/1.1\r\n\r\n", Endpoints::Metrics, &state).await;
+        assert!(response.contains(prometheus::CONTENT_TYPE), "{}", response);
+        assert!(response.contains("batfi_capacity_percent{battery=\"\"} 42\n"));
+        assert!(response.contains("# TYPE batfi_power_watts gauge\nbatfi_power_watts{battery=\"\"} 7.5\n"));
+        // The body is drained before answering, so clients aren't reset mid-upload
+        let response = exchange("POST /v1/battery HTTP/1.1\r\nContent-Length: 4\r\n\r\n{}{}", Endpoints::Api, &state).await;
+        assert!(response.starts_with("HTTP/1.1 405"));
+        let response = exchange("POST /v1/battery HTTP/1.1\r\nContent-Length: 1000000\r\n\r\n", Endpoints::Api, &state).await;
+        assert!(response.starts_with("HTTP/1.1 413"), "{}", response);
+        let response = exchange("GET /v1/events?since=soon HTTP/1.1\r\n\r\n", Endpoints::Api, &state).await;
+        assert!(response.starts_with("HTTP/1.1 400"), "{}", response);
+        // The scrape port is not a way into the JSON API, nor the API into metrics
+        let response = exchange("GET /v1/history HTTP/1.1\r\n\r\n", Endpoints::Metrics, &state).await;
+        assert!(response.starts_with("HTTP/1.1 404"), "{}", response);
+        let response = exchange("GET /metrics HTTP/1.1\r\n\r\n", Endpoints::Api, &state).await;
+        assert!(response.starts_with("HTTP/1.1 404"), "{}", response);
+    }
+
+    #[test]
+    fn since_needs_a_unit_or_an_absolute_marker() {
+        let now = now_secs();
+        assert!(parse_since("10m").is_some_and(|since| now - since >= 600));
+        assert_eq!(parse_since("@1700000000"), Some(1_700_000_000));
+        assert_eq!(parse_since("2023-11-14T22:13:20Z"), Some(1_700_000_000));
+        // Seconds ago, or a timestamp? Either reading could be meant
+        assert_eq!(parse_since("600"), None);
+        assert_eq!(parse_since("1700000000"), None);
+        assert_eq!(parse_since("@10m"), None);
+    }
+}
@@ -0,0 +1,407 @@
+use std::ffi::CStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::attr::Attrs;
+use crate::standby::{read_s2idle_residency_us, SuspendRecord, SLEEP_HOOK_PATH};
+use crate::{cli, data_dir, health, parse_duration_secs, power_supply_path};
+
+const SERVICE_UNIT: &str = "batfi.service";
+const LOG_SERVICE_UNIT: &str = "batfi-log.service";
+const LOG_TIMER_UNIT: &str = "batfi-log.timer";
+
+/// Where units go: the per-user unit directory, or /etc/systemd/system
+fn unit_dir(user: bool) -> Option<PathBuf> {
+    if !user {
+        return Some(PathBuf::from("/etc/systemd/system"));
+    }
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("systemd/user"))
+}
+
+/// `batfi` plus the global options the units must keep: config file and profile
+fn base_args(matches: &clap::ArgMatches) -> Vec<String> {
+    let exe = std::env::current_exe()
+        .ok()
+        .and_then(|path| path.canonicalize().ok())
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|| "/usr/bin/batfi".to_string());
+    let mut args = vec![exe];
+    if let Some(path) = cli::selected_config(matches) {
+        let path = path.canonicalize().unwrap_or(path);
+        args.extend(["--config".to_string(), path.display().to_string()]);
+    }
+    if let Some(profile) = matches.get_one::<String>("profile") {
+        args.extend(["--profile".to_string(), profile.clone()]);
+    }
+    args
+}
+
+/// systemd splits ExecStart like a shell; quote only when needed
+fn quote(arg: &str) -> String {
+    if arg.chars().any(|c| c.is_whitespace() || c == '"' || c == '\\') {
+        format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        arg.to_string()
+    }
+}
+
+/// Quote for /bin/sh: nothing inside single quotes is special, and an embedded `'` closes the
+/// quotes, adds an escaped one and reopens them
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+fn service_unit(command: &str, user: bool) -> String {
+    let target = if user { "default.target" } else { "multi-user.target" };
+    format!(
+        "[Unit]\n\
+         Description=batfi battery monitor daemon\n\
+         \n\
+         [Service]\n\
+         Type=simple\n\
+         ExecStart={} daemon\n\
+         Restart=on-failure\n\
+         RestartSec=5\n\
+         \n\
+         [Install]\n\
+         WantedBy={}\n",
+        command, target
+    )
+}
+
+fn log_service_unit(command: &str, log_file: &Path) -> String {
+    format!(
+        "[Unit]\n\
+         Description=Append one batfi sample to {}\n\
+         \n\
+         [Service]\n\
+         Type=oneshot\n\
+         ExecStart={} --json status --local\n\
+         StandardOutput=append:{}\n",
+        log_file.display(),
+        command,
+        log_file.display()
+    )
+}
+
+fn log_timer_unit(interval_secs: u64) -> String {
+    format!(
+        "[Unit]\n\
+         Description=Periodic batfi sample log\n\
+         \n\
+         [Timer]\n\
+         OnBootSec=1min\n\
+         OnUnitActiveSec={}s\n\
+         AccuracySec=10s\n\
+         \n\
+         [Install]\n\
+         WantedBy=timers.target\n",
+        interval_secs
+    )
+}
+
+/// The account the sleep hook records suspends for, fixed at install time
+#[derive(Debug)]
+struct HookUser {
+    name: String,
+    uid: libc::uid_t,
+    data_home: PathBuf,
+}
+
+/// Name, uid and home directory from a passwd entry
+fn passwd_entry(entry: *const libc::passwd) -> Option<(String, libc::uid_t, PathBuf)> {
+    // SAFETY: getpwnam/getpwuid return null or a valid entry, read before the next lookup
+    let entry = unsafe { entry.as_ref()? };
+    let name = unsafe { CStr::from_ptr(entry.pw_name) }.to_str().ok()?.to_string();
+    let home = unsafe { CStr::from_ptr(entry.pw_dir) }.to_str().ok()?;
+    Some((name, entry.pw_uid, PathBuf::from(home)))
+}
+
+/// Whoever is installing: the user behind `sudo batfi install-service`, not root, whose
+/// data directory would otherwise be root's
+fn hook_user() -> Option<HookUser> {
+    if let Some(sudo_user) = std::env::var("SUDO_USER").ok().filter(|name| name != "root") {
+        let name = std::ffi::CString::new(sudo_user).ok()?;
+        let (name, uid, home) = passwd_entry(unsafe { libc::getpwnam(name.as_ptr()) })?;
+        return Some(HookUser { name, uid, data_home: home.join(".local/share") });
+    }
+    let (name, uid, _) = passwd_entry(unsafe { libc::getpwuid(libc::getuid()) })?;
+    let data_home = data_dir()?.parent()?.to_path_buf();
+    Some(HookUser { name, uid, data_home })
+}
+
+/// systemd-sleep hook recording the battery around every suspend. systemd-sleep runs it as
+/// root with a bare environment, so it drops to the installing user and points batfi at
+/// their data directory; files it creates there stay theirs.
+/// Every value is shell-quoted, since the hook runs as root.
+fn sleep_hook(args: &[String], user: &HookUser) -> String {
+    let env = format!("XDG_DATA_HOME={}", shell_quote(&user.data_home.display().to_string()));
+    let command: Vec<String> = args.iter().map(|arg| shell_quote(arg)).collect();
+    let command = command.join(" ");
+    let run = match user.uid {
+        0 => format!("{} exec {}", env, command),
+        _ => format!("exec runuser -u {} -- env {} {}", shell_quote(&user.name), env, command),
+    };
+    format!(
+        "#!/bin/sh\n\
+         # Written by batfi install-service: records standby drain for batfi health and report\n\
+         {} sleep-hook \"$1\" \"$2\"\n",
+        run
+    )
+}
+
+fn write_sleep_hook(hook: &str) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let path = Path::new(SLEEP_HOOK_PATH);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, hook)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+}
+
+/// Run systemctl, reporting (not failing on) errors so the units stay installed
+fn systemctl(user: bool, args: &[&str]) -> bool {
+    let mut command = Command::new("systemctl");
+    if user {
+        command.arg("--user");
+    }
+    match command.args(args).status() {
+        Ok(status) if status.success() => true,
+        Ok(status) => {
+            eprintln!("⚠️  systemctl {} exited with {}", args.join(" "), status);
+            false
+        }
+        Err(e) => {
+            eprintln!("⚠️  Could not run systemctl: {}", e);
+            false
+        }
+    }
+}
+
+/// `batfi install-service [--user] [--log-every DURATION]`
+pub fn run_install(install_matches: &clap::ArgMatches, matches: &clap::ArgMatches) {
+    let user = install_matches.get_flag("user");
+    let log_every = install_matches.get_one::<String>("log-every");
+    let log_secs = log_every.map(|text| match parse_duration_secs(text).filter(|secs| *secs >= 60) {
+        Some(secs) => secs,
+        None => {
+            eprintln!("❌ Invalid --log-every '{}' (at least 1m, e.g. 5m or 1h)", text);
+            std::process::exit(1);
+        }
+    });
+
+    let args = base_args(matches);
+    let command: Vec<String> = args.iter().map(|arg| quote(arg)).collect();
+    let command = command.join(" ");
+    let log_file = data_dir().unwrap_or_else(|| PathBuf::from("/var/lib/batfi")).join("status-log.jsonl");
+    let mut units = vec![(SERVICE_UNIT, service_unit(&command, user))];
+    if let Some(secs) = log_secs {
+        units.push((LOG_SERVICE_UNIT, log_service_unit(&command, &log_file)));
+        units.push((LOG_TIMER_UNIT, log_timer_unit(secs)));
+    }
+
+    let hook = install_matches.get_flag("sleep-hook").then(|| match hook_user() {
+        Some(user) => sleep_hook(&args, &user),
+        None => {
+            eprintln!("❌ Could not tell which user the sleep hook should record suspends for");
+            std::process::exit(1);
+        }
+    });
+
+    if install_matches.get_flag("print") {
+        for (name, unit) in &units {
+            println!("# {}\n{}", name, unit);
+        }
+        if let Some(hook) = &hook {
+            println!("# {}\n{}", SLEEP_HOOK_PATH, hook);
+        }
+        return;
+    }
+
+    let Some(dir) = unit_dir(user) else {
+        eprintln!("❌ Could not find the systemd user unit directory (HOME is not set)");
+        std::process::exit(1);
+    };
+    if let Err(e) = fs::create_dir_all(&dir) {
+        eprintln!("❌ Could not create {}: {}", dir.display(), e);
+        std::process::exit(1);
+    }
+    for (name, unit) in &units {
+        let path = dir.join(name);
+        if let Err(e) = fs::write(&path, unit) {
+            let hint = if user { "" } else { " (run as root, or use --user)" };
+            eprintln!("❌ Could not write {}: {}{}", path.display(), e, hint);
+            std::process::exit(1);
+        }
+        println!("✅ Wrote {}", path.display());
+    }
+    if let Some(hook) = &hook {
+        if let Err(e) = write_sleep_hook(hook) {
+            eprintln!("❌ Could not write {}: {} (run as root)", SLEEP_HOOK_PATH, e);
+            std::process::exit(1);
+        }
+        println!("✅ Wrote {}", SLEEP_HOOK_PATH);
+    }
+    if log_secs.is_some() {
+        if let Some(parent) = log_file.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+    }
+
+    // The oneshot log service is started by its timer, not enabled itself
+    let mut enable = vec![SERVICE_UNIT];
+    if log_secs.is_some() {
+        enable.push(LOG_TIMER_UNIT);
+    }
+    let mut args = vec!["enable", "--now"];
+    args.extend(&enable);
+    if systemctl(user, &["daemon-reload"]) && systemctl(user, &args) {
+        println!("🚀 Enabled and started {}", enable.join(", "));
+        if log_secs.is_some() {
+            println!("📝 Logging a sample to {}", log_file.display());
+        }
+    } else {
+        let scope = if user { "--user " } else { "" };
+        println!("   Enable it yourself with: systemctl {}daemon-reload && systemctl {}enable --now {}", scope, scope, enable.join(" "));
+    }
+}
+
+/// `batfi uninstall-service [--user]`: stop, disable and remove what install-service wrote
+pub fn run_uninstall(uninstall_matches: &clap::ArgMatches) {
+    let user = uninstall_matches.get_flag("user");
+    let Some(dir) = unit_dir(user) else {
+        eprintln!("❌ Could not find the systemd user unit directory (HOME is not set)");
+        std::process::exit(1);
+    };
+    let installed: Vec<&str> = [LOG_TIMER_UNIT, LOG_SERVICE_UNIT, SERVICE_UNIT]
+        .into_iter()
+        .filter(|name| dir.join(name).exists())
+        .collect();
+    // The sleep hook is system-wide, so only the system-wide uninstall removes it
+    let hook = Path::new(SLEEP_HOOK_PATH);
+    if !user && hook.exists() {
+        match fs::remove_file(hook) {
+            Ok(()) => println!("🗑️  Removed {}", hook.display()),
+            Err(e) => eprintln!("❌ Could not remove {}: {}", hook.display(), e),
+        }
+    }
+    if installed.is_empty() {
+        println!("ℹ️  No batfi units in {}", dir.display());
+        return;
+    }
+
+    let mut args = vec!["disable", "--now"];
+    args.extend(&installed);
+    systemctl(user, &args);
+    for name in &installed {
+        let path = dir.join(name);
+        match fs::remove_file(&path) {
+            Ok(()) => println!("🗑️  Removed {}", path.display()),
+            Err(e) => {
+                eprintln!("❌ Could not remove {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+    systemctl(user, &["daemon-reload"]);
+}
+
+/// The battery as the hook left it before suspending
+#[derive(Debug, Serialize, Deserialize)]
+struct SleepMark {
+    battery: String,
+    timestamp: u64,
+    energy_wh: Option<f64>,
+    capacity: u8,
+    discharging: bool,
+    residency_us: Option<u64>,
+}
+
+impl SleepMark {
+    fn read(battery: &str) -> Self {
+        let base_path = power_supply_path(battery);
+        let attrs = Attrs::new(|name: &str| fs::read_to_string(format!("{}/{}", base_path, name)).ok().map(|s| s.trim().to_string()));
+        let status = fs::read_to_string(format!("{}/status", base_path)).unwrap_or_default();
+        Self {
+            battery: battery.to_string(),
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+            energy_wh: attrs.energy_wh("now"),
+            capacity: attrs.integer("capacity").unwrap_or(0),
+            discharging: status.trim() == "Discharging",
+            residency_us: read_s2idle_residency_us(),
+        }
+    }
+}
+
+fn sleep_mark_path(dir: &Path) -> PathBuf {
+    dir.join("sleep-mark.json")
+}
+
+/// `batfi sleep-hook pre|post`, run by systemd-sleep: note the battery before suspending,
+/// then record the suspend in the health history after resuming. Never fails loudly;
+/// a hook must not hold up suspend.
+pub fn run_sleep_hook(battery: &str, hook_matches: &clap::ArgMatches) {
+    let Some(dir) = data_dir() else { return };
+    let path = sleep_mark_path(&dir);
+    let now = SleepMark::read(battery);
+    match hook_matches.get_one::<String>("phase").map(String::as_str) {
+        Some("pre") => {
+            let written = fs::create_dir_all(path.parent().unwrap_or(&path))
+                .and_then(|_| fs::write(&path, serde_json::to_string(&now).unwrap_or_default()));
+            if let Err(e) = written {
+                eprintln!("⚠️  Could not write {}: {}", path.display(), e);
+            }
+        }
+        Some("post") => {
+            let mark = fs::read_to_string(&path).ok().and_then(|text| serde_json::from_str::<SleepMark>(&text).ok());
+            let _ = fs::remove_file(&path);
+            let Some(before) = mark.filter(|mark| mark.battery == battery && mark.timestamp < now.timestamp) else { return };
+            let record = SuspendRecord::new(
+                (before.timestamp, now.timestamp),
+                (before.residency_us, now.residency_us),
+                (before.energy_wh, now.energy_wh),
+                (before.capacity, now.capacity),
+                before.discharging && now.discharging,
+            );
+            let sample = health::HealthSample { timestamp: now.timestamp, battery: battery.to_string(), suspend: Some(record), ..Default::default() };
+            if let Err(e) = health::append_health_sample(Some(&dir), &sample) {
+                eprintln!("⚠️  Could not record the suspend: {}", e);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sleep_hook_runs_as_the_installing_user() {
+        let user = HookUser { name: "alice".to_string(), uid: 1000, data_home: PathBuf::from("/home/alice/.local/share") };
+        let hook = sleep_hook(&["/usr/bin/batfi".to_string()], &user);
+        assert!(hook.ends_with(
+            "exec runuser -u 'alice' -- env XDG_DATA_HOME='/home/alice/.local/share' '/usr/bin/batfi' sleep-hook \"$1\" \"$2\"\n"
+        ));
+
+        let root = HookUser { name: "root".to_string(), uid: 0, data_home: PathBuf::from("/root/.local/share") };
+        assert!(!sleep_hook(&["/usr/bin/batfi".to_string()], &root).contains("runuser"));
+    }
+
+    #[test]
+    fn sleep_hook_values_stay_literal_in_the_shell() {
+        let user = HookUser { name: "alice".to_string(), uid: 1000, data_home: PathBuf::from("/home/alice/$(id)") };
+        let args = ["/usr/bin/batfi", "--profile", "x'; rm -rf / #`id`"].map(String::from);
+        let hook = sleep_hook(&args, &user);
+        assert!(hook.contains("XDG_DATA_HOME='/home/alice/$(id)'"));
+        assert!(hook.contains("'--profile' 'x'\\''; rm -rf / #`id`'"));
+    }
+}
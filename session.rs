@@ -0,0 +1,246 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::BatteryInfo;
+
+/// Energy rise (Wh) between runs that means the pack was charged while we weren't watching
+const RECHARGED_WH: f64 = 0.5;
+/// Samples further apart than this are bridged with the gauge's energy delta instead of power
+const MAX_INTEGRATION_GAP_SECS: u64 = 60;
+/// Charging stints shorter than this are not worth a history entry
+const MIN_SESSION_SECS: u64 = 60;
+
+/// One finished charging session, as recorded in the health history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChargeSession {
+    pub start: u64,
+    pub duration_secs: u64,
+    pub start_percent: u8,
+    pub end_percent: u8,
+    pub energy_added_wh: f64,
+    pub average_power_w: f64,
+}
+
+/// The charging session in progress
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveCharge {
+    pub start: u64,
+    pub start_percent: u8,
+    pub energy_added_wh: f64,
+    last_sample: u64,
+    last_energy_wh: Option<f64>,
+}
+
+/// Persisted plug state of one battery: when it was last full and when the current discharge began
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionTracker {
+    battery: String,
+    last_status: String,
+    /// Last time the pack reported Full (or 100%) while on AC
+    pub last_full: Option<u64>,
+    /// Start of the current discharge, with the energy and capacity at that point
+    pub unplugged_at: Option<u64>,
+    pub unplugged_energy_wh: Option<f64>,
+    pub unplugged_capacity: Option<u8>,
+    pub charging: Option<ActiveCharge>,
+    /// Where `save` writes the state; None keeps it in memory
+    #[serde(skip)]
+    dir: Option<PathBuf>,
+}
+
+impl SessionTracker {
+    fn path(dir: Option<&Path>, battery: &str) -> Option<PathBuf> {
+        Some(dir?.join(format!("session-{}.json", battery)))
+    }
+
+    /// Load the persisted state for a battery, or start empty
+    pub fn load(dir: Option<&Path>, battery: &str) -> Self {
+        let mut session = Self::path(dir, battery)
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str::<Self>(&json).ok())
+            .unwrap_or_else(|| Self { battery: battery.to_string(), ..Default::default() });
+        session.dir = dir.map(Path::to_path_buf);
+        session
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Self::path(self.dir.as_deref(), &self.battery)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no data directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string(self).map_err(std::io::Error::other)?;
+        fs::write(path, json)
+    }
+
+    /// Feed one sample; the state is only written back on plug/full transitions.
+    /// Returns the charging session that just ended, if any.
+    pub fn update(
+        &mut self,
+        timestamp: u64,
+        status: &str,
+        capacity: u8,
+        energy_now_wh: Option<f64>,
+        power_w: Option<f64>,
+    ) -> Option<ChargeSession> {
+        let full = status == "Full" || (capacity >= 100 && status != "Discharging");
+        let was_full = self.last_status == "Full";
+        let mut changed = self.last_status != status;
+
+        if full || was_full {
+            // Leaving Full also counts: that is the moment it was last full
+            self.last_full = Some(timestamp);
+        }
+
+        if status == "Discharging" {
+            let recharged = match (self.unplugged_energy_wh, energy_now_wh) {
+                (Some(start), Some(now)) => now > start + RECHARGED_WH,
+                _ => false,
+            };
+            if self.unplugged_at.is_none() || self.last_status != "Discharging" || recharged {
+                self.unplugged_at = Some(timestamp);
+                self.unplugged_energy_wh = energy_now_wh;
+                self.unplugged_capacity = Some(capacity);
+                changed = true;
+            }
+        } else if self.unplugged_at.is_some() {
+            self.unplugged_at = None;
+            self.unplugged_energy_wh = None;
+            self.unplugged_capacity = None;
+            changed = true;
+        }
+
+        let mut finished = None;
+        if status == "Charging" {
+            match self.charging.as_mut() {
+                Some(active) => active.add_sample(timestamp, energy_now_wh, power_w),
+                None => {
+                    self.charging = Some(ActiveCharge {
+                        start: timestamp,
+                        start_percent: capacity,
+                        energy_added_wh: 0.0,
+                        last_sample: timestamp,
+                        last_energy_wh: energy_now_wh,
+                    });
+                    changed = true;
+                }
+            }
+        } else if let Some(mut active) = self.charging.take() {
+            active.add_sample(timestamp, energy_now_wh, power_w);
+            finished = active.finish(timestamp, capacity);
+            changed = true;
+        }
+
+        self.last_status = status.to_string();
+        if changed {
+            let _ = self.save();
+        }
+        finished
+    }
+
+    /// Seconds since the pack was last full, 0 while it still is
+    pub fn secs_since_full(&self, now: u64) -> Option<u64> {
+        if self.last_status == "Full" {
+            return Some(0);
+        }
+        Some(now.saturating_sub(self.last_full?))
+    }
+
+    /// Seconds on battery in the current discharge
+    pub fn secs_unplugged(&self, now: u64) -> Option<u64> {
+        Some(now.saturating_sub(self.unplugged_at?))
+    }
+
+    /// The charging session in progress, if any
+    pub fn active_charge(&self) -> Option<&ActiveCharge> {
+        self.charging.as_ref()
+    }
+
+    /// Energy drawn from the pack since it was unplugged
+    pub fn energy_since_unplug_wh(&self, energy_now_wh: Option<f64>) -> Option<f64> {
+        Some((self.unplugged_energy_wh? - energy_now_wh?).max(0.0))
+    }
+}
+
+impl ActiveCharge {
+    /// Integrate charging power since the previous sample, bridging long gaps
+    /// (e.g. batfi was not running) with the rise of the gauge's energy reading
+    fn add_sample(&mut self, timestamp: u64, energy_now_wh: Option<f64>, power_w: Option<f64>) {
+        let dt = timestamp.saturating_sub(self.last_sample);
+        let added = match (power_w, self.last_energy_wh, energy_now_wh) {
+            (Some(power), _, _) if dt <= MAX_INTEGRATION_GAP_SECS => power.abs() * dt as f64 / 3600.0,
+            (_, Some(last), Some(now)) => (now - last).max(0.0),
+            _ => 0.0,
+        };
+        self.energy_added_wh += added;
+        self.last_sample = timestamp;
+        if energy_now_wh.is_some() {
+            self.last_energy_wh = energy_now_wh;
+        }
+    }
+
+    fn finish(self, timestamp: u64, end_percent: u8) -> Option<ChargeSession> {
+        let duration_secs = timestamp.saturating_sub(self.start);
+        if duration_secs < MIN_SESSION_SECS {
+            return None;
+        }
+        Some(ChargeSession {
+            start: self.start,
+            duration_secs,
+            start_percent: self.start_percent,
+            end_percent,
+            energy_added_wh: self.energy_added_wh,
+            average_power_w: self.energy_added_wh / (duration_secs as f64 / 3600.0),
+        })
+    }
+}
+
+/// Totals for one `batfi watch` run, for the summary and the on-exit hook
+#[derive(Debug, Clone, Default)]
+pub struct RunSummary {
+    start: Option<u64>,
+    last_sample: Option<u64>,
+    pub samples: u32,
+    pub start_percent: Option<u8>,
+    pub end_percent: Option<u8>,
+    /// Discharging energy integrated from power readings
+    pub energy_used_wh: f64,
+    power_sum_w: f64,
+    power_samples: u32,
+}
+
+impl RunSummary {
+    pub fn record(&mut self, timestamp: u64, info: &BatteryInfo) {
+        let power = info.power_w.map(f64::abs);
+        if let (Some(last), Some(power)) = (self.last_sample, power) {
+            let dt = timestamp.saturating_sub(last);
+            if info.status == "Discharging" && dt <= MAX_INTEGRATION_GAP_SECS {
+                self.energy_used_wh += power * dt as f64 / 3600.0;
+            }
+        }
+        if let Some(power) = power {
+            self.power_sum_w += power;
+            self.power_samples += 1;
+        }
+        self.start.get_or_insert(timestamp);
+        self.start_percent.get_or_insert(info.capacity_percent);
+        self.end_percent = Some(info.capacity_percent);
+        self.last_sample = Some(timestamp);
+        self.samples += 1;
+    }
+
+    pub fn duration_secs(&self) -> u64 {
+        match (self.start, self.last_sample) {
+            (Some(start), Some(last)) => last.saturating_sub(start),
+            _ => 0,
+        }
+    }
+
+    /// Mean of the raw power readings, charging or not
+    pub fn average_power_w(&self) -> Option<f64> {
+        (self.power_samples > 0).then(|| self.power_sum_w / self.power_samples as f64)
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use crate::backend::PowerSupplyBackend;
+
+/// Device name simulated runs report under
+pub const BATTERY_NAME: &str = "SIM0";
+pub const SCENARIOS: [&str; 3] = ["discharge", "charge", "cycle"];
+
+const FULL_WH: f64 = 50.0;
+const DESIGN_WH: f64 = 57.0;
+const CELLS_IN_SERIES: f64 = 3.0;
+/// Pack internal resistance, for the sag under load
+const RESISTANCE_OHM: f64 = 0.15;
+/// Open-circuit cell voltage by state of charge (percent, volts)
+const CELL_OCV: [(f64, f64); 8] = [
+    (0.0, 3.0),
+    (5.0, 3.45),
+    (10.0, 3.6),
+    (20.0, 3.7),
+    (40.0, 3.8),
+    (60.0, 3.9),
+    (80.0, 4.05),
+    (100.0, 4.2),
+];
+/// Constant-power charging until here, then the charger tapers off
+const TAPER_START_PERCENT: f64 = 80.0;
+/// `cycle` turns around at these levels
+const CYCLE_LOW_PERCENT: f64 = 10.0;
+/// Samples spent at Full before `cycle` unplugs again
+const CYCLE_FULL_SAMPLES: u32 = 5;
+pub const DEFAULT_DISCHARGE_W: f64 = 12.0;
+pub const DEFAULT_CHARGE_W: f64 = 45.0;
+/// Same seed every run, so a scenario replays identically
+const SEED: u64 = 0x5eed_ba7f;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Discharging,
+    Charging,
+    Full,
+}
+
+impl Phase {
+    fn status(&self) -> &'static str {
+        match self {
+            Phase::Discharging => "Discharging",
+            Phase::Charging => "Charging",
+            Phase::Full => "Full",
+        }
+    }
+}
+
+/// A synthetic pack fed through the normal pipeline: noisy load, a Li-ion voltage
+/// curve with sag, CC/CV charging and status transitions
+pub struct SimulatedBackend {
+    scenario: String,
+    energy_wh: f64,
+    discharge_w: f64,
+    charge_w: f64,
+    step_secs: f64,
+    phase: Phase,
+    full_samples: u32,
+    rng: u64,
+    attrs: HashMap<&'static str, String>,
+}
+
+/// "12W", "12w" or "12" → 12.0
+pub fn parse_rate(text: &str) -> Option<f64> {
+    text.trim().trim_end_matches(['W', 'w']).trim().parse().ok().filter(|w: &f64| *w > 0.0 && *w < 500.0)
+}
+
+impl SimulatedBackend {
+    /// `rate_w` is the scenario's own direction (the charger for `charge`, the load otherwise);
+    /// `step_secs` is simulated time per sample (update interval × speed)
+    pub fn new(scenario: &str, from_percent: f64, rate_w: Option<f64>, step_secs: f64) -> Self {
+        let charging = scenario == "charge";
+        let phase = if charging { Phase::Charging } else { Phase::Discharging };
+        Self {
+            scenario: scenario.to_string(),
+            energy_wh: FULL_WH * from_percent.clamp(0.0, 100.0) / 100.0,
+            discharge_w: rate_w.filter(|_| !charging).unwrap_or(DEFAULT_DISCHARGE_W),
+            charge_w: rate_w.filter(|_| charging).unwrap_or(DEFAULT_CHARGE_W),
+            step_secs,
+            phase,
+            full_samples: 0,
+            rng: SEED,
+            attrs: HashMap::new(),
+        }
+    }
+
+    /// xorshift64: no dependency, and the sequence is fixed by SEED
+    fn next_unit(&mut self) -> f64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (self.rng >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Roughly normal noise (Irwin–Hall) with the given standard deviation
+    fn noise(&mut self, sigma: f64) -> f64 {
+        ((0..12).map(|_| self.next_unit()).sum::<f64>() - 6.0) * sigma
+    }
+
+    fn percent(&self) -> f64 {
+        self.energy_wh / FULL_WH * 100.0
+    }
+
+    fn open_circuit_voltage(&self) -> f64 {
+        let soc = self.percent();
+        let cell = CELL_OCV
+            .windows(2)
+            .find(|pair| soc <= pair[1].0)
+            .map(|pair| {
+                let ((s0, v0), (s1, v1)) = (pair[0], pair[1]);
+                v0 + (v1 - v0) * (soc - s0) / (s1 - s0)
+            })
+            .unwrap_or(CELL_OCV[CELL_OCV.len() - 1].1);
+        cell * CELLS_IN_SERIES
+    }
+
+    /// Power into (+) or out of (−) the pack for the next step
+    fn next_power(&mut self) -> f64 {
+        match self.phase {
+            Phase::Discharging => {
+                // Load noise plus the odd burst of activity
+                let burst = if self.next_unit() < 0.05 { self.discharge_w * 0.8 } else { 0.0 };
+                -(self.discharge_w + self.noise(self.discharge_w * 0.05) + burst).max(0.5)
+            }
+            Phase::Charging => {
+                let taper = if self.percent() > TAPER_START_PERCENT {
+                    ((100.0 - self.percent()) / (100.0 - TAPER_START_PERCENT)).max(0.05)
+                } else {
+                    1.0
+                };
+                (self.charge_w * taper + self.noise(0.2)).max(0.2)
+            }
+            Phase::Full => 0.0,
+        }
+    }
+
+    fn advance_phase(&mut self) {
+        let percent = self.percent();
+        self.phase = match (self.scenario.as_str(), self.phase) {
+            (_, Phase::Charging) if percent >= 100.0 => Phase::Full,
+            ("cycle", Phase::Discharging) if percent <= CYCLE_LOW_PERCENT => Phase::Charging,
+            ("cycle", Phase::Full) if self.full_samples >= CYCLE_FULL_SAMPLES => Phase::Discharging,
+            (_, phase) => phase,
+        };
+        self.full_samples = if self.phase == Phase::Full { self.full_samples + 1 } else { 0 };
+    }
+}
+
+impl PowerSupplyBackend for SimulatedBackend {
+    fn refresh(&mut self) {
+        self.advance_phase();
+        let mut power = self.next_power();
+        if self.phase == Phase::Discharging && self.energy_wh <= 0.0 {
+            power = 0.0; // Flat: a real machine would have shut down
+        }
+        self.energy_wh = (self.energy_wh + power * self.step_secs / 3600.0).clamp(0.0, FULL_WH);
+
+        let ocv = self.open_circuit_voltage();
+        let current_a = power / ocv;
+        let voltage = ocv + current_a * RESISTANCE_OHM; // Sags under load, rises while charging
+
+        let micro = |value: f64| ((value * 1_000_000.0).round() as i64).to_string();
+        self.attrs = HashMap::from([
+            ("status", self.phase.status().to_string()),
+            ("capacity", (self.percent().round() as u8).to_string()),
+            ("energy_now", micro(self.energy_wh)),
+            ("energy_full", micro(FULL_WH)),
+            ("energy_full_design", micro(DESIGN_WH)),
+            ("power_now", micro(power.abs())),
+            ("voltage_now", micro(voltage)),
+            ("current_now", micro(current_a)),
+            ("cycle_count", "123".to_string()),
+            ("manufacturer", "batfi".to_string()),
+            ("model_name", "Simulated".to_string()),
+            ("technology", "Li-ion".to_string()),
+        ]);
+    }
+
+    fn exists(&self) -> bool {
+        true
+    }
+
+    fn read_attr(&self, attr: &str) -> Option<String> {
+        self.attrs.get(attr).cloned()
+    }
+}
@@ -0,0 +1,71 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use crate::alerts::{Alert, AlertChannel, Severity};
+
+/// Players tried in order when none is configured
+const DEFAULT_PLAYERS: [&str; 3] = ["paplay", "pw-play", "aplay"];
+
+/// Rings the terminal bell (BEL) for alerts at or above a severity
+pub struct BellChannel {
+    min_severity: Severity,
+}
+
+impl BellChannel {
+    pub fn new(min_severity: Severity) -> Self {
+        Self { min_severity }
+    }
+}
+
+impl AlertChannel for BellChannel {
+    fn send(&self, alert: &Alert) {
+        if alert.severity < self.min_severity {
+            return;
+        }
+        // stderr keeps --json and --format output on stdout clean
+        let mut stderr = std::io::stderr();
+        let _ = stderr.write_all(b"\x07");
+        let _ = stderr.flush();
+    }
+}
+
+/// Plays a sound file through an external player for alerts at or above a severity
+pub struct SoundChannel {
+    file: String,
+    player: Vec<String>,
+    min_severity: Severity,
+}
+
+impl SoundChannel {
+    /// `player` may carry arguments (e.g. "mpv --really-quiet"); None picks the first installed default
+    pub fn new(file: &str, player: Option<&str>, min_severity: Severity) -> Option<Self> {
+        let player = match player {
+            Some(command) => command.split_whitespace().map(String::from).collect(),
+            None => vec![DEFAULT_PLAYERS.iter().find(|name| on_path(name))?.to_string()],
+        };
+        Some(Self { file: file.to_string(), player, min_severity })
+    }
+}
+
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+impl AlertChannel for SoundChannel {
+    fn send(&self, alert: &Alert) {
+        if alert.severity < self.min_severity || self.player.is_empty() {
+            return;
+        }
+        let mut command = Command::new(&self.player[0]);
+        command.args(&self.player[1..]).arg(&self.file).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+        match command.spawn() {
+            // Reap the player in the background so a long sound never stalls sampling
+            Ok(mut child) => {
+                thread::spawn(move || child.wait());
+            }
+            Err(e) => crate::warn(&format!("Could not run {}: {}", self.player[0], e)),
+        }
+    }
+}
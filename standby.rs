@@ -0,0 +1,191 @@
+//! Whether suspend actually reached deep idle (S0ix) and what standby cost.
+//!
+//! Modern-standby laptops "suspend" to s2idle, and only drain little if the SoC then
+//! drops into S0ix. One misbehaving device keeps it out and the battery is gone by
+//! morning. The low-power residency counters tell the two apart across a suspend gap.
+//!
+//! Suspends are recorded by the monitor when it sees a sampling gap, or, with the
+//! systemd-sleep hook from `batfi install-service --sleep-hook`, right at suspend and
+//! resume whether or not anything is running.
+
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::sysfs_path;
+
+/// Share of a suspend spent in S0ix that counts as having reached deep idle
+pub const DEEP_IDLE_PERCENT: f64 = 80.0;
+/// Suspends summarized in reports
+const SUMMARY_SUSPENDS: usize = 10;
+
+/// Residency counters in µs, most portable first: the kernel's own hardware sleep
+/// total (6.5+), Intel's cpuidle LPI counter, then the debugfs ones that need root
+const RESIDENCY_COUNTERS: [&str; 3] = [
+    "power/suspend_stats/total_hw_sleep",
+    "devices/system/cpu/cpuidle/low_power_idle_system_residency_us",
+    "kernel/debug/pmc_core/slp_s0_residency_usec",
+];
+const AMD_PMC_STATS: &str = "kernel/debug/amd_pmc/s0ix_stats";
+
+/// Total time spent in S0ix so far (µs), and the counter it came from
+pub fn read_residency_us() -> Option<(u64, &'static str)> {
+    for counter in RESIDENCY_COUNTERS {
+        if let Some(us) = fs::read_to_string(sysfs_path(counter)).ok().and_then(|s| s.trim().parse().ok()) {
+            return Some((us, counter));
+        }
+    }
+    let stats = fs::read_to_string(sysfs_path(AMD_PMC_STATS)).ok()?;
+    parse_amd_pmc(&stats).map(|us| (us, AMD_PMC_STATS))
+}
+
+/// "Total Time (in us) in S0i3: 1234" from amd_pmc, or the older "Time (in us) in S0i3:"
+fn parse_amd_pmc(stats: &str) -> Option<u64> {
+    let line = stats.lines().find(|line| line.contains("Total Time (in us) in S0i3")).or_else(|| {
+        stats.lines().find(|line| line.contains("Time (in us) in S0i3"))
+    })?;
+    line.rsplit(':').next()?.trim().parse().ok()
+}
+
+/// The S0ix residency counter, or None when suspend goes to S3 ("deep"), which has no
+/// S0ix to miss
+pub fn read_s2idle_residency_us() -> Option<u64> {
+    let (us, _) = read_residency_us()?;
+    (mem_sleep_mode().as_deref() != Some("deep")).then_some(us)
+}
+
+/// The active suspend mode from /sys/power/mem_sleep, e.g. "s2idle" or "deep"
+pub fn mem_sleep_mode() -> Option<String> {
+    let modes = fs::read_to_string(sysfs_path("power/mem_sleep")).ok()?;
+    let start = modes.find('[')? + 1;
+    let end = start + modes[start..].find(']')?;
+    Some(modes[start..end].to_string())
+}
+
+/// One suspend gap, as recorded in the health history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SuspendRecord {
+    pub start: u64,
+    pub duration_secs: u64,
+    /// Share of the gap spent in S0ix, when a residency counter is readable
+    pub deep_idle_percent: Option<f64>,
+    /// Average drain over the gap, when it was spent on battery
+    pub drain_w: Option<f64>,
+    pub drain_percent_per_hour: Option<f64>,
+}
+
+impl SuspendRecord {
+    /// A gap between two samples; residency is in µs at either end
+    pub fn new(
+        (start, end): (u64, u64),
+        residency_us: (Option<u64>, Option<u64>),
+        energy_wh: (Option<f64>, Option<f64>),
+        (start_percent, end_percent): (u8, u8),
+        on_battery: bool,
+    ) -> Self {
+        let duration_secs = end.saturating_sub(start);
+        let hours = duration_secs as f64 / 3600.0;
+        let deep_idle_percent = match residency_us {
+            (Some(before), Some(after)) if duration_secs > 0 && after >= before => {
+                Some(((after - before) as f64 / (duration_secs as f64 * 1_000_000.0) * 100.0).min(100.0))
+            }
+            _ => None,
+        };
+        let drain_w = match energy_wh {
+            (Some(before), Some(after)) if on_battery && hours > 0.0 => Some(((before - after) / hours).max(0.0)),
+            _ => None,
+        };
+        let drain_percent_per_hour =
+            (on_battery && hours > 0.0).then(|| (start_percent.saturating_sub(end_percent)) as f64 / hours);
+        Self { start, duration_secs, deep_idle_percent, drain_w, drain_percent_per_hour }
+    }
+
+    pub fn reached_deep_idle(&self) -> Option<bool> {
+        self.deep_idle_percent.map(|percent| percent >= DEEP_IDLE_PERCENT)
+    }
+}
+
+/// Recent suspends at a glance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StandbySummary {
+    pub suspends: usize,
+    /// Suspends with a residency reading, and how many of those reached deep idle
+    pub measured: usize,
+    pub reached_deep_idle: usize,
+    pub deep_idle_percent: Option<f64>,
+    pub drain_w: Option<f64>,
+    pub drain_percent_per_hour: Option<f64>,
+}
+
+/// Duration-weighted averages over the latest suspends, newest last in `records`
+pub fn summarize<'a>(records: impl DoubleEndedIterator<Item = &'a SuspendRecord>) -> Option<StandbySummary> {
+    let recent: Vec<&SuspendRecord> = records.rev().take(SUMMARY_SUSPENDS).collect();
+    if recent.is_empty() {
+        return None;
+    }
+    let weighted = |value: fn(&SuspendRecord) -> Option<f64>| {
+        let (sum, secs) = recent.iter().filter_map(|r| Some((value(r)? * r.duration_secs as f64, r.duration_secs as f64)))
+            .fold((0.0, 0.0), |(sum, secs), (v, d)| (sum + v, secs + d));
+        (secs > 0.0).then(|| sum / secs)
+    };
+    Some(StandbySummary {
+        suspends: recent.len(),
+        measured: recent.iter().filter(|r| r.deep_idle_percent.is_some()).count(),
+        reached_deep_idle: recent.iter().filter(|r| r.reached_deep_idle() == Some(true)).count(),
+        deep_idle_percent: weighted(|r| r.deep_idle_percent),
+        drain_w: weighted(|r| r.drain_w),
+        drain_percent_per_hour: weighted(|r| r.drain_percent_per_hour),
+    })
+}
+
+/// "9 of 10 suspends reached deep idle (94% of the time), 0.6 W / 1.1%/h" for reports
+pub fn summary_line(summary: &StandbySummary) -> String {
+    let idle = match (summary.measured, summary.deep_idle_percent) {
+        (0, _) | (_, None) => format!("{} suspend(s), S0ix residency not readable", summary.suspends),
+        (measured, Some(percent)) => format!(
+            "{} of {} suspends reached deep idle ({:.0}% of the time)",
+            summary.reached_deep_idle, measured, percent
+        ),
+    };
+    match (summary.drain_w, summary.drain_percent_per_hour) {
+        (Some(w), Some(pct)) => format!("{}, {:.1} W / {:.1}%/h drain", idle, w, pct),
+        (None, Some(pct)) => format!("{}, {:.1}%/h drain", idle, pct),
+        _ => idle,
+    }
+}
+
+/// Where `install-service --sleep-hook` puts the hook; systemd-sleep runs everything here
+/// with "pre" or "post" before suspending and after resuming
+pub const SLEEP_HOOK_PATH: &str = "/usr/lib/systemd/system-sleep/batfi";
+
+/// Whether the sleep hook records suspends, so the monitor shouldn't record them again
+pub fn sleep_hook_installed() -> bool {
+    std::path::Path::new(SLEEP_HOOK_PATH).exists()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn residency_and_drain_across_a_suspend() {
+        // 8h asleep, 7.2h of it in S0ix, 4 Wh and 8% gone
+        let hours = 8 * 3600;
+        let record = SuspendRecord::new((0, hours), (Some(1_000), Some(1_000 + 7 * 3_600_000_000 + 720_000_000)), (Some(40.0), Some(36.0)), (80, 72), true);
+        assert_eq!(record.deep_idle_percent.map(|p| p.round()), Some(90.0));
+        assert_eq!((record.drain_w, record.drain_percent_per_hour), (Some(0.5), Some(1.0)));
+        assert_eq!(record.reached_deep_idle(), Some(true));
+
+        // Stuck in shallow idle, and the counter only read at one end
+        let shallow = SuspendRecord::new((0, 3600), (Some(0), Some(360_000_000)), (None, None), (50, 45), true);
+        let unknown = SuspendRecord::new((0, 3600), (None, Some(5)), (None, None), (50, 50), false);
+        assert_eq!(shallow.reached_deep_idle(), Some(false));
+        assert_eq!((unknown.deep_idle_percent, unknown.drain_percent_per_hour), (None, None));
+
+        let summary = summarize([record, shallow, unknown].iter()).unwrap();
+        assert_eq!((summary.suspends, summary.measured, summary.reached_deep_idle), (3, 2, 1));
+        assert!(summary_line(&summary).starts_with("1 of 2 suspends reached deep idle (81%"));
+
+        assert_eq!(parse_amd_pmc("=== S0ix statistics ===\nS0ix Entry Time: 1\nTotal Time (in us) in S0i3: 42\n"), Some(42));
+    }
+}
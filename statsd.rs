@@ -0,0 +1,83 @@
+use std::net::UdpSocket;
+
+use crate::BatteryInfo;
+
+/// Keep datagrams under a typical MTU so they aren't fragmented
+const MAX_DATAGRAM_BYTES: usize = 1400;
+
+/// Sends one gauge per BatteryInfo field to a StatsD/DogStatsD server over UDP
+#[derive(Debug)]
+pub struct StatsdEmitter {
+    socket: UdpSocket,
+    prefix: String,
+    tags: Vec<String>,
+}
+
+impl StatsdEmitter {
+    pub fn new(addr: &str, prefix: &str, tags: Vec<String>) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.connect(addr)?;
+        Ok(Self {
+            socket,
+            prefix: prefix.trim_end_matches('.').to_string(),
+            tags,
+        })
+    }
+
+    fn gauge_line(&self, name: &str, value: f64) -> String {
+        let mut line = if self.prefix.is_empty() {
+            format!("{}:{}|g", name, value)
+        } else {
+            format!("{}.{}:{}|g", self.prefix, name, value)
+        };
+        // DogStatsD tag extension; plain StatsD users simply don't configure tags
+        if !self.tags.is_empty() {
+            line.push_str("|#");
+            line.push_str(&self.tags.join(","));
+        }
+        line
+    }
+
+    /// All available gauges for one sample, packed into as few datagrams as fit
+    pub fn datagrams(&self, info: &BatteryInfo) -> Vec<String> {
+        let gauges = [
+            ("capacity_percent", Some(info.capacity_percent as f64)),
+            ("health_percent", Some(info.health_percent)),
+            ("power_w", info.power_w),
+            ("smoothed_power_w", info.smoothed_power_w),
+            ("voltage_v", info.voltage_v),
+            ("current_ma", info.current_ma.map(|c| c as f64)),
+            ("energy_now_wh", info.energy_now_wh),
+            ("energy_full_wh", info.energy_full_wh),
+            ("time_remaining_minutes", info.time_remaining_minutes.map(|t| t as f64)),
+            ("temperature_c", info.temperature_c),
+            ("cpu_temperature_c", info.cpu_temperature_c),
+            ("charging", Some(if info.status == "Charging" { 1.0 } else { 0.0 })),
+        ];
+
+        let mut datagrams = Vec::new();
+        let mut datagram = String::new();
+        for (name, value) in gauges {
+            let Some(value) = value else { continue };
+            let line = self.gauge_line(name, value);
+            if !datagram.is_empty() && datagram.len() + line.len() + 1 > MAX_DATAGRAM_BYTES {
+                datagrams.push(std::mem::take(&mut datagram));
+            }
+            if !datagram.is_empty() {
+                datagram.push('\n');
+            }
+            datagram.push_str(&line);
+        }
+        if !datagram.is_empty() {
+            datagrams.push(datagram);
+        }
+        datagrams
+    }
+
+    /// Emit all available gauges for one sample; send errors are ignored (UDP is best effort)
+    pub fn emit(&self, info: &BatteryInfo) {
+        for datagram in self.datagrams(info) {
+            let _ = self.socket.send(datagram.as_bytes());
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{format_minutes, BatteryInfo, ChargeLevel};
+
+/// One-line output formats for status bars
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarFormat {
+    Polybar,
+    Xmobar,
+    Dzen,
+}
+
+impl BarFormat {
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "polybar" => Some(BarFormat::Polybar),
+            "xmobar" => Some(BarFormat::Xmobar),
+            "dzen" => Some(BarFormat::Dzen),
+            _ => None,
+        }
+    }
+}
+
+/// Nerd Font battery ramp, one glyph per capacity decile (0%, 10%, …, 100%)
+const RAMP_ICONS: [&str; 11] = [
+    "\u{f008e}", "\u{f007a}", "\u{f007b}", "\u{f007c}", "\u{f007d}", "\u{f007e}",
+    "\u{f007f}", "\u{f0080}", "\u{f0081}", "\u{f0082}", "\u{f0079}",
+];
+const CHARGING_ICON: &str = "\u{f0084}";
+
+fn ramp_icon(info: &BatteryInfo) -> &'static str {
+    if info.status == "Charging" {
+        return CHARGING_ICON;
+    }
+    RAMP_ICONS[(info.capacity_percent.min(100) / 10) as usize]
+}
+
+/// Hex equivalents of the TUI's red/yellow/green/cyan capacity colors
+fn hex_color(level: ChargeLevel) -> &'static str {
+    match level {
+        ChargeLevel::Critical => "#ff5555",
+        ChargeLevel::Low => "#f1fa8c",
+        ChargeLevel::Normal => "#50fa7b",
+        ChargeLevel::High => "#8be9fd",
+    }
+}
+
+/// Capacity and smoothed ETA, without any markup
+fn plain_text(info: &BatteryInfo) -> String {
+    match info.time_remaining_minutes {
+        Some(minutes) => format!("{}% {}", info.capacity_percent, format_minutes(minutes)),
+        None => format!("{}%", info.capacity_percent),
+    }
+}
+
+/// Render a sample as a single status bar line
+pub fn render(format: BarFormat, info: &BatteryInfo) -> String {
+    let color = hex_color(ChargeLevel::from_capacity(info.capacity_percent));
+    match format {
+        BarFormat::Polybar => format!("%{{F{}}}{} {}%{{F-}}", color, ramp_icon(info), plain_text(info)),
+        BarFormat::Xmobar => format!("<fc={}>{} {}</fc>", color, ramp_icon(info), plain_text(info)),
+        BarFormat::Dzen => format!("^fg({}){} {}^fg()", color, ramp_icon(info), plain_text(info)),
+    }
+}
@@ -0,0 +1,168 @@
+//! Runs the real binary against recorded sysfs trees in tests/fixtures.
+
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use serde_json::Value;
+use tempfile::TempDir;
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+/// Config, data and runtime directory of one test's runs, deleted when the test ends
+fn scratch() -> TempDir {
+    tempfile::tempdir().expect("scratch dir")
+}
+
+/// `batfi --sysfs-root <fixture>`, isolated from the user's config, data and daemon in `scratch`
+fn batfi(name: &str, scratch: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_batfi"));
+    command
+        .arg("--sysfs-root")
+        .arg(fixture(name))
+        .env("XDG_CONFIG_HOME", scratch)
+        .env("XDG_DATA_HOME", scratch)
+        .env("XDG_RUNTIME_DIR", scratch)
+        .env_remove("BATFI_CONFIG")
+        .env_remove("BATFI_SYSFS_ROOT")
+        .env_remove("BATFI_BATTERY");
+    command
+}
+
+/// `batfi --sysfs-root <fixture> --json ARGS`
+fn run(name: &str, scratch: &Path, args: &[&str]) -> std::process::Output {
+    batfi(name, scratch).arg("--json").args(args).output().expect("batfi runs")
+}
+
+fn status(name: &str, scratch: &Path, extra: &[&str]) -> Value {
+    let mut args = extra.to_vec();
+    args.extend(["status", "--local"]);
+    let output = run(name, scratch, &args);
+    assert!(output.status.success(), "{}: {}", name, String::from_utf8_lossy(&output.stderr));
+    serde_json::from_slice(&output.stdout).unwrap_or_else(|e| panic!("{}: invalid JSON ({}): {}", name, e, String::from_utf8_lossy(&output.stdout)))
+}
+
+fn approx(value: &Value, expected: f64) -> bool {
+    value.as_f64().is_some_and(|v| (v - expected).abs() < 0.01)
+}
+
+#[test]
+fn intel_laptop_discharging_with_coretemp() {
+    let scratch = scratch();
+    let info = status("intel-laptop", scratch.path(), &[]);
+    assert_eq!(info["status"], "Discharging");
+    assert_eq!(info["capacity_percent"], 72);
+    assert!(approx(&info["energy_now_wh"], 36.54));
+    assert!(approx(&info["power_w"], 9.12));
+    assert!(approx(&info["voltage_v"], 11.88));
+    assert_eq!(info["cycles"], 214);
+    // Battery temp is in decidegrees; the package sensor wins over cores and acpitz
+    assert!(approx(&info["temperature_c"], 31.2));
+    assert!(approx(&info["cpu_temperature_c"], 54.0));
+    assert!(approx(&info["health_percent"], 50.75 / 57.0 * 100.0));
+    assert_eq!(info["manufacturer"], "SMP");
+    assert_eq!(info["ac_online"], false);
+}
+
+#[test]
+fn amd_laptop_charging_with_k10temp() {
+    let scratch = scratch();
+    let info = status("amd-laptop", scratch.path(), &[]);
+    assert_eq!(info["status"], "Charging");
+    assert_eq!(info["capacity_percent"], 41);
+    assert!(approx(&info["power_w"], 31.4));
+    // Tctl, not the GPU edge sensor
+    assert!(approx(&info["cpu_temperature_c"], 61.25));
+    assert!(info["temperature_c"].is_null());
+    // ACAD, the AMD naming for the mains adapter
+    assert_eq!(info["ac_online"], true);
+}
+
+#[test]
+fn charge_only_battery_derives_energy_and_power() {
+    let scratch = scratch();
+    let info = status("charge-only", scratch.path(), &[]);
+    assert_eq!(info["status"], "Discharging");
+    // 2640 mAh × 7.6 V and 1.1 A × 7.6 V
+    assert!(approx(&info["energy_now_wh"], 20.064));
+    assert!(approx(&info["power_w"], 8.36));
+    assert!(approx(&info["health_percent"], 88.0));
+    assert!(info["charge_now_mah"].is_null());
+
+    let info = status("charge-only", scratch.path(), &["--units", "mah"]);
+    assert!(approx(&info["charge_now_mah"], 2640.0));
+    assert!(approx(&info["charge_full_mah"], 4400.0));
+}
+
+#[test]
+fn dual_battery_reports_each_pack() {
+    let scratch = scratch();
+    let first = status("dual-battery", scratch.path(), &[]);
+    assert_eq!(first["capacity_percent"], 35);
+
+    let all = status("dual-battery", scratch.path(), &["--battery", "all"]);
+    let packs = all["batteries"].as_array().expect("one entry per battery");
+    assert_eq!(packs.len(), 2);
+    assert_eq!(packs[0]["battery"], "BAT0");
+    assert_eq!(packs[0]["status"], "Discharging");
+    assert_eq!(packs[0]["drain_order"], 1);
+    assert_eq!(packs[1]["battery"], "BAT1");
+    assert_eq!(packs[1]["capacity_percent"], 100);
+    assert!(approx(&packs[1]["energy_now_wh"], 70.0));
+    // Both packs' energy behind one estimate
+    assert_eq!(all["status"], "Discharging");
+    assert!(approx(&all["energy_now_wh"], packs[0]["energy_now_wh"].as_f64().unwrap() + 70.0));
+
+    let output = batfi("dual-battery", scratch.path()).args(["--battery", "all", "--format", "plasma", "--once"]).output().expect("batfi runs");
+    let plasma: Value = serde_json::from_slice(&output.stdout).expect("Plasma document");
+    let names: Vec<&Value> = plasma["batteries"].as_array().expect("battery list").iter().map(|pack| &pack["name"]).collect();
+    assert_eq!(names, ["BAT0", "BAT1"]);
+}
+
+#[test]
+fn no_temp_device_leaves_temperatures_empty() {
+    let scratch = scratch();
+    let info = status("no-temp", scratch.path(), &[]);
+    assert_eq!(info["capacity_percent"], 90);
+    assert!(info["temperature_c"].is_null());
+    assert!(info["cpu_temperature_c"].is_null());
+    assert!(approx(&info["health_percent"], 100.0));
+    assert!(info["ac_online"].is_null());
+}
+
+#[test]
+fn ctrl_c_stops_watch_cleanly() {
+    let scratch = scratch();
+    let hook_output = scratch.path().join("on-exit");
+    let mut child = batfi("intel-laptop", scratch.path())
+        .args(["--interval", "1", "--on-exit"])
+        .arg(format!("echo $BATFI_SAMPLES > {}", hook_output.display()))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("batfi runs");
+    // The first sample comes after the handler is installed; signalling earlier would just kill it
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut first = String::new();
+    stdout.read_line(&mut first).unwrap();
+    assert!(first.contains("Discharging"), "{}", first);
+    Command::new("kill").args(["-INT", &child.id().to_string()]).status().expect("kill runs");
+
+    let mut rest = String::new();
+    stdout.read_to_string(&mut rest).unwrap();
+    let status = child.wait().unwrap();
+    assert!(status.success(), "exited with {:?}", status);
+    // The hook still ran, with the samples taken before the interrupt
+    let samples = std::fs::read_to_string(&hook_output).expect("on_exit ran");
+    assert!(samples.trim().parse::<u32>().is_ok_and(|n| n >= 1), "{}", samples);
+}
+
+#[test]
+fn missing_tree_fails_cleanly() {
+    let scratch = scratch();
+    let output = run("does-not-exist", scratch.path(), &["status", "--local"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No batteries found"));
+}
@@ -0,0 +1,81 @@
+//! Golden output for every format that bars and scripts parse, fed by the simulated battery.
+//!
+//! A failing snapshot means a user-visible format changed. If that was intended, review
+//! and accept the new output with `cargo insta review` (or `INSTA_UPDATE=always cargo test`).
+
+use batfi::simulate::SimulatedBackend;
+use batfi::statusbar::{self, BarFormat};
+use batfi::statsd::StatsdEmitter;
+use batfi::{fields, plasma, BatteryInfo, BatteryMonitor};
+use insta::assert_snapshot;
+
+/// Samples taken before rendering, enough for the estimators to produce an ETA
+const TICKS: usize = 30;
+/// Fixed sample time for the timestamped formats
+const TIMESTAMP: u64 = 1_700_000_000;
+
+/// Replace values measured against the wall clock, which vary between runs, with fixed ones
+fn pin_clock(info: &mut BatteryInfo) {
+    info.last_full_secs_ago = info.last_full_secs_ago.map(|_| 3600);
+    info.unplugged_secs = info.unplugged_secs.map(|_| 1800);
+    info.energy_since_unplug_wh = info.energy_since_unplug_wh.map(|_| 4.5);
+    info.charge_session_secs = info.charge_session_secs.map(|_| 900);
+    info.charge_session_added_wh = info.charge_session_added_wh.map(|_| 9.0);
+    // Fitted over wall-clock timestamps, so only present when the ticks straddle a second
+    info.percent_per_hour = None;
+    info.time_to_80_minutes = None;
+}
+
+/// Run `scenario` for TICKS samples from a fresh data directory and snapshot every format as `<label>_<format>`
+fn snapshot_scenario(label: &str, scenario: &str, from_percent: f64) {
+    let dir = tempfile::tempdir().expect("scratch data dir");
+    // A sysfs root that doesn't exist reads as empty, so the host's temperature sensors,
+    // adapters and CPUs stay out of the output. Only the first test's call counts, and its
+    // path stays missing after that test's directory is deleted.
+    batfi::set_sysfs_root(dir.path().join("no-sysfs"));
+
+    let backend = SimulatedBackend::new(scenario, from_percent, None, 2.0);
+    let mut monitor = BatteryMonitor::with_backend("SIM0", Box::new(backend));
+    monitor.set_data_dir(Some(dir.path().to_path_buf()));
+    let mut info: Option<BatteryInfo> = None;
+    for _ in 0..TICKS {
+        info = monitor.get_battery_info();
+    }
+    let mut info = info.expect("simulated battery always reads");
+    pin_clock(&mut info);
+
+    for (name, format) in [("polybar", BarFormat::Polybar), ("xmobar", BarFormat::Xmobar), ("dzen", BarFormat::Dzen)] {
+        assert_snapshot!(format!("{}_{}", label, name), statusbar::render(format, &info));
+    }
+    assert_snapshot!(format!("{}_json", label), monitor.to_json(&info));
+    let mut document = plasma::document(&[(&monitor, &info)]);
+    document.timestamp = TIMESTAMP;
+    assert_snapshot!(format!("{}_plasma", label), serde_json::to_string_pretty(&document).unwrap());
+
+    let selected: Vec<String> = ["capacity", "status", "power", "time_remaining_minutes"].map(String::from).to_vec();
+    assert_snapshot!(format!("{}_fields_plain", label), fields::render_plain(&info, &selected, " | "));
+    assert_snapshot!(format!("{}_fields_json", label), fields::to_json(&info, Some(&selected)));
+
+    let log = format!("{}\n{}", batfi::LOG_CSV_HEADER, batfi::log_csv_row(TIMESTAMP, &info));
+    assert_snapshot!(format!("{}_log_csv", label), log);
+    assert_snapshot!(format!("{}_log_json", label), batfi::log_json_line(TIMESTAMP, &info, None));
+
+    let statsd = StatsdEmitter::new("127.0.0.1:8125", "batfi", vec!["host:test".to_string()]).expect("UDP socket");
+    assert_snapshot!(format!("{}_statsd", label), statsd.datagrams(&info).join("\n---\n"));
+}
+
+#[test]
+fn discharge_formats() {
+    snapshot_scenario("discharge", "discharge", 90.0);
+}
+
+#[test]
+fn charge_formats() {
+    snapshot_scenario("charge", "charge", 20.0);
+}
+
+#[test]
+fn full_formats() {
+    // Plugged in at 100%: the charger stops and the pack reports Full
+    snapshot_scenario("full", "charge", 100.0);
+}
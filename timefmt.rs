@@ -0,0 +1,112 @@
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use serde_json::Value;
+
+/// How remaining/charging times are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EtaFormat {
+    /// 2h 41m
+    Human,
+    /// 2:41
+    Clock,
+    /// 161m
+    Minutes,
+}
+
+/// How sample timestamps are written in logs and JSON
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampFormat {
+    Unix,
+    /// ISO-8601 with the local UTC offset
+    Iso,
+    /// ISO-8601 in UTC
+    IsoUtc,
+}
+
+pub const ETA_FORMATS: [&str; 3] = ["human", "clock", "minutes"];
+pub const TIMESTAMP_FORMATS: [&str; 3] = ["unix", "iso", "iso-utc"];
+
+impl EtaFormat {
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "human" => Some(EtaFormat::Human),
+            "clock" => Some(EtaFormat::Clock),
+            "minutes" => Some(EtaFormat::Minutes),
+            _ => None,
+        }
+    }
+}
+
+impl TimestampFormat {
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "unix" => Some(TimestampFormat::Unix),
+            "iso" => Some(TimestampFormat::Iso),
+            "iso-utc" => Some(TimestampFormat::IsoUtc),
+            _ => None,
+        }
+    }
+}
+
+static FORMATS: OnceLock<(EtaFormat, TimestampFormat)> = OnceLock::new();
+
+/// Apply the configured formats; unknown names keep the defaults
+pub fn init(eta: &str, timestamps: &str) {
+    let _ = FORMATS.set((
+        EtaFormat::parse(eta).unwrap_or(EtaFormat::Human),
+        TimestampFormat::parse(timestamps).unwrap_or(TimestampFormat::Unix),
+    ));
+}
+
+fn formats() -> (EtaFormat, TimestampFormat) {
+    *FORMATS.get_or_init(|| (EtaFormat::Human, TimestampFormat::Unix))
+}
+
+/// A duration in minutes in the configured ETA format
+pub fn eta(minutes: u32) -> String {
+    let (hours, mins) = (minutes / 60, minutes % 60);
+    match formats().0 {
+        EtaFormat::Human if hours > 0 => format!("{}h {:02}m", hours, mins),
+        EtaFormat::Human => format!("{}m", mins),
+        EtaFormat::Clock => format!("{}:{:02}", hours, mins),
+        EtaFormat::Minutes => format!("{}m", minutes),
+    }
+}
+
+fn utc(secs: u64) -> DateTime<Utc> {
+    DateTime::from_timestamp(secs as i64, 0).unwrap_or_default()
+}
+
+/// A Unix timestamp in the configured format, for CSV and text output
+pub fn timestamp(secs: u64) -> String {
+    match formats().1 {
+        TimestampFormat::Unix => secs.to_string(),
+        TimestampFormat::Iso => utc(secs).with_timezone(&Local).to_rfc3339_opts(SecondsFormat::Secs, false),
+        TimestampFormat::IsoUtc => utc(secs).to_rfc3339_opts(SecondsFormat::Secs, true),
+    }
+}
+
+/// Like `timestamp`, but Unix times stay JSON numbers
+pub fn timestamp_json(secs: u64) -> Value {
+    match formats().1 {
+        TimestampFormat::Unix => Value::from(secs),
+        _ => Value::from(timestamp(secs)),
+    }
+}
+
+/// Time of day for tables: UTC with iso-utc, local time otherwise
+pub fn time_of_day(secs: u64) -> String {
+    match formats().1 {
+        TimestampFormat::IsoUtc => utc(secs).format("%H:%M:%SZ").to_string(),
+        _ => utc(secs).with_timezone(&Local).format("%H:%M:%S").to_string(),
+    }
+}
+
+/// Date and time of day for tables spanning several days
+pub fn date_time(secs: u64) -> String {
+    match formats().1 {
+        TimestampFormat::IsoUtc => utc(secs).format("%Y-%m-%d %H:%M:%SZ").to_string(),
+        _ => utc(secs).with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string(),
+    }
+}
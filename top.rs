@@ -0,0 +1,327 @@
+//! `batfi top`: one row per power supply and temperature sensor, refreshed in place and
+//! sortable from the keyboard, like htop for power.
+
+use std::cmp::Ordering;
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::attr::Attrs;
+use crate::list;
+
+/// One line of the table: a power_supply device or a temperature sensor
+#[derive(Debug, Clone, Serialize)]
+pub struct TopRow {
+    pub name: String,
+    /// battery, ac, usb, peripheral… for supplies; "cpu sensor" or "battery sensor" for sensors
+    pub kind: String,
+    pub capacity_percent: Option<f64>,
+    /// Charging/Discharging for batteries, online/offline for adapters
+    pub status: Option<String>,
+    pub power_w: Option<f64>,
+    pub voltage_v: Option<f64>,
+    pub current_ma: Option<f64>,
+    pub celsius: Option<f64>,
+}
+
+/// Table columns, in display order; the number keys pick them by position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    Name,
+    Kind,
+    Capacity,
+    Status,
+    Power,
+    Voltage,
+    Current,
+    Temperature,
+}
+
+const COLUMNS: [Column; 8] = [
+    Column::Name,
+    Column::Kind,
+    Column::Capacity,
+    Column::Status,
+    Column::Power,
+    Column::Voltage,
+    Column::Current,
+    Column::Temperature,
+];
+
+impl Column {
+    fn header(self) -> &'static str {
+        match self {
+            Column::Name => "NAME",
+            Column::Kind => "KIND",
+            Column::Capacity => "CHARGE",
+            Column::Status => "STATUS",
+            Column::Power => "POWER",
+            Column::Voltage => "VOLTAGE",
+            Column::Current => "CURRENT",
+            Column::Temperature => "TEMP",
+        }
+    }
+
+    fn width(self) -> usize {
+        match self {
+            Column::Name => 22,
+            Column::Kind => 15,
+            Column::Status => 12,
+            _ => 9,
+        }
+    }
+
+    /// Text columns read left to right and sort A→Z; numbers sort largest first
+    fn is_text(self) -> bool {
+        matches!(self, Column::Name | Column::Kind | Column::Status)
+    }
+
+    fn cell(self, row: &TopRow) -> String {
+        let number = |value: Option<f64>, unit: &str, decimals: usize| {
+            value.map(|v| format!("{:.*}{}", decimals, v, unit)).unwrap_or_else(|| "—".to_string())
+        };
+        match self {
+            Column::Name => row.name.clone(),
+            Column::Kind => row.kind.clone(),
+            Column::Capacity => number(row.capacity_percent, "%", 0),
+            Column::Status => row.status.clone().unwrap_or_else(|| "—".to_string()),
+            Column::Power => number(row.power_w, "W", 2),
+            Column::Voltage => number(row.voltage_v, "V", 2),
+            Column::Current => number(row.current_ma, "mA", 0),
+            Column::Temperature => number(row.celsius, "°C", 1),
+        }
+    }
+
+    fn value(self, row: &TopRow) -> Option<f64> {
+        match self {
+            Column::Capacity => row.capacity_percent,
+            Column::Power => row.power_w,
+            Column::Voltage => row.voltage_v,
+            Column::Current => row.current_ma,
+            Column::Temperature => row.celsius,
+            Column::Name | Column::Kind | Column::Status => None,
+        }
+    }
+}
+
+fn supply_row(device: list::PowerSupplyDevice) -> TopRow {
+    let path = Path::new(&device.path);
+    let read = |name: &str| fs::read_to_string(path.join(name)).ok().map(|s| s.trim().to_string());
+    let attrs = Attrs::new(read);
+    let voltage_v = attrs.voltage_v();
+    let current_ma = attrs.current_ma().map(|ma| f64::from(ma.abs()));
+    let status = read("status").or_else(|| {
+        read("online").map(|online| if online == "1" { "online" } else { "offline" }.to_string())
+    });
+    TopRow {
+        name: device.nickname.unwrap_or(device.name),
+        kind: device.kind,
+        capacity_percent: attrs.number("capacity"),
+        status,
+        power_w: attrs.power_w().or_else(|| Some(voltage_v? * current_ma? / 1000.0)),
+        voltage_v,
+        current_ma,
+        celsius: attrs.number("temp").map(|decidegrees| decidegrees / 10.0),
+    }
+}
+
+/// Every power supply and temperature sensor with its current values
+pub fn sample_rows() -> Vec<TopRow> {
+    let devices = list::list_devices();
+    let sensors = devices.temperature_sensors.into_iter().map(|sensor| TopRow {
+        name: sensor.name,
+        kind: format!("{} sensor", sensor.role),
+        capacity_percent: None,
+        status: None,
+        power_w: None,
+        voltage_v: None,
+        current_ma: None,
+        celsius: sensor.celsius,
+    });
+    devices.power_supplies.into_iter().map(supply_row).chain(sensors).collect()
+}
+
+/// Sort by `column`; rows without a value go last whichever way the column runs
+fn sort_rows(rows: &mut [TopRow], column: Column, reversed: bool) {
+    rows.sort_by(|a, b| {
+        let order = if column.is_text() {
+            column.cell(a).to_lowercase().cmp(&column.cell(b).to_lowercase())
+        } else {
+            match (column.value(a), column.value(b)) {
+                (Some(a), Some(b)) => b.total_cmp(&a),
+                (Some(_), None) => return Ordering::Less,
+                (None, Some(_)) => return Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+        };
+        if reversed { order.reverse() } else { order }
+    });
+}
+
+/// Keystrokes unbuffered and unechoed for as long as it lives, drawn on the alternate screen
+struct RawTerminal {
+    original: libc::termios,
+}
+
+impl RawTerminal {
+    fn enter() -> io::Result<Self> {
+        // SAFETY: termios is plain data, and tcgetattr fills it in before it is read
+        let mut original: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut raw = original;
+        // ISIG off too, so Ctrl+C arrives as a key and the terminal is restored on the way out
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG);
+        raw.c_cc[libc::VMIN] = 0;
+        raw.c_cc[libc::VTIME] = 0;
+        // SAFETY: `raw` is a valid termios copied from the current settings
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        print!("\x1b[?1049h\x1b[?25l");
+        Ok(Self { original })
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+        // SAFETY: restores the settings saved in `enter`
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original) };
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Key {
+    Quit,
+    Left,
+    Right,
+    Reverse,
+    Column(usize),
+}
+
+/// Wait up to `timeout` for a key; None when the time runs out first
+fn read_key(timeout: Duration) -> Option<Key> {
+    let mut poll = libc::pollfd { fd: libc::STDIN_FILENO, events: libc::POLLIN, revents: 0 };
+    // SAFETY: one valid pollfd for the duration of the call
+    if unsafe { libc::poll(&mut poll, 1, timeout.as_millis() as libc::c_int) } <= 0 {
+        return None;
+    }
+    let mut buffer = [0u8; 8];
+    let read = io::stdin().read(&mut buffer).ok()?;
+    match &buffer[..read] {
+        b"q" | b"Q" | b"\x03" | b"\x1b" => Some(Key::Quit),
+        b"\x1b[D" | b"<" | b"h" => Some(Key::Left),
+        b"\x1b[C" | b">" | b"l" => Some(Key::Right),
+        b"r" | b"R" => Some(Key::Reverse),
+        [digit @ b'1'..=b'8'] => Some(Key::Column((digit - b'1') as usize)),
+        _ => None,
+    }
+}
+
+fn draw(rows: &[TopRow], sort: usize, reversed: bool, interval: Duration) {
+    let mut frame = String::from("\x1b[H\x1b[2J");
+    frame.push_str(&format!(" \x1b[1;36m⚡ batfi top\x1b[0m \x1b[2m— {} devices and sensors, every {}s\x1b[0m\r\n\r\n", rows.len(), interval.as_secs()));
+    for (i, column) in COLUMNS.iter().enumerate() {
+        let arrow = match (i == sort, reversed) {
+            (false, _) => " ",
+            (true, false) => if column.is_text() { "▲" } else { "▼" },
+            (true, true) => if column.is_text() { "▼" } else { "▲" },
+        };
+        let header = format!("{}{}", column.header(), arrow);
+        let style = if i == sort { "\x1b[1;7m" } else { "\x1b[1m" };
+        match column.is_text() {
+            true => frame.push_str(&format!(" {}{:<width$}\x1b[0m", style, header, width = column.width())),
+            false => frame.push_str(&format!(" {}{:>width$}\x1b[0m", style, header, width = column.width())),
+        }
+    }
+    frame.push_str("\r\n");
+    for row in rows {
+        for column in COLUMNS {
+            let mut cell = column.cell(row);
+            if cell.chars().count() > column.width() {
+                cell = cell.chars().take(column.width() - 1).chain(['…']).collect();
+            }
+            match column.is_text() {
+                true => frame.push_str(&format!(" {:<width$}", cell, width = column.width())),
+                false => frame.push_str(&format!(" {:>width$}", cell, width = column.width())),
+            }
+        }
+        frame.push_str("\r\n");
+    }
+    frame.push_str("\r\n \x1b[2m←/→ or 1-8 sort • r reverse • q quit\x1b[0m");
+    print!("{}", frame);
+    let _ = io::stdout().flush();
+}
+
+/// `batfi top`: the live table, or a single JSON snapshot with --json or when not on a terminal
+pub fn run_top(interval: Duration, json_output: bool) {
+    if json_output || !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        let mut rows = sample_rows();
+        sort_rows(&mut rows, Column::Power, false);
+        println!("{}", serde_json::to_string_pretty(&rows).unwrap_or_else(|_| "[]".to_string()));
+        return;
+    }
+    let _terminal = match RawTerminal::enter() {
+        Ok(terminal) => terminal,
+        Err(e) => {
+            eprintln!("❌ Could not switch the terminal to raw mode: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut sort = COLUMNS.iter().position(|c| *c == Column::Power).unwrap_or(0);
+    let mut reversed = false;
+    let mut rows = sample_rows();
+    loop {
+        sort_rows(&mut rows, COLUMNS[sort], reversed);
+        draw(&rows, sort, reversed, interval);
+        match read_key(interval) {
+            Some(Key::Quit) => return,
+            Some(Key::Left) => sort = (sort + COLUMNS.len() - 1) % COLUMNS.len(),
+            Some(Key::Right) => sort = (sort + 1) % COLUMNS.len(),
+            Some(Key::Reverse) => reversed = !reversed,
+            Some(Key::Column(column)) => {
+                // Picking the sorted column again flips it, like htop
+                reversed = column == sort && !reversed;
+                sort = column;
+            }
+            // Only a timeout brings new readings; keys just re-sort what is on screen
+            None => rows = sample_rows(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(name: &str, power_w: Option<f64>) -> TopRow {
+        TopRow {
+            name: name.to_string(),
+            kind: "battery".to_string(),
+            capacity_percent: None,
+            status: None,
+            power_w,
+            voltage_v: None,
+            current_ma: None,
+            celsius: None,
+        }
+    }
+
+    #[test]
+    fn missing_values_sort_last_both_ways() {
+        let mut rows = vec![row("a", Some(2.0)), row("b", None), row("c", Some(9.0))];
+        sort_rows(&mut rows, Column::Power, false);
+        assert_eq!(rows.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), ["c", "a", "b"]);
+        sort_rows(&mut rows, Column::Power, true);
+        assert_eq!(rows.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), ["a", "c", "b"]);
+        sort_rows(&mut rows, Column::Name, false);
+        assert_eq!(rows.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), ["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,232 @@
+use std::process::Command;
+use std::thread;
+
+use ksni::blocking::TrayMethods;
+use ksni::menu::StandardItem;
+use ksni::{Icon, MenuItem, ToolTip};
+
+use crate::charge_limit::{self, DEFAULT_CHARGE_LIMIT};
+use crate::{format_minutes, BatteryInfo, BatteryMonitor, ChargeLevel};
+
+const ICON_SIZE: usize = 32;
+
+/// 3x5 bitmap digits, one row per entry, most significant bit on the left
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+type Argb = [u8; 4];
+const OUTLINE: Argb = [255, 220, 220, 220];
+const TEXT: Argb = [255, 255, 255, 255];
+const SHADOW: Argb = [255, 0, 0, 0];
+
+fn level_color(capacity: u8) -> Argb {
+    match ChargeLevel::from_capacity(capacity) {
+        ChargeLevel::Critical => [255, 0xe0, 0x3c, 0x3c],
+        ChargeLevel::Low => [255, 0xe0, 0xb0, 0x20],
+        ChargeLevel::Normal => [255, 0x3c, 0xb0, 0x4c],
+        ChargeLevel::High => [255, 0x30, 0xa8, 0xc8],
+    }
+}
+
+struct Canvas {
+    pixels: Vec<Argb>,
+}
+
+impl Canvas {
+    fn new() -> Self {
+        Self { pixels: vec![[0; 4]; ICON_SIZE * ICON_SIZE] }
+    }
+
+    fn fill(&mut self, x0: usize, y0: usize, x1: usize, y1: usize, color: Argb) {
+        for y in y0..y1.min(ICON_SIZE) {
+            for x in x0..x1.min(ICON_SIZE) {
+                self.pixels[y * ICON_SIZE + x] = color;
+            }
+        }
+    }
+
+    /// Draw a number with 2x-scaled bitmap digits centred at (cx, cy)
+    fn number(&mut self, value: u8, cx: usize, cy: usize, color: Argb) {
+        let text = value.to_string();
+        let width = text.len() * 8 - 2;
+        let left = cx.saturating_sub(width / 2);
+        let top = cy.saturating_sub(5);
+        for (i, ch) in text.bytes().enumerate() {
+            let glyph = DIGITS[(ch - b'0') as usize];
+            for (row, bits) in glyph.iter().enumerate() {
+                for col in 0..3 {
+                    if bits & (0b100 >> col) != 0 {
+                        let x = left + i * 8 + col * 2;
+                        let y = top + row * 2;
+                        self.fill(x, y, x + 2, y + 2, color);
+                    }
+                }
+            }
+        }
+    }
+
+    fn into_icon(self) -> Icon {
+        Icon {
+            width: ICON_SIZE as i32,
+            height: ICON_SIZE as i32,
+            data: self.pixels.concat(),
+        }
+    }
+}
+
+/// A horizontal battery filled to the current level with the percentage on top
+fn render_icon(capacity: u8) -> Icon {
+    let mut canvas = Canvas::new();
+    // Body outline and terminal nub
+    canvas.fill(0, 7, 29, 25, OUTLINE);
+    canvas.fill(2, 9, 27, 23, [0; 4]);
+    canvas.fill(29, 12, 32, 20, OUTLINE);
+
+    let fill_width = (capacity.min(100) as usize * 25).div_ceil(100);
+    canvas.fill(2, 9, 2 + fill_width, 23, level_color(capacity));
+
+    canvas.number(capacity.min(100), 15, 17, SHADOW);
+    canvas.number(capacity.min(100), 14, 16, TEXT);
+    canvas.into_icon()
+}
+
+/// Launch the interactive monitor in the user's terminal
+fn open_monitor() {
+    let Ok(exe) = std::env::current_exe() else { return };
+    let terminal = std::env::var("TERMINAL").unwrap_or_else(|_| "x-terminal-emulator".to_string());
+    if let Err(e) = Command::new(&terminal).arg("-e").arg(exe).spawn() {
+        eprintln!("⚠️  Could not start {}: {}", terminal, e);
+    }
+}
+
+struct BatteryTray {
+    battery: String,
+    base_path: String,
+    info: Option<BatteryInfo>,
+    charge_limit: Option<u8>,
+}
+
+impl ksni::Tray for BatteryTray {
+    fn id(&self) -> String {
+        "batfi".into()
+    }
+
+    fn title(&self) -> String {
+        match &self.info {
+            Some(info) => format!("Battery {}%", info.capacity_percent),
+            None => "Battery".into(),
+        }
+    }
+
+    fn icon_name(&self) -> String {
+        // Fallback for hosts that ignore pixmaps
+        "battery".into()
+    }
+
+    fn icon_pixmap(&self) -> Vec<Icon> {
+        self.info.as_ref().map(|info| vec![render_icon(info.capacity_percent)]).unwrap_or_default()
+    }
+
+    fn tool_tip(&self) -> ToolTip {
+        let Some(info) = &self.info else {
+            return ToolTip { title: "Batfi".into(), ..Default::default() };
+        };
+        let mut lines = vec![match info.time_remaining_minutes {
+            Some(minutes) => format!("{} · {} left", info.status, format_minutes(minutes)),
+            None => info.status.clone(),
+        }];
+        if let Some(power) = info.smoothed_power_w.or(info.power_w) {
+            lines.push(format!("Power: {:.1}W", power));
+        }
+        let temps: Vec<String> = [("Battery", info.temperature_c), ("CPU", info.cpu_temperature_c)]
+            .iter()
+            .filter_map(|(name, temp)| temp.map(|t| format!("{} {:.0}°C", name, t)))
+            .collect();
+        if !temps.is_empty() {
+            lines.push(temps.join(" · "));
+        }
+        ToolTip {
+            title: format!("{} {}%", self.battery, info.capacity_percent),
+            description: lines.join("\n"),
+            ..Default::default()
+        }
+    }
+
+    fn activate(&mut self, _x: i32, _y: i32) {
+        open_monitor();
+    }
+
+    fn menu(&self) -> Vec<MenuItem<Self>> {
+        let mut items = Vec::new();
+        if let Some(limit) = self.charge_limit {
+            let target = if limit < 100 { 100 } else { DEFAULT_CHARGE_LIMIT };
+            items.push(
+                StandardItem {
+                    label: format!("Set charge limit to {}% (now {}%)", target, limit),
+                    activate: Box::new(move |tray: &mut Self| {
+                        match charge_limit::set_limit(&tray.base_path, target) {
+                            Ok(()) => tray.charge_limit = Some(target),
+                            Err(e) => eprintln!("⚠️  Could not set charge limit: {}", e),
+                        }
+                    }),
+                    ..Default::default()
+                }
+                .into(),
+            );
+        }
+        items.push(
+            StandardItem {
+                label: "Open monitor".into(),
+                activate: Box::new(|_: &mut Self| open_monitor()),
+                ..Default::default()
+            }
+            .into(),
+        );
+        items.push(MenuItem::Separator);
+        items.push(
+            StandardItem {
+                label: "Quit".into(),
+                icon_name: "application-exit".into(),
+                activate: Box::new(|_: &mut Self| std::process::exit(0)),
+                ..Default::default()
+            }
+            .into(),
+        );
+        items
+    }
+}
+
+/// `batfi tray`: sample continuously and mirror the state in a StatusNotifierItem
+pub fn run_tray(mut monitor: BatteryMonitor) -> Result<(), ksni::Error> {
+    let tray = BatteryTray {
+        battery: monitor.battery_name().to_string(),
+        base_path: monitor.base_path().to_string(),
+        info: None,
+        charge_limit: charge_limit::read_limit(monitor.base_path()),
+    };
+    let handle = tray.spawn()?;
+
+    loop {
+        if let Some(info) = monitor.get_battery_info() {
+            let limit = charge_limit::read_limit(monitor.base_path());
+            handle.update(|tray: &mut BatteryTray| {
+                tray.info = Some(info);
+                tray.charge_limit = limit;
+            });
+        }
+        if handle.is_closed() {
+            return Ok(());
+        }
+        thread::sleep(monitor.update_interval());
+    }
+}
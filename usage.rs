@@ -0,0 +1,190 @@
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, Timelike};
+use serde::{Deserialize, Serialize};
+
+use crate::distribution::{Percentiles, PowerDistribution};
+use crate::{events, BatteryReading};
+
+/// How often a running monitor appends a sample to the usage log
+pub const USAGE_RECORD_INTERVAL_SECS: u64 = 60;
+/// Consecutive samples further apart than this were not watched continuously (suspend, batfi not running)
+const MAX_SAMPLE_GAP_SECS: u64 = USAGE_RECORD_INTERVAL_SECS * 3;
+/// Labels of the six-hour blocks plug-ins are bucketed into
+pub const TIME_OF_DAY_BUCKETS: [&str; 4] = ["night", "morning", "afternoon", "evening"];
+
+/// One persisted reading of the usage log, tagged with its battery
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageRecord {
+    pub battery: String,
+    #[serde(flatten)]
+    pub reading: BatteryReading,
+}
+
+/// Totals over a `batfi summary` period
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsageSummary {
+    pub battery: String,
+    pub period: String,
+    pub since: u64,
+    pub samples: usize,
+    /// Time watched while discharging, a proxy for screen-on time
+    pub on_battery_minutes: u64,
+    pub energy_used_wh: f64,
+    /// Sum of depth-of-discharge, in full cycles
+    pub cycles_consumed: f64,
+    pub average_power_w: Option<f64>,
+    /// Spread of discharging power; bursts show in p90/p99 long before they move the average
+    pub power_percentiles: Option<Percentiles>,
+    pub temperature_min_c: Option<f64>,
+    pub temperature_avg_c: Option<f64>,
+    pub temperature_max_c: Option<f64>,
+}
+
+/// Plug habits over a window: how often the charger goes in and out, and where the time goes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlugStats {
+    pub plugs: u32,
+    pub unplugs: u32,
+    /// Plug-ins per block of the local day, in `TIME_OF_DAY_BUCKETS` order
+    pub plugs_by_time_of_day: [u32; 4],
+    /// On AC and no longer charging (Full, or held at a charge limit)
+    pub full_on_ac_secs: u64,
+    pub charging_secs: u64,
+    pub discharging_secs: u64,
+}
+
+impl PlugStats {
+    fn watched_secs(&self) -> u64 {
+        self.full_on_ac_secs + self.charging_secs + self.discharging_secs
+    }
+
+    /// Share of watched time in a state, or None before anything was watched
+    pub fn percent_of_time(&self, secs: u64) -> Option<f64> {
+        let total = self.watched_secs();
+        (total > 0).then(|| secs as f64 / total as f64 * 100.0)
+    }
+}
+
+/// Count plug events from the journal in `dir` and split watched time by charging state
+pub fn plug_stats(dir: Option<&Path>, battery: &str, since: u64) -> PlugStats {
+    let mut stats = PlugStats::default();
+    for event in events::load_events(dir, since).iter().filter(|event| event.battery == battery) {
+        match event.kind.as_str() {
+            "plugged" => {
+                stats.plugs += 1;
+                let hour = DateTime::from_timestamp(event.timestamp as i64, 0)
+                    .map(|t| t.with_timezone(&Local).hour())
+                    .unwrap_or(0);
+                stats.plugs_by_time_of_day[(hour / 6) as usize] += 1;
+            }
+            "unplugged" => stats.unplugs += 1,
+            _ => {}
+        }
+    }
+
+    for pair in load_usage(dir, battery, since).windows(2) {
+        let dt = pair[1].timestamp.saturating_sub(pair[0].timestamp);
+        if dt > MAX_SAMPLE_GAP_SECS {
+            continue;
+        }
+        match pair[0].status.as_str() {
+            "Discharging" => stats.discharging_secs += dt,
+            "Charging" => stats.charging_secs += dt,
+            "Full" | "Not charging" => stats.full_on_ac_secs += dt,
+            _ => {}
+        }
+    }
+    stats
+}
+
+/// Location of the usage log in a data directory (XDG_DATA_HOME/batfi/usage.jsonl for the user's)
+fn usage_log_path(dir: Option<&Path>) -> Option<PathBuf> {
+    Some(dir?.join("usage.jsonl"))
+}
+
+pub fn append_usage_sample(dir: Option<&Path>, battery: &str, reading: &BatteryReading) -> std::io::Result<()> {
+    let path = usage_log_path(dir)
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no data directory"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let record = UsageRecord { battery: battery.to_string(), reading: reading.clone() };
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let line = serde_json::to_string(&record).map_err(std::io::Error::other)?;
+    writeln!(file, "{}", line)
+}
+
+/// A battery's readings logged in `dir` at or after `since`, oldest first
+pub fn load_usage(dir: Option<&Path>, battery: &str, since: u64) -> Vec<BatteryReading> {
+    let Some(file) = usage_log_path(dir).and_then(|path| fs::File::open(path).ok()) else {
+        return Vec::new();
+    };
+
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<UsageRecord>(&line).ok())
+        .filter(|record| record.battery == battery && record.reading.timestamp >= since)
+        .map(|record| record.reading)
+        .collect()
+}
+
+/// Fold the readings of one period into totals
+pub fn summarize(battery: &str, period: &str, since: u64, readings: &[BatteryReading]) -> UsageSummary {
+    let mut on_battery_secs = 0;
+    let mut watched_energy_wh = 0.0;
+    let mut energy_used_wh = 0.0;
+    let mut discharged_percent = 0u64;
+
+    for pair in readings.windows(2) {
+        let (before, after) = (&pair[0], &pair[1]);
+        if after.status != "Discharging" {
+            continue;
+        }
+        // The gauge keeps counting while we're not watching, so drops across gaps still count
+        if after.capacity_percent < before.capacity_percent {
+            discharged_percent += (before.capacity_percent - after.capacity_percent) as u64;
+        }
+        let drop_wh = match (before.energy_now_wh, after.energy_now_wh) {
+            (Some(a), Some(b)) if b <= a => Some(a - b),
+            _ => None,
+        };
+        let dt = after.timestamp.saturating_sub(before.timestamp);
+        let watched = before.status == "Discharging" && dt <= MAX_SAMPLE_GAP_SECS;
+        let used = drop_wh.or_else(|| {
+            let power = after.power_now_w?;
+            watched.then(|| power * dt as f64 / 3600.0)
+        });
+        energy_used_wh += used.unwrap_or(0.0);
+        if watched {
+            on_battery_secs += dt;
+            watched_energy_wh += used.unwrap_or(0.0);
+        }
+    }
+
+    let power: PowerDistribution = readings
+        .iter()
+        .filter(|r| r.status == "Discharging")
+        .filter_map(|r| r.power_now_w)
+        .collect();
+    let temperatures: Vec<f64> = readings.iter().filter_map(|r| r.temperature_c).collect();
+    let temperature_avg_c = (!temperatures.is_empty()).then(|| temperatures.iter().sum::<f64>() / temperatures.len() as f64);
+
+    UsageSummary {
+        battery: battery.to_string(),
+        period: period.to_string(),
+        since,
+        samples: readings.len(),
+        on_battery_minutes: on_battery_secs / 60,
+        energy_used_wh,
+        cycles_consumed: discharged_percent as f64 / 100.0,
+        average_power_w: (on_battery_secs > 0).then(|| watched_energy_wh / (on_battery_secs as f64 / 3600.0)),
+        power_percentiles: power.percentiles(),
+        temperature_min_c: temperatures.iter().cloned().reduce(f64::min),
+        temperature_avg_c,
+        temperature_max_c: temperatures.iter().cloned().reduce(f64::max),
+    }
+}
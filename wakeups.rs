@@ -0,0 +1,163 @@
+//! Who keeps waking the CPU: interrupt and wakeup-event deltas while discharging, so a
+//! shrinking runtime can be pinned on a device rather than guessed at.
+//!
+//! Interrupts come from /proc/interrupts; wakeup sources from debugfs, which is usually
+//! readable by root only and is skipped otherwise.
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use crate::sysfs_path;
+
+/// Offenders listed in the dashboard panel
+pub const TOP_OFFENDERS: usize = 5;
+/// Seconds of discharging before rates mean anything
+pub const MIN_OBSERVED_SECS: u64 = 30;
+
+/// Where a count came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Kind {
+    Interrupt,
+    WakeupSource,
+}
+
+/// A device and how often it fired while on battery
+#[derive(Debug, Clone, PartialEq)]
+pub struct Offender {
+    pub kind: Kind,
+    pub name: String,
+    pub per_minute: f64,
+}
+
+type Counts = BTreeMap<(Kind, String), u64>;
+
+/// Per-device interrupt totals from /proc/interrupts. Only numbered lines are devices;
+/// LOC, RES and friends are per-CPU housekeeping that every machine has plenty of.
+pub fn parse_interrupts(text: &str) -> Counts {
+    let mut counts = Counts::new();
+    for line in text.lines().skip(1) {
+        let Some((irq, rest)) = line.trim_start().split_once(':') else { continue };
+        if irq.parse::<u32>().is_err() {
+            continue;
+        }
+        let tokens: Vec<&str> = rest.split_whitespace().collect();
+        let cpus = tokens.iter().take_while(|t| t.parse::<u64>().is_ok()).count();
+        let total: u64 = tokens[..cpus].iter().filter_map(|t| t.parse::<u64>().ok()).sum();
+        // After the counts: chip, hardware IRQ and trigger, then the device names
+        let devices = tokens.get(cpus + 2..).filter(|d| !d.is_empty()).unwrap_or(&tokens[cpus..]);
+        let name = format!("{} (IRQ {})", devices.join(" "), irq);
+        *counts.entry((Kind::Interrupt, name)).or_default() += total;
+    }
+    counts
+}
+
+/// Event counts per wakeup source from debugfs' wakeup_sources table
+pub fn parse_wakeup_sources(text: &str) -> Counts {
+    let mut counts = Counts::new();
+    for line in text.lines().skip(1) {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        // name active_count event_count wakeup_count ...
+        if let (Some(name), Some(Ok(events))) = (tokens.first(), tokens.get(2).map(|t| t.parse::<u64>())) {
+            *counts.entry((Kind::WakeupSource, name.to_string())).or_default() += events;
+        }
+    }
+    counts
+}
+
+fn read_counts() -> Counts {
+    let mut counts = fs::read_to_string("/proc/interrupts").map(|text| parse_interrupts(&text)).unwrap_or_default();
+    if let Ok(text) = fs::read_to_string(sysfs_path("kernel/debug/wakeup_sources")) {
+        counts.extend(parse_wakeup_sources(&text));
+    }
+    counts
+}
+
+/// Counts accumulated over discharging stretches only; time on the charger isn't the
+/// battery's problem
+#[derive(Debug, Default)]
+pub struct WakeupTracker {
+    last: Option<(u64, Counts)>,
+    totals: Counts,
+    observed_secs: u64,
+}
+
+impl WakeupTracker {
+    pub fn sample(&mut self, timestamp: u64, status: &str) {
+        self.record(timestamp, status, read_counts());
+    }
+
+    fn record(&mut self, timestamp: u64, status: &str, counts: Counts) {
+        if let (Some((then, before)), "Discharging") = (self.last.as_ref(), status) {
+            for (key, now) in &counts {
+                // A device that went away and came back restarts at zero
+                let delta = now.saturating_sub(before.get(key).copied().unwrap_or(*now));
+                *self.totals.entry(key.clone()).or_default() += delta;
+            }
+            self.observed_secs += timestamp.saturating_sub(*then);
+        }
+        self.last = Some((timestamp, counts));
+    }
+
+    pub fn observed_secs(&self) -> u64 {
+        self.observed_secs
+    }
+
+    /// The busiest devices, most frequent first; empty until enough time was observed
+    pub fn top(&self, n: usize) -> Vec<Offender> {
+        if self.observed_secs < MIN_OBSERVED_SECS {
+            return Vec::new();
+        }
+        let minutes = self.observed_secs as f64 / 60.0;
+        let mut offenders: Vec<Offender> = self
+            .totals
+            .iter()
+            .filter(|(_, total)| **total > 0)
+            .map(|((kind, name), total)| Offender { kind: *kind, name: name.clone(), per_minute: *total as f64 / minutes })
+            .collect();
+        offenders.sort_by(|a, b| b.per_minute.total_cmp(&a.per_minute));
+        offenders.truncate(n);
+        offenders
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERRUPTS: &str = "           CPU0       CPU1
+  1:          0         12  IR-IO-APIC    1-edge      i8042
+  9:          4          0  IR-IO-APIC    9-fasteoi   acpi
+142:        100        200  IR-PCI-MSI 327680-edge      xhci_hcd
+LOC:      99999      88888   Local timer interrupts
+";
+
+    #[test]
+    fn discharging_deltas_rank_devices() {
+        let counts = parse_interrupts(INTERRUPTS);
+        assert_eq!(counts[&(Kind::Interrupt, "xhci_hcd (IRQ 142)".to_string())], 300);
+        assert_eq!(counts.len(), 3);
+
+        let sources = parse_wakeup_sources(
+            "name\t\tactive_count\tevent_count\twakeup_count\n\
+             ACAD        \t3\t5\t0\n",
+        );
+        assert_eq!(sources[&(Kind::WakeupSource, "ACAD".to_string())], 5);
+
+        let mut tracker = WakeupTracker::default();
+        tracker.record(0, "Discharging", counts.clone());
+        let mut later = counts.clone();
+        *later.get_mut(&(Kind::Interrupt, "xhci_hcd (IRQ 142)".to_string())).unwrap() += 600;
+        *later.get_mut(&(Kind::Interrupt, "acpi (IRQ 9)".to_string())).unwrap() += 60;
+        tracker.record(60, "Discharging", later.clone());
+        // Time on the charger doesn't count
+        let mut charging = later.clone();
+        *charging.get_mut(&(Kind::Interrupt, "i8042 (IRQ 1)".to_string())).unwrap() += 10_000;
+        tracker.record(120, "Charging", charging);
+
+        assert_eq!(tracker.observed_secs(), 60);
+        let top = tracker.top(TOP_OFFENDERS);
+        assert_eq!(top.len(), 2);
+        assert_eq!((top[0].name.as_str(), top[0].per_minute), ("xhci_hcd (IRQ 142)", 600.0));
+        assert_eq!(top[1].per_minute, 60.0);
+    }
+}
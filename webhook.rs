@@ -0,0 +1,96 @@
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::alerts::{render_template, Alert, AlertChannel};
+use crate::BatteryInfo;
+
+/// Delivery attempts per alert (first try plus retries)
+const WEBHOOK_ATTEMPTS: u32 = 4;
+/// Delay before the first retry; doubled after every failure
+const WEBHOOK_INITIAL_BACKOFF_SECS: u64 = 1;
+const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+
+#[derive(Serialize)]
+struct DefaultPayload<'a> {
+    rule: &'a str,
+    severity: &'a str,
+    message: &'a str,
+    battery: &'a str,
+    host: &'a str,
+    timestamp: u64,
+    info: &'a BatteryInfo,
+}
+
+/// POSTs a JSON payload for each alert, retrying with exponential backoff
+pub struct WebhookChannel {
+    url: String,
+    template: Option<String>,
+    host: String,
+}
+
+impl WebhookChannel {
+    pub fn new(url: &str, template: Option<String>, host: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            template,
+            host: host.to_string(),
+        }
+    }
+
+    fn body(&self, alert: &Alert) -> String {
+        match &self.template {
+            Some(template) => render_template(template, alert, &self.host),
+            None => serde_json::to_string(&DefaultPayload {
+                rule: &alert.rule,
+                severity: alert.severity.as_str(),
+                message: &alert.message,
+                battery: &alert.battery,
+                host: &self.host,
+                timestamp: alert.timestamp,
+                info: &alert.info,
+            })
+            .unwrap_or_else(|_| "{}".to_string()),
+        }
+    }
+}
+
+/// POST `body` to `url`, retrying failed attempts with exponential backoff
+pub fn post_with_retry(url: &str, content_type: &str, headers: &[(String, String)], body: &str) -> Result<(), String> {
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(Duration::from_secs(WEBHOOK_TIMEOUT_SECS)))
+        .build()
+        .into();
+
+    let mut backoff = Duration::from_secs(WEBHOOK_INITIAL_BACKOFF_SECS);
+    let mut last_error = String::new();
+    for attempt in 1..=WEBHOOK_ATTEMPTS {
+        let mut request = agent.post(url).header("Content-Type", content_type);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        match request.send(body) {
+            Ok(_) => return Ok(()),
+            Err(e) => last_error = e.to_string(),
+        }
+        if attempt < WEBHOOK_ATTEMPTS {
+            thread::sleep(backoff);
+            backoff *= 2;
+        }
+    }
+    Err(last_error)
+}
+
+impl AlertChannel for WebhookChannel {
+    fn send(&self, alert: &Alert) {
+        let url = self.url.clone();
+        let body = self.body(alert);
+        // Deliver in the background so retries never stall sampling
+        thread::spawn(move || {
+            if let Err(e) = post_with_retry(&url, "application/json", &[], &body) {
+                crate::warn(&format!("Webhook {} failed after {} attempts: {}", url, WEBHOOK_ATTEMPTS, e));
+            }
+        });
+    }
+}